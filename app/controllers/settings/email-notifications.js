@@ -27,14 +27,14 @@ export default class EmailNotificationsSettingsController extends Controller {
   @action
   async saveEmailNotifications() {
     try {
-      await ajax(`/api/v1/me/email_notifications`, {
+      await ajax(`/api/v1/me/notification_settings`, {
         method: 'PUT',
-        body: JSON.stringify(
-          this.ownedCrates.map(c => ({
+        body: JSON.stringify({
+          crates: this.ownedCrates.map(c => ({
             id: parseInt(c.id, 10),
             email_notifications: c.email_notifications,
           })),
-        ),
+        }),
       });
       this.setProperties({
         emailNotificationsError: false,
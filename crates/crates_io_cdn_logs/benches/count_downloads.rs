@@ -1,4 +1,4 @@
-use crates_io_cdn_logs::{cloudfront, fastly};
+use crates_io_cdn_logs::{caddy, cloudfront, combined, fastly, DownloadFilter};
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use std::io::Cursor;
 
@@ -12,16 +12,40 @@ fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("cloudfront", |b| {
         // Insert a call to `to_async` to convert the bencher to async mode.
         // The timing loops are the same as with the normal bencher.
-        b.to_async(&rt)
-            .iter(|| cloudfront::count_downloads(black_box(Cursor::new(bytes))));
+        b.to_async(&rt).iter(|| async {
+            let mut filter = DownloadFilter::default();
+            cloudfront::count_downloads(black_box(Cursor::new(bytes)), &mut filter).await
+        });
     });
 
     let bytes = include_bytes!("../test_data/fastly/basic.log");
     c.bench_function("fastly", |b| {
         // Insert a call to `to_async` to convert the bencher to async mode.
         // The timing loops are the same as with the normal bencher.
-        b.to_async(&rt)
-            .iter(|| fastly::count_downloads(black_box(Cursor::new(bytes))));
+        b.to_async(&rt).iter(|| async {
+            let mut filter = DownloadFilter::default();
+            fastly::count_downloads(black_box(Cursor::new(bytes)), &mut filter).await
+        });
+    });
+
+    let bytes = include_bytes!("../test_data/combined/basic.log");
+    c.bench_function("combined", |b| {
+        // Insert a call to `to_async` to convert the bencher to async mode.
+        // The timing loops are the same as with the normal bencher.
+        b.to_async(&rt).iter(|| async {
+            let mut filter = DownloadFilter::default();
+            combined::count_downloads(black_box(Cursor::new(bytes)), &mut filter).await
+        });
+    });
+
+    let bytes = include_bytes!("../test_data/caddy/basic.log");
+    c.bench_function("caddy", |b| {
+        // Insert a call to `to_async` to convert the bencher to async mode.
+        // The timing loops are the same as with the normal bencher.
+        b.to_async(&rt).iter(|| async {
+            let mut filter = DownloadFilter::default();
+            caddy::count_downloads(black_box(Cursor::new(bytes)), &mut filter).await
+        });
     });
 }
 
@@ -1,7 +1,8 @@
 use anyhow::Context;
 use clap::Parser;
-use crates_io_cdn_logs::{count_downloads, Decompressor};
+use crates_io_cdn_logs::{count_downloads, Decompressor, DownloadFilter, FilterConfig};
 use std::path::PathBuf;
+use std::time::Duration;
 use tokio::fs::File;
 use tokio::io::BufReader;
 use tracing::level_filters::LevelFilter;
@@ -11,6 +12,15 @@ use tracing_subscriber::{fmt, EnvFilter};
 struct Options {
     /// The path to the CDN log file to parse
     path: PathBuf,
+
+    /// Only count one download per client IP address and crate version
+    /// within this number of seconds
+    #[arg(long)]
+    dedup_window: Option<u64>,
+
+    /// Discard downloads with a `User-Agent` header containing this value
+    #[arg(long)]
+    block_user_agent: Vec<String>,
 }
 
 #[tokio::main]
@@ -31,13 +41,19 @@ async fn main() -> anyhow::Result<()> {
         .and_then(|ext| ext.to_str())
         .unwrap_or_default();
 
+    let config = FilterConfig {
+        dedup_window: options.dedup_window.map(Duration::from_secs),
+        blocked_user_agents: options.block_user_agent,
+    };
+    let mut filter = DownloadFilter::new(&config);
+
     let downloads = match extension {
         "gz" | "zst" => {
             let decompressor = Decompressor::from_extension(reader, Some(extension))?;
             let reader = BufReader::new(decompressor);
-            count_downloads(reader).await?
+            count_downloads(reader, &mut filter).await?
         }
-        _ => count_downloads(reader).await?,
+        _ => count_downloads(reader, &mut filter).await?,
     };
     println!("{downloads:?}");
     println!();
@@ -50,6 +66,15 @@ async fn main() -> anyhow::Result<()> {
     println!("Number of needed inserts: {total_inserts}");
     println!("Total number of downloads: {total_downloads}");
 
+    for (client, downloads) in downloads.sum_downloads_by_client().iter() {
+        println!("  {client}: {downloads}");
+    }
+
+    let discarded = filter.discarded();
+    println!("Number of discarded downloads: {}", discarded.total());
+    println!("  duplicate: {}", discarded.duplicate);
+    println!("  blocked user agent: {}", discarded.blocked_user_agent);
+
     Ok(())
 }
 
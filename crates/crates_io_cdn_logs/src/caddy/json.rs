@@ -0,0 +1,150 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer};
+use std::borrow::Cow;
+
+/// This struct corresponds to a JSON log line from Caddy's structured
+/// logging.
+///
+/// Only the fields that are needed for counting downloads are included.
+/// Log lines of other loggers (e.g. `http.log.error`) do not have a
+/// `request` field.
+///
+/// see <https://caddyserver.com/docs/caddyfile/directives/log#format-modules>.
+#[derive(Debug, Deserialize)]
+pub struct LogLine<'a> {
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub ts: DateTime<Utc>,
+    #[serde(borrow, default)]
+    pub request: Option<Request<'a>>,
+    #[serde(default)]
+    pub status: u16,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Request<'a> {
+    #[serde(borrow)]
+    pub method: Cow<'a, str>,
+    #[serde(borrow)]
+    pub uri: Cow<'a, str>,
+    /// The address of the client, taking trusted proxies into account.
+    #[serde(borrow, default)]
+    pub client_ip: Option<Cow<'a, str>>,
+    /// The address of the direct peer, used if `client_ip` is missing.
+    #[serde(borrow, default)]
+    pub remote_ip: Option<Cow<'a, str>>,
+    #[serde(borrow, default)]
+    pub headers: Headers<'a>,
+}
+
+/// The request headers are logged with their canonical names and a list of
+/// values.
+#[derive(Debug, Default, Deserialize)]
+pub struct Headers<'a> {
+    #[serde(borrow, default, rename = "User-Agent")]
+    pub user_agent: Vec<Cow<'a, str>>,
+}
+
+impl LogLine<'_> {
+    pub fn method(&self) -> Option<&str> {
+        self.request.as_ref().map(|request| request.method.as_ref())
+    }
+
+    pub fn uri(&self) -> Option<&str> {
+        self.request.as_ref().map(|request| request.uri.as_ref())
+    }
+
+    pub fn user_agent(&self) -> Option<&str> {
+        let request = self.request.as_ref()?;
+        request.headers.user_agent.first().map(AsRef::as_ref)
+    }
+
+    pub fn ip(&self) -> Option<&str> {
+        let request = self.request.as_ref()?;
+        let ip = request.client_ip.as_ref().or(request.remote_ip.as_ref());
+        ip.map(AsRef::as_ref).filter(|ip| !ip.is_empty())
+    }
+}
+
+/// Caddy logs the timestamp as floating point Unix time by default, but it
+/// can be configured to use RFC 3339 strings (`time_format iso8601`) instead.
+fn deserialize_timestamp<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DateTime<Utc>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Timestamp {
+        Unix(f64),
+        Rfc3339(DateTime<Utc>),
+    }
+
+    match Timestamp::deserialize(deserializer)? {
+        Timestamp::Rfc3339(date_time) => Ok(date_time),
+        Timestamp::Unix(ts) => {
+            let secs = ts.floor();
+            let nanos = ((ts - secs) * 1e9) as u32;
+            DateTime::from_timestamp(secs as i64, nanos)
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid timestamp: {ts}")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_none, assert_ok};
+    use insta::assert_debug_snapshot;
+
+    #[test]
+    fn test_parse() {
+        let input = r#"{"level":"info","ts":1705449200.4605572,"logger":"http.log.access","msg":"handled request","request":{"remote_ip":"1.2.3.4","remote_port":"54298","client_ip":"1.2.3.4","proto":"HTTP/2.0","method":"GET","host":"static.crates.io","uri":"/crates/strsim/strsim-0.10.0.crate","headers":{"Accept":["*/*"],"User-Agent":["cargo 1.74.0 (ecb9851af 2023-10-18)"]}},"bytes_read":0,"user_id":"","duration":0.001203,"size":11355,"status":200,"resp_headers":{"Content-Type":["application/gzip"]}}"#;
+        let output = assert_ok!(serde_json::from_str::<LogLine<'_>>(input));
+        assert_debug_snapshot!(output, @r###"
+        LogLine {
+            ts: 2024-01-16T23:53:20.460557222Z,
+            request: Some(
+                Request {
+                    method: "GET",
+                    uri: "/crates/strsim/strsim-0.10.0.crate",
+                    client_ip: Some(
+                        "1.2.3.4",
+                    ),
+                    remote_ip: Some(
+                        "1.2.3.4",
+                    ),
+                    headers: Headers {
+                        user_agent: [
+                            "cargo 1.74.0 (ecb9851af 2023-10-18)",
+                        ],
+                    },
+                },
+            ),
+            status: 200,
+        }
+        "###);
+
+        assert_eq!(output.method(), Some("GET"));
+        assert_eq!(output.uri(), Some("/crates/strsim/strsim-0.10.0.crate"));
+        assert_eq!(
+            output.user_agent(),
+            Some("cargo 1.74.0 (ecb9851af 2023-10-18)")
+        );
+        assert_eq!(output.ip(), Some("1.2.3.4"));
+    }
+
+    #[test]
+    fn test_parse_iso8601() {
+        let input = r#"{"level":"info","ts":"2024-01-16T23:53:20.460Z","logger":"http.log.access","msg":"handled request","request":{"method":"GET","uri":"/crates/strsim/strsim-0.10.0.crate","headers":{}},"status":200}"#;
+        let output = assert_ok!(serde_json::from_str::<LogLine<'_>>(input));
+        assert_eq!(output.ts.to_string(), "2024-01-16 23:53:20.460 UTC");
+        assert_none!(output.user_agent());
+        assert_none!(output.ip());
+    }
+
+    #[test]
+    fn test_parse_other_logger() {
+        let input = r#"{"level":"info","ts":1705449200.4605572,"logger":"tls","msg":"finished cleaning storage units"}"#;
+        let output = assert_ok!(serde_json::from_str::<LogLine<'_>>(input));
+        assert_none!(output.method());
+        assert_none!(output.uri());
+    }
+}
@@ -0,0 +1,158 @@
+//! # Caddy log parsing
+//!
+//! see <https://caddyserver.com/docs/caddyfile/directives/log>.
+
+mod json;
+
+use crate::filter::Request;
+use crate::paths::parse_path;
+use crate::{ClientType, DownloadFilter, DownloadsMap};
+use std::borrow::Cow;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+use tracing::{debug_span, instrument, warn};
+
+#[instrument(level = "debug", skip(reader))]
+pub async fn count_downloads(
+    reader: impl AsyncBufRead + Unpin,
+    filter: &mut DownloadFilter,
+) -> anyhow::Result<DownloadsMap> {
+    let mut downloads = DownloadsMap::new();
+
+    let mut lines = reader.lines();
+    while let Some(line) = lines.next_line().await? {
+        let span = debug_span!("process_line");
+        let _guard = span.enter();
+
+        let json = match parse_json(&line) {
+            Ok(json) => json,
+            Err(error) => {
+                warn!("Failed to parse JSON: {error}");
+                continue;
+            }
+        };
+
+        let (Some(method), Some(uri)) = (json.method(), json.uri()) else {
+            // Ignore log lines that are not access logs.
+            continue;
+        };
+
+        if method != "GET" {
+            // Ignore non-GET requests.
+            continue;
+        }
+
+        if json.status != 200 {
+            // Ignore non-200 responses.
+            continue;
+        }
+
+        let uri = decode_uri(uri);
+
+        let Some((name, version)) = parse_path(&uri) else {
+            continue;
+        };
+
+        let client = ClientType::from_user_agent(json.user_agent());
+
+        let request = Request {
+            ip: json.ip(),
+            time: Some(json.ts),
+            user_agent: json.user_agent(),
+        };
+
+        if !filter.accept(&name, &version, &request) {
+            continue;
+        }
+
+        let date = json.ts.date_naive();
+        downloads.add(name, version, date, client);
+    }
+
+    Ok(downloads)
+}
+
+#[instrument(level = "debug", skip(json))]
+fn parse_json(json: &str) -> Result<json::LogLine<'_>, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+/// Deal with paths like `/crates/tikv-jemalloc-sys/tikv-jemalloc-sys-0.5.4%2B5.3.0-patched.crate`.
+///
+/// Caddy logs the request URI as it was sent by the client, so only a single
+/// round of percent-decoding is needed.
+#[instrument(level = "debug", skip(uri))]
+fn decode_uri(uri: &str) -> Cow<'_, str> {
+    percent_encoding::percent_decode_str(uri).decode_utf8_lossy()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use claims::assert_ok;
+    use insta::assert_debug_snapshot;
+    use std::io::Cursor;
+
+    #[tokio::test]
+    async fn test_basic() {
+        let _guard = enable_tracing_output();
+
+        let mut cursor = Cursor::new(include_bytes!("../../test_data/caddy/basic.log"));
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
+
+        assert_debug_snapshot!(downloads, @r###"
+        DownloadsMap {
+            2024-01-16  strsim@0.10.0 .. 1
+            2024-01-16  tikv-jemalloc-sys@0.5.2+5.3.0-patched .. 1
+            2024-01-16  tinyvec@1.6.0 .. 1
+            2024-01-16  winnow@0.5.4 .. 1
+            2024-01-17  cc@1.0.73 .. 2
+            2024-01-17  libc@0.2.126 .. 1
+        }
+        "###);
+
+        assert_debug_snapshot!(downloads.sum_downloads_by_client(), @r###"
+        {
+            "cargo": 4,
+            "ci": 1,
+            "browser": 1,
+            "unknown": 1,
+        }
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_unrelated_traffic() {
+        let _guard = enable_tracing_output();
+
+        let mut cursor = Cursor::new(include_bytes!(
+            "../../test_data/caddy/unrelated-traffic.log"
+        ));
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
+
+        assert_debug_snapshot!(downloads, @r###"
+        DownloadsMap {
+            2024-01-16  strsim@0.10.0 .. 1
+        }
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_recoverable_errors() {
+        let _guard = enable_tracing_output();
+
+        let mut cursor = Cursor::new(include_bytes!(
+            "../../test_data/caddy/recoverable-errors.log"
+        ));
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
+
+        assert_debug_snapshot!(downloads, @r###"
+        DownloadsMap {
+            2024-01-16  strsim@0.10.0 .. 1
+        }
+        "###);
+    }
+}
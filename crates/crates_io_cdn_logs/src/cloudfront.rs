@@ -3,9 +3,10 @@
 //! see <https://docs.aws.amazon.com/AmazonCloudFront/latest/DeveloperGuide/AccessLogs.html#LogFileFormat>
 //! and <https://www.w3.org/TR/WD-logfile.html>.
 
+use crate::filter::Request;
 use crate::paths::parse_path;
-use crate::DownloadsMap;
-use chrono::NaiveDate;
+use crate::{ClientType, DownloadFilter, DownloadsMap};
+use chrono::{NaiveDate, NaiveTime};
 use std::borrow::Cow;
 use tokio::io::{AsyncBufRead, AsyncBufReadExt};
 use tracing::{instrument, warn};
@@ -15,17 +16,26 @@ const HEADER_VERSION: &str = "#Version:";
 const HEADER_FIELDS: &str = "#Fields:";
 
 const FIELD_DATE: &str = "date";
+const FIELD_TIME: &str = "time";
+const FIELD_IP: &str = "c-ip";
 const FIELD_METHOD: &str = "cs-method";
 const FIELD_PATH: &str = "cs-uri-stem";
 const FIELD_STATUS: &str = "sc-status";
+const FIELD_USER_AGENT: &str = "cs(User-Agent)";
 
 #[instrument(level = "debug", skip(reader))]
-pub async fn count_downloads(reader: impl AsyncBufRead + Unpin) -> anyhow::Result<DownloadsMap> {
+pub async fn count_downloads(
+    reader: impl AsyncBufRead + Unpin,
+    filter: &mut DownloadFilter,
+) -> anyhow::Result<DownloadsMap> {
     let mut num_fields = 0;
     let mut date_index = None;
+    let mut time_index = None;
+    let mut ip_index = None;
     let mut method_index = None;
     let mut path_index = None;
     let mut status_index = None;
+    let mut user_agent_index = None;
 
     let mut downloads = DownloadsMap::new();
 
@@ -44,9 +54,12 @@ pub async fn count_downloads(reader: impl AsyncBufRead + Unpin) -> anyhow::Resul
 
             num_fields = fields.len();
             date_index = fields.iter().position(|f| f == &FIELD_DATE);
+            time_index = fields.iter().position(|f| f == &FIELD_TIME);
+            ip_index = fields.iter().position(|f| f == &FIELD_IP);
             method_index = fields.iter().position(|f| f == &FIELD_METHOD);
             path_index = fields.iter().position(|f| f == &FIELD_PATH);
             status_index = fields.iter().position(|f| f == &FIELD_STATUS);
+            user_agent_index = fields.iter().position(|f| f == &FIELD_USER_AGENT);
 
             continue;
         }
@@ -99,7 +112,36 @@ pub async fn count_downloads(reader: impl AsyncBufRead + Unpin) -> anyhow::Resul
             }
         };
 
-        downloads.add(name, version, date);
+        // The `User-Agent` field is optional in the log format, so we don't
+        // use `get_value()` here to avoid warnings for every single line.
+        let user_agent = user_agent_index.and_then(|i| values.get(i));
+        let user_agent = user_agent.map(|user_agent| decode_user_agent(user_agent));
+        let client = ClientType::from_user_agent(user_agent.as_deref());
+
+        // The remaining fields are only used for filtering, and are optional
+        // in the log format too.
+        let get_optional = |index: Option<usize>| {
+            index
+                .and_then(|i| values.get(i))
+                .copied()
+                .filter(|value| *value != "-")
+        };
+
+        let time = get_optional(time_index)
+            .and_then(|time| time.parse::<NaiveTime>().ok())
+            .map(|time| date.and_time(time).and_utc());
+
+        let request = Request {
+            ip: get_optional(ip_index),
+            time,
+            user_agent: user_agent.as_deref(),
+        };
+
+        if !filter.accept(&name, &version, &request) {
+            continue;
+        }
+
+        downloads.add(name, version, date, client);
     }
 
     Ok(downloads)
@@ -110,6 +152,11 @@ fn decode_path(path: &str) -> Cow<'_, str> {
     percent_encoding::percent_decode_str(path).decode_utf8_lossy()
 }
 
+#[instrument(level = "debug", skip(user_agent))]
+fn decode_user_agent(user_agent: &str) -> Cow<'_, str> {
+    percent_encoding::percent_decode_str(user_agent).decode_utf8_lossy()
+}
+
 fn get_value<'a>(values: &'a [&'a str], index: Option<usize>, field_name: &'static str) -> &'a str {
     index
         .and_then(|i| values.get(i))
@@ -124,16 +171,19 @@ fn get_value<'a>(values: &'a [&'a str], index: Option<usize>, field_name: &'stat
 mod tests {
     use super::*;
     use crate::test_utils::*;
+    use crate::FilterConfig;
     use claims::{assert_err, assert_ok};
     use insta::{assert_debug_snapshot, assert_snapshot};
     use std::io::Cursor;
+    use std::time::Duration;
 
     #[tokio::test]
     async fn test_basic() {
         let _guard = enable_tracing_output();
 
         let mut cursor = Cursor::new(include_bytes!("../test_data/cloudfront/basic.log"));
-        let downloads = assert_ok!(count_downloads(&mut cursor).await);
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
 
         assert_debug_snapshot!(downloads, @r###"
         DownloadsMap {
@@ -167,7 +217,8 @@ mod tests {
         let mut cursor = Cursor::new(include_bytes!(
             "../test_data/cloudfront/percent-encoding.log"
         ));
-        let downloads = assert_ok!(count_downloads(&mut cursor).await);
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
 
         assert_debug_snapshot!(downloads, @r###"
         DownloadsMap {
@@ -176,6 +227,59 @@ mod tests {
         "###);
     }
 
+    #[tokio::test]
+    async fn test_user_agents() {
+        let _guard = enable_tracing_output();
+
+        let mut cursor = Cursor::new(include_bytes!("../test_data/cloudfront/user-agents.log"));
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
+
+        assert_eq!(downloads.sum_downloads(), 5);
+        assert_debug_snapshot!(downloads.sum_downloads_by_client(), @r###"
+        {
+            "cargo": 1,
+            "ci": 1,
+            "browser": 1,
+            "other": 1,
+            "unknown": 1,
+        }
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_filtering() {
+        let _guard = enable_tracing_output();
+
+        let bytes = include_bytes!("../test_data/cloudfront/filtering.log");
+
+        let mut filter = DownloadFilter::default();
+        let downloads = assert_ok!(count_downloads(Cursor::new(bytes), &mut filter).await);
+        assert_eq!(downloads.sum_downloads(), 8);
+        assert_eq!(filter.discarded().total(), 0);
+
+        let config = FilterConfig {
+            dedup_window: Some(Duration::from_secs(60)),
+            blocked_user_agents: vec!["scrapy".into(), "bot".into()],
+        };
+        let mut filter = DownloadFilter::new(&config);
+        let downloads = assert_ok!(count_downloads(Cursor::new(bytes), &mut filter).await);
+
+        assert_debug_snapshot!(downloads, @r###"
+        DownloadsMap {
+            2024-01-16  bindgen@0.65.1 .. 3
+            2024-01-16  tar@0.4.38 .. 2
+        }
+        "###);
+
+        assert_debug_snapshot!(filter.discarded(), @r###"
+        DiscardedCounts {
+            duplicate: 1,
+            blocked_user_agent: 2,
+        }
+        "###);
+    }
+
     #[tokio::test]
     async fn test_unrelated_traffic() {
         let _guard = enable_tracing_output();
@@ -183,7 +287,8 @@ mod tests {
         let mut cursor = Cursor::new(include_bytes!(
             "../test_data/cloudfront/unrelated-traffic.log"
         ));
-        let downloads = assert_ok!(count_downloads(&mut cursor).await);
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
 
         assert_debug_snapshot!(downloads, @r###"
         DownloadsMap {
@@ -199,7 +304,8 @@ mod tests {
         let mut cursor = Cursor::new(include_bytes!(
             "../test_data/cloudfront/recoverable-errors.log"
         ));
-        let downloads = assert_ok!(count_downloads(&mut cursor).await);
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
 
         assert_debug_snapshot!(downloads, @r###"
         DownloadsMap {
@@ -215,7 +321,7 @@ mod tests {
         let mut cursor = Cursor::new(include_bytes!(
             "../test_data/cloudfront/unknown-version.log"
         ));
-        let error = assert_err!(count_downloads(&mut cursor).await);
+        let error = assert_err!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
 
         assert_snapshot!(error, @"Unsupported version: 2.0");
     }
@@ -0,0 +1,313 @@
+//! # nginx/Apache log parsing
+//!
+//! Supports the "combined" log format that is used by default by nginx and
+//! commonly used by Apache:
+//!
+//! ```text
+//! $remote_addr - $remote_user [$time_local] "$request" $status $body_bytes_sent "$http_referer" "$http_user_agent"
+//! ```
+//!
+//! The "common" log format, which is lacking the last two fields, is
+//! supported too, but the downloads will be counted with an unknown
+//! [ClientType].
+//!
+//! see <https://nginx.org/en/docs/http/ngx_http_log_module.html#log_format>
+//! and <https://httpd.apache.org/docs/current/logs.html#combined>.
+
+use crate::filter::Request;
+use crate::paths::parse_path;
+use crate::{ClientType, DownloadFilter, DownloadsMap};
+use chrono::DateTime;
+use std::borrow::Cow;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+use tracing::{debug_span, instrument, warn};
+
+const TIME_FORMAT: &str = "%d/%b/%Y:%H:%M:%S %z";
+
+#[instrument(level = "debug", skip(reader))]
+pub async fn count_downloads(
+    reader: impl AsyncBufRead + Unpin,
+    filter: &mut DownloadFilter,
+) -> anyhow::Result<DownloadsMap> {
+    let mut downloads = DownloadsMap::new();
+
+    let mut lines = reader.lines();
+    while let Some(line) = lines.next_line().await? {
+        let span = debug_span!("process_line");
+        let _guard = span.enter();
+
+        let Some(line) = parse_line(&line) else {
+            warn!("Failed to parse log line");
+            continue;
+        };
+
+        if line.method != "GET" {
+            // Ignore non-GET requests.
+            continue;
+        }
+
+        if line.status != "200" {
+            // Ignore non-200 responses.
+            continue;
+        }
+
+        let path = decode_path(line.path);
+
+        let Some((name, version)) = parse_path(&path) else {
+            continue;
+        };
+
+        // The local time of the server is converted to UTC to be consistent
+        // with the other log formats.
+        let date_time = match DateTime::parse_from_str(line.time, TIME_FORMAT) {
+            Ok(date_time) => date_time.to_utc(),
+            Err(error) => {
+                warn!(time = %line.time, %error, "Failed to parse time");
+                continue;
+            }
+        };
+
+        let user_agent = line.user_agent.map(unescape);
+        let client = ClientType::from_user_agent(user_agent.as_deref());
+
+        let request = Request {
+            ip: Some(line.ip),
+            time: Some(date_time),
+            user_agent: user_agent.as_deref(),
+        };
+
+        if !filter.accept(&name, &version, &request) {
+            continue;
+        }
+
+        downloads.add(name, version, date_time.date_naive(), client);
+    }
+
+    Ok(downloads)
+}
+
+/// The fields of a log line that are relevant for counting downloads.
+#[derive(Debug, PartialEq, Eq)]
+struct LogLine<'a> {
+    ip: &'a str,
+    time: &'a str,
+    method: &'a str,
+    path: &'a str,
+    status: &'a str,
+    user_agent: Option<&'a str>,
+}
+
+/// Splits a log line into its fields.
+///
+/// Like the rest of the log parsing code, this avoids regular expressions
+/// for performance reasons.
+#[instrument(level = "debug", skip(line))]
+fn parse_line(line: &str) -> Option<LogLine<'_>> {
+    // `$remote_addr - $remote_user [$time_local] ...`
+    let (ip, _) = line.split_once(' ')?;
+    let (_, rest) = line.split_once(" [")?;
+    let (time, rest) = rest.split_once("] ")?;
+
+    // `"$request" ...`
+    let (request, rest) = parse_quoted(rest)?;
+    let mut request = request.split(' ');
+    let method = request.next()?;
+    let path = request.next()?;
+
+    // ` $status $body_bytes_sent ...`
+    let mut rest = rest.trim_start().splitn(3, ' ');
+    let status = rest.next()?;
+    let _body_bytes_sent = rest.next()?;
+
+    // ` "$http_referer" "$http_user_agent"` (combined format only)
+    let user_agent = rest
+        .next()
+        .and_then(parse_quoted)
+        .and_then(|(_referer, rest)| parse_quoted(rest.trim_start()))
+        .map(|(user_agent, _)| user_agent);
+
+    Some(LogLine {
+        ip,
+        time,
+        method,
+        path,
+        status,
+        user_agent,
+    })
+}
+
+/// Parses a double-quoted string at the start of the input and returns its
+/// (still escaped) content and the remaining input.
+fn parse_quoted(input: &str) -> Option<(&str, &str)> {
+    let input = input.strip_prefix('"')?;
+
+    let mut escaped = false;
+    for (pos, char) in input.char_indices() {
+        match char {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some((&input[..pos], &input[pos + 1..])),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Reverts the escaping of double quotes and backslashes in quoted fields.
+///
+/// nginx escapes these characters as `\x22` and `\x5C`, while Apache uses
+/// `\"` and `\\`.
+fn unescape(value: &str) -> Cow<'_, str> {
+    if !value.contains('\\') {
+        return Cow::Borrowed(value);
+    }
+
+    // The escape sequences are decoded in a single pass, so that the result
+    // of one decoded sequence is never decoded again (e.g. `\x5C\x22`).
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(pos) = rest.find('\\') {
+        result.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+
+        let (decoded, len) = if rest.starts_with("\\x22") {
+            ('"', 4)
+        } else if rest.starts_with("\\x5C") {
+            ('\\', 4)
+        } else if rest.starts_with("\\\"") {
+            ('"', 2)
+        } else if rest.starts_with("\\\\") {
+            ('\\', 2)
+        } else {
+            ('\\', 1)
+        };
+
+        result.push(decoded);
+        rest = &rest[len..];
+    }
+    result.push_str(rest);
+
+    Cow::Owned(result)
+}
+
+/// Deal with paths like `/crates/tikv-jemalloc-sys/tikv-jemalloc-sys-0.5.4%2B5.3.0-patched.crate`.
+///
+/// nginx and Apache log the request line as it was sent by the client, so
+/// only a single round of percent-decoding is needed.
+#[instrument(level = "debug", skip(path))]
+fn decode_path(path: &str) -> Cow<'_, str> {
+    percent_encoding::percent_decode_str(path).decode_utf8_lossy()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use claims::{assert_none, assert_ok, assert_some};
+    use insta::assert_debug_snapshot;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_parse_line() {
+        let line = r#"1.2.3.4 - - [16/Jan/2024:23:53:20 +0000] "GET /crates/strsim/strsim-0.10.0.crate HTTP/1.1" 200 11355 "-" "cargo 1.74.0 (ecb9851af 2023-10-18)""#;
+        assert_debug_snapshot!(assert_some!(parse_line(line)), @r###"
+        LogLine {
+            ip: "1.2.3.4",
+            time: "16/Jan/2024:23:53:20 +0000",
+            method: "GET",
+            path: "/crates/strsim/strsim-0.10.0.crate",
+            status: "200",
+            user_agent: Some(
+                "cargo 1.74.0 (ecb9851af 2023-10-18)",
+            ),
+        }
+        "###);
+
+        // common log format
+        let line = r#"1.2.3.4 - frank [16/Jan/2024:23:53:20 -0700] "GET /crates/strsim/strsim-0.10.0.crate HTTP/1.0" 200 11355"#;
+        let line = assert_some!(parse_line(line));
+        assert_eq!(line.status, "200");
+        assert_none!(line.user_agent);
+
+        // escaped quotes
+        let line = r#"::1 - - [16/Jan/2024:23:53:20 +0000] "GET /crates/strsim/strsim-0.10.0.crate HTTP/2.0" 200 11355 "-" "foo \"bar\" \x22baz\x22""#;
+        let user_agent = assert_some!(assert_some!(parse_line(line)).user_agent);
+        assert_eq!(unescape(user_agent), r#"foo "bar" "baz""#);
+
+        // escape sequences are only decoded once
+        assert_eq!(unescape(r"\x5C\x22"), r#"\""#);
+        assert_eq!(unescape(r"\\x22"), r"\x22");
+        assert_eq!(unescape(r"trailing \"), r"trailing \");
+
+        assert_none!(parse_line(""));
+        assert_none!(parse_line("1.2.3.4 - - [16/Jan/2024:23:53:20 +0000]"));
+        assert_none!(parse_line(
+            r#"1.2.3.4 - - [16/Jan/2024:23:53:20 +0000] "GET"#
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_basic() {
+        let _guard = enable_tracing_output();
+
+        let mut cursor = Cursor::new(include_bytes!("../test_data/combined/basic.log"));
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
+
+        assert_debug_snapshot!(downloads, @r###"
+        DownloadsMap {
+            2024-01-16  strsim@0.10.0 .. 1
+            2024-01-16  tikv-jemalloc-sys@0.5.2+5.3.0-patched .. 1
+            2024-01-16  tinyvec@1.6.0 .. 1
+            2024-01-16  winnow@0.5.4 .. 1
+            2024-01-17  anstyle@1.0.1 .. 1
+            2024-01-17  cc@1.0.73 .. 2
+            2024-01-17  libc@0.2.126 .. 1
+        }
+        "###);
+
+        assert_debug_snapshot!(downloads.sum_downloads_by_client(), @r###"
+        {
+            "cargo": 5,
+            "ci": 1,
+            "browser": 1,
+            "unknown": 1,
+        }
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_unrelated_traffic() {
+        let _guard = enable_tracing_output();
+
+        let mut cursor = Cursor::new(include_bytes!(
+            "../test_data/combined/unrelated-traffic.log"
+        ));
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
+
+        assert_debug_snapshot!(downloads, @r###"
+        DownloadsMap {
+            2024-01-16  strsim@0.10.0 .. 1
+        }
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_recoverable_errors() {
+        let _guard = enable_tracing_output();
+
+        let mut cursor = Cursor::new(include_bytes!(
+            "../test_data/combined/recoverable-errors.log"
+        ));
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
+
+        assert_debug_snapshot!(downloads, @r###"
+        DownloadsMap {
+            2024-01-16  strsim@0.10.0 .. 1
+        }
+        "###);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::ClientType;
 use chrono::NaiveDate;
 use derive_deref::Deref;
 use semver::Version;
@@ -5,16 +6,17 @@ use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 
 #[derive(Clone, Default, Deref)]
-pub struct DownloadsMap(HashMap<(String, Version, NaiveDate), u64>);
+pub struct DownloadsMap(HashMap<(String, Version, NaiveDate), ClientCounts>);
 
 impl DownloadsMap {
     pub fn new() -> Self {
         Self(HashMap::new())
     }
 
-    /// Increments the download count for the given crate version on the given date.
-    pub fn add(&mut self, name: String, version: Version, date: NaiveDate) {
-        *self.0.entry((name, version, date)).or_default() += 1;
+    /// Increments the download count for the given crate version on the given
+    /// date and by the given type of client.
+    pub fn add(&mut self, name: String, version: Version, date: NaiveDate, client: ClientType) {
+        self.0.entry((name, version, date)).or_default().add(client);
     }
 
     /// Returns a [HashSet] of all crate names in the map.
@@ -24,11 +26,23 @@ impl DownloadsMap {
 
     /// Returns the total number of downloads across all crates and versions.
     pub fn sum_downloads(&self) -> u64 {
-        self.0.values().sum()
+        self.0.values().map(ClientCounts::total).sum()
+    }
+
+    /// Returns the total number of downloads across all crates and versions,
+    /// broken down by the type of client.
+    pub fn sum_downloads_by_client(&self) -> ClientCounts {
+        let mut sum = ClientCounts::default();
+        for counts in self.0.values() {
+            for (client, downloads) in counts.iter() {
+                sum.0[client as usize] += downloads;
+            }
+        }
+        sum
     }
 
     /// Converts the map into a vector of `(crate, version, date, downloads)` tuples.
-    pub fn into_vec(self) -> Vec<(String, Version, NaiveDate, u64)> {
+    pub fn into_vec(self) -> Vec<(String, Version, NaiveDate, ClientCounts)> {
         self.0
             .into_iter()
             .map(|((name, version, date), downloads)| (name, version, date, downloads))
@@ -41,7 +55,7 @@ impl Debug for DownloadsMap {
         let mut downloads = self
             .0
             .iter()
-            .map(|((krate, version, date), downloads)| (date, krate, version, downloads))
+            .map(|((krate, version, date), counts)| (date, krate, version, counts.total()))
             .collect::<Vec<_>>();
 
         downloads.sort();
@@ -58,6 +72,47 @@ impl Debug for DownloadsMap {
     }
 }
 
+/// The number of downloads of a crate version on a specific date, broken
+/// down by [ClientType].
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClientCounts([u64; ClientType::ALL.len()]);
+
+impl ClientCounts {
+    fn add(&mut self, client: ClientType) {
+        self.0[client as usize] += 1;
+    }
+
+    /// Returns the number of downloads by the given type of client.
+    pub fn get(&self, client: ClientType) -> u64 {
+        self.0[client as usize]
+    }
+
+    /// Returns the number of downloads across all types of clients.
+    pub fn total(&self) -> u64 {
+        self.0.iter().sum()
+    }
+
+    /// Returns an iterator over all client types with at least one download.
+    pub fn iter(&self) -> impl Iterator<Item = (ClientType, u64)> {
+        let counts = *self;
+        ClientType::ALL
+            .into_iter()
+            .map(move |client| (client, counts.get(client)))
+            .filter(|(_, downloads)| *downloads > 0)
+    }
+}
+
+impl Debug for ClientCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(
+                self.iter()
+                    .map(|(client, downloads)| (client.as_str(), downloads)),
+            )
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -66,10 +121,21 @@ mod tests {
     use semver::Version;
 
     fn add(downloads: &mut DownloadsMap, name: &str, version: &str, date: &str) {
+        add_with_client(downloads, name, version, date, ClientType::Cargo);
+    }
+
+    fn add_with_client(
+        downloads: &mut DownloadsMap,
+        name: &str,
+        version: &str,
+        date: &str,
+        client: ClientType,
+    ) {
         downloads.add(
             name.to_string(),
             version.parse::<Version>().unwrap(),
             date.parse::<NaiveDate>().unwrap(),
+            client,
         );
     }
 
@@ -106,4 +172,52 @@ mod tests {
         }
         "###);
     }
+
+    #[test]
+    fn test_client_counts() {
+        let mut downloads = DownloadsMap::new();
+
+        add(&mut downloads, "xmas", "2.0.0", "2023-12-25");
+        add(&mut downloads, "xmas", "2.0.0", "2023-12-25");
+        add_with_client(
+            &mut downloads,
+            "xmas",
+            "2.0.0",
+            "2023-12-25",
+            ClientType::Ci,
+        );
+        add_with_client(
+            &mut downloads,
+            "xmas",
+            "1.0.0",
+            "2023-12-25",
+            ClientType::Browser,
+        );
+
+        let key = (
+            "xmas".to_string(),
+            Version::new(2, 0, 0),
+            "2023-12-25".parse().unwrap(),
+        );
+        let counts = downloads.get(&key).unwrap();
+        assert_eq!(counts.total(), 3);
+        assert_eq!(counts.get(ClientType::Cargo), 2);
+        assert_eq!(counts.get(ClientType::Ci), 1);
+        assert_eq!(counts.get(ClientType::Browser), 0);
+        assert_debug_snapshot!(counts, @r###"
+        {
+            "cargo": 2,
+            "ci": 1,
+        }
+        "###);
+
+        assert_eq!(downloads.sum_downloads(), 4);
+        assert_debug_snapshot!(downloads.sum_downloads_by_client(), @r###"
+        {
+            "cargo": 2,
+            "ci": 1,
+            "browser": 1,
+        }
+        "###);
+    }
 }
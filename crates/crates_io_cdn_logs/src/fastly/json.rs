@@ -37,6 +37,18 @@ impl LogLine<'_> {
             LogLine::V1(line) => line.status,
         }
     }
+
+    pub fn user_agent(&self) -> Option<&str> {
+        match self {
+            LogLine::V1(line) => line.user_agent.as_deref(),
+        }
+    }
+
+    pub fn ip(&self) -> Option<&str> {
+        match self {
+            LogLine::V1(line) => line.ip.as_deref(),
+        }
+    }
 }
 
 /// This struct corresponds to the `"version": "1"` variant of the [LogLine] enum.
@@ -45,13 +57,16 @@ impl LogLine<'_> {
 /// repository, there are a couple of differences:
 ///
 /// - The `bytes` field is not included, because we don't need it.
-/// - The `ip` field is not included, because we don't need it.
 /// - The `method` and `status` fields are not optional, because we handle
 ///   parsing errors gracefully.
 /// - The `date_time` field is using `chrono` like the rest of the
 ///   crates.io codebase.
 /// - The `method` and `url` fields are using `Cow` to avoid
 ///   unnecessary allocations.
+/// - The `user_agent` field is optional, because older log lines don't
+///   include it.
+/// - The `ip` field is optional, because it is only used for filtering
+///   repeated downloads.
 #[derive(Debug, Deserialize)]
 pub struct LogLineV1<'a> {
     pub date_time: DateTime<Utc>,
@@ -60,6 +75,10 @@ pub struct LogLineV1<'a> {
     #[serde(borrow)]
     pub url: Cow<'a, str>,
     pub status: u16,
+    #[serde(borrow, default)]
+    pub user_agent: Option<Cow<'a, str>>,
+    #[serde(borrow, default)]
+    pub ip: Option<Cow<'a, str>>,
 }
 
 #[cfg(test)]
@@ -79,6 +98,10 @@ mod tests {
                 method: "GET",
                 url: "https://static.staging.crates.io/?1705420437",
                 status: 403,
+                user_agent: None,
+                ip: Some(
+                    "45.79.107.220",
+                ),
             },
         )
         "###);
@@ -90,6 +113,8 @@ mod tests {
         assert_eq!(output.method(), "GET");
         assert_eq!(output.url(), "https://static.staging.crates.io/?1705420437");
         assert_eq!(output.status(), 403);
+        assert_eq!(output.user_agent(), None);
+        assert_eq!(output.ip(), Some("45.79.107.220"));
 
         match output {
             LogLine::V1(l) => {
@@ -4,14 +4,18 @@
 
 mod json;
 
+use crate::filter::Request;
 use crate::paths::parse_path;
-use crate::DownloadsMap;
+use crate::{ClientType, DownloadFilter, DownloadsMap};
 use std::borrow::Cow;
 use tokio::io::{AsyncBufRead, AsyncBufReadExt};
 use tracing::{debug_span, instrument, warn};
 
 #[instrument(level = "debug", skip(reader))]
-pub async fn count_downloads(reader: impl AsyncBufRead + Unpin) -> anyhow::Result<DownloadsMap> {
+pub async fn count_downloads(
+    reader: impl AsyncBufRead + Unpin,
+    filter: &mut DownloadFilter,
+) -> anyhow::Result<DownloadsMap> {
     let mut downloads = DownloadsMap::new();
 
     let mut lines = reader.lines();
@@ -52,9 +56,21 @@ pub async fn count_downloads(reader: impl AsyncBufRead + Unpin) -> anyhow::Resul
             continue;
         };
 
-        let date = json.date_time().date_naive();
+        let date_time = json.date_time();
+        let client = ClientType::from_user_agent(json.user_agent());
 
-        downloads.add(name, version, date);
+        let request = Request {
+            ip: json.ip(),
+            time: Some(date_time),
+            user_agent: json.user_agent(),
+        };
+
+        if !filter.accept(&name, &version, &request) {
+            continue;
+        }
+
+        let date = date_time.date_naive();
+        downloads.add(name, version, date, client);
     }
 
     Ok(downloads)
@@ -94,7 +110,8 @@ mod tests {
         let _guard = enable_tracing_output();
 
         let mut cursor = Cursor::new(include_bytes!("../../test_data/fastly/basic.log"));
-        let downloads = assert_ok!(count_downloads(&mut cursor).await);
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
 
         assert_debug_snapshot!(downloads, @r###"
         DownloadsMap {
@@ -131,7 +148,8 @@ mod tests {
         let mut cursor = Cursor::new(include_bytes!(
             "../../test_data/fastly/percent-encoding.log"
         ));
-        let downloads = assert_ok!(count_downloads(&mut cursor).await);
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
 
         assert_debug_snapshot!(downloads, @r###"
         DownloadsMap {
@@ -140,6 +158,26 @@ mod tests {
         "###);
     }
 
+    #[tokio::test]
+    async fn test_user_agents() {
+        let _guard = enable_tracing_output();
+
+        let mut cursor = Cursor::new(include_bytes!("../../test_data/fastly/user-agents.log"));
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
+
+        assert_eq!(downloads.sum_downloads(), 5);
+        assert_debug_snapshot!(downloads.sum_downloads_by_client(), @r###"
+        {
+            "cargo": 1,
+            "ci": 1,
+            "browser": 1,
+            "other": 1,
+            "unknown": 1,
+        }
+        "###);
+    }
+
     #[tokio::test]
     async fn test_unrelated_traffic() {
         let _guard = enable_tracing_output();
@@ -147,7 +185,8 @@ mod tests {
         let mut cursor = Cursor::new(include_bytes!(
             "../../test_data/fastly/unrelated-traffic.log"
         ));
-        let downloads = assert_ok!(count_downloads(&mut cursor).await);
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
 
         assert_debug_snapshot!(downloads, @r###"
         DownloadsMap {
@@ -163,7 +202,8 @@ mod tests {
         let mut cursor = Cursor::new(include_bytes!(
             "../../test_data/fastly/recoverable-errors.log"
         ));
-        let downloads = assert_ok!(count_downloads(&mut cursor).await);
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
 
         assert_debug_snapshot!(downloads, @r###"
         DownloadsMap {
@@ -0,0 +1,248 @@
+//! # Download filtering
+//!
+//! Not every successful request for a crate file is a genuine download. This
+//! module implements a filtering stage that the log parsers run every
+//! potential download through before it is counted.
+//!
+//! The following requests can be discarded, depending on the [FilterConfig]:
+//!
+//! - Repeated downloads of the same crate version from the same client IP
+//!   address within a configurable time window.
+//! - Requests from known scrapers, identified by their `User-Agent` header.
+//!
+//! Note that `HEAD` and range requests are never counted by the log parsers
+//! in the first place, since only responses with a `200 OK` status are
+//! counted. `HEAD` requests don't transfer the crate file, and range requests
+//! are answered with a `206 Partial Content` status.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use semver::Version;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, instrument};
+
+/// The rules of a [DownloadFilter].
+///
+/// The default configuration does not discard any downloads.
+#[derive(Debug, Clone, Default)]
+pub struct FilterConfig {
+    /// Repeated downloads of the same crate version from the same client IP
+    /// address are only counted once within this time window.
+    pub dedup_window: Option<Duration>,
+    /// Case-insensitive substrings of `User-Agent` headers that identify
+    /// known scrapers.
+    pub blocked_user_agents: Vec<String>,
+}
+
+/// The number of discarded downloads, by the reason they were discarded for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiscardedCounts {
+    /// Repeated downloads within the deduplication window.
+    pub duplicate: u64,
+    /// Downloads from blocked `User-Agent` headers.
+    pub blocked_user_agent: u64,
+}
+
+impl DiscardedCounts {
+    pub fn total(&self) -> u64 {
+        self.duplicate + self.blocked_user_agent
+    }
+}
+
+/// The information about a single potential download that is relevant for
+/// the [DownloadFilter].
+#[derive(Debug)]
+pub(crate) struct Request<'a> {
+    /// The IP address of the client, if the log format includes it.
+    pub ip: Option<&'a str>,
+    /// The time of the request, if the log format includes it with more
+    /// than daily precision.
+    pub time: Option<DateTime<Utc>>,
+    /// The (decoded) `User-Agent` header of the request.
+    pub user_agent: Option<&'a str>,
+}
+
+/// A stateful filter that decides which requests are counted as downloads
+/// and keeps track of the number of discarded requests.
+///
+/// A new filter should be used for each log file.
+#[derive(Debug, Default)]
+pub struct DownloadFilter {
+    dedup_window: Option<TimeDelta>,
+    /// The lowercase versions of [FilterConfig::blocked_user_agents].
+    blocked_user_agents: Vec<String>,
+    /// The time of the last counted download for each client IP address and
+    /// crate version.
+    last_counted: HashMap<(String, String, Version), DateTime<Utc>>,
+    discarded: DiscardedCounts,
+}
+
+impl DownloadFilter {
+    pub fn new(config: &FilterConfig) -> Self {
+        let blocked_user_agents = config
+            .blocked_user_agents
+            .iter()
+            .map(|user_agent| user_agent.trim().to_ascii_lowercase())
+            .filter(|user_agent| !user_agent.is_empty())
+            .collect();
+
+        Self {
+            dedup_window: config
+                .dedup_window
+                .and_then(|d| TimeDelta::from_std(d).ok()),
+            blocked_user_agents,
+            last_counted: HashMap::new(),
+            discarded: DiscardedCounts::default(),
+        }
+    }
+
+    /// Returns the number of requests that were discarded so far.
+    pub fn discarded(&self) -> DiscardedCounts {
+        self.discarded
+    }
+
+    /// Returns `true` if the request should be counted as a download of the
+    /// given crate version, or `false` if it was discarded.
+    #[instrument(level = "debug", skip(self, request))]
+    pub(crate) fn accept(&mut self, name: &str, version: &Version, request: &Request<'_>) -> bool {
+        if self.is_blocked_user_agent(request.user_agent) {
+            debug!(user_agent = ?request.user_agent, "Discarding download from blocked user agent");
+            self.discarded.blocked_user_agent += 1;
+            return false;
+        }
+
+        if self.is_duplicate(name, version, request) {
+            debug!(ip = ?request.ip, "Discarding repeated download");
+            self.discarded.duplicate += 1;
+            return false;
+        }
+
+        true
+    }
+
+    fn is_blocked_user_agent(&self, user_agent: Option<&str>) -> bool {
+        if self.blocked_user_agents.is_empty() {
+            return false;
+        }
+
+        let Some(user_agent) = user_agent else {
+            return false;
+        };
+
+        let lowercase = user_agent.to_ascii_lowercase();
+        self.blocked_user_agents
+            .iter()
+            .any(|blocked| lowercase.contains(blocked))
+    }
+
+    /// Checks if the same client IP address already downloaded the same
+    /// crate version within the deduplication window, and otherwise records
+    /// the request as the last counted download.
+    fn is_duplicate(&mut self, name: &str, version: &Version, request: &Request<'_>) -> bool {
+        let Some(window) = self.dedup_window else {
+            return false;
+        };
+
+        let (Some(ip), Some(time)) = (request.ip, request.time) else {
+            return false;
+        };
+
+        let key = (ip.to_string(), name.to_string(), version.clone());
+        if let Some(last_counted) = self.last_counted.get(&key) {
+            // Log lines are not guaranteed to be sorted by time, so we
+            // compare the absolute difference.
+            if (time - *last_counted).abs() < window {
+                return true;
+            }
+        }
+
+        self.last_counted.insert(key, time);
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn request<'a>(ip: &'a str, seconds: u32, user_agent: &'a str) -> Request<'a> {
+        let time = Utc.with_ymd_and_hms(2024, 1, 16, 12, 0, seconds).unwrap();
+        Request {
+            ip: Some(ip),
+            time: Some(time),
+            user_agent: Some(user_agent),
+        }
+    }
+
+    #[test]
+    fn test_default_config() {
+        let mut filter = DownloadFilter::default();
+        let version = Version::new(1, 0, 0);
+
+        let request = request("1.2.3.4", 0, "Scrapy/2.11.0");
+        assert!(filter.accept("foo", &version, &request));
+        assert!(filter.accept("foo", &version, &request));
+
+        assert_eq!(filter.discarded(), DiscardedCounts::default());
+    }
+
+    #[test]
+    fn test_dedup_window() {
+        let config = FilterConfig {
+            dedup_window: Some(Duration::from_secs(10)),
+            ..Default::default()
+        };
+        let mut filter = DownloadFilter::new(&config);
+        let v1 = Version::new(1, 0, 0);
+        let v2 = Version::new(2, 0, 0);
+        let cargo = "cargo 1.74.0 (ecb9851af 2023-10-18)";
+
+        assert!(filter.accept("foo", &v1, &request("1.2.3.4", 0, cargo)));
+        assert!(!filter.accept("foo", &v1, &request("1.2.3.4", 5, cargo)));
+        assert!(filter.accept("foo", &v1, &request("1.2.3.4", 15, cargo)));
+
+        // different IP address, crate or version
+        assert!(filter.accept("foo", &v1, &request("5.6.7.8", 16, cargo)));
+        assert!(filter.accept("bar", &v1, &request("1.2.3.4", 16, cargo)));
+        assert!(filter.accept("foo", &v2, &request("1.2.3.4", 16, cargo)));
+
+        // requests without IP address or time are never deduplicated
+        let request = Request {
+            ip: None,
+            ..request("1.2.3.4", 17, cargo)
+        };
+        assert!(filter.accept("foo", &v1, &request));
+        assert!(filter.accept("foo", &v1, &request));
+
+        assert_eq!(filter.discarded().duplicate, 1);
+        assert_eq!(filter.discarded().total(), 1);
+    }
+
+    #[test]
+    fn test_blocked_user_agents() {
+        let config = FilterConfig {
+            blocked_user_agents: vec!["scrapy".into(), " ".into(), "Googlebot".into()],
+            ..Default::default()
+        };
+        let mut filter = DownloadFilter::new(&config);
+        let version = Version::new(1, 0, 0);
+
+        let ua = "Scrapy/2.11.0 (+https://scrapy.org)";
+        assert!(!filter.accept("foo", &version, &request("1.2.3.4", 0, ua)));
+        let ua = "Mozilla/5.0 (compatible; googlebot/2.1)";
+        assert!(!filter.accept("foo", &version, &request("1.2.3.4", 0, ua)));
+
+        let ua = "cargo 1.74.0 (ecb9851af 2023-10-18)";
+        assert!(filter.accept("foo", &version, &request("1.2.3.4", 0, ua)));
+
+        let request = Request {
+            user_agent: None,
+            ..request("1.2.3.4", 0, "")
+        };
+        assert!(filter.accept("foo", &version, &request));
+
+        assert_eq!(filter.discarded().blocked_user_agent, 2);
+        assert_eq!(filter.discarded().total(), 2);
+    }
+}
@@ -1,19 +1,28 @@
+pub mod caddy;
 pub mod cloudfront;
+pub mod combined;
 mod compression;
 mod download_map;
 pub mod fastly;
+mod filter;
 mod paths;
 #[cfg(test)]
 mod test_utils;
+mod user_agent;
 
 pub use crate::compression::Decompressor;
-pub use crate::download_map::DownloadsMap;
+pub use crate::download_map::{ClientCounts, DownloadsMap};
+pub use crate::filter::{DiscardedCounts, DownloadFilter, FilterConfig};
+pub use crate::user_agent::ClientType;
 use std::io::Cursor;
 use tokio::io::{AsyncBufRead, AsyncReadExt};
 use tracing::instrument;
 
 #[instrument(skip_all)]
-pub async fn count_downloads<R>(mut reader: R) -> anyhow::Result<DownloadsMap>
+pub async fn count_downloads<R>(
+    mut reader: R,
+    filter: &mut DownloadFilter,
+) -> anyhow::Result<DownloadsMap>
 where
     R: AsyncBufRead + Unpin,
 {
@@ -25,7 +34,7 @@ where
             // not support it, but we can use `Cursor` to prepend the `#` back
             // onto the reader.
             let reader = Cursor::new(b"#").chain(reader);
-            cloudfront::count_downloads(reader).await
+            cloudfront::count_downloads(reader, filter).await
         }
         // Fastly log lines start with a `<123>` field.
         b'<' => {
@@ -33,7 +42,25 @@ where
             // not support it, but we can use `Cursor` to prepend the `<` back
             // onto the reader.
             let reader = Cursor::new(b"<").chain(reader);
-            fastly::count_downloads(reader).await
+            fastly::count_downloads(reader, filter).await
+        }
+        // Caddy log lines are JSON objects.
+        b'{' => {
+            // We can't use `AsyncSeek` here because `async-compression` does
+            // not support it, but we can use `Cursor` to prepend the `{` back
+            // onto the reader.
+            let reader = Cursor::new(b"{").chain(reader);
+            caddy::count_downloads(reader, filter).await
+        }
+        // nginx/Apache log lines start with the IPv4 or IPv6 address of the
+        // client. IPv6 addresses may also start with a hex letter, like
+        // `fd00::1`.
+        byte @ (b'0'..=b'9' | b'a'..=b'f' | b'A'..=b'F' | b':') => {
+            // We can't use `AsyncSeek` here because `async-compression` does
+            // not support it, but we can use `Cursor` to prepend the byte back
+            // onto the reader.
+            let reader = Cursor::new([byte]).chain(reader);
+            combined::count_downloads(reader, filter).await
         }
         // Anything else is rejected.
         byte => {
@@ -56,7 +83,8 @@ mod tests {
         let _guard = enable_tracing_output();
 
         let mut cursor = Cursor::new(include_bytes!("../test_data/cloudfront/basic.log"));
-        let downloads = assert_ok!(count_downloads(&mut cursor).await);
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
 
         assert_debug_snapshot!(downloads, @r###"
         DownloadsMap {
@@ -92,7 +120,7 @@ mod tests {
         let decompressor = assert_ok!(Decompressor::from_extension(cursor, Some("gz")));
         let reader = tokio::io::BufReader::new(decompressor);
 
-        let downloads = assert_ok!(count_downloads(reader).await);
+        let downloads = assert_ok!(count_downloads(reader, &mut DownloadFilter::default()).await);
 
         assert_debug_snapshot!(downloads, @r###"
         DownloadsMap {
@@ -124,7 +152,8 @@ mod tests {
         let _guard = enable_tracing_output();
 
         let mut cursor = Cursor::new(include_bytes!("../test_data/fastly/basic.log"));
-        let downloads = assert_ok!(count_downloads(&mut cursor).await);
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
 
         assert_debug_snapshot!(downloads, @r###"
         DownloadsMap {
@@ -163,7 +192,7 @@ mod tests {
         let decompressor = assert_ok!(Decompressor::from_extension(cursor, Some("zst")));
         let reader = tokio::io::BufReader::new(decompressor);
 
-        let downloads = assert_ok!(count_downloads(reader).await);
+        let downloads = assert_ok!(count_downloads(reader, &mut DownloadFilter::default()).await);
 
         assert_debug_snapshot!(downloads, @r###"
         DownloadsMap {
@@ -193,12 +222,70 @@ mod tests {
         "###);
     }
 
+    #[tokio::test]
+    async fn test_combined() {
+        let _guard = enable_tracing_output();
+
+        let mut cursor = Cursor::new(include_bytes!("../test_data/combined/basic.log"));
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
+
+        assert_debug_snapshot!(downloads, @r###"
+        DownloadsMap {
+            2024-01-16  strsim@0.10.0 .. 1
+            2024-01-16  tikv-jemalloc-sys@0.5.2+5.3.0-patched .. 1
+            2024-01-16  tinyvec@1.6.0 .. 1
+            2024-01-16  winnow@0.5.4 .. 1
+            2024-01-17  anstyle@1.0.1 .. 1
+            2024-01-17  cc@1.0.73 .. 2
+            2024-01-17  libc@0.2.126 .. 1
+        }
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_combined_ipv6() {
+        let _guard = enable_tracing_output();
+
+        let mut cursor = Cursor::new(include_bytes!("../test_data/combined/ipv6.log"));
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
+
+        assert_debug_snapshot!(downloads, @r###"
+        DownloadsMap {
+            2024-01-16  strsim@0.10.0 .. 1
+            2024-01-16  tinyvec@1.6.0 .. 1
+            2024-01-16  winnow@0.5.4 .. 1
+        }
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_caddy() {
+        let _guard = enable_tracing_output();
+
+        let mut cursor = Cursor::new(include_bytes!("../test_data/caddy/basic.log"));
+        let downloads =
+            assert_ok!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
+
+        assert_debug_snapshot!(downloads, @r###"
+        DownloadsMap {
+            2024-01-16  strsim@0.10.0 .. 1
+            2024-01-16  tikv-jemalloc-sys@0.5.2+5.3.0-patched .. 1
+            2024-01-16  tinyvec@1.6.0 .. 1
+            2024-01-16  winnow@0.5.4 .. 1
+            2024-01-17  cc@1.0.73 .. 2
+            2024-01-17  libc@0.2.126 .. 1
+        }
+        "###);
+    }
+
     #[tokio::test]
     async fn test_unknown() {
         let _guard = enable_tracing_output();
 
-        let mut cursor = Cursor::new(b"foo");
-        let error = assert_err!(count_downloads(&mut cursor).await);
-        assert_snapshot!(error, @"Failed to determine log file format. Unrecognized first byte: 102.");
+        let mut cursor = Cursor::new(b"xyz");
+        let error = assert_err!(count_downloads(&mut cursor, &mut DownloadFilter::default()).await);
+        assert_snapshot!(error, @"Failed to determine log file format. Unrecognized first byte: 120.");
     }
 }
@@ -0,0 +1,115 @@
+use std::fmt::{Display, Formatter};
+use tracing::instrument;
+
+/// Substrings of `User-Agent` headers that identify requests from CI and
+/// build automation services.
+const CI_MARKERS: &[&str] = &[
+    "azure-pipelines",
+    "buildkite",
+    "circleci",
+    "github-actions",
+    "gitlab-runner",
+    "jenkins",
+    "teamcity",
+    "travis",
+];
+
+/// The type of client that downloaded a crate file, derived from the
+/// `User-Agent` header of the request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ClientType {
+    /// `cargo` itself, e.g. `cargo 1.74.0 (ecb9851af 2023-10-18)`.
+    Cargo,
+    /// CI and build automation services.
+    Ci,
+    /// Web browsers.
+    Browser,
+    /// Any other tool with a `User-Agent` header.
+    Other,
+    /// Requests without a `User-Agent` header, or log formats that don't
+    /// include it.
+    Unknown,
+}
+
+impl ClientType {
+    /// All client types, in the order of their discriminants.
+    pub const ALL: [ClientType; 5] = [
+        ClientType::Cargo,
+        ClientType::Ci,
+        ClientType::Browser,
+        ClientType::Other,
+        ClientType::Unknown,
+    ];
+
+    /// Classifies a (percent-decoded) `User-Agent` header value.
+    ///
+    /// Like the rest of the log parsing code, this avoids regular expressions
+    /// for performance reasons.
+    #[instrument(level = "debug")]
+    pub fn from_user_agent(user_agent: Option<&str>) -> Self {
+        let user_agent = match user_agent.map(str::trim) {
+            None | Some("") | Some("-") => return ClientType::Unknown,
+            Some(user_agent) => user_agent,
+        };
+
+        if user_agent.starts_with("cargo ") || user_agent.starts_with("cargo/") {
+            return ClientType::Cargo;
+        }
+
+        let lowercase = user_agent.to_ascii_lowercase();
+        if CI_MARKERS.iter().any(|marker| lowercase.contains(marker)) {
+            return ClientType::Ci;
+        }
+
+        if user_agent.starts_with("Mozilla/") {
+            return ClientType::Browser;
+        }
+
+        ClientType::Other
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClientType::Cargo => "cargo",
+            ClientType::Ci => "ci",
+            ClientType::Browser => "browser",
+            ClientType::Other => "other",
+            ClientType::Unknown => "unknown",
+        }
+    }
+}
+
+impl Display for ClientType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_user_agent() {
+        let from = |ua| ClientType::from_user_agent(ua);
+
+        assert_eq!(from(None), ClientType::Unknown);
+        assert_eq!(from(Some("")), ClientType::Unknown);
+        assert_eq!(from(Some("-")), ClientType::Unknown);
+
+        let ua = "cargo 1.74.0 (ecb9851af 2023-10-18)";
+        assert_eq!(from(Some(ua)), ClientType::Cargo);
+        assert_eq!(from(Some("cargo/1.60.0")), ClientType::Cargo);
+
+        let ua = "Buildkite-Agent/3.59.0 (linux; amd64)";
+        assert_eq!(from(Some(ua)), ClientType::Ci);
+        let ua = "GitHub-Actions-Cache/1.0";
+        assert_eq!(from(Some(ua)), ClientType::Ci);
+
+        let ua = "Mozilla/5.0 (X11; Linux x86_64; rv:121.0) Gecko/20100101 Firefox/121.0";
+        assert_eq!(from(Some(ua)), ClientType::Browser);
+
+        assert_eq!(from(Some("curl/8.4.0")), ClientType::Other);
+        assert_eq!(from(Some("python-requests/2.31.0")), ClientType::Other);
+    }
+}
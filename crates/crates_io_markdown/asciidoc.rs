@@ -0,0 +1,857 @@
+//! Render a subset of AsciiDoc to HTML.
+//!
+//! Supported are section titles, attribute entries and references,
+//! paragraphs, admonitions, nested bullet, numbered and check lists,
+//! listing, literal, quote, example, sidebar, open, passthrough and table
+//! blocks, block titles, images, horizontal rules and the usual inline
+//! formatting and macros. Comments and preprocessor directives are skipped,
+//! and other block attributes are ignored.
+
+use crate::markup::{self, Image};
+use crate::RenderOptions;
+use comrak::Anchorizer;
+use htmlescape::encode_minimal;
+use std::collections::HashMap;
+
+/// Renders AsciiDoc to unsanitized HTML.
+pub fn to_html(text: &str, options: &RenderOptions) -> String {
+    let lines = markup::lines(text);
+    let lines = lines.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let mut renderer = Renderer {
+        options: options.clone(),
+        attributes: HashMap::new(),
+        anchorizer: Anchorizer::new(),
+        html: String::new(),
+    };
+    renderer.blocks(&lines);
+    renderer.html
+}
+
+const ADMONITIONS: [&str; 5] = ["NOTE", "TIP", "IMPORTANT", "WARNING", "CAUTION"];
+
+/// Returns the level and the title of a section title like `== Title`.
+fn section_title(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '=').count();
+    let title = line[level..].strip_prefix(' ')?;
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+
+    // Titles may also be followed by the same number of `=` characters.
+    let title = title.trim_end_matches('=').trim();
+    (!title.is_empty()).then_some((level, title))
+}
+
+/// Whether the line opens or closes a delimited block like `----`.
+fn is_delimiter(line: &str) -> bool {
+    if line == "--" || line == "|===" {
+        return true;
+    }
+
+    let mut chars = line.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+
+    line.len() >= 4
+        && matches!(first, '-' | '.' | '_' | '=' | '*' | '+' | '/')
+        && chars.all(|c| c == first)
+}
+
+/// Whether the line is a preprocessor directive like `ifdef::env-github[]`.
+///
+/// Conditional content is always included, since attributes set by the
+/// environment are not known.
+fn is_preprocessor_directive(line: &str) -> bool {
+    [
+        "ifdef::",
+        "ifndef::",
+        "ifeval::",
+        "endif::",
+        "include::",
+        "toc::",
+    ]
+    .iter()
+    .any(|prefix| line.starts_with(prefix))
+        && line.ends_with(']')
+}
+
+/// Returns the name and the value of an attribute entry like `:name: value`.
+/// The value is `None` if the attribute is unset with `:name!:`.
+fn attribute_entry(line: &str) -> Option<(&str, Option<&str>)> {
+    let (name, value) = line.strip_prefix(':')?.split_once(':')?;
+    if value.starts_with(|c: char| !c.is_whitespace()) {
+        return None;
+    }
+
+    let unset = name.strip_prefix('!').or_else(|| name.strip_suffix('!'));
+    let is_valid = |name: &str| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-'))
+    };
+
+    match unset {
+        Some(name) => is_valid(name).then_some((name, None)),
+        None => is_valid(name).then_some((name, Some(value.trim()))),
+    }
+}
+
+/// Splits block or macro attributes like `source,rust` or
+/// `alt,link=https://example.com` into positional and named attributes.
+fn parse_attributes(attributes: &str) -> (Vec<&str>, HashMap<&str, &str>) {
+    let mut positional = Vec::new();
+    let mut named = HashMap::new();
+
+    for attribute in split_attributes(attributes) {
+        match attribute.split_once('=') {
+            Some((name, value)) if !name.trim().contains(' ') => {
+                named.insert(name.trim(), unquote(value));
+            }
+            _ => positional.push(unquote(attribute)),
+        }
+    }
+
+    (positional, named)
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim().trim_matches('"')
+}
+
+/// Splits attributes at commas that are not enclosed in double quotes.
+fn split_attributes(attributes: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+
+    for (index, c) in attributes.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                result.push(&attributes[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    result.push(&attributes[start..]);
+    result
+}
+
+fn image(src: &str, attributes: &str) -> Image {
+    let (positional, named) = parse_attributes(attributes);
+    let attribute = |name: &str, index: Option<usize>| {
+        named
+            .get(name)
+            .or_else(|| positional.get(index?))
+            .filter(|value| !value.is_empty())
+            .map(|value| value.to_string())
+    };
+
+    Image {
+        src: src.to_string(),
+        alt: attribute("alt", Some(0)),
+        width: attribute("width", Some(1)),
+        height: attribute("height", Some(2)),
+        target: attribute("link", None),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ListKind {
+    Unordered,
+    Ordered,
+}
+
+impl ListKind {
+    fn tag(self) -> &'static str {
+        match self {
+            ListKind::Unordered => "ul",
+            ListKind::Ordered => "ol",
+        }
+    }
+}
+
+struct ListItem {
+    kind: ListKind,
+    depth: usize,
+    text: String,
+}
+
+/// Returns the list item that the line starts, with the depth of the item
+/// determined by the number of marker characters.
+fn list_item(line: &str) -> Option<ListItem> {
+    let line = line.trim_start();
+
+    let (kind, depth, marker_len) = match line.chars().next()? {
+        c @ ('*' | '.') => {
+            let depth = line.chars().take_while(|d| *d == c).count();
+            let kind = match c {
+                '*' => ListKind::Unordered,
+                _ => ListKind::Ordered,
+            };
+            (kind, depth, depth)
+        }
+        '-' => (ListKind::Unordered, 1, 1),
+        _ => {
+            let digits = line.chars().take_while(char::is_ascii_digit).count();
+            if digits == 0 || !line[digits..].starts_with('.') {
+                return None;
+            }
+            (ListKind::Ordered, 1, digits + 1)
+        }
+    };
+
+    let text = line[marker_len..].strip_prefix(' ')?.trim();
+    (depth <= 5 && !text.is_empty()).then(|| ListItem {
+        kind,
+        depth,
+        text: text.to_string(),
+    })
+}
+
+struct Renderer {
+    options: RenderOptions,
+    attributes: HashMap<String, String>,
+    anchorizer: Anchorizer,
+    html: String,
+}
+
+impl Renderer {
+    fn blocks(&mut self, lines: &[&str]) {
+        let mut block_attributes = None;
+        let mut i = 0;
+
+        while i < lines.len() {
+            let line = lines[i];
+
+            if line.is_empty() || is_preprocessor_directive(line) {
+                i += 1;
+            } else if line == "////" {
+                let len = lines[i + 1..].iter().position(|l| *l == line);
+                i += len.map_or(lines.len(), |len| len + 2);
+            } else if line.starts_with("//") {
+                i += 1;
+            } else if let Some((name, value)) = attribute_entry(line) {
+                match value {
+                    Some(value) => self.attributes.insert(name.into(), value.into()),
+                    None => self.attributes.remove(name),
+                };
+                i += 1;
+            } else if line.starts_with("[[") && line.ends_with("]]") {
+                i += 1;
+            } else if line.starts_with('[') && line.ends_with(']') {
+                block_attributes = Some(&line[1..line.len() - 1]);
+                i += 1;
+            } else if line.starts_with('.') && line[1..].starts_with(|c: char| c.is_alphanumeric())
+            {
+                let inner = self.inline(&line[1..]);
+                self.html
+                    .push_str(&format!("<p><strong>{inner}</strong></p>\n"));
+                i += 1;
+            } else {
+                i += self.block(&lines[i..], block_attributes.take().unwrap_or_default());
+            }
+        }
+    }
+
+    /// Renders the blocks into a separate string instead of the output.
+    fn render(&mut self, lines: &[&str]) -> String {
+        let html = std::mem::take(&mut self.html);
+        self.blocks(lines);
+        std::mem::replace(&mut self.html, html)
+    }
+
+    /// Renders the block at the start of `lines`, and returns the number of
+    /// lines it consists of.
+    fn block(&mut self, lines: &[&str], attributes: &str) -> usize {
+        let line = lines[0];
+        let (positional, named) = parse_attributes(attributes);
+        let style = positional.first().copied().unwrap_or_default();
+
+        if let Some((level, title)) = section_title(line) {
+            let title = self.substitute_attributes(title);
+            let inner = self.inline(&title);
+            markup::heading(&mut self.html, &mut self.anchorizer, level, &title, &inner);
+            return 1;
+        }
+
+        if line == "'''" {
+            self.html.push_str("<hr>\n");
+            return 1;
+        }
+
+        if line == "<<<" {
+            return 1;
+        }
+
+        if let Some(language) = line.strip_prefix("```") {
+            let len = lines[1..].iter().position(|l| *l == "```");
+            let content = &lines[1..len.map_or(lines.len(), |len| len + 1)];
+            markup::code_block(
+                &mut self.html,
+                &self.options,
+                Some(language.trim()),
+                &content.join("\n"),
+            );
+            return len.map_or(lines.len(), |len| len + 2);
+        }
+
+        if is_delimiter(line) {
+            let len = lines[1..].iter().position(|l| *l == line);
+            let content = &lines[1..len.map_or(lines.len(), |len| len + 1)];
+            self.delimited_block(line, content, &positional, &named);
+            return len.map_or(lines.len(), |len| len + 2);
+        }
+
+        if let Some(rest) = line.strip_prefix("image::") {
+            if let Some((src, attributes)) = rest.strip_suffix(']').and_then(|r| r.split_once('['))
+            {
+                let src = self.substitute_attributes(src);
+                let image = image(&src, &self.substitute_attributes(attributes));
+                self.html.push_str(&format!("<p>{}</p>\n", image.to_html()));
+                return 1;
+            }
+        }
+
+        if list_item(line).is_some() {
+            return self.list(lines);
+        }
+
+        if line.starts_with(' ') {
+            let len = lines
+                .iter()
+                .position(|l| l.is_empty())
+                .unwrap_or(lines.len());
+            let block = markup::dedent_all(&lines[..len]);
+            markup::code_block(&mut self.html, &self.options, None, &block.join("\n"));
+            return len;
+        }
+
+        let len = lines
+            .iter()
+            .position(|l| l.is_empty() || is_delimiter(l))
+            .unwrap_or(lines.len());
+        let paragraph = lines[..len]
+            .iter()
+            .filter(|l| !l.starts_with("//"))
+            .copied()
+            .collect::<Vec<_>>();
+
+        let label = ADMONITIONS.iter().find_map(|label| {
+            let text = paragraph[0].strip_prefix(label)?.strip_prefix(": ")?;
+            Some((*label, text))
+        });
+
+        if let Some((label, text)) = label {
+            let mut lines = vec![text];
+            lines.extend(&paragraph[1..]);
+            let inner = self.paragraph(&lines);
+            markup::admonition(&mut self.html, &title_case(label), &inner);
+        } else if ADMONITIONS.contains(&style) {
+            let inner = self.paragraph(&paragraph);
+            markup::admonition(&mut self.html, &title_case(style), &inner);
+        } else {
+            let inner = self.paragraph(&paragraph);
+            self.html.push_str(&inner);
+        }
+
+        len
+    }
+
+    fn delimited_block(
+        &mut self,
+        delimiter: &str,
+        content: &[&str],
+        positional: &[&str],
+        named: &HashMap<&str, &str>,
+    ) {
+        let style = positional.first().copied().unwrap_or_default();
+
+        match delimiter.chars().next() {
+            Some('-') if delimiter != "--" => {
+                let language = match style {
+                    "source" | "" => positional.get(1).copied(),
+                    _ => None,
+                };
+                markup::code_block(&mut self.html, &self.options, language, &content.join("\n"));
+            }
+            Some('.') => {
+                markup::code_block(&mut self.html, &self.options, None, &content.join("\n"))
+            }
+            Some('+') => {
+                self.html.push_str(&content.join("\n"));
+                self.html.push('\n');
+            }
+            Some('|') => self.table(content, positional, named),
+            Some('_') => {
+                let inner = self.render(content);
+                self.html.push_str("<blockquote>\n");
+                self.html.push_str(&inner);
+                self.html.push_str("</blockquote>\n");
+            }
+            _ => {
+                let inner = self.render(content);
+                if ADMONITIONS.contains(&style) {
+                    markup::admonition(&mut self.html, &title_case(style), &inner);
+                } else {
+                    self.html.push_str(&inner);
+                }
+            }
+        }
+    }
+
+    /// Renders a table, where each cell starts with a `|` character. The
+    /// first row is the header row if it is followed by an empty line, or if
+    /// the table has the `header` option.
+    fn table(&mut self, content: &[&str], positional: &[&str], named: &HashMap<&str, &str>) {
+        let content = markup::trim_blank_lines(content);
+
+        let mut cells: Vec<String> = Vec::new();
+        let mut first_row_len = None;
+        for line in content {
+            if let Some(line) = line.strip_prefix('|') {
+                let row = line.split('|').map(|cell| cell.trim().to_string());
+                let len = cells.len();
+                cells.extend(row);
+                first_row_len.get_or_insert(cells.len() - len);
+            } else if line.is_empty() {
+                continue;
+            } else if let Some(cell) = cells.last_mut() {
+                cell.push('\n');
+                cell.push_str(line.trim());
+            }
+        }
+
+        let columns = named
+            .get("cols")
+            .map(|cols| match cols.split_once('*') {
+                Some((count, _)) => count.trim().parse().unwrap_or(1),
+                None => cols.split(',').count(),
+            })
+            .or(first_row_len)
+            .unwrap_or(1)
+            .max(1);
+
+        let has_header = named.get("options").is_some_and(|o| o.contains("header"))
+            || positional.iter().any(|a| a.contains("%header"))
+            || (content.len() > 1 && content[0].starts_with('|') && content[1].is_empty());
+
+        let mut rows = cells.chunks(columns);
+
+        self.html.push_str("<table>\n");
+        if has_header {
+            if let Some(row) = rows.next() {
+                let row = self.table_row(row, "th");
+                self.html.push_str(&format!("<thead>\n{row}</thead>\n"));
+            }
+        }
+
+        let body = rows
+            .map(|row| self.table_row(row, "td"))
+            .collect::<String>();
+        if !body.is_empty() {
+            self.html.push_str(&format!("<tbody>\n{body}</tbody>\n"));
+        }
+        self.html.push_str("</table>\n");
+    }
+
+    fn table_row(&self, row: &[String], tag: &str) -> String {
+        let mut html = String::from("<tr>");
+        for cell in row {
+            let inner = self.inline(&self.substitute_attributes(cell));
+            html.push_str(&format!("<{tag}>{inner}</{tag}>"));
+        }
+        html.push_str("</tr>\n");
+        html
+    }
+
+    fn list(&mut self, lines: &[&str]) -> usize {
+        let mut items: Vec<ListItem> = Vec::new();
+
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            if let Some(item) = list_item(line) {
+                items.push(item);
+                i += 1;
+            } else if line.is_empty() {
+                // List items may be separated by empty lines.
+                let blank_lines = lines[i..].iter().take_while(|l| l.is_empty()).count();
+                match lines.get(i + blank_lines) {
+                    Some(next) if list_item(next).is_some() => i += blank_lines,
+                    _ => break,
+                }
+            } else if is_delimiter(line) {
+                break;
+            } else if line == "+" || line.starts_with("//") {
+                i += 1;
+            } else {
+                if let Some(item) = items.last_mut() {
+                    item.text.push('\n');
+                    item.text.push_str(line.trim());
+                }
+                i += 1;
+            }
+        }
+
+        let mut open: Vec<(ListKind, usize)> = Vec::new();
+        for item in &items {
+            while let Some(&(kind, depth)) = open.last() {
+                if depth < item.depth || (depth == item.depth && kind == item.kind) {
+                    break;
+                }
+                self.html.push_str(&format!("</li>\n</{}>\n", kind.tag()));
+                open.pop();
+            }
+
+            if open.last() == Some(&(item.kind, item.depth)) {
+                self.html.push_str("</li>\n");
+            } else {
+                if !open.is_empty() {
+                    self.html.push('\n');
+                }
+                self.html.push_str(&format!("<{}>\n", item.kind.tag()));
+                open.push((item.kind, item.depth));
+            }
+
+            self.html.push_str("<li>");
+            let text = match checkbox(&item.text) {
+                Some((checked, text)) => {
+                    let checked = if checked { " checked" } else { "" };
+                    let checkbox = format!("<input type=\"checkbox\" disabled{checked}> ");
+                    self.html.push_str(&checkbox);
+                    text
+                }
+                None => &item.text,
+            };
+            let inner = self.inline(&self.substitute_attributes(text));
+            self.html.push_str(&inner);
+        }
+
+        while let Some((kind, _)) = open.pop() {
+            self.html.push_str(&format!("</li>\n</{}>\n", kind.tag()));
+        }
+
+        i
+    }
+
+    /// Renders a paragraph, with lines ending in ` +` as hard line breaks.
+    fn paragraph(&self, lines: &[&str]) -> String {
+        let text = self.substitute_attributes(&lines.join("\n"));
+        let inner = text
+            .split(" +\n")
+            .map(|text| self.inline(text.strip_suffix(" +").unwrap_or(text)))
+            .collect::<Vec<_>>()
+            .join("<br>\n");
+
+        format!("<p>{inner}</p>\n")
+    }
+
+    /// Replaces attribute references like `{name}` with the values of the
+    /// attributes. References to unknown attributes are kept as they are.
+    fn substitute_attributes(&self, text: &str) -> String {
+        let mut result = String::new();
+        let mut rest = text;
+
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let value = rest[1..]
+                .split_once('}')
+                .and_then(|(name, _)| Some((name, self.attributes.get(name)?)));
+
+            match value {
+                Some((name, value)) if !result.ends_with('\\') => {
+                    result.push_str(value);
+                    rest = &rest[name.len() + 2..];
+                }
+                _ => {
+                    result.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+
+        result.push_str(rest);
+        result
+    }
+
+    fn inline(&self, text: &str) -> String {
+        let mut html = String::new();
+        let mut rest = text;
+        let mut prev = None;
+
+        while let Some(c) = rest.chars().next() {
+            if c == '\\' && rest[1..].starts_with(['*', '_', '`', '<', '{']) {
+                html.push_str(&encode_minimal(&rest[1..2]));
+                prev = rest[1..2].chars().next();
+                rest = &rest[2..];
+                continue;
+            }
+
+            if let Some((markup, len)) = self.inline_markup(rest, markup::can_start_markup(prev)) {
+                html.push_str(&markup);
+                prev = rest[..len].chars().next_back();
+                rest = &rest[len..];
+                continue;
+            }
+
+            html.push_str(&encode_minimal(c.encode_utf8(&mut [0; 4])));
+            prev = Some(c);
+            rest = &rest[c.len_utf8()..];
+        }
+
+        html
+    }
+
+    /// Renders the inline markup at the start of the text, and returns the
+    /// HTML and the length of the markup. Constrained markup like `*bold*`
+    /// is only recognized at word boundaries.
+    fn inline_markup(&self, text: &str, constrained: bool) -> Option<(String, usize)> {
+        if let Some((inner, len)) = unconstrained(text, "``") {
+            return Some((format!("<code>{}</code>", encode_minimal(inner)), len));
+        }
+
+        if let Some((inner, len)) = unconstrained(text, "**") {
+            return Some((format!("<strong>{}</strong>", self.inline(inner)), len));
+        }
+
+        if let Some((inner, len)) = unconstrained(text, "__") {
+            return Some((format!("<em>{}</em>", self.inline(inner)), len));
+        }
+
+        if let Some(rest) = text.strip_prefix("<<") {
+            let end = rest.find(">>")?;
+            let (id, label) = rest[..end]
+                .split_once(',')
+                .unwrap_or((&rest[..end], &rest[..end]));
+            let html = markup::link(&format!("#{}", id.trim()), &encode_minimal(label.trim()));
+            return Some((html, end + 4));
+        }
+
+        if !constrained {
+            return None;
+        }
+
+        if let Some((inner, len)) = delimited(text, "`+", "+`") {
+            return Some((format!("<code>{}</code>", encode_minimal(inner)), len));
+        }
+
+        if let Some((inner, len)) = delimited(text, "`", "`") {
+            return Some((format!("<code>{}</code>", encode_minimal(inner)), len));
+        }
+
+        if let Some((inner, len)) = delimited(text, "*", "*") {
+            return Some((format!("<strong>{}</strong>", self.inline(inner)), len));
+        }
+
+        if let Some((inner, len)) = delimited(text, "_", "_") {
+            return Some((format!("<em>{}</em>", self.inline(inner)), len));
+        }
+
+        self.inline_macro(text)
+    }
+
+    /// Renders inline macros like `link:target[text]`, `image:src[alt]` and
+    /// URLs with an optional `[text]`.
+    fn inline_macro(&self, text: &str) -> Option<(String, usize)> {
+        if let Some((src, attributes, len)) = inline_macro(text, "image:") {
+            return Some((image(src, attributes).to_html(), len));
+        }
+
+        let link = inline_macro(text, "link:")
+            .map(|(target, attributes, len)| (target.to_string(), attributes, len))
+            .or_else(|| {
+                let (id, attributes, len) = inline_macro(text, "xref:")?;
+                Some((format!("#{id}"), attributes, len))
+            });
+
+        if let Some((target, attributes, len)) = link {
+            let label = link_label(attributes).unwrap_or(&target);
+            return Some((markup::link(&target, &self.inline(label)), len));
+        }
+
+        if let Some((address, label, len)) = inline_macro(text, "mailto:") {
+            let label = link_label(label).unwrap_or(address);
+            let html = markup::link(&format!("mailto:{address}"), &self.inline(label));
+            return Some((html, len));
+        }
+
+        let url_len = markup::url_len(text)?;
+        let url = &text[..url_len];
+        if let Some(attributes) = text[url_len..].strip_prefix('[') {
+            if let Some(end) = attributes.find(']') {
+                let label = link_label(&attributes[..end]).unwrap_or(url);
+                let html = markup::link(url, &self.inline(label));
+                return Some((html, url_len + end + 2));
+            }
+        }
+
+        Some((markup::link(url, &encode_minimal(url)), url_len))
+    }
+}
+
+/// Returns the target, the attributes and the total length of an inline
+/// macro like `name:target[attributes]`.
+fn inline_macro<'t>(text: &'t str, name: &str) -> Option<(&'t str, &'t str, usize)> {
+    let rest = text.strip_prefix(name)?;
+    let target_len = rest.find('[')?;
+    let target = &rest[..target_len];
+    if target.is_empty() || target.contains(char::is_whitespace) {
+        return None;
+    }
+
+    let attributes_len = rest[target_len + 1..].find(']')?;
+    let attributes = &rest[target_len + 1..target_len + 1 + attributes_len];
+    Some((
+        target,
+        attributes,
+        name.len() + target_len + attributes_len + 2,
+    ))
+}
+
+/// Returns the text of a link from the link macro attributes, or `None` if
+/// the text is empty. A trailing `^`, which opens the link in a new window,
+/// is removed.
+fn link_label(attributes: &str) -> Option<&str> {
+    let label = match attributes.split_once(",window=") {
+        Some((label, _)) => label,
+        None => attributes,
+    };
+    let label = label.trim().trim_matches('"').trim_end_matches('^');
+    (!label.is_empty()).then_some(label)
+}
+
+/// Returns whether the checkbox of a checklist item like `[x] done` is
+/// checked, and the remaining text of the item.
+fn checkbox(text: &str) -> Option<(bool, &str)> {
+    let (checkbox, text) = text.split_at_checked(4)?;
+    match checkbox {
+        "[x] " | "[*] " => Some((true, text)),
+        "[ ] " => Some((false, text)),
+        _ => None,
+    }
+}
+
+/// Returns the inner text and the total length of constrained markup at the
+/// start of the text, if it starts with `open` and has a matching `close`.
+fn delimited<'t>(text: &'t str, open: &str, close: &str) -> Option<(&'t str, usize)> {
+    let inner = text.strip_prefix(open)?;
+    let end = markup::find_closing(inner, close)?;
+    Some((&inner[..end], open.len() + end + close.len()))
+}
+
+/// Returns the inner text and the total length of unconstrained markup like
+/// `**bold**`, which may also be used within words.
+fn unconstrained<'t>(text: &'t str, delimiter: &str) -> Option<(&'t str, usize)> {
+    let inner = text.strip_prefix(delimiter)?;
+    let end = inner.find(delimiter).filter(|end| *end > 0)?;
+    Some((&inner[..end], 2 * delimiter.len() + end))
+}
+
+fn title_case(label: &str) -> String {
+    let mut chars = label.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::asciidoc_to_html;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn section_titles_and_attributes() {
+        let text = "= My Crate\n:toc:\n:url-docs: https://docs.rs/my-crate\n\n// a comment\n\n== Usage ==\n\nSee the {url-docs}[documentation] and {unknown}.\n\n=== Details\n\n'''\n";
+        assert_snapshot!(asciidoc_to_html(text, None, "", &Default::default()), @r###"
+        <h1><a href="#my-crate" id="user-content-my-crate" rel="nofollow noopener noreferrer"></a>My Crate</h1>
+        <h2><a href="#usage" id="user-content-usage" rel="nofollow noopener noreferrer"></a>Usage</h2>
+        <p>See the <a href="https://docs.rs/my-crate" rel="nofollow noopener noreferrer">documentation</a> and {unknown}.</p>
+        <h3><a href="#details" id="user-content-details" rel="nofollow noopener noreferrer"></a>Details</h3>
+        <hr>
+        "###);
+    }
+
+    #[test]
+    fn inline_markup() {
+        let text = "Some _emphasis_, *strong*, **un**constrained and `mono` text, a snake_case_word, +\na link:CONTRIBUTING.adoc[guide], a https://crates.io[link^], <<usage,cross reference>> and https://example.com.\n";
+        assert_snapshot!(asciidoc_to_html(text, Some("https://github.com/rust-lang/test"), "", &Default::default()), @r###"
+        <p>Some <em>emphasis</em>, <strong>strong</strong>, <strong>un</strong>constrained and <code>mono</code> text, a snake_case_word,<br>
+        a <a href="https://github.com/rust-lang/test/blob/HEAD/CONTRIBUTING.adoc" rel="nofollow noopener noreferrer">guide</a>, a <a href="https://crates.io" rel="nofollow noopener noreferrer">link</a>, <a href="#usage" rel="nofollow noopener noreferrer">cross reference</a> and <a href="https://example.com" rel="nofollow noopener noreferrer">https://example.com</a>.</p>
+        "###);
+    }
+
+    #[test]
+    fn lists() {
+        let text = "* first\n** nested\n* second\ncontinued\n\n* [x] done\n\n. one\n. two\n";
+        assert_snapshot!(asciidoc_to_html(text, None, "", &Default::default()), @r###"
+        <ul>
+        <li>first
+        <ul>
+        <li>nested</li>
+        </ul>
+        </li>
+        <li>second
+        continued</li>
+        <li><input type="checkbox" disabled="" checked=""> done</li>
+        </ul>
+        <ol>
+        <li>one</li>
+        <li>two</li>
+        </ol>
+        "###);
+    }
+
+    #[test]
+    fn blocks() {
+        let text = "[source,rust]\n----\nfn main() {}\n----\n\n....\n<script>\n....\n\n```toml\n[dependencies]\n```\n\nNOTE: Be careful.\n\n[WARNING]\n====\nReally.\n====\n\n____\nQuoted\n____\n\nifdef::env-github[]\n.Title\nimage::logo.png[Logo,100,link=https://example.com]\nendif::[]\n\n++++\n<p align=\"center\">Hi<script>alert(1)</script></p>\n++++\n";
+        assert_snapshot!(asciidoc_to_html(text, Some("https://github.com/rust-lang/test"), "", &Default::default()), @r###"
+        <pre><code class="language-rust">fn main() {}
+        </code></pre>
+        <pre><code>&lt;script&gt;
+        </code></pre>
+        <pre><code class="language-toml">[dependencies]
+        </code></pre>
+        <blockquote>
+        <p><strong>Note</strong></p>
+        <p>Be careful.</p>
+        </blockquote>
+        <blockquote>
+        <p><strong>Warning</strong></p>
+        <p>Really.</p>
+        </blockquote>
+        <blockquote>
+        <p>Quoted</p>
+        </blockquote>
+        <p><strong>Title</strong></p>
+        <p><a href="https://example.com" rel="nofollow noopener noreferrer"><img src="https://github.com/rust-lang/test/raw/HEAD/logo.png" alt="Logo" width="100"></a></p>
+        <p align="center">Hi</p>
+        "###);
+    }
+
+    #[test]
+    fn tables() {
+        let text = "[cols=\"1,2\"]\n|===\n|Name |Description\n\n|foo\n|The `foo` crate\n|===\n";
+        assert_snapshot!(asciidoc_to_html(text, None, "", &Default::default()), @r###"
+        <table>
+        <thead>
+        <tr><th>Name</th><th>Description</th></tr>
+        </thead>
+        <tbody>
+        <tr><td>foo</td><td>The <code>foo</code> crate</td></tr>
+        </tbody>
+        </table>
+        "###);
+    }
+}
@@ -0,0 +1,198 @@
+//! Splitting of rendered changelogs into sections per release.
+
+use crate::toc::headings;
+use serde::{Deserialize, Serialize};
+
+/// A section of a rendered changelog, usually describing a single release.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangelogSection {
+    /// The plain text of the section heading.
+    pub title: String,
+    /// The `id` of the heading anchor, including the `user-content-` prefix.
+    pub anchor: Option<String>,
+    /// The version that the heading refers to, e.g. `1.2.0` for a heading
+    /// like `[v1.2] - 2024-08-01`.
+    pub version: Option<String>,
+    /// The byte offset of the start of the section, including its heading,
+    /// within the rendered changelog.
+    pub start: usize,
+    /// The byte offset of the end of the section within the rendered
+    /// changelog.
+    pub end: usize,
+}
+
+/// Splits a changelog that was rendered by `text_to_html` into sections.
+///
+/// The sections start at the headings of the highest level that contains a
+/// version number, so that e.g. a `# Changelog` title or `### Fixed`
+/// subsections are not treated as separate sections. Sections without a
+/// version number on that level (like `## Unreleased`) are included as well.
+///
+/// Returns an empty list if none of the headings contain a version number.
+pub fn changelog_sections(html: &str) -> Vec<ChangelogSection> {
+    let headings = headings(html);
+
+    let Some(release_level) = headings
+        .iter()
+        .filter(|heading| find_version(&heading.text()).is_some())
+        .map(|heading| heading.level)
+        .min()
+    else {
+        return Vec::new();
+    };
+
+    let mut sections = Vec::new();
+    for (index, heading) in headings.iter().enumerate() {
+        if heading.level != release_level {
+            continue;
+        }
+
+        let end = headings[index + 1..]
+            .iter()
+            .find(|next| next.level <= release_level)
+            .map_or(html.len(), |next| next.start);
+        let end = heading.start + html[heading.start..end].trim_end().len();
+
+        let title = heading.text();
+        sections.push(ChangelogSection {
+            version: find_version(&title),
+            title,
+            anchor: heading.anchor.map(String::from),
+            start: heading.start,
+            end,
+        });
+    }
+
+    sections
+}
+
+/// Finds the first version number like `1.2.3`, `v1.2` or `1.0.0-beta.1`
+/// within the text, and returns it with missing patch versions filled in.
+fn find_version(text: &str) -> Option<String> {
+    let mut prev = None;
+    for (index, c) in text.char_indices() {
+        let at_word_start = prev.map_or(true, |prev: char| !prev.is_alphanumeric() && prev != '.');
+        prev = Some(c);
+        if !at_word_start {
+            continue;
+        }
+
+        let candidate = match c {
+            'v' | 'V' => &text[index + 1..],
+            _ => &text[index..],
+        };
+        if let Some(version) = parse_version(candidate) {
+            return Some(version);
+        }
+    }
+
+    None
+}
+
+/// Parses the version number at the start of the text.
+fn parse_version(text: &str) -> Option<String> {
+    let core_len = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let core = text[..core_len].trim_end_matches('.');
+
+    let components = core.split('.').collect::<Vec<_>>();
+    if !(2..=3).contains(&components.len()) || components.iter().any(|c| c.is_empty()) {
+        return None;
+    }
+
+    let mut version = components.join(".");
+    if components.len() == 2 {
+        version.push_str(".0");
+    }
+
+    // Pre-release and build metadata, e.g. `-beta.1+build.5`
+    let rest = &text[core.len()..];
+    if rest.starts_with(['-', '+']) && core.len() == core_len {
+        let suffix_len = rest[1..]
+            .find(|c: char| !c.is_ascii_alphanumeric() && !matches!(c, '.' | '-' | '+'))
+            .map_or(rest.len(), |len| len + 1);
+        let suffix = rest[..suffix_len].trim_end_matches(['.', '-', '+']);
+        if suffix.len() > 1 {
+            version.push_str(suffix);
+        }
+    }
+
+    Some(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_version;
+    use crate::{changelog_sections, text_to_html};
+    use insta::assert_debug_snapshot;
+
+    #[test]
+    fn versions_in_headings() {
+        assert_eq!(find_version("1.2.3"), Some("1.2.3".into()));
+        assert_eq!(find_version("[v1.2] - 2024-08-01"), Some("1.2.0".into()));
+        assert_eq!(
+            find_version("Version 1.0.0-beta.1 (2024-08-01)"),
+            Some("1.0.0-beta.1".into())
+        );
+        assert_eq!(find_version("foo-v0.3.0"), Some("0.3.0".into()));
+        assert_eq!(find_version("Release 1.0."), Some("1.0.0".into()));
+        assert_eq!(find_version("Unreleased"), None);
+        assert_eq!(find_version("2024"), None);
+        assert_eq!(find_version("abc1.2.3"), None);
+    }
+
+    #[test]
+    fn sections() {
+        let text = "# Changelog\n\nAll notable changes.\n\n## [Unreleased]\n\n- Nothing yet\n\n## [1.1.0] - 2024-08-01\n\n### Added\n\n- `foo()`\n\n## 1.0.0\n\nInitial release\n";
+        let rendered = text_to_html(text, "CHANGELOG.md", None, None);
+        let sections = changelog_sections(&rendered);
+        assert_debug_snapshot!(sections, @r###"
+        [
+            ChangelogSection {
+                title: "[Unreleased]",
+                anchor: Some(
+                    "user-content-unreleased",
+                ),
+                version: None,
+                start: 135,
+                end: 278,
+            },
+            ChangelogSection {
+                title: "[1.1.0] - 2024-08-01",
+                anchor: Some(
+                    "user-content-110---2024-08-01",
+                ),
+                version: Some(
+                    "1.1.0",
+                ),
+                start: 279,
+                end: 544,
+            },
+            ChangelogSection {
+                title: "1.0.0",
+                anchor: Some(
+                    "user-content-100",
+                ),
+                version: Some(
+                    "1.0.0",
+                ),
+                start: 545,
+                end: 658,
+            },
+        ]
+        "###);
+
+        let section = &sections[2];
+        assert_eq!(
+            &rendered[section.start..section.end],
+            "<h2><a href=\"#100\" id=\"user-content-100\" rel=\"nofollow noopener noreferrer\"></a>1.0.0</h2>\n<p>Initial release</p>"
+        );
+    }
+
+    #[test]
+    fn no_versions() {
+        let rendered = text_to_html("# Changes\n\n## Unreleased\n", "CHANGELOG.md", None, None);
+        assert_eq!(changelog_sections(&rendered), vec![]);
+    }
+}
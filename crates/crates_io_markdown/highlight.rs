@@ -0,0 +1,1111 @@
+//! Server-side syntax highlighting of code blocks.
+//!
+//! The highlighting is intentionally simple: based on a small description of
+//! the syntax of each language, the code is split into comments, strings,
+//! numbers, keywords and a few other kinds of tokens, which are wrapped in
+//! `<span>` elements. The spans use the same `hljs-*` classes as highlight.js,
+//! so that the existing highlight.js themes apply to the output as well.
+
+use comrak::adapters::SyntaxHighlighterAdapter;
+use htmlescape::encode_minimal;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// The classes of the `<span>` elements emitted by [`highlight`], which have
+/// to be allowed by the HTML sanitizer.
+pub const CLASSES: &[&str] = &[
+    "hljs-attr",
+    "hljs-built_in",
+    "hljs-comment",
+    "hljs-keyword",
+    "hljs-literal",
+    "hljs-meta",
+    "hljs-name",
+    "hljs-number",
+    "hljs-section",
+    "hljs-string",
+    "hljs-symbol",
+    "hljs-tag",
+    "hljs-title",
+    "hljs-type",
+    "hljs-variable",
+    "function_",
+];
+
+/// Highlights the code, and returns it as HTML. Returns `None` if the
+/// language is not supported.
+pub fn highlight(language: &str, code: &str) -> Option<String> {
+    let syntax = match language.to_lowercase().as_str() {
+        "bash" | "sh" | "shell" | "zsh" => &BASH,
+        "c" | "clike" | "h" => &C,
+        "glsl" => &GLSL,
+        "go" | "golang" => &GO,
+        "ini" => &INI,
+        "javascript" | "js" => &JAVASCRIPT,
+        "json" => &JSON,
+        "protobuf" | "proto" => &PROTOBUF,
+        "ruby" | "rb" => &RUBY,
+        "rust" | "rs" => &RUST,
+        "scss" | "css" => &SCSS,
+        "sql" => &SQL,
+        "toml" => &TOML,
+        "yaml" | "yml" => &YAML,
+        "xml" | "markup" | "html" | "svg" => return Some(highlight_xml(code)),
+        _ => return None,
+    };
+
+    let mut highlighter = Highlighter {
+        syntax,
+        code,
+        pos: 0,
+        html: String::new(),
+    };
+    highlighter.run();
+    Some(highlighter.html)
+}
+
+/// A `comrak` plugin that highlights the code blocks of Markdown documents.
+pub struct HighlighterAdapter;
+
+impl SyntaxHighlighterAdapter for HighlighterAdapter {
+    fn write_highlighted(
+        &self,
+        output: &mut dyn Write,
+        lang: Option<&str>,
+        code: &str,
+    ) -> io::Result<()> {
+        match lang.and_then(|lang| highlight(lang, code)) {
+            Some(html) => output.write_all(html.as_bytes()),
+            None => comrak::html::escape(output, code.as_bytes()),
+        }
+    }
+
+    fn write_pre_tag(
+        &self,
+        output: &mut dyn Write,
+        attributes: HashMap<String, String>,
+    ) -> io::Result<()> {
+        comrak::html::write_opening_tag(output, "pre", attributes)
+    }
+
+    fn write_code_tag(
+        &self,
+        output: &mut dyn Write,
+        attributes: HashMap<String, String>,
+    ) -> io::Result<()> {
+        comrak::html::write_opening_tag(output, "code", attributes)
+    }
+}
+
+/// How the beginning of a line is highlighted.
+#[derive(Clone, Copy, PartialEq)]
+enum LineStart {
+    /// Like any other code.
+    Code,
+    /// Like in TOML and INI files, with `[section]` headers and `key = value`
+    /// pairs.
+    Config,
+    /// Like in YAML files, with `key: value` pairs.
+    Yaml,
+    /// Like in C, with preprocessor directives.
+    Preprocessor,
+}
+
+/// The syntax of a language, as far as it is relevant for highlighting.
+struct Syntax {
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    keywords: &'static [&'static str],
+    literals: &'static [&'static str],
+    types: &'static [&'static str],
+    built_ins: &'static [&'static str],
+    /// Keywords that are followed by the name of a function.
+    function_keywords: &'static [&'static str],
+    /// Characters that start variables, like `$` in shell scripts.
+    variable_prefixes: &'static [char],
+    /// Whether keywords are case-insensitive, like in SQL.
+    case_insensitive: bool,
+    /// Whether identifiers and strings followed by a colon are keys, like in
+    /// JSON objects.
+    keys_before_colon: bool,
+    line_start: LineStart,
+    /// Whether the language is Rust, which has lifetimes, macros, attributes
+    /// and raw strings.
+    rust: bool,
+}
+
+const DEFAULT: Syntax = Syntax {
+    line_comments: &[],
+    block_comment: None,
+    quotes: &['"', '\''],
+    keywords: &[],
+    literals: &["true", "false"],
+    types: &[],
+    built_ins: &[],
+    function_keywords: &[],
+    variable_prefixes: &[],
+    case_insensitive: false,
+    keys_before_colon: false,
+    line_start: LineStart::Code,
+    rust: false,
+};
+
+static BASH: Syntax = Syntax {
+    line_comments: &["#"],
+    keywords: &[
+        "if", "then", "else", "elif", "fi", "for", "while", "until", "in", "do", "done", "case",
+        "esac", "function", "return", "local", "export", "declare", "readonly", "unset", "select",
+        "break", "continue", "exit",
+    ],
+    built_ins: &[
+        "alias", "cd", "echo", "eval", "exec", "printf", "pwd", "read", "set", "shift", "source",
+        "test", "trap",
+    ],
+    function_keywords: &["function"],
+    variable_prefixes: &['$'],
+    ..DEFAULT
+};
+
+static C: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    keywords: &[
+        "auto", "break", "case", "const", "continue", "default", "do", "else", "enum", "extern",
+        "for", "goto", "if", "inline", "register", "restrict", "return", "sizeof", "static",
+        "struct", "switch", "typedef", "union", "volatile", "while",
+    ],
+    literals: &["true", "false", "NULL", "nullptr"],
+    types: &[
+        "bool", "char", "double", "float", "int", "long", "short", "signed", "unsigned", "void",
+        "size_t", "int8_t", "int16_t", "int32_t", "int64_t", "uint8_t", "uint16_t", "uint32_t",
+        "uint64_t",
+    ],
+    line_start: LineStart::Preprocessor,
+    ..DEFAULT
+};
+
+static GLSL: Syntax = Syntax {
+    keywords: &[
+        "attribute",
+        "break",
+        "case",
+        "const",
+        "continue",
+        "default",
+        "discard",
+        "do",
+        "else",
+        "for",
+        "highp",
+        "if",
+        "in",
+        "inout",
+        "layout",
+        "lowp",
+        "mediump",
+        "out",
+        "precision",
+        "return",
+        "struct",
+        "switch",
+        "uniform",
+        "varying",
+        "while",
+    ],
+    types: &[
+        "bool",
+        "float",
+        "int",
+        "uint",
+        "void",
+        "vec2",
+        "vec3",
+        "vec4",
+        "ivec2",
+        "ivec3",
+        "ivec4",
+        "mat2",
+        "mat3",
+        "mat4",
+        "sampler2D",
+        "samplerCube",
+    ],
+    ..C
+};
+
+static GO: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\'', '`'],
+    keywords: &[
+        "break",
+        "case",
+        "chan",
+        "const",
+        "continue",
+        "default",
+        "defer",
+        "else",
+        "fallthrough",
+        "for",
+        "func",
+        "go",
+        "goto",
+        "if",
+        "import",
+        "interface",
+        "map",
+        "package",
+        "range",
+        "return",
+        "select",
+        "struct",
+        "switch",
+        "type",
+        "var",
+    ],
+    literals: &["true", "false", "nil", "iota"],
+    types: &[
+        "bool",
+        "byte",
+        "complex64",
+        "complex128",
+        "error",
+        "float32",
+        "float64",
+        "int",
+        "int8",
+        "int16",
+        "int32",
+        "int64",
+        "rune",
+        "string",
+        "uint",
+        "uint8",
+        "uint16",
+        "uint32",
+        "uint64",
+        "uintptr",
+    ],
+    built_ins: &[
+        "append", "cap", "close", "copy", "delete", "len", "make", "new", "panic", "print",
+        "println", "recover",
+    ],
+    function_keywords: &["func"],
+    ..DEFAULT
+};
+
+static INI: Syntax = Syntax {
+    line_comments: &["#", ";"],
+    quotes: &['"'],
+    literals: &["true", "false", "yes", "no", "on", "off"],
+    line_start: LineStart::Config,
+    ..DEFAULT
+};
+
+static JAVASCRIPT: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\'', '`'],
+    keywords: &[
+        "async",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "debugger",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "export",
+        "extends",
+        "finally",
+        "for",
+        "from",
+        "function",
+        "if",
+        "import",
+        "in",
+        "instanceof",
+        "let",
+        "new",
+        "of",
+        "return",
+        "static",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "try",
+        "typeof",
+        "var",
+        "void",
+        "while",
+        "with",
+        "yield",
+    ],
+    literals: &["true", "false", "null", "undefined", "NaN", "Infinity"],
+    built_ins: &[
+        "Array", "JSON", "Math", "Number", "Object", "Promise", "String", "console", "document",
+        "module", "require", "window",
+    ],
+    function_keywords: &["function"],
+    ..DEFAULT
+};
+
+static JSON: Syntax = Syntax {
+    quotes: &['"'],
+    literals: &["true", "false", "null"],
+    keys_before_colon: true,
+    ..DEFAULT
+};
+
+static PROTOBUF: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    keywords: &[
+        "enum", "extend", "import", "map", "max", "message", "oneof", "option", "optional",
+        "package", "public", "repeated", "required", "reserved", "returns", "rpc", "service",
+        "stream", "syntax", "to", "weak",
+    ],
+    types: &[
+        "bool", "bytes", "double", "fixed32", "fixed64", "float", "int32", "int64", "sfixed32",
+        "sfixed64", "sint32", "sint64", "string", "uint32", "uint64",
+    ],
+    ..DEFAULT
+};
+
+static RUBY: Syntax = Syntax {
+    line_comments: &["#"],
+    keywords: &[
+        "alias", "and", "begin", "break", "case", "class", "def", "do", "else", "elsif", "end",
+        "ensure", "for", "if", "in", "module", "next", "not", "or", "redo", "rescue", "retry",
+        "return", "self", "super", "then", "undef", "unless", "until", "when", "while", "yield",
+    ],
+    literals: &["true", "false", "nil"],
+    built_ins: &[
+        "attr_accessor",
+        "attr_reader",
+        "attr_writer",
+        "extend",
+        "include",
+        "private",
+        "protected",
+        "public",
+        "puts",
+        "require",
+        "require_relative",
+    ],
+    function_keywords: &["def"],
+    variable_prefixes: &['@', '$'],
+    ..DEFAULT
+};
+
+static RUST: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "type",
+        "union", "unsafe", "use", "where", "while",
+    ],
+    types: &[
+        "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+        "f32", "f64", "bool", "char", "str", "String", "Vec", "Option", "Result", "Box",
+    ],
+    built_ins: &["Some", "None", "Ok", "Err"],
+    function_keywords: &["fn"],
+    rust: true,
+    ..DEFAULT
+};
+
+static SCSS: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    keywords: &[
+        "@charset",
+        "@each",
+        "@else",
+        "@extend",
+        "@for",
+        "@forward",
+        "@function",
+        "@if",
+        "@import",
+        "@include",
+        "@media",
+        "@mixin",
+        "@return",
+        "@use",
+        "@while",
+        "!default",
+        "!important",
+    ],
+    variable_prefixes: &['$'],
+    keys_before_colon: true,
+    ..DEFAULT
+};
+
+static SQL: Syntax = Syntax {
+    line_comments: &["--"],
+    block_comment: Some(("/*", "*/")),
+    keywords: &[
+        "add",
+        "all",
+        "alter",
+        "and",
+        "as",
+        "asc",
+        "begin",
+        "between",
+        "by",
+        "case",
+        "column",
+        "commit",
+        "constraint",
+        "create",
+        "default",
+        "delete",
+        "desc",
+        "distinct",
+        "drop",
+        "else",
+        "end",
+        "exists",
+        "foreign",
+        "from",
+        "group",
+        "having",
+        "if",
+        "in",
+        "index",
+        "inner",
+        "insert",
+        "into",
+        "is",
+        "join",
+        "key",
+        "left",
+        "like",
+        "limit",
+        "not",
+        "offset",
+        "on",
+        "or",
+        "order",
+        "outer",
+        "primary",
+        "references",
+        "right",
+        "rollback",
+        "select",
+        "set",
+        "table",
+        "then",
+        "transaction",
+        "union",
+        "unique",
+        "update",
+        "values",
+        "when",
+        "where",
+        "with",
+    ],
+    literals: &["true", "false", "null"],
+    types: &[
+        "bigint",
+        "boolean",
+        "char",
+        "date",
+        "float",
+        "int",
+        "integer",
+        "json",
+        "jsonb",
+        "numeric",
+        "real",
+        "serial",
+        "smallint",
+        "text",
+        "timestamp",
+        "uuid",
+        "varchar",
+    ],
+    case_insensitive: true,
+    ..DEFAULT
+};
+
+static TOML: Syntax = Syntax {
+    line_comments: &["#"],
+    line_start: LineStart::Config,
+    ..DEFAULT
+};
+
+static YAML: Syntax = Syntax {
+    line_comments: &["#"],
+    literals: &["true", "false", "null", "yes", "no", "~"],
+    line_start: LineStart::Yaml,
+    ..DEFAULT
+};
+
+struct Highlighter<'a> {
+    syntax: &'static Syntax,
+    code: &'a str,
+    pos: usize,
+    html: String,
+}
+
+impl<'a> Highlighter<'a> {
+    fn rest(&self) -> &'a str {
+        &self.code[self.pos..]
+    }
+
+    fn prev_char(&self) -> Option<char> {
+        self.code[..self.pos].chars().next_back()
+    }
+
+    /// Appends the next `len` bytes of the code, wrapped in a `<span>` with
+    /// the given class.
+    fn span(&mut self, class: &str, len: usize) {
+        let text = &self.code[self.pos..self.pos + len];
+        self.html.push_str(&format!(
+            "<span class=\"{class}\">{}</span>",
+            encode_minimal(text)
+        ));
+        self.pos += len;
+    }
+
+    /// Appends the next `len` bytes of the code without highlighting.
+    fn text(&mut self, len: usize) {
+        let text = &self.code[self.pos..self.pos + len];
+        self.html.push_str(&encode_minimal(text));
+        self.pos += len;
+    }
+
+    fn run(&mut self) {
+        let mut line_start = true;
+        let mut expect_function_name = false;
+
+        while let Some(c) = self.rest().chars().next() {
+            if line_start && self.line_start() {
+                line_start = false;
+                continue;
+            }
+
+            if c == '\n' {
+                line_start = true;
+            } else if !c.is_whitespace() {
+                line_start = false;
+            }
+
+            if let Some(len) = self.comment_len() {
+                self.span("hljs-comment", len);
+            } else if let Some(len) = self.string_len() {
+                let class = match self.is_key(len) {
+                    true => "hljs-attr",
+                    false => "hljs-string",
+                };
+                self.span(class, len);
+            } else if self.syntax.rust && self.rust_special() {
+            } else if c.is_ascii_digit() && !self.prev_char().is_some_and(is_ident_char) {
+                let len = number_len(self.rest());
+                self.span("hljs-number", len);
+            } else if self.syntax.variable_prefixes.contains(&c) && self.variable() {
+            } else if is_ident_start(c) || (c == '@' || c == '!') && self.syntax.keys_before_colon {
+                let len = c.len_utf8() + ident_len(&self.rest()[c.len_utf8()..]);
+                if expect_function_name && is_ident_start(c) {
+                    self.span("hljs-title function_", len);
+                    expect_function_name = false;
+                    continue;
+                }
+
+                let word = &self.rest()[..len];
+                expect_function_name = self.syntax.function_keywords.contains(&word);
+                match self.word_class(word, len) {
+                    Some(class) => self.span(class, len),
+                    None => self.text(len),
+                }
+            } else {
+                self.text(c.len_utf8());
+            }
+        }
+    }
+
+    /// Highlights the start of the line for languages with a special line
+    /// structure, and returns whether anything was highlighted.
+    fn line_start(&mut self) -> bool {
+        let rest = self.rest();
+        let line = rest.split('\n').next().unwrap_or_default();
+        let indent = line.len() - line.trim_start().len();
+        let trimmed = &line[indent..];
+
+        match self.syntax.line_start {
+            LineStart::Code => false,
+            LineStart::Preprocessor if trimmed.starts_with('#') => {
+                self.text(indent);
+                self.span("hljs-meta", trimmed.len());
+                true
+            }
+            LineStart::Config if trimmed.starts_with('[') => {
+                let len = trimmed.find(']').map_or(trimmed.len(), |end| {
+                    end + trimmed[end..].chars().take_while(|c| *c == ']').count()
+                });
+                self.text(indent);
+                self.span("hljs-section", len);
+                true
+            }
+            LineStart::Config => {
+                let Some(key_len) = trimmed.find('=') else {
+                    return false;
+                };
+                let key = trimmed[..key_len].trim_end();
+                if key.is_empty() || key.starts_with(['#', ';']) {
+                    return false;
+                }
+                self.text(indent);
+                self.span("hljs-attr", key.len());
+                true
+            }
+            LineStart::Yaml if trimmed == "---" || trimmed == "..." => {
+                self.text(indent);
+                self.span("hljs-meta", trimmed.len());
+                true
+            }
+            LineStart::Yaml => {
+                let item_len = match trimmed.starts_with("- ") {
+                    true => 2,
+                    false => 0,
+                };
+                let entry = &trimmed[item_len..];
+                let Some(key_len) = entry
+                    .find(": ")
+                    .or_else(|| entry.strip_suffix(':').map(|key| key.len()))
+                else {
+                    return false;
+                };
+                if entry.starts_with(['#', '"', '\'', '{', '[']) {
+                    return false;
+                }
+                self.text(indent + item_len);
+                self.span("hljs-attr", key_len);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn comment_len(&self) -> Option<usize> {
+        let rest = self.rest();
+
+        for prefix in self.syntax.line_comments {
+            // `#` only starts comments at the beginning of words, so that
+            // e.g. `$#` in shell scripts is not a comment.
+            let at_word_start =
+                *prefix != "#" || self.prev_char().map_or(true, char::is_whitespace);
+            if rest.starts_with(prefix) && at_word_start {
+                return Some(rest.find('\n').unwrap_or(rest.len()));
+            }
+        }
+
+        let (start, end) = self.syntax.block_comment?;
+        let inner = rest.strip_prefix(start)?;
+        let len = inner.find(end).map_or(inner.len(), |len| len + end.len());
+        Some(start.len() + len)
+    }
+
+    fn string_len(&self) -> Option<usize> {
+        let rest = self.rest();
+        let quote = rest
+            .chars()
+            .next()
+            .filter(|c| self.syntax.quotes.contains(c))?;
+
+        // Triple-quoted strings like in TOML
+        let triple = quote.to_string().repeat(3);
+        if rest.starts_with(&triple) {
+            let len = rest[3..].find(&triple).map_or(rest.len(), |len| len + 6);
+            return Some(len);
+        }
+
+        // Single quotes are used for lifetimes in Rust, which are handled in
+        // `rust_special()` instead.
+        if quote == '\'' && self.syntax.rust {
+            let mut chars = rest[1..].chars();
+            let is_char = match chars.next() {
+                Some('\\') => true,
+                Some(_) => chars.next() == Some('\''),
+                None => false,
+            };
+            if !is_char {
+                return None;
+            }
+        }
+
+        string_literal_len(rest, quote)
+    }
+
+    /// Whether the string or identifier of the given length is followed by a
+    /// colon, which makes it a key in languages like JSON.
+    fn is_key(&self, len: usize) -> bool {
+        self.syntax.keys_before_colon
+            && self.rest()[len..]
+                .trim_start_matches([' ', '\t'])
+                .starts_with(':')
+    }
+
+    /// Highlights lifetimes, macros, attributes and raw strings in Rust code,
+    /// and returns whether anything was highlighted.
+    fn rust_special(&mut self) -> bool {
+        let rest = self.rest();
+
+        // Lifetimes like `'a` and `'static`
+        if let Some(lifetime) = rest.strip_prefix('\'') {
+            let len = ident_len(lifetime);
+            if len > 0 {
+                self.span("hljs-symbol", len + 1);
+                return true;
+            }
+        }
+
+        // Attributes like `#[derive(Debug)]` and `#![no_std]`
+        if rest.starts_with("#[") || rest.starts_with("#![") {
+            let mut depth = 0;
+            let len = rest
+                .char_indices()
+                .find(|(_, c)| {
+                    match c {
+                        '[' => depth += 1,
+                        ']' => depth -= 1,
+                        _ => {}
+                    }
+                    depth == 0 && *c == ']'
+                })
+                .map_or(rest.len(), |(index, _)| index + 1);
+            self.span("hljs-meta", len);
+            return true;
+        }
+
+        // Raw and byte strings like `r#"..."#` and `b"..."`
+        if !self.prev_char().is_some_and(is_ident_char) {
+            let prefix_len = ["br", "r", "b"]
+                .iter()
+                .find(|prefix| {
+                    rest.strip_prefix(*prefix)
+                        .is_some_and(|rest| rest.starts_with(['"', '#']))
+                })
+                .map(|prefix| prefix.len());
+
+            if let Some(prefix_len) = prefix_len {
+                let hashes = rest[prefix_len..].chars().take_while(|c| *c == '#').count();
+                if rest[prefix_len + hashes..].starts_with('"') {
+                    let start = prefix_len + hashes + 1;
+                    let end = format!("\"{}", "#".repeat(hashes));
+                    let len = match hashes == 0 && prefix_len == 1 && rest.starts_with('b') {
+                        true => string_literal_len(&rest[1..], '"').map(|len| len + 1),
+                        false => rest[start..].find(&end).map(|len| start + len + end.len()),
+                    };
+                    self.span("hljs-string", len.unwrap_or(rest.len()));
+                    return true;
+                }
+            }
+        }
+
+        // Macros like `println!`
+        let len = ident_len(rest);
+        if len > 0
+            && rest[len..].starts_with('!')
+            && !rest[len..].starts_with("!=")
+            && !self.prev_char().is_some_and(is_ident_char)
+        {
+            self.span("hljs-built_in", len + 1);
+            return true;
+        }
+
+        false
+    }
+
+    /// Highlights variables like `$HOME` or `${HOME}`, and returns whether
+    /// anything was highlighted.
+    fn variable(&mut self) -> bool {
+        let rest = self.rest();
+        let prefix_len = rest
+            .chars()
+            .take_while(|c| self.syntax.variable_prefixes.contains(c))
+            .count();
+        let name = &rest[prefix_len..];
+
+        let len = if name.starts_with('{') {
+            name.find('}').map(|len| len + 1)
+        } else {
+            let len = match name.chars().next() {
+                Some(c) if c.is_ascii_digit() || matches!(c, '?' | '#' | '@' | '*' | '!') => 1,
+                _ => ident_len(name),
+            };
+            (len > 0).then_some(len)
+        };
+
+        match len {
+            Some(len) => {
+                self.span("hljs-variable", prefix_len + len);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn word_class(&self, word: &str, len: usize) -> Option<&'static str> {
+        let lowercase;
+        let word = match self.syntax.case_insensitive {
+            true => {
+                lowercase = word.to_lowercase();
+                lowercase.as_str()
+            }
+            false => word,
+        };
+
+        if self.is_key(len) {
+            Some("hljs-attr")
+        } else if self.syntax.keywords.contains(&word) {
+            Some("hljs-keyword")
+        } else if self.syntax.literals.contains(&word) {
+            Some("hljs-literal")
+        } else if self.syntax.types.contains(&word) {
+            Some("hljs-type")
+        } else if self.syntax.built_ins.contains(&word) {
+            Some("hljs-built_in")
+        } else {
+            None
+        }
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn ident_len(text: &str) -> usize {
+    match text.chars().next() {
+        Some(c) if is_ident_start(c) => text.find(|c| !is_ident_char(c)).unwrap_or(text.len()),
+        _ => 0,
+    }
+}
+
+/// Returns the length of the number at the start of the text, including
+/// prefixes like `0x`, suffixes like `u8` or `px` and a fractional part.
+fn number_len(text: &str) -> usize {
+    let mut len = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let is_fraction = c == '.' && chars.peek().is_some_and(|(_, next)| next.is_ascii_digit());
+        if !is_ident_char(c) && !is_fraction {
+            break;
+        }
+        len = index + c.len_utf8();
+    }
+    len
+}
+
+/// Returns the length of the string literal at the start of the text, which
+/// starts and ends with `quote` and may contain backslash escapes.
+fn string_literal_len(text: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (index, c) in text.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return Some(index + c.len_utf8());
+        }
+    }
+
+    Some(text.len())
+}
+
+/// Highlights XML and HTML tags, attributes and comments.
+fn highlight_xml(code: &str) -> String {
+    let mut html = String::new();
+    let mut rest = code;
+
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            html.push_str(&encode_minimal(rest));
+            break;
+        };
+
+        html.push_str(&encode_minimal(&rest[..start]));
+        rest = &rest[start..];
+
+        let (class, end) = if rest.starts_with("<!--") {
+            ("hljs-comment", "-->")
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            ("hljs-meta", ">")
+        } else if rest[1..].starts_with(|c: char| c.is_alphabetic() || c == '/') {
+            ("hljs-tag", ">")
+        } else {
+            html.push_str("&lt;");
+            rest = &rest[1..];
+            continue;
+        };
+
+        let len = rest.find(end).map_or(rest.len(), |len| len + end.len());
+        let (tag, remaining) = rest.split_at(len);
+        rest = remaining;
+
+        if class != "hljs-tag" {
+            html.push_str(&format!(
+                "<span class=\"{class}\">{}</span>",
+                encode_minimal(tag)
+            ));
+            continue;
+        }
+
+        html.push_str("<span class=\"hljs-tag\">");
+        let name_start = if tag.starts_with("</") { 2 } else { 1 };
+        let name_len = tag[name_start..]
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .unwrap_or(tag.len() - name_start);
+        html.push_str(&encode_minimal(&tag[..name_start]));
+        html.push_str(&format!(
+            "<span class=\"hljs-name\">{}</span>",
+            encode_minimal(&tag[name_start..name_start + name_len])
+        ));
+        xml_attributes(&mut html, &tag[name_start + name_len..]);
+        html.push_str("</span>");
+    }
+
+    html
+}
+
+/// Highlights the attributes of an XML tag, e.g. ` key="value">`.
+fn xml_attributes(html: &mut String, mut rest: &str) {
+    while let Some(c) = rest.chars().next() {
+        let len = if c == '"' || c == '\'' {
+            let len = string_literal_len(rest, c).unwrap_or(rest.len());
+            html.push_str(&format!(
+                "<span class=\"hljs-string\">{}</span>",
+                encode_minimal(&rest[..len])
+            ));
+            len
+        } else if c.is_alphabetic() {
+            let len = rest
+                .find(|c: char| c.is_whitespace() || matches!(c, '=' | '>' | '/'))
+                .unwrap_or(rest.len());
+            html.push_str(&format!(
+                "<span class=\"hljs-attr\">{}</span>",
+                encode_minimal(&rest[..len])
+            ));
+            len
+        } else {
+            html.push_str(&encode_minimal(&rest[..c.len_utf8()]));
+            c.len_utf8()
+        };
+        rest = &rest[len..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::highlight;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn unsupported_languages() {
+        assert_eq!(highlight("mermaid", "graph LR"), None);
+        assert_eq!(highlight("brainfuck", "+[-->-[>>+>-----<<]<--<---]"), None);
+    }
+
+    #[test]
+    fn rust() {
+        let code = "#[derive(Debug)]\nstruct Foo<'a> {\n    name: &'a str, // the name\n}\n\nfn main() {\n    let x = 0x1f_u8 + 1.5;\n    println!(\"{x} <{}>\", r#\"raw \"str\"\"#, 'c');\n}\n";
+        assert_snapshot!(highlight("rust", code).unwrap(), @r###"
+        <span class="hljs-meta">#[derive(Debug)]</span>
+        <span class="hljs-keyword">struct</span> Foo&lt;<span class="hljs-symbol">&#x27;a</span>&gt; {
+            name: &amp;<span class="hljs-symbol">&#x27;a</span> <span class="hljs-type">str</span>, <span class="hljs-comment">// the name</span>
+        }
+
+        <span class="hljs-keyword">fn</span> <span class="hljs-title function_">main</span>() {
+            <span class="hljs-keyword">let</span> x = <span class="hljs-number">0x1f_u8</span> + <span class="hljs-number">1.5</span>;
+            <span class="hljs-built_in">println!</span>(<span class="hljs-string">&quot;{x} &lt;{}&gt;&quot;</span>, <span class="hljs-string">r#&quot;raw &quot;str&quot;&quot;#</span>, <span class="hljs-string">&#x27;c&#x27;</span>);
+        }
+        "###);
+    }
+
+    #[test]
+    fn toml() {
+        let code =
+            "[package]\nname = \"foo\" # the name\nversion = \"0.1.0\"\n\n[[bin]]\ntest = false\n";
+        assert_snapshot!(highlight("toml", code).unwrap(), @r###"
+        <span class="hljs-section">[package]</span>
+        <span class="hljs-attr">name</span> = <span class="hljs-string">&quot;foo&quot;</span> <span class="hljs-comment"># the name</span>
+        <span class="hljs-attr">version</span> = <span class="hljs-string">&quot;0.1.0&quot;</span>
+
+        <span class="hljs-section">[[bin]]</span>
+        <span class="hljs-attr">test</span> = <span class="hljs-literal">false</span>
+        "###);
+    }
+
+    #[test]
+    fn json() {
+        let code = "{\n  \"name\": \"foo\",\n  \"count\": 42,\n  \"enabled\": true,\n  \"tags\": null\n}\n";
+        assert_snapshot!(highlight("json", code).unwrap(), @r###"
+        {
+          <span class="hljs-attr">&quot;name&quot;</span>: <span class="hljs-string">&quot;foo&quot;</span>,
+          <span class="hljs-attr">&quot;count&quot;</span>: <span class="hljs-number">42</span>,
+          <span class="hljs-attr">&quot;enabled&quot;</span>: <span class="hljs-literal">true</span>,
+          <span class="hljs-attr">&quot;tags&quot;</span>: <span class="hljs-literal">null</span>
+        }
+        "###);
+    }
+
+    #[test]
+    fn shell() {
+        let code = "# install the crate\ncargo install foo\necho \"${HOME}/bin\" $# # count\n";
+        assert_snapshot!(highlight("sh", code).unwrap(), @r###"
+        <span class="hljs-comment"># install the crate</span>
+        cargo install foo
+        <span class="hljs-built_in">echo</span> <span class="hljs-string">&quot;${HOME}/bin&quot;</span> <span class="hljs-variable">$#</span> <span class="hljs-comment"># count</span>
+        "###);
+    }
+
+    #[test]
+    fn yaml() {
+        let code =
+            "---\njobs:\n  - name: test\n    run: cargo test # all tests\n    enabled: true\n";
+        assert_snapshot!(highlight("yaml", code).unwrap(), @r###"
+        <span class="hljs-meta">---</span>
+        <span class="hljs-attr">jobs</span>:
+          - <span class="hljs-attr">name</span>: test
+            <span class="hljs-attr">run</span>: cargo test <span class="hljs-comment"># all tests</span>
+            <span class="hljs-attr">enabled</span>: <span class="hljs-literal">true</span>
+        "###);
+    }
+
+    #[test]
+    fn sql() {
+        let code = "SELECT name FROM crates WHERE id = 1 -- the first crate\n";
+        assert_snapshot!(highlight("sql", code).unwrap(), @r###"
+        <span class="hljs-keyword">SELECT</span> name <span class="hljs-keyword">FROM</span> crates <span class="hljs-keyword">WHERE</span> id = <span class="hljs-number">1</span> <span class="hljs-comment">-- the first crate</span>
+        "###);
+    }
+
+    #[test]
+    fn xml() {
+        let code = "<!-- comment -->\n<a href=\"https://crates.io\">a &amp; b</a>\n1 < 2\n";
+        assert_snapshot!(highlight("html", code).unwrap(), @r###"
+        <span class="hljs-comment">&lt;!-- comment --&gt;</span>
+        <span class="hljs-tag">&lt;<span class="hljs-name">a</span> <span class="hljs-attr">href</span>=<span class="hljs-string">&quot;https://crates.io&quot;</span>&gt;</span>a &amp;amp; b<span class="hljs-tag">&lt;/<span class="hljs-name">a</span>&gt;</span>
+        1 &lt; 2
+        "###);
+    }
+}
@@ -0,0 +1,98 @@
+//! Rewriting of image URLs to go through a camo-style image proxy.
+
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use std::fmt;
+use url::Url;
+
+/// An image proxy like [camo](https://github.com/atmos/camo), which fetches
+/// third-party images on behalf of the visitors, so that their IP addresses
+/// are not leaked to the image hosts.
+///
+/// The proxy URLs have the form `<base_url>/<digest>/<encoded_url>`, where
+/// `digest` is the hex-encoded HMAC-SHA1 signature of the original URL using
+/// the shared key, and `encoded_url` is the hex-encoded original URL.
+#[derive(Clone)]
+pub struct ImageProxy {
+    base_url: Url,
+    key: Vec<u8>,
+}
+
+impl ImageProxy {
+    /// Creates a new image proxy configuration with the base URL of the
+    /// proxy and the key that is shared with it.
+    pub fn new(mut base_url: Url, key: impl Into<Vec<u8>>) -> Self {
+        let path = base_url.path().trim_end_matches('/').to_string();
+        base_url.set_path(&path);
+        base_url.set_query(None);
+        base_url.set_fragment(None);
+
+        Self {
+            base_url,
+            key: key.into(),
+        }
+    }
+
+    /// The path of the base URL without a trailing slash.
+    fn base_path(&self) -> &str {
+        self.base_url.path().trim_end_matches('/')
+    }
+
+    /// Returns `true` if the given URL already points to the proxy, i.e. it
+    /// has the same scheme, host and port as the base URL and its path is
+    /// below the base path.
+    pub(crate) fn is_proxied(&self, url: &Url) -> bool {
+        url.scheme() == self.base_url.scheme()
+            && url.host() == self.base_url.host()
+            && url.port_or_known_default() == self.base_url.port_or_known_default()
+            && url
+                .path()
+                .strip_prefix(self.base_path())
+                .is_some_and(|rest| rest.starts_with('/'))
+    }
+
+    /// Returns the signed proxy URL for the given image URL, or `None` if
+    /// the URL does not need to be proxied, because it is not an absolute
+    /// `http(s)` URL or already points to the proxy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crates_io_markdown::ImageProxy;
+    /// use url::Url;
+    ///
+    /// let base_url = Url::parse("https://camo.example.com").unwrap();
+    /// let proxy = ImageProxy::new(base_url, "secret");
+    /// assert_eq!(
+    ///     proxy.proxy_url("https://example.com/logo.png").as_deref(),
+    ///     Some("https://camo.example.com/c1d283efcedc4d3c9b45dd80a12c028e40866098/68747470733a2f2f6578616d706c652e636f6d2f6c6f676f2e706e67"),
+    /// );
+    /// assert_eq!(proxy.proxy_url("#logo"), None);
+    /// assert_eq!(proxy.proxy_url("https://camo.example.com/abc/def"), None);
+    /// assert!(proxy.proxy_url("https://camo.example.com.evil.com/logo.png").is_some());
+    /// ```
+    pub fn proxy_url(&self, url: &str) -> Option<String> {
+        let parsed = Url::parse(url).ok()?;
+        if !matches!(parsed.scheme(), "http" | "https") || self.is_proxied(&parsed) {
+            return None;
+        }
+
+        let mut mac =
+            Hmac::<Sha1>::new_from_slice(&self.key).expect("HMAC accepts keys of any size");
+        mac.update(url.as_bytes());
+        let digest = hex::encode(mac.finalize().into_bytes());
+
+        let mut proxy_url = self.base_url.clone();
+        let path = format!("{}/{digest}/{}", self.base_path(), hex::encode(url));
+        proxy_url.set_path(&path);
+        Some(proxy_url.into())
+    }
+}
+
+impl fmt::Debug for ImageProxy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImageProxy")
+            .field("base_url", &self.base_url.as_str())
+            .finish_non_exhaustive()
+    }
+}
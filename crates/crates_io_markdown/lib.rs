@@ -1,4 +1,18 @@
-//! Render Markdown files to HTML.
+//! Render Markdown, reStructuredText and AsciiDoc files to HTML.
+
+mod asciidoc;
+mod changelog;
+mod highlight;
+mod image_proxy;
+mod links;
+mod markup;
+mod rst;
+mod toc;
+
+pub use changelog::{changelog_sections, ChangelogSection};
+pub use image_proxy::ImageProxy;
+pub use links::extract_links;
+pub use toc::{extract_toc, TocEntry};
 
 use ammonia::{Builder, UrlRelative, UrlRelativeEvaluate};
 use comrak::nodes::{AstNode, NodeValue};
@@ -10,6 +24,7 @@ use url::Url;
 /// Context for markdown to HTML rendering.
 struct MarkdownRenderer<'a> {
     html_sanitizer: Builder<'a>,
+    options: RenderOptions,
 }
 
 impl<'a> MarkdownRenderer<'a> {
@@ -17,58 +32,23 @@ impl<'a> MarkdownRenderer<'a> {
     ///
     /// Per `text_to_html`, `base_url` is the base URL prepended to any
     /// relative links in the input document.  See that function for more detail.
-    fn new(base_url: Option<&'a str>, base_dir: &'a str) -> MarkdownRenderer<'a> {
-        let allowed_classes = hashmap(&[
-            (
-                "code",
-                hashset(&[
-                    // Languages
-                    "language-bash",
-                    "language-c",
-                    "language-glsl",
-                    "language-go",
-                    "language-ini",
-                    "language-javascript",
-                    "language-json",
-                    "language-xml",
-                    "language-mermaid",
-                    "language-protobuf",
-                    "language-ruby",
-                    "language-rust",
-                    "language-scss",
-                    "language-sql",
-                    "language-toml",
-                    "language-yaml",
-                    // Aliases
-                    "language-rs",
-                    "language-clike",
-                    "language-markup",
-                ]),
-            ),
-            ("section", hashset(&["footnotes"])),
-        ]);
-        let sanitize_url = UrlRelative::Custom(Box::new(SanitizeUrl::new(base_url, base_dir)));
-
-        let mut html_sanitizer = Builder::default();
-        html_sanitizer
-            .add_tags(&["input", "ol", "picture", "section", "source"])
-            .link_rel(Some("nofollow noopener noreferrer"))
-            .add_generic_attributes(&["align"])
-            .add_tag_attributes("a", &["id", "target"])
-            .add_tag_attributes("input", &["checked", "disabled", "type"])
-            .add_tag_attributes("li", &["id"])
-            .add_tag_attributes("source", &["media", "srcset"])
-            .allowed_classes(allowed_classes)
-            .url_relative(sanitize_url)
-            .id_prefix(Some("user-content-"));
-        MarkdownRenderer { html_sanitizer }
+    fn new(
+        base_url: Option<&'a str>,
+        base_dir: &'a str,
+        options: &RenderOptions,
+    ) -> MarkdownRenderer<'a> {
+        let html_sanitizer = html_sanitizer(base_url, base_dir, options.image_proxy.as_ref());
+        MarkdownRenderer {
+            html_sanitizer,
+            options: options.clone(),
+        }
     }
 
     /// Renders the given markdown to HTML using the current settings.
     fn to_html(&self, text: &str) -> String {
         use comrak::{
-            format_html, parse_document, Arena, ComrakExtensionOptions, ComrakOptions,
-            ComrakRenderOptions,
+            format_html_with_plugins, parse_document, Arena, ComrakExtensionOptions, ComrakOptions,
+            ComrakPlugins, ComrakRenderOptions,
         };
 
         let mut render_options = ComrakRenderOptions::default();
@@ -105,13 +85,137 @@ impl<'a> MarkdownRenderer<'a> {
             }
         });
 
+        let mut plugins = ComrakPlugins::default();
+        if self.options.syntax_highlighting {
+            plugins.render.codefence_syntax_highlighter = Some(&highlight::HighlighterAdapter);
+        }
+
         let mut html = Vec::new();
-        format_html(root, &options, &mut html).unwrap();
+        format_html_with_plugins(root, &options, &mut html, &plugins).unwrap();
         let rendered = String::from_utf8(html).unwrap();
         self.html_sanitizer.clean(&rendered).to_string()
     }
 }
 
+/// Creates the HTML sanitizer that is used for the output of all renderers.
+///
+/// Per `text_to_html`, `base_url` is the base URL prepended to any
+/// relative links in the input document.  See that function for more detail.
+///
+/// If an `image_proxy` is given, the sources of all `<img>` tags and the
+/// `srcset` candidates of all `<source>` tags are rewritten to go through
+/// the proxy.
+fn html_sanitizer<'a>(
+    base_url: Option<&'a str>,
+    base_dir: &'a str,
+    image_proxy: Option<&ImageProxy>,
+) -> Builder<'a> {
+    let allowed_classes = hashmap(&[
+        (
+            "code",
+            hashset(&[
+                // Languages
+                "language-bash",
+                "language-c",
+                "language-glsl",
+                "language-go",
+                "language-ini",
+                "language-javascript",
+                "language-json",
+                "language-xml",
+                "language-mermaid",
+                "language-protobuf",
+                "language-ruby",
+                "language-rust",
+                "language-scss",
+                "language-sql",
+                "language-toml",
+                "language-yaml",
+                // Aliases
+                "language-rs",
+                "language-clike",
+                "language-markup",
+            ]),
+        ),
+        ("section", hashset(&["footnotes"])),
+        ("span", hashset(highlight::CLASSES)),
+    ]);
+    let sanitize_url = UrlRelative::Custom(Box::new(SanitizeUrl::new(base_url, base_dir)));
+
+    let mut html_sanitizer = Builder::default();
+    html_sanitizer
+        .add_tags(&["input", "ol", "picture", "section", "source"])
+        .link_rel(Some("nofollow noopener noreferrer"))
+        .add_generic_attributes(&["align"])
+        .add_tag_attributes("a", &["id", "target"])
+        .add_tag_attributes("input", &["checked", "disabled", "type"])
+        .add_tag_attributes("li", &["id"])
+        .add_tag_attributes("source", &["media", "srcset"])
+        .allowed_classes(allowed_classes)
+        .url_relative(sanitize_url)
+        .id_prefix(Some("user-content-"));
+
+    if let Some(image_proxy) = image_proxy.cloned() {
+        let sanitize_url = SanitizeUrl::new(base_url, base_dir);
+        html_sanitizer.attribute_filter(move |element, attribute, value| {
+            // The filter runs before `url_relative`, so relative image URLs
+            // have to be resolved here to be able to proxy them.
+            let resolve = |url| match Url::parse(url) {
+                Ok(_) => Some(Cow::Borrowed(url)),
+                Err(_) => sanitize_url.evaluate(url),
+            };
+
+            match (element, attribute) {
+                ("img", "src") => {
+                    let url = resolve(value)?;
+                    Some(image_proxy.proxy_url(&url).map_or(url, Cow::Owned))
+                }
+                ("source", "srcset") => proxy_srcset(&image_proxy, value, resolve).map(Cow::Owned),
+                _ => Some(Cow::Borrowed(value)),
+            }
+        });
+    }
+
+    html_sanitizer
+}
+
+/// Rewrites all image candidates of a `srcset` attribute to go through the
+/// image proxy.
+///
+/// `srcset` is not treated as a URL attribute by `ammonia`, so candidates
+/// that can not be proxied are dropped instead of being passed through. If
+/// no candidate is left, the attribute is removed.
+fn proxy_srcset<'v>(
+    image_proxy: &ImageProxy,
+    srcset: &'v str,
+    resolve: impl Fn(&'v str) -> Option<Cow<'v, str>>,
+) -> Option<String> {
+    let candidates = srcset
+        .split(',')
+        .filter_map(|candidate| {
+            let candidate = candidate.trim();
+            let (url, descriptor) = candidate
+                .split_once(char::is_whitespace)
+                .unwrap_or((candidate, ""));
+
+            let url = resolve(url)?;
+            let parsed = Url::parse(&url).ok()?;
+            let url = match image_proxy.is_proxied(&parsed) {
+                true => url.into_owned(),
+                false => image_proxy.proxy_url(&url)?,
+            };
+
+            Some(
+                format!("{url} {}", descriptor.trim())
+                    .trim_end()
+                    .to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    (!candidates.is_empty()).then(|| candidates.join(", "))
+}
+
 /// Iterate the nodes in the CommonMark AST, used in comrak.
 fn iter_nodes<'a, F>(node: &'a AstNode<'a>, f: &F)
 where
@@ -238,26 +342,80 @@ impl UrlRelativeEvaluate<'_> for SanitizeUrl {
     }
 }
 
+/// Options for rendering text files to HTML.
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Whether code blocks should be highlighted on the server, using the
+    /// same `hljs-*` classes as highlight.js. If disabled, code blocks are
+    /// only annotated with a `language-*` class, and highlighting is left to
+    /// the client.
+    pub syntax_highlighting: bool,
+    /// The image proxy that all images should be loaded through. If not set,
+    /// images are loaded directly from their original location.
+    pub image_proxy: Option<ImageProxy>,
+}
+
 /// Renders Markdown text to sanitized HTML with a given `base_url`.
 /// See `text_to_html` for the interpretation of `base_url`.
-fn markdown_to_html(text: &str, base_url: Option<&str>, base_dir: &str) -> String {
-    let renderer = MarkdownRenderer::new(base_url, base_dir);
+fn markdown_to_html(
+    text: &str,
+    base_url: Option<&str>,
+    base_dir: &str,
+    options: &RenderOptions,
+) -> String {
+    let renderer = MarkdownRenderer::new(base_url, base_dir, options);
     renderer.to_html(text)
 }
 
+/// Renders reStructuredText to sanitized HTML with a given `base_url`.
+/// See `text_to_html` for the interpretation of `base_url`.
+fn rst_to_html(
+    text: &str,
+    base_url: Option<&str>,
+    base_dir: &str,
+    options: &RenderOptions,
+) -> String {
+    let rendered = rst::to_html(text, options);
+    html_sanitizer(base_url, base_dir, options.image_proxy.as_ref())
+        .clean(&rendered)
+        .to_string()
+}
+
+/// Renders AsciiDoc to sanitized HTML with a given `base_url`.
+/// See `text_to_html` for the interpretation of `base_url`.
+fn asciidoc_to_html(
+    text: &str,
+    base_url: Option<&str>,
+    base_dir: &str,
+    options: &RenderOptions,
+) -> String {
+    let rendered = asciidoc::to_html(text, options);
+    html_sanitizer(base_url, base_dir, options.image_proxy.as_ref())
+        .clean(&rendered)
+        .to_string()
+}
+
 /// Any file with a filename ending in one of these extensions will be rendered as Markdown.
 /// Note we also render a file as Markdown if _no_ extension is on the filename.
 static MARKDOWN_EXTENSIONS: [&str; 7] =
     ["md", "markdown", "mdown", "mdwn", "mkd", "mkdn", "mkdown"];
 
+/// Any file with a filename ending in one of these extensions will be rendered as
+/// reStructuredText.
+static RST_EXTENSIONS: [&str; 2] = ["rst", "rest"];
+
+/// Any file with a filename ending in one of these extensions will be rendered as AsciiDoc.
+static ASCIIDOC_EXTENSIONS: [&str; 3] = ["adoc", "asciidoc", "asc"];
+
 /// Renders a text file to sanitized HTML.  An appropriate rendering method is chosen depending
-/// on the extension of the supplied `filename`.
+/// on the extension of the supplied `filename`: Markdown, reStructuredText and AsciiDoc files
+/// are rendered natively, and any other files are rendered as escaped plain text.
 ///
 /// The returned text will not contain any harmful HTML tag or attribute (such as iframe,
 /// onclick, onmouseover, etc.).
 ///
 /// The `base_url` parameter will be used as the base for any relative links found in the
-/// Markdown, as long as its host part is github.com, gitlab.com, or bitbucket.org.  The
+/// document, as long as its host part is github.com, gitlab.com, or bitbucket.org.  The
 /// supplied URL will be used as a directory base whether or not the relative link is
 /// prefixed with '/'.  If `None` is passed, relative links will be omitted.
 ///
@@ -275,6 +433,36 @@ pub fn text_to_html<P: AsRef<Path>>(
     readme_path_in_pkg: P,
     base_url: Option<&str>,
     pkg_path_in_vcs: Option<P>,
+) -> String {
+    let options = RenderOptions::default();
+    text_to_html_with_options(
+        text,
+        readme_path_in_pkg,
+        base_url,
+        pkg_path_in_vcs,
+        &options,
+    )
+}
+
+/// Renders a text file to sanitized HTML, like `text_to_html`, but with
+/// additional rendering options.
+///
+/// # Examples
+///
+/// ```
+/// use crates_io_markdown::{text_to_html_with_options, RenderOptions};
+///
+/// let text = "```toml\n[dependencies]\n```";
+/// let options = RenderOptions { syntax_highlighting: true, ..Default::default() };
+/// let rendered = text_to_html_with_options(text, "README.md", None, None, &options);
+/// assert_eq!(rendered, "<pre><code class=\"language-toml\"><span class=\"hljs-section\">[dependencies]</span>\n</code></pre>\n");
+/// ```
+pub fn text_to_html_with_options<P: AsRef<Path>>(
+    text: &str,
+    readme_path_in_pkg: P,
+    base_url: Option<&str>,
+    pkg_path_in_vcs: Option<P>,
+    options: &RenderOptions,
 ) -> String {
     let path_in_vcs = match pkg_path_in_vcs {
         None => readme_path_in_pkg.as_ref().to_path_buf(),
@@ -284,12 +472,19 @@ pub fn text_to_html<P: AsRef<Path>>(
     let base_dir = path_in_vcs.parent().and_then(|p| p.to_str()).unwrap_or("");
 
     if path_in_vcs.extension().is_none() {
-        return markdown_to_html(text, base_url, base_dir);
+        return markdown_to_html(text, base_url, base_dir, options);
     }
 
     if let Some(ext) = path_in_vcs.extension().and_then(|ext| ext.to_str()) {
-        if MARKDOWN_EXTENSIONS.contains(&ext.to_lowercase().as_str()) {
-            return markdown_to_html(text, base_url, base_dir);
+        let ext = ext.to_lowercase();
+        if MARKDOWN_EXTENSIONS.contains(&ext.as_str()) {
+            return markdown_to_html(text, base_url, base_dir, options);
+        }
+        if RST_EXTENSIONS.contains(&ext.as_str()) {
+            return rst_to_html(text, base_url, base_dir, options);
+        }
+        if ASCIIDOC_EXTENSIONS.contains(&ext.as_str()) {
+            return asciidoc_to_html(text, base_url, base_dir, options);
         }
     }
 
@@ -321,13 +516,13 @@ mod tests {
     #[test]
     fn empty_text() {
         let text = "";
-        assert_eq!(markdown_to_html(text, None, ""), "");
+        assert_eq!(markdown_to_html(text, None, "", &Default::default()), "");
     }
 
     #[test]
     fn text_with_script_tag() {
         let text = "foo_readme\n\n<script>alert('Hello World')</script>";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(text, None, "", &Default::default()), @r###"
         <p>foo_readme</p>
         &lt;script&gt;alert('Hello World')&lt;/script&gt;
         "###);
@@ -336,7 +531,7 @@ mod tests {
     #[test]
     fn text_with_iframe_tag() {
         let text = "foo_readme\n\n<iframe>alert('Hello World')</iframe>";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(text, None, "", &Default::default()), @r###"
         <p>foo_readme</p>
         &lt;iframe&gt;alert('Hello World')&lt;/iframe&gt;
         "###);
@@ -345,7 +540,7 @@ mod tests {
     #[test]
     fn text_with_unknown_tag() {
         let text = "foo_readme\n\n<unknown>alert('Hello World')</unknown>";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(text, None, "", &Default::default()), @r###"
         <p>foo_readme</p>
         <p>alert('Hello World')</p>
         "###);
@@ -354,7 +549,7 @@ mod tests {
     #[test]
     fn text_with_kbd_tag() {
         let text = "foo_readme\n\nHello <kbd>alert('Hello World')</kbd>";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(text, None, "", &Default::default()), @r###"
         <p>foo_readme</p>
         <p>Hello <kbd>alert('Hello World')</kbd></p>
         "###);
@@ -363,7 +558,7 @@ mod tests {
     #[test]
     fn text_with_inline_javascript() {
         let text = r#"foo_readme\n\n<a href="https://crates.io/crates/cargo-registry" onclick="window.alert('Got you')">Crate page</a>"#;
-        assert_snapshot!(markdown_to_html(text, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(text, None, "", &Default::default()), @r###"
         <p>foo_readme\n\n<a href="https://crates.io/crates/cargo-registry" rel="nofollow noopener noreferrer">Crate page</a></p>
         "###);
     }
@@ -373,7 +568,7 @@ mod tests {
     #[test]
     fn text_with_fancy_single_quotes() {
         let text = "wb’";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(text, None, "", &Default::default()), @r###"
         <p>wb’</p>
         "###);
     }
@@ -381,7 +576,7 @@ mod tests {
     #[test]
     fn code_block_with_syntax_highlighting() {
         let code_block = "```rust\nprintln!(\"Hello World\");\n```";
-        assert_snapshot!(markdown_to_html(code_block, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(code_block, None, "", &Default::default()), @r###"
         <pre><code class="language-rust">println!("Hello World");
         </code></pre>
         "###);
@@ -390,7 +585,7 @@ mod tests {
     #[test]
     fn code_block_with_mermaid_highlighting() {
         let code_block = "```mermaid\ngraph LR\nA --> C\nC --> A\n```";
-        assert_snapshot!(markdown_to_html(code_block, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(code_block, None, "", &Default::default()), @r###"
         <pre><code class="language-mermaid">graph LR
         A --&gt; C
         C --&gt; A
@@ -401,7 +596,7 @@ mod tests {
     #[test]
     fn code_block_with_syntax_highlighting_even_if_annot_has_no_run() {
         let code_block = "```rust, no_run\nprintln!(\"Hello World\");\n```";
-        assert_snapshot!(markdown_to_html(code_block, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(code_block, None, "", &Default::default()), @r###"
         <pre><code class="language-rust">println!("Hello World");
         </code></pre>
         "###);
@@ -410,28 +605,65 @@ mod tests {
     #[test]
     fn code_block_with_syntax_highlighting_with_aliases() {
         let code_block = "```rs, no_run\nprintln!(\"Hello World\");\n```";
-        assert_snapshot!(markdown_to_html(code_block, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(code_block, None, "", &Default::default()), @r###"
         <pre><code class="language-rs">println!("Hello World");
         </code></pre>
         "###);
 
         let code_block = "```markup, no_run\n<hello>World</hello>\n```";
-        assert_snapshot!(markdown_to_html(code_block, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(code_block, None, "", &Default::default()), @r###"
         <pre><code class="language-markup">&lt;hello&gt;World&lt;/hello&gt;
         </code></pre>
         "###);
 
         let code_block = "```clike, no_run\nint main() { }\n```";
-        assert_snapshot!(markdown_to_html(code_block, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(code_block, None, "", &Default::default()), @r###"
         <pre><code class="language-clike">int main() { }
         </code></pre>
         "###);
     }
 
+    #[test]
+    fn code_block_with_server_side_syntax_highlighting() {
+        let options = RenderOptions {
+            syntax_highlighting: true,
+            ..Default::default()
+        };
+
+        let code_block =
+            "```rust, no_run\nfn main() {\n    println!(\"Hello World\"); // <3\n}\n```";
+        assert_snapshot!(markdown_to_html(code_block, None, "", &options), @r###"
+        <pre><code class="language-rust"><span class="hljs-keyword">fn</span> <span class="hljs-title function_">main</span>() {
+            <span class="hljs-built_in">println!</span>(<span class="hljs-string">"Hello World"</span>); <span class="hljs-comment">// &lt;3</span>
+        }
+        </code></pre>
+        "###);
+
+        let code_block = "```mermaid\ngraph LR\nA --> C\n```";
+        assert_snapshot!(markdown_to_html(code_block, None, "", &options), @r###"
+        <pre><code class="language-mermaid">graph LR
+        A --&gt; C
+        </code></pre>
+        "###);
+
+        let text = ".. code-block:: toml\n\n    [dependencies]\n    foo = \"1\"\n";
+        assert_snapshot!(rst_to_html(text, None, "", &options), @r###"
+        <pre><code class="language-toml"><span class="hljs-section">[dependencies]</span>
+        <span class="hljs-attr">foo</span> = <span class="hljs-string">"1"</span>
+        </code></pre>
+        "###);
+
+        let text = "[source,bash]\n----\nexport FOO=1 # comment\n----\n";
+        assert_snapshot!(asciidoc_to_html(text, None, "", &options), @r###"
+        <pre><code class="language-bash"><span class="hljs-keyword">export</span> FOO=<span class="hljs-number">1</span> <span class="hljs-comment"># comment</span>
+        </code></pre>
+        "###);
+    }
+
     #[test]
     fn text_with_forbidden_class_attribute() {
         let text = "<p class='bad-class'>Hello World!</p>";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(text, None, "", &Default::default()), @r###"
         <p>Hello World!</p>
         "###);
     }
@@ -439,7 +671,7 @@ mod tests {
     #[test]
     fn text_with_footnote() {
         let text = "Hello World![^1]\n\n[^1]: Hello Ferris, actually!";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(text, None, "", &Default::default()), @r###"
         <p>Hello World!<sup><a href="#user-content-fn-1" id="user-content-fnref-1" rel="nofollow noopener noreferrer">1</a></sup></p>
         <section class="footnotes">
         <ol>
@@ -467,7 +699,7 @@ There can also be some text in between!
 
     Add as many paragraphs as you like."#;
 
-        assert_snapshot!(markdown_to_html(text, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(text, None, "", &Default::default()), @r###"
         <p>Here's a simple footnote,<sup><a href="#user-content-fn-1" id="user-content-fnref-1" rel="nofollow noopener noreferrer">1</a></sup> and here's a longer one.<sup><a href="#user-content-fn-bignote" id="user-content-fnref-bignote" rel="nofollow noopener noreferrer">2</a></sup></p>
         <p>There can also be some text in between!</p>
         <section class="footnotes">
@@ -503,7 +735,7 @@ There can also be some text in between!
                     if extra_slash { "/" } else { "" },
                 );
 
-                let result = markdown_to_html(absolute, Some(&url), "");
+                let result = markdown_to_html(absolute, Some(&url), "", &Default::default());
                 assert_eq!(
                     result,
                     format!(
@@ -511,7 +743,7 @@ There can also be some text in between!
                     )
                 );
 
-                let result = markdown_to_html(relative, Some(&url), "");
+                let result = markdown_to_html(relative, Some(&url), "", &Default::default());
                 assert_eq!(
                     result,
                     format!(
@@ -519,7 +751,7 @@ There can also be some text in between!
                     )
                 );
 
-                let result = markdown_to_html(image, Some(&url), "");
+                let result = markdown_to_html(image, Some(&url), "", &Default::default());
                 assert_eq!(
                     result,
                     format!(
@@ -527,7 +759,7 @@ There can also be some text in between!
                     )
                 );
 
-                let result = markdown_to_html(html_image, Some(&url), "");
+                let result = markdown_to_html(html_image, Some(&url), "", &Default::default());
                 assert_eq!(
                     result,
                     format!(
@@ -535,7 +767,7 @@ There can also be some text in between!
                     )
                 );
 
-                let result = markdown_to_html(svg, Some(&url), "");
+                let result = markdown_to_html(svg, Some(&url), "", &Default::default());
                 assert_eq!(
                     result,
                     format!(
@@ -543,7 +775,7 @@ There can also be some text in between!
                     )
                 );
 
-                let result = markdown_to_html(svg, Some(&url), "subdir");
+                let result = markdown_to_html(svg, Some(&url), "subdir", &Default::default());
                 assert_eq!(
                     result,
                     format!(
@@ -551,7 +783,8 @@ There can also be some text in between!
                     )
                 );
 
-                let result = markdown_to_html(svg, Some(&url), "subdir1/subdir2");
+                let result =
+                    markdown_to_html(svg, Some(&url), "subdir1/subdir2", &Default::default());
                 assert_eq!(
                     result,
                     format!(
@@ -561,7 +794,12 @@ There can also be some text in between!
             }
         }
 
-        let result = markdown_to_html(absolute, Some("https://google.com/"), "");
+        let result = markdown_to_html(
+            absolute,
+            Some("https://google.com/"),
+            "",
+            &Default::default(),
+        );
         assert_eq!(
             result,
             "<p><a rel=\"nofollow noopener noreferrer\">hi</a></p>\n"
@@ -573,7 +811,7 @@ There can also be some text in between!
         let text =
             "[![crates.io](https://img.shields.io/crates/v/clap.svg)](https://crates.io/crates/clap)";
         let repository = "https://github.com/kbknapp/clap-rs/";
-        assert_snapshot!(markdown_to_html(text, Some(repository), ""), @r###"
+        assert_snapshot!(markdown_to_html(text, Some(repository), "", &Default::default()), @r###"
         <p><a href="https://crates.io/crates/clap" rel="nofollow noopener noreferrer"><img src="https://img.shields.io/crates/v/clap.svg" alt="crates.io"></a></p>
         "###);
     }
@@ -582,11 +820,11 @@ There can also be some text in between!
     fn rustdoc_links() {
         let repository = "https://github.com/foo/bar/";
 
-        assert_snapshot!(markdown_to_html("[stylish](::stylish)", Some(repository), ""), @r###"
+        assert_snapshot!(markdown_to_html("[stylish](::stylish)", Some(repository), "", &Default::default()), @r###"
         <p><a rel="nofollow noopener noreferrer">stylish</a></p>
         "###);
 
-        assert_snapshot!(markdown_to_html("[Display](stylish::Display)", Some(repository), ""), @r###"
+        assert_snapshot!(markdown_to_html("[Display](stylish::Display)", Some(repository), "", &Default::default()), @r###"
         <p><a rel="nofollow noopener noreferrer">Display</a></p>
         "###);
     }
@@ -624,9 +862,33 @@ There can also be some text in between!
         "###);
     }
 
+    #[test]
+    fn text_to_html_renders_rst_and_asciidoc() {
+        for f in &["readme.rst", "README.REST", "s/readme.rst"] {
+            assert_eq!(
+                text_to_html("*lobster*", f, None, None),
+                "<p><em>lobster</em></p>\n"
+            );
+        }
+
+        for f in &["readme.adoc", "README.asciidoc", "s/readme.asc"] {
+            assert_eq!(
+                text_to_html("_lobster_", f, None, None),
+                "<p><em>lobster</em></p>\n"
+            );
+        }
+
+        assert_snapshot!(text_to_html("`lobster <docs/lobster>`_", "s/readme.rst", Some("https://github.com/rust-lang/test"), None), @r###"
+        <p><a href="https://github.com/rust-lang/test/blob/HEAD/s/docs/lobster" rel="nofollow noopener noreferrer">lobster</a></p>
+        "###);
+        assert_snapshot!(text_to_html("link:docs/lobster[lobster]", "s/readme.adoc", Some("https://github.com/rust-lang/test"), None), @r###"
+        <p><a href="https://github.com/rust-lang/test/blob/HEAD/s/docs/lobster" rel="nofollow noopener noreferrer">lobster</a></p>
+        "###);
+    }
+
     #[test]
     fn text_to_html_renders_other_things() {
-        for f in &["readme.exe", "readem.org", "blah.adoc"] {
+        for f in &["readme.exe", "readem.org", "blah.txt"] {
             assert_eq!(
                 text_to_html("<script>lobster</script>\n\nis my friend\n", f, None, None),
                 "&lt;script&gt;lobster&lt;/script&gt;<br>\n<br>\nis my friend<br>\n"
@@ -637,7 +899,7 @@ There can also be some text in between!
     #[test]
     fn header_has_tags() {
         let text = "# My crate\n\nHello, world!\n";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(text, None, "", &Default::default()), @r###"
         <h1><a href="#my-crate" id="user-content-my-crate" rel="nofollow noopener noreferrer"></a>My crate</h1>
         <p>Hello, world!</p>
         "###);
@@ -647,7 +909,7 @@ There can also be some text in between!
     fn manual_anchor_is_sanitized() {
         let text =
             "<h1><a href=\"#my-crate\" id=\"my-crate\"></a>My crate</h1>\n<p>Hello, world!</p>\n";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(text, None, "", &Default::default()), @r###"
         <h1><a href="#my-crate" id="user-content-my-crate" rel="nofollow noopener noreferrer"></a>My crate</h1>
         <p>Hello, world!</p>
         "###);
@@ -656,7 +918,7 @@ There can also be some text in between!
     #[test]
     fn tables_with_rowspan_and_colspan() {
         let text = "<table><tr><th rowspan=\"1\" colspan=\"2\">Target</th></tr></table>\n";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(text, None, "", &Default::default()), @r###"
         <table><tbody><tr><th rowspan="1" colspan="2">Target</th></tr></tbody></table>
         "###);
     }
@@ -664,7 +926,7 @@ There can also be some text in between!
     #[test]
     fn text_alignment() {
         let text = "<h1 align=\"center\">foo-bar</h1>\n<h5 align=\"center\">Hello World!</h5>\n";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(text, None, "", &Default::default()), @r###"
         <h1 align="center">foo-bar</h1>
         <h5 align="center">Hello World!</h5>
         "###);
@@ -674,7 +936,7 @@ There can also be some text in between!
     fn image_alignment() {
         let text =
             "<p align=\"center\"><img src=\"https://img.shields.io/crates/v/clap.svg\" alt=\"\"></p>\n";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(text, None, "", &Default::default()), @r###"
         <p align="center"><img src="https://img.shields.io/crates/v/clap.svg" alt=""></p>
         "###);
     }
@@ -687,11 +949,54 @@ There can also be some text in between!
     <img src="https://test.crates.io/logo.svg" alt="logo" width="200">
 </picture>
         "#;
-        assert_snapshot!(markdown_to_html(text, None, ""), @r###"
+        assert_snapshot!(markdown_to_html(text, None, "", &Default::default()), @r###"
         <picture>
             <source media="(prefers-color-scheme: dark)" srcset="https://test.crates.io/logo_dark.svg">
             <img src="https://test.crates.io/logo.svg" alt="logo" width="200">
         </picture>
         "###);
     }
+
+    #[test]
+    fn images_with_image_proxy() {
+        let options = RenderOptions {
+            image_proxy: Some(ImageProxy::new(
+                Url::parse("https://camo.example.com/").unwrap(),
+                "secret",
+            )),
+            ..Default::default()
+        };
+
+        let text = "![logo](https://example.com/logo.png) ![local](docs/logo.png) [link](https://example.com/)\n\n<img src=\"https://camo.example.com/abc/def\">\n";
+        assert_snapshot!(markdown_to_html(text, Some("https://github.com/rust-lang/test"), "", &options), @r###"
+        <p><img src="https://camo.example.com/c1d283efcedc4d3c9b45dd80a12c028e40866098/68747470733a2f2f6578616d706c652e636f6d2f6c6f676f2e706e67" alt="logo"> <img src="https://camo.example.com/4f7ff7aadad52ae07248ee3936efe3dd6066eed2/68747470733a2f2f6769746875622e636f6d2f727573742d6c616e672f746573742f7261772f484541442f646f63732f6c6f676f2e706e67" alt="local"> <a href="https://example.com/" rel="nofollow noopener noreferrer">link</a></p>
+        <img src="https://camo.example.com/abc/def">
+        "###);
+
+        // Relative images are dropped if there is no base URL to resolve them.
+        assert_snapshot!(markdown_to_html(text, None, "", &options), @r###"
+        <p><img src="https://camo.example.com/c1d283efcedc4d3c9b45dd80a12c028e40866098/68747470733a2f2f6578616d706c652e636f6d2f6c6f676f2e706e67" alt="logo"> <img alt="local"> <a href="https://example.com/" rel="nofollow noopener noreferrer">link</a></p>
+        <img src="https://camo.example.com/abc/def">
+        "###);
+
+        let text = r#"
+<picture>
+    <source media="(prefers-color-scheme: dark)" srcset="https://example.com/logo_dark.png, https://example.com/logo_dark@2x.png 2x">
+    <source srcset="javascript:alert(1)">
+    <img src="https://camo.example.com.evil.com/logo.png">
+</picture>
+        "#;
+        assert_snapshot!(markdown_to_html(text, None, "", &options), @r###"
+        <picture>
+            <source media="(prefers-color-scheme: dark)" srcset="https://camo.example.com/8193e0566792b6a734e105d9ff65bea78f590698/68747470733a2f2f6578616d706c652e636f6d2f6c6f676f5f6461726b2e706e67, https://camo.example.com/03b7e41cf63b40cd8dc733026acf2a6f625f6e3e/68747470733a2f2f6578616d706c652e636f6d2f6c6f676f5f6461726b4032782e706e67 2x">
+            <source>
+            <img src="https://camo.example.com/4c90ee9519a3845bf07f527a0665c5556698348c/68747470733a2f2f63616d6f2e6578616d706c652e636f6d2e6576696c2e636f6d2f6c6f676f2e706e67">
+        </picture>
+        "###);
+
+        let text = "image:https://example.com/logo.png[logo]\n";
+        assert_snapshot!(asciidoc_to_html(text, None, "", &options), @r###"
+        <p><img src="https://camo.example.com/c1d283efcedc4d3c9b45dd80a12c028e40866098/68747470733a2f2f6578616d706c652e636f6d2f6c6f676f2e706e67" alt="logo"></p>
+        "###);
+    }
 }
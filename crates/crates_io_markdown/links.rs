@@ -0,0 +1,50 @@
+//! Extraction of links from rendered documents.
+
+use htmlescape::decode_html;
+
+/// Extracts the unique absolute `http(s)` link targets from HTML that was
+/// rendered by `text_to_html`, in the order of their first appearance.
+///
+/// # Examples
+///
+/// ```
+/// use crates_io_markdown::{extract_links, text_to_html};
+///
+/// let text = "[Docs](https://docs.rs/foo?a=1&b=2), [again](https://docs.rs/foo?a=1&b=2) and [usage](#usage)";
+/// let rendered = text_to_html(text, "README.md", None, None);
+/// assert_eq!(extract_links(&rendered), vec!["https://docs.rs/foo?a=1&b=2"]);
+/// ```
+pub fn extract_links(html: &str) -> Vec<String> {
+    let mut links: Vec<String> = Vec::new();
+    let mut offset = 0;
+
+    while let Some(pos) = html[offset..].find("<a ") {
+        let start = offset + pos + 3;
+        let Some(len) = html[start..].find('>') else {
+            break;
+        };
+        offset = start + len;
+
+        let Some(href) = attribute(&html[start..offset], "href") else {
+            continue;
+        };
+        let href = decode_html(href).unwrap_or_else(|_| href.to_string());
+        if (href.starts_with("http://") || href.starts_with("https://")) && !links.contains(&href) {
+            links.push(href);
+        }
+    }
+
+    links
+}
+
+/// Returns the value of a double-quoted attribute within the attributes of a
+/// tag, as produced by the HTML sanitizer.
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let needle = format!("{name}=\"");
+    let start = attributes
+        .match_indices(&needle)
+        .find(|(index, _)| *index == 0 || attributes[..*index].ends_with(' '))
+        .map(|(index, _)| index + needle.len())?;
+    let len = attributes[start..].find('"')?;
+    Some(&attributes[start..start + len])
+}
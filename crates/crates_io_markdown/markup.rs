@@ -0,0 +1,200 @@
+//! Helpers shared by the reStructuredText and AsciiDoc renderers.
+//!
+//! The renderers produce unsanitized HTML, which is cleaned up by the same
+//! `ammonia` configuration as the Markdown output afterwards. Text content
+//! still has to be escaped though, since it would otherwise be interpreted
+//! as HTML.
+
+use crate::{highlight, RenderOptions};
+use comrak::Anchorizer;
+use htmlescape::{encode_attribute, encode_minimal};
+
+/// Appends a heading with an anchor link, in the same format as `comrak`
+/// renders Markdown headings.
+pub fn heading(
+    html: &mut String,
+    anchorizer: &mut Anchorizer,
+    level: usize,
+    title: &str,
+    inner: &str,
+) {
+    let level = level.clamp(1, 6);
+    let id = anchorizer.anchorize(title.to_string());
+    html.push_str(&format!(
+        "<h{level}><a href=\"#{id}\" id=\"{id}\"></a>{inner}</h{level}>\n"
+    ));
+}
+
+/// Appends a code block, with a `language-*` class if the language is known.
+///
+/// If syntax highlighting is enabled and the language is supported, the
+/// code is highlighted as well.
+pub fn code_block(html: &mut String, options: &RenderOptions, language: Option<&str>, code: &str) {
+    let language = language.filter(|language| !language.is_empty());
+
+    html.push_str("<pre><code");
+    if let Some(language) = language {
+        html.push_str(" class=\"language-");
+        html.push_str(&encode_attribute(&language.to_lowercase()));
+        html.push('"');
+    }
+    html.push('>');
+    let highlighted = language
+        .filter(|_| options.syntax_highlighting)
+        .and_then(|language| highlight::highlight(language, code));
+    match highlighted {
+        Some(highlighted) => html.push_str(&highlighted),
+        None => html.push_str(&encode_minimal(code)),
+    }
+    if !code.is_empty() && !code.ends_with('\n') {
+        html.push('\n');
+    }
+    html.push_str("</code></pre>\n");
+}
+
+/// Appends an admonition like "Note" or "Warning" as a block quote.
+pub fn admonition(html: &mut String, label: &str, inner: &str) {
+    html.push_str("<blockquote>\n<p><strong>");
+    html.push_str(&encode_minimal(label));
+    html.push_str("</strong></p>\n");
+    html.push_str(inner);
+    html.push_str("</blockquote>\n");
+}
+
+/// An image with optional dimensions and an optional link target.
+#[derive(Debug, Clone, Default)]
+pub struct Image {
+    pub src: String,
+    pub alt: Option<String>,
+    pub width: Option<String>,
+    pub height: Option<String>,
+    pub target: Option<String>,
+}
+
+impl Image {
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        if let Some(target) = &self.target {
+            html.push_str(&format!("<a href=\"{}\">", encode_attribute(target)));
+        }
+        html.push_str(&format!("<img src=\"{}\"", encode_attribute(&self.src)));
+        if let Some(alt) = &self.alt {
+            html.push_str(&format!(" alt=\"{}\"", encode_attribute(alt)));
+        }
+        if let Some(width) = &self.width {
+            html.push_str(&format!(" width=\"{}\"", encode_attribute(width)));
+        }
+        if let Some(height) = &self.height {
+            html.push_str(&format!(" height=\"{}\"", encode_attribute(height)));
+        }
+        html.push('>');
+        if self.target.is_some() {
+            html.push_str("</a>");
+        }
+        html
+    }
+}
+
+/// Returns a link with the given (already escaped) inner HTML.
+pub fn link(href: &str, inner: &str) -> String {
+    format!("<a href=\"{}\">{inner}</a>", encode_attribute(href))
+}
+
+/// Returns the length of the bare `http(s)://` URL at the start of the text,
+/// excluding trailing punctuation, or `None` if the text does not start with
+/// a URL.
+pub fn url_len(text: &str) -> Option<usize> {
+    let scheme_len = ["https://", "http://"]
+        .iter()
+        .find(|scheme| text.starts_with(*scheme))?
+        .len();
+
+    let end = text
+        .find(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"' | '[' | ']'))
+        .unwrap_or(text.len());
+
+    let url = text[..end].trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '\'']);
+    (url.len() > scheme_len).then_some(url.len())
+}
+
+/// Whether inline markup may start after the given character, i.e. at the
+/// start of the text, after whitespace or after opening punctuation.
+pub fn can_start_markup(prev: Option<char>) -> bool {
+    match prev {
+        None => true,
+        Some(c) => {
+            c.is_whitespace() || matches!(c, '(' | '[' | '{' | '<' | '\'' | '"' | '-' | '/' | ':')
+        }
+    }
+}
+
+/// Finds the end of inline markup that started right before `text`, i.e.
+/// the first occurrence of `delimiter` that is preceded by a non-whitespace
+/// character and followed by the end of the text, whitespace or punctuation.
+///
+/// Returns the byte offset of the delimiter within `text`.
+pub fn find_closing(text: &str, delimiter: &str) -> Option<usize> {
+    if text.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let mut offset = 0;
+    while let Some(pos) = text[offset..].find(delimiter) {
+        let pos = offset + pos;
+        let before = text[..pos].chars().next_back();
+        let after = text[pos + delimiter.len()..].chars().next();
+
+        let valid_before = before.is_some_and(|c| !c.is_whitespace());
+        let valid_after = after.map_or(true, |c| !c.is_alphanumeric());
+        if valid_before && valid_after {
+            return Some(pos);
+        }
+
+        offset = pos + 1;
+    }
+
+    None
+}
+
+/// Returns the number of leading whitespace characters of the line.
+pub fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Removes up to `indent` leading whitespace characters from the line.
+pub fn dedent(line: &str, indent: usize) -> &str {
+    let trimmed = line.trim_start();
+    let offset = (line.len() - trimmed.len()).min(indent);
+    &line[offset..]
+}
+
+/// Removes the indentation that all non-blank lines have in common.
+pub fn dedent_all<'a>(lines: &[&'a str]) -> Vec<&'a str> {
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indentation(line))
+        .min()
+        .unwrap_or(0);
+
+    lines.iter().map(|line| dedent(line, indent)).collect()
+}
+
+/// Removes leading and trailing blank lines.
+pub fn trim_blank_lines<'a, 'b>(mut lines: &'b [&'a str]) -> &'b [&'a str] {
+    while lines.first().is_some_and(|line| line.trim().is_empty()) {
+        lines = &lines[1..];
+    }
+    while lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines = &lines[..lines.len() - 1];
+    }
+    lines
+}
+
+/// Splits the text into lines, expanding tabs and removing trailing
+/// whitespace.
+pub fn lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| line.replace('\t', "    ").trim_end().to_string())
+        .collect()
+}
@@ -0,0 +1,755 @@
+//! Render a subset of reStructuredText to HTML.
+//!
+//! Supported are section titles, transitions, paragraphs, bullet and
+//! enumerated lists, block quotes, literal blocks, the `code`, `image`,
+//! `figure`, `raw` and admonition directives, hyperlink targets, image and
+//! text substitutions (as commonly used for badges) and the usual inline
+//! markup. Grid tables are rendered as preformatted text, comments and
+//! unknown directives are skipped, and everything else is rendered as
+//! paragraphs.
+
+use crate::markup::{self, Image};
+use crate::RenderOptions;
+use comrak::Anchorizer;
+use htmlescape::encode_minimal;
+use std::collections::HashMap;
+
+/// Renders reStructuredText to unsanitized HTML.
+pub fn to_html(text: &str, options: &RenderOptions) -> String {
+    let lines = markup::lines(text);
+    let lines = lines.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let mut renderer = Renderer {
+        options: options.clone(),
+        definitions: Definitions::collect(&lines),
+        anchorizer: Anchorizer::new(),
+        title_styles: Vec::new(),
+        html: String::new(),
+    };
+    renderer.blocks(&lines);
+    renderer.html
+}
+
+/// The hyperlink targets and substitution definitions of a document, which
+/// may be referenced before they are defined.
+#[derive(Default)]
+struct Definitions {
+    targets: HashMap<String, String>,
+    substitutions: HashMap<String, Substitution>,
+}
+
+enum Substitution {
+    Image(Image),
+    Text(String),
+}
+
+impl Definitions {
+    fn collect(lines: &[&str]) -> Self {
+        let mut definitions = Self::default();
+
+        for (i, line) in lines.iter().enumerate() {
+            let Some(rest) = line.trim_start().strip_prefix(".. ") else {
+                continue;
+            };
+
+            if let Some(target) = rest.strip_prefix('_') {
+                if let Some((name, url)) = split_target(target) {
+                    definitions.targets.insert(normalize_name(name), url);
+                }
+            } else if let Some(rest) = rest.strip_prefix('|') {
+                let Some((name, directive)) = rest.split_once("| ") else {
+                    continue;
+                };
+
+                let body = &lines[i + 1..i + 1 + indented_block(&lines[i + 1..])];
+                let (options, _) = directive_body(body);
+
+                let substitution = if let Some(src) = directive.strip_prefix("image::") {
+                    Substitution::Image(image(src, &options))
+                } else if let Some(text) = directive.strip_prefix("replace::") {
+                    Substitution::Text(text.trim().to_string())
+                } else {
+                    continue;
+                };
+
+                definitions
+                    .substitutions
+                    .insert(normalize_name(name), substitution);
+            }
+        }
+
+        definitions
+    }
+}
+
+/// Splits a hyperlink target like `name: url` or `` `some name`: url `` into
+/// its name and URL. Internal targets without a URL are ignored.
+fn split_target(target: &str) -> Option<(&str, String)> {
+    let (name, url) = match target.strip_prefix('`') {
+        Some(target) => target.split_once("`:")?,
+        None => target.split_once(':')?,
+    };
+
+    // URLs may be split across multiple lines, but not contain whitespace.
+    let url = url.split_whitespace().collect::<String>();
+    (!url.is_empty()).then_some((name, url))
+}
+
+/// Normalizes reference and substitution names, which are case-insensitive
+/// and whitespace-neutral.
+fn normalize_name(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Returns the number of lines of the indented block at the start of
+/// `lines`, excluding trailing blank lines.
+fn indented_block(lines: &[&str]) -> usize {
+    let len = lines
+        .iter()
+        .position(|line| !line.is_empty() && markup::indentation(line) == 0)
+        .unwrap_or(lines.len());
+
+    lines[..len]
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .map_or(0, |index| index + 1)
+}
+
+/// Splits the body of a directive into its `:name: value` options and its
+/// content.
+fn directive_body<'a>(body: &[&'a str]) -> (HashMap<&'a str, &'a str>, Vec<&'a str>) {
+    let body = markup::dedent_all(body);
+
+    let mut options = HashMap::new();
+    let mut content_start = 0;
+    for line in &body {
+        let Some((name, value)) = line
+            .strip_prefix(':')
+            .and_then(|option| option.split_once(':'))
+        else {
+            break;
+        };
+
+        options.insert(name, value.trim());
+        content_start += 1;
+    }
+
+    let content = markup::trim_blank_lines(&body[content_start..]).to_vec();
+    (options, content)
+}
+
+fn image(src: &str, options: &HashMap<&str, &str>) -> Image {
+    let option = |name: &str| options.get(name).map(|value| value.to_string());
+
+    Image {
+        src: src.split_whitespace().collect(),
+        alt: option("alt"),
+        width: option("width"),
+        height: option("height"),
+        target: option("target"),
+    }
+}
+
+/// Whether the line is a section title adornment or a transition, i.e. a
+/// line consisting of the same punctuation character repeated.
+fn is_adornment(line: &str) -> bool {
+    let mut chars = line.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+
+    line.len() >= 3 && first.is_ascii_punctuation() && chars.all(|c| c == first)
+}
+
+fn is_grid_table_border(line: &str) -> bool {
+    (line.starts_with("+-") || line.starts_with("+="))
+        && line.ends_with('+')
+        && line.chars().all(|c| matches!(c, '+' | '-' | '='))
+}
+
+#[derive(Debug, PartialEq)]
+enum ListKind {
+    Bullet(char),
+    Enumerated,
+}
+
+/// Returns the kind of list item that the line starts, and the offset of
+/// its text.
+fn list_marker(line: &str) -> Option<(ListKind, usize)> {
+    let (kind, marker_len) = match line.chars().next()? {
+        c @ ('-' | '*' | '+' | '•') => (ListKind::Bullet(c), c.len_utf8()),
+        '(' => {
+            let digits = line[1..].find(|c: char| !c.is_ascii_digit())?;
+            if digits == 0 || !line[1 + digits..].starts_with(')') {
+                return None;
+            }
+            (ListKind::Enumerated, digits + 2)
+        }
+        _ => {
+            let digits = line
+                .find(|c: char| !c.is_ascii_digit() && c != '#')
+                .unwrap_or(line.len());
+            let valid = digits > 0 && (&line[..digits] == "#" || !line[..digits].contains('#'));
+            if !valid || !line[digits..].starts_with(['.', ')']) {
+                return None;
+            }
+            (ListKind::Enumerated, digits + 1)
+        }
+    };
+
+    let text = &line[marker_len..];
+    if text.is_empty() {
+        return Some((kind, marker_len));
+    }
+    if !text.starts_with(' ') {
+        return None;
+    }
+
+    Some((kind, line.len() - text.trim_start().len()))
+}
+
+struct Renderer {
+    options: RenderOptions,
+    definitions: Definitions,
+    anchorizer: Anchorizer,
+    /// The adornment styles of the section titles in the order of their
+    /// first appearance, which determines the section levels.
+    title_styles: Vec<(char, bool)>,
+    html: String,
+}
+
+impl Renderer {
+    fn blocks(&mut self, lines: &[&str]) {
+        let mut i = 0;
+        while i < lines.len() {
+            if lines[i].is_empty() {
+                i += 1;
+            } else {
+                i += self.block(&lines[i..]);
+            }
+        }
+    }
+
+    /// Renders the blocks into a separate string instead of the output.
+    fn render(&mut self, lines: &[&str]) -> String {
+        let html = std::mem::take(&mut self.html);
+        self.blocks(lines);
+        std::mem::replace(&mut self.html, html)
+    }
+
+    /// Renders the block at the start of `lines`, and returns the number of
+    /// lines it consists of.
+    fn block(&mut self, lines: &[&str]) -> usize {
+        let line = lines[0];
+
+        if markup::indentation(line) > 0 {
+            let len = indented_block(lines);
+            let inner = self.render(&markup::dedent_all(&lines[..len]));
+            self.html.push_str("<blockquote>\n");
+            self.html.push_str(&inner);
+            self.html.push_str("</blockquote>\n");
+            return len;
+        }
+
+        if let Some(len) = self.section_title(lines) {
+            return len;
+        }
+
+        if is_adornment(line) && line.len() >= 4 {
+            self.html.push_str("<hr>\n");
+            return 1;
+        }
+
+        if line == ".." || line.starts_with(".. ") {
+            return self.explicit_markup(lines);
+        }
+
+        if is_grid_table_border(line) {
+            let len = lines
+                .iter()
+                .position(|line| line.is_empty())
+                .unwrap_or(lines.len());
+            markup::code_block(
+                &mut self.html,
+                &self.options,
+                None,
+                &lines[..len].join("\n"),
+            );
+            return len;
+        }
+
+        if let Some(len) = self.list(lines) {
+            return len;
+        }
+
+        self.paragraph(lines)
+    }
+
+    fn section_title(&mut self, lines: &[&str]) -> Option<usize> {
+        let (title, style, len) = if is_adornment(lines[0]) {
+            let title = lines.get(1)?.trim();
+            let underline = *lines.get(2)?;
+            if title.is_empty() || underline != lines[0] {
+                return None;
+            }
+            (title, (lines[0].chars().next()?, true), 3)
+        } else {
+            let title = lines[0];
+            let underline = *lines.get(1)?;
+            let min_len = title.chars().count().min(4);
+            if !is_adornment(underline) || underline.chars().count() < min_len {
+                return None;
+            }
+            (title, (underline.chars().next()?, false), 2)
+        };
+
+        let level = match self.title_styles.iter().position(|s| *s == style) {
+            Some(index) => index + 1,
+            None => {
+                self.title_styles.push(style);
+                self.title_styles.len()
+            }
+        };
+
+        let inner = self.inline(title);
+        markup::heading(&mut self.html, &mut self.anchorizer, level, title, &inner);
+        Some(len)
+    }
+
+    /// Renders comments, directives, hyperlink targets and substitution
+    /// definitions, which all start with `.. `.
+    fn explicit_markup(&mut self, lines: &[&str]) -> usize {
+        let len = 1 + indented_block(&lines[1..]);
+        let body = &lines[1..len];
+        let rest = lines[0].strip_prefix("..").unwrap_or_default().trim();
+
+        // Hyperlink targets and substitution definitions were already
+        // collected in `Definitions::collect()`.
+        if rest.starts_with(['_', '|']) {
+            return len;
+        }
+
+        if let Some((name, argument)) = rest.split_once("::") {
+            let is_directive = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+
+            if is_directive {
+                self.directive(name, argument.trim(), body);
+            }
+        }
+
+        len
+    }
+
+    fn directive(&mut self, name: &str, argument: &str, body: &[&str]) {
+        let (options, content) = directive_body(body);
+
+        match name {
+            "code" | "code-block" | "sourcecode" => {
+                let language = argument.split_whitespace().next();
+                markup::code_block(&mut self.html, &self.options, language, &content.join("\n"));
+            }
+            "image" => {
+                let image = image(argument, &options);
+                self.html.push_str(&format!("<p>{}</p>\n", image.to_html()));
+            }
+            "figure" => {
+                let image = image(argument, &options);
+                self.html.push_str(&format!("<p>{}</p>\n", image.to_html()));
+                self.blocks(&content);
+            }
+            "raw" if argument == "html" => {
+                self.html.push_str(&content.join("\n"));
+                self.html.push('\n');
+            }
+            "attention" | "caution" | "danger" | "error" | "hint" | "important" | "note"
+            | "tip" | "warning" => {
+                let mut lines = Vec::new();
+                if !argument.is_empty() {
+                    lines.extend([argument, ""]);
+                }
+                lines.extend(content);
+
+                let mut label = name.to_string();
+                label[..1].make_ascii_uppercase();
+
+                let inner = self.render(&lines);
+                markup::admonition(&mut self.html, &label, &inner);
+            }
+            "admonition" => {
+                let inner = self.render(&content);
+                markup::admonition(&mut self.html, argument, &inner);
+            }
+            _ => {}
+        }
+    }
+
+    fn list(&mut self, lines: &[&str]) -> Option<usize> {
+        let (kind, _) = list_marker(lines[0])?;
+        let tag = match kind {
+            ListKind::Bullet(_) => "ul",
+            ListKind::Enumerated => "ol",
+        };
+
+        self.html.push_str(&format!("<{tag}>\n"));
+
+        let mut i = 0;
+        while let Some((item_kind, offset)) = lines.get(i).and_then(|line| list_marker(line)) {
+            if item_kind != kind {
+                break;
+            }
+
+            let len = 1 + indented_block(&lines[i + 1..]);
+            let mut item = vec![&lines[i][offset..]];
+            item.extend(
+                lines[i + 1..i + len]
+                    .iter()
+                    .map(|l| markup::dedent(l, offset)),
+            );
+            self.list_item(&item);
+
+            i += len;
+            while lines.get(i).is_some_and(|line| line.is_empty()) {
+                i += 1;
+            }
+        }
+
+        self.html.push_str(&format!("</{tag}>\n"));
+        Some(i)
+    }
+
+    fn list_item(&mut self, item: &[&str]) {
+        if item.iter().all(|line| !line.is_empty()) {
+            let inner = self.inline(&item.join("\n"));
+            self.html.push_str(&format!("<li>{inner}</li>\n"));
+        } else {
+            let inner = self.render(item);
+            self.html.push_str(&format!("<li>\n{inner}</li>\n"));
+        }
+    }
+
+    /// Renders a paragraph, and the literal block following it if the
+    /// paragraph ends with `::`.
+    fn paragraph(&mut self, lines: &[&str]) -> usize {
+        let mut len = lines
+            .iter()
+            .position(|line| line.is_empty())
+            .unwrap_or(lines.len());
+
+        let text = lines[..len].join("\n");
+        let Some(text) = text.strip_suffix("::") else {
+            let inner = self.inline(&text);
+            self.html.push_str(&format!("<p>{inner}</p>\n"));
+            return len;
+        };
+
+        // `Paragraph::` is rendered as `Paragraph:`, `Paragraph ::` as
+        // `Paragraph` and a lone `::` is omitted completely.
+        let text = match text.strip_suffix(char::is_whitespace) {
+            Some(text) => text.trim_end().to_string(),
+            None if text.is_empty() => String::new(),
+            None => format!("{text}:"),
+        };
+        if !text.is_empty() {
+            let inner = self.inline(&text);
+            self.html.push_str(&format!("<p>{inner}</p>\n"));
+        }
+
+        let blank_lines = lines[len..].iter().take_while(|l| l.is_empty()).count();
+        let block_len = indented_block(&lines[len + blank_lines..]);
+        if block_len > 0 {
+            let start = len + blank_lines;
+            let block = markup::dedent_all(&lines[start..start + block_len]);
+            markup::code_block(&mut self.html, &self.options, None, &block.join("\n"));
+            len = start + block_len;
+        }
+
+        len
+    }
+
+    fn inline(&self, text: &str) -> String {
+        let mut html = String::new();
+        let mut rest = text;
+        let mut prev = None;
+
+        while let Some(c) = rest.chars().next() {
+            if c == '\\' {
+                if let Some(escaped) = rest[1..].chars().next() {
+                    if !escaped.is_whitespace() {
+                        html.push_str(&encode_minimal(escaped.encode_utf8(&mut [0; 4])));
+                    }
+                    prev = Some(escaped);
+                    rest = &rest[1 + escaped.len_utf8()..];
+                    continue;
+                }
+            }
+
+            if markup::can_start_markup(prev) {
+                if let Some((markup, len)) = self.inline_markup(rest) {
+                    html.push_str(&markup);
+                    prev = rest[..len].chars().next_back();
+                    rest = &rest[len..];
+                    continue;
+                }
+            }
+
+            html.push_str(&encode_minimal(c.encode_utf8(&mut [0; 4])));
+            prev = Some(c);
+            rest = &rest[c.len_utf8()..];
+        }
+
+        html
+    }
+
+    /// Renders the inline markup at the start of the text, and returns the
+    /// HTML and the length of the markup.
+    fn inline_markup(&self, text: &str) -> Option<(String, usize)> {
+        if let Some((inner, len)) = delimited(text, "``", "``") {
+            return Some((format!("<code>{}</code>", encode_minimal(inner)), len));
+        }
+
+        if let Some((inner, len)) = delimited(text, "**", "**") {
+            return Some((format!("<strong>{}</strong>", encode_minimal(inner)), len));
+        }
+
+        if let Some((inner, len)) = delimited(text, "*", "*") {
+            return Some((format!("<em>{}</em>", encode_minimal(inner)), len));
+        }
+
+        if let Some((inner, len)) = delimited(text, "`", "`") {
+            return Some(self.interpreted_text(inner, len, &text[len..]));
+        }
+
+        if text.starts_with(':') {
+            if let Some(role) = self.role(text) {
+                return Some(role);
+            }
+        }
+
+        if let Some((name, len)) = delimited(text, "|", "|") {
+            return self.substitution(name, len, &text[len..]);
+        }
+
+        if let Some(len) = markup::url_len(text) {
+            let url = &text[..len];
+            return Some((markup::link(url, &encode_minimal(url)), len));
+        }
+
+        self.reference(text)
+    }
+
+    /// Renders hyperlink references like `` `text <url>`_ `` or
+    /// `` `name`_ ``, and other interpreted text as citations.
+    fn interpreted_text(&self, inner: &str, len: usize, after: &str) -> (String, usize) {
+        let suffix_len = reference_suffix_len(after);
+        if suffix_len == 0 {
+            return (format!("<cite>{}</cite>", encode_minimal(inner)), len);
+        }
+
+        let embedded = inner
+            .strip_suffix('>')
+            .and_then(|inner| inner.rsplit_once('<'))
+            .map(|(label, url)| (label.trim(), url.trim()));
+
+        let (label, url) = match embedded {
+            Some((label, url)) => {
+                let url = match url.strip_suffix('_') {
+                    Some(name) => self.target(name),
+                    None => Some(url),
+                };
+                (
+                    if label.is_empty() {
+                        url.unwrap_or_default()
+                    } else {
+                        label
+                    },
+                    url,
+                )
+            }
+            None => (inner, self.target(inner)),
+        };
+
+        let label = encode_minimal(label);
+        let html = match url {
+            Some(url) => markup::link(url, &label),
+            None => label,
+        };
+
+        (html, len + suffix_len)
+    }
+
+    /// Renders roles like ``:code:`text` ``.
+    fn role(&self, text: &str) -> Option<(String, usize)> {
+        let role_len = text[1..].find(":`")?;
+        let role = &text[1..1 + role_len];
+        if role.is_empty() || role.contains(char::is_whitespace) {
+            return None;
+        }
+
+        let (inner, len) = delimited(&text[role_len + 2..], "`", "`")?;
+        let inner = encode_minimal(inner);
+        let html = match role.rsplit(':').next().unwrap_or_default() {
+            "emphasis" => format!("<em>{inner}</em>"),
+            "strong" => format!("<strong>{inner}</strong>"),
+            "sub" | "subscript" => format!("<sub>{inner}</sub>"),
+            "sup" | "superscript" => format!("<sup>{inner}</sup>"),
+            "title" | "title-reference" => format!("<cite>{inner}</cite>"),
+            _ => format!("<code>{inner}</code>"),
+        };
+
+        Some((html, role_len + 2 + len))
+    }
+
+    /// Renders substitution references like `|name|`, which may also be
+    /// hyperlink references like `|name|_`.
+    fn substitution(&self, name: &str, len: usize, after: &str) -> Option<(String, usize)> {
+        let substitution = self.definitions.substitutions.get(&normalize_name(name))?;
+        let html = match substitution {
+            Substitution::Image(image) => image.to_html(),
+            Substitution::Text(text) => encode_minimal(text),
+        };
+
+        let suffix_len = reference_suffix_len(after);
+        let html = match self.target(name).filter(|_| suffix_len > 0) {
+            Some(url) => markup::link(url, &html),
+            None => html,
+        };
+
+        Some((html, len + suffix_len))
+    }
+
+    /// Renders simple hyperlink references like `name_`.
+    fn reference(&self, text: &str) -> Option<(String, usize)> {
+        let len = text
+            .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')))
+            .unwrap_or(text.len());
+        let word = text[..len].trim_end_matches(['.', '-', '+']);
+
+        let name = word.strip_suffix("__").or_else(|| word.strip_suffix('_'))?;
+        let url = self.target(name)?;
+        Some((markup::link(url, &encode_minimal(name)), word.len()))
+    }
+
+    fn target(&self, name: &str) -> Option<&str> {
+        let url = self.definitions.targets.get(&normalize_name(name))?;
+        Some(url.as_str())
+    }
+}
+
+/// Returns the inner text and the total length of the inline markup at the
+/// start of the text, if it starts with `open` and has a matching `close`.
+fn delimited<'t>(text: &'t str, open: &str, close: &str) -> Option<(&'t str, usize)> {
+    let inner = text.strip_prefix(open)?;
+    let end = markup::find_closing(inner, close)?;
+    Some((&inner[..end], open.len() + end + close.len()))
+}
+
+/// Returns the length of the `_` or `__` suffix of hyperlink references.
+fn reference_suffix_len(text: &str) -> usize {
+    if text.starts_with("__") {
+        2
+    } else if text.starts_with('_') {
+        1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rst_to_html;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn section_titles() {
+        let text = "=======\nMy Crate\n=======\n\nIntro\n-----\n\nUsage\n-----\n\nDetails\n~~~~~~~\n\n----\n\nEnd\n---\n";
+        assert_snapshot!(rst_to_html(text, None, "", &Default::default()), @r###"
+        <h1><a href="#my-crate" id="user-content-my-crate" rel="nofollow noopener noreferrer"></a>My Crate</h1>
+        <h2><a href="#intro" id="user-content-intro" rel="nofollow noopener noreferrer"></a>Intro</h2>
+        <h2><a href="#usage" id="user-content-usage" rel="nofollow noopener noreferrer"></a>Usage</h2>
+        <h3><a href="#details" id="user-content-details" rel="nofollow noopener noreferrer"></a>Details</h3>
+        <hr>
+        <h2><a href="#end" id="user-content-end" rel="nofollow noopener noreferrer"></a>End</h2>
+        "###);
+    }
+
+    #[test]
+    fn inline_markup() {
+        let text = "Some *emphasis*, **strong** and ``literal`` text, a :code:`role`,\nan escaped \\*star\\* and a snake_case_ word. See https://crates.io.\n";
+        assert_snapshot!(rst_to_html(text, None, "", &Default::default()), @r###"
+        <p>Some <em>emphasis</em>, <strong>strong</strong> and <code>literal</code> text, a <code>role</code>,
+        an escaped *star* and a snake_case_ word. See <a href="https://crates.io" rel="nofollow noopener noreferrer">https://crates.io</a>.</p>
+        "###);
+    }
+
+    #[test]
+    fn lists() {
+        let text = "- first\n- second\n  continued\n\n- third\n\n  with a paragraph\n\n#. one\n#. two\n\n1) three\n";
+        assert_snapshot!(rst_to_html(text, None, "", &Default::default()), @r###"
+        <ul>
+        <li>first</li>
+        <li>second
+        continued</li>
+        <li>
+        <p>third</p>
+        <p>with a paragraph</p>
+        </li>
+        </ul>
+        <ol>
+        <li>one</li>
+        <li>two</li>
+        <li>three</li>
+        </ol>
+        "###);
+    }
+
+    #[test]
+    fn literal_and_code_blocks() {
+        let text = "Example::\n\n    let x = 1;\n\n    <script>\n\n.. code-block:: rust\n\n    fn main() {}\n\nBlock quote:\n\n    Quoted\n";
+        assert_snapshot!(rst_to_html(text, None, "", &Default::default()), @r###"
+        <p>Example:</p>
+        <pre><code>let x = 1;
+
+        &lt;script&gt;
+        </code></pre>
+        <pre><code class="language-rust">fn main() {}
+        </code></pre>
+        <p>Block quote:</p>
+        <blockquote>
+        <p>Quoted</p>
+        </blockquote>
+        "###);
+    }
+
+    #[test]
+    fn links_and_substitutions() {
+        let text = "|build| |docs|_\n\nSee `the docs <https://docs.rs/foo>`_, `Guide`_ and example_.\n\n.. |build| image:: https://img.shields.io/badge.svg\n   :alt: Build Status\n   :target: https://ci.example.com\n.. |docs| image:: docs.svg\n.. _docs: https://docs.rs/foo\n.. _Guide: guide.rst\n.. _example: https://example.com\n";
+        assert_snapshot!(rst_to_html(text, Some("https://github.com/rust-lang/test"), "", &Default::default()), @r###"
+        <p><a href="https://ci.example.com" rel="nofollow noopener noreferrer"><img src="https://img.shields.io/badge.svg" alt="Build Status"></a> <a href="https://docs.rs/foo" rel="nofollow noopener noreferrer"><img src="https://github.com/rust-lang/test/raw/HEAD/docs.svg?sanitize=true"></a></p>
+        <p>See <a href="https://docs.rs/foo" rel="nofollow noopener noreferrer">the docs</a>, <a href="https://github.com/rust-lang/test/blob/HEAD/guide.rst" rel="nofollow noopener noreferrer">Guide</a> and <a href="https://example.com" rel="nofollow noopener noreferrer">example</a>.</p>
+        "###);
+    }
+
+    #[test]
+    fn directives() {
+        let text = ".. note:: Be careful.\n\n   Really.\n\n.. This is a comment.\n\n.. contents::\n\n.. image:: logo.png\n   :width: 100\n\n.. raw:: html\n\n   <p align=\"center\">Hi<script>alert(1)</script></p>\n\n+---+---+\n| a | b |\n+---+---+\n";
+        assert_snapshot!(rst_to_html(text, Some("https://github.com/rust-lang/test"), "", &Default::default()), @r###"
+        <blockquote>
+        <p><strong>Note</strong></p>
+        <p>Be careful.</p>
+        <p>Really.</p>
+        </blockquote>
+        <p><img src="https://github.com/rust-lang/test/raw/HEAD/logo.png" width="100"></p>
+        <p align="center">Hi</p>
+        <pre><code>+---+---+
+        | a | b |
+        +---+---+
+        </code></pre>
+        "###);
+    }
+}
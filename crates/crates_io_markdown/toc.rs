@@ -0,0 +1,217 @@
+//! Extraction of the table of contents from rendered documents.
+
+use htmlescape::decode_html;
+use serde::{Deserialize, Serialize};
+
+/// A heading of a rendered document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TocEntry {
+    /// The level of the heading, from 1 to 6.
+    pub level: u8,
+    /// The plain text of the heading, without any markup.
+    pub text: String,
+    /// The `id` of the heading anchor, including the `user-content-` prefix.
+    pub anchor: String,
+}
+
+/// Extracts the table of contents from HTML that was rendered by
+/// `text_to_html`.
+///
+/// Only headings with an anchor are included, which excludes headings that
+/// were written as raw HTML without an `id`.
+///
+/// # Examples
+///
+/// ```
+/// use crates_io_markdown::{extract_toc, text_to_html, TocEntry};
+///
+/// let rendered = text_to_html("# My crate\n\n## Usage\n", "README.md", None, None);
+/// assert_eq!(extract_toc(&rendered), vec![
+///     TocEntry { level: 1, text: "My crate".into(), anchor: "user-content-my-crate".into() },
+///     TocEntry { level: 2, text: "Usage".into(), anchor: "user-content-usage".into() },
+/// ]);
+/// ```
+pub fn extract_toc(html: &str) -> Vec<TocEntry> {
+    headings(html)
+        .into_iter()
+        .filter_map(|heading| {
+            Some(TocEntry {
+                level: heading.level,
+                text: heading.text(),
+                anchor: heading.anchor?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// A heading within rendered HTML.
+pub(crate) struct Heading<'a> {
+    /// The byte offset of the opening tag.
+    pub start: usize,
+    pub level: u8,
+    /// The inner HTML of the heading.
+    pub inner: &'a str,
+    /// The `id` of the heading anchor, if there is one.
+    pub anchor: Option<&'a str>,
+}
+
+impl Heading<'_> {
+    /// Returns the plain text of the heading, without any markup.
+    pub fn text(&self) -> String {
+        let text = strip_tags(self.inner);
+        let text = decode_html(&text).unwrap_or(text);
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Finds all headings within HTML that was rendered by `text_to_html`.
+pub(crate) fn headings(html: &str) -> Vec<Heading<'_>> {
+    let mut headings = Vec::new();
+    let mut offset = 0;
+
+    while let Some(pos) = html[offset..].find("<h") {
+        let start = offset + pos;
+        offset = start + 2;
+
+        let Some(level) = heading_level(&html[offset..]) else {
+            continue;
+        };
+
+        let Some(inner_start) = html[offset..].find('>').map(|pos| offset + pos + 1) else {
+            break;
+        };
+        let close = format!("</h{level}>");
+        let Some(inner_end) = html[inner_start..]
+            .find(&close)
+            .map(|pos| inner_start + pos)
+        else {
+            break;
+        };
+
+        let inner = &html[inner_start..inner_end];
+        offset = inner_end + close.len();
+
+        headings.push(Heading {
+            start,
+            level,
+            inner,
+            anchor: anchor_id(inner),
+        });
+    }
+
+    headings
+}
+
+/// Returns the level of the heading tag whose name starts right after the
+/// `<h`, or `None` if the tag is not a heading.
+fn heading_level(tag: &str) -> Option<u8> {
+    let mut chars = tag.chars();
+    let level = chars
+        .next()?
+        .to_digit(10)
+        .filter(|level| (1..=6).contains(level))?;
+    match chars.next()? {
+        '>' | ' ' => Some(level as u8),
+        _ => None,
+    }
+}
+
+/// Returns the first `id` attribute within the inner HTML of a heading.
+fn anchor_id(html: &str) -> Option<&str> {
+    let start = html.find(" id=\"")? + 5;
+    let len = html[start..].find('"')?;
+    Some(&html[start..start + len]).filter(|id| !id.is_empty())
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{extract_toc, text_to_html};
+    use insta::assert_debug_snapshot;
+
+    #[test]
+    fn markdown_headings() {
+        let text = "# My `crate` &amp; more\n\n<h2 align=\"center\">Raw</h2>\n\n## Usage\n\n### Usage\n\n###### *Deep* [link](https://example.com)\n";
+        let rendered = text_to_html(text, "README.md", None, None);
+        assert_debug_snapshot!(extract_toc(&rendered), @r###"
+        [
+            TocEntry {
+                level: 1,
+                text: "My crate & more",
+                anchor: "user-content-my-crate--more",
+            },
+            TocEntry {
+                level: 2,
+                text: "Usage",
+                anchor: "user-content-usage",
+            },
+            TocEntry {
+                level: 3,
+                text: "Usage",
+                anchor: "user-content-usage-1",
+            },
+            TocEntry {
+                level: 6,
+                text: "Deep link",
+                anchor: "user-content-deep-link",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn rst_and_asciidoc_headings() {
+        let text = "My Crate\n========\n\nUsage\n-----\n";
+        let rendered = text_to_html(text, "README.rst", None, None);
+        assert_debug_snapshot!(extract_toc(&rendered), @r###"
+        [
+            TocEntry {
+                level: 1,
+                text: "My Crate",
+                anchor: "user-content-my-crate",
+            },
+            TocEntry {
+                level: 2,
+                text: "Usage",
+                anchor: "user-content-usage",
+            },
+        ]
+        "###);
+
+        let text = "= My Crate\n\n== Usage\n";
+        let rendered = text_to_html(text, "README.adoc", None, None);
+        assert_debug_snapshot!(extract_toc(&rendered), @r###"
+        [
+            TocEntry {
+                level: 1,
+                text: "My Crate",
+                anchor: "user-content-my-crate",
+            },
+            TocEntry {
+                level: 2,
+                text: "Usage",
+                anchor: "user-content-usage",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn no_headings() {
+        let rendered = text_to_html("Hello <h1>world", "README.md", None, None);
+        assert_eq!(extract_toc(&rendered), vec![]);
+    }
+}
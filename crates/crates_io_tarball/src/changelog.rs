@@ -0,0 +1,46 @@
+/// A changelog file found in the root directory of a crate tarball.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Changelog {
+    /// The file name of the changelog, relative to the package root.
+    pub path: String,
+    pub contents: String,
+}
+
+/// Changelogs larger than this are ignored.
+pub const MAX_CHANGELOG_SIZE: u64 = 1024 * 1024;
+
+/// The recognized file names of changelogs, without extension, in order of
+/// preference.
+const NAMES: [&str; 4] = ["changelog", "changes", "history", "release-notes"];
+
+/// The recognized extensions of changelogs, in order of preference.
+const EXTENSIONS: [&str; 7] = ["md", "markdown", "rst", "adoc", "asciidoc", "txt", ""];
+
+/// Returns the rank of the file name if it is the name of a changelog, with
+/// lower ranks being preferred, or `None` if it is not a changelog.
+pub(crate) fn changelog_rank(file_name: &str) -> Option<usize> {
+    let file_name = file_name.to_ascii_lowercase();
+    let (stem, extension) = file_name.split_once('.').unwrap_or((&file_name, ""));
+
+    let name_rank = NAMES.iter().position(|name| *name == stem)?;
+    let extension_rank = EXTENSIONS.iter().position(|ext| *ext == extension)?;
+    Some(name_rank * EXTENSIONS.len() + extension_rank)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::changelog_rank;
+
+    #[test]
+    fn test_changelog_rank() {
+        assert_none!(changelog_rank("README.md"));
+        assert_none!(changelog_rank("CHANGELOG.html"));
+        assert_none!(changelog_rank("changelog.md.bak"));
+
+        let changelog_md = assert_some!(changelog_rank("CHANGELOG.md"));
+        let changelog = assert_some!(changelog_rank("Changelog"));
+        let changes_md = assert_some!(changelog_rank("changes.md"));
+        assert!(changelog_md < changelog);
+        assert!(changelog < changes_md);
+    }
+}
@@ -4,8 +4,11 @@ extern crate claims;
 
 #[cfg(any(feature = "builder", test))]
 pub use crate::builder::TarballBuilder;
+use crate::changelog::changelog_rank;
+pub use crate::changelog::{Changelog, MAX_CHANGELOG_SIZE};
 use crate::limit_reader::LimitErrorReader;
 use crate::manifest::validate_manifest;
+pub use crate::readme::{find_readme, PackageReadme};
 pub use crate::vcs_info::CargoVcsInfo;
 use cargo_manifest::AbstractFilesystem;
 pub use cargo_manifest::{Manifest, StringOrBool};
@@ -18,14 +21,19 @@ use tracing::instrument;
 
 #[cfg(any(feature = "builder", test))]
 mod builder;
+mod changelog;
 mod limit_reader;
 mod manifest;
+mod readme;
 mod vcs_info;
 
 #[derive(Debug)]
 pub struct TarballInfo {
     pub manifest: Manifest,
     pub vcs_info: Option<CargoVcsInfo>,
+    /// The changelog in the package root, if there is one that is valid
+    /// UTF-8 and not larger than `MAX_CHANGELOG_SIZE`.
+    pub changelog: Option<Changelog>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -67,6 +75,7 @@ pub fn process_tarball<R: Read>(
     let pkg_root = Path::new(&pkg_name);
 
     let mut vcs_info = None;
+    let mut changelog: Option<(usize, Changelog)> = None;
     let mut paths = Vec::new();
     let mut manifests = BTreeMap::new();
 
@@ -116,6 +125,16 @@ pub fn process_tarball<R: Read>(
                 validate_manifest(&manifest)?;
 
                 manifests.insert(owned_entry_path, manifest);
+            } else if let Some(rank) = entry_file.to_str().and_then(changelog_rank) {
+                let is_preferred = changelog.as_ref().map_or(true, |(best, _)| rank < *best);
+                if is_preferred && entry.size() <= MAX_CHANGELOG_SIZE {
+                    let path = entry_file.to_string_lossy().into_owned();
+                    let mut contents = Vec::new();
+                    entry.read_to_end(&mut contents)?;
+                    if let Ok(contents) = String::from_utf8(contents) {
+                        changelog = Some((rank, Changelog { path, contents }));
+                    }
+                }
             }
         }
     }
@@ -142,7 +161,13 @@ pub fn process_tarball<R: Read>(
 
     manifest.complete_from_abstract_filesystem(&PathsFileSystem(paths))?;
 
-    Ok(TarballInfo { manifest, vcs_info })
+    let changelog = changelog.map(|(_, changelog)| changelog);
+
+    Ok(TarballInfo {
+        manifest,
+        vcs_info,
+        changelog,
+    })
 }
 
 struct PathsFileSystem(Vec<PathBuf>);
@@ -224,6 +249,29 @@ mod tests {
         assert_eq!(vcs_info.path_in_vcs, "path/in/vcs");
     }
 
+    #[test]
+    fn process_tarball_test_changelog() {
+        let tarball = TarballBuilder::new()
+            .add_file("foo-0.0.1/Cargo.toml", MANIFEST)
+            .add_file("foo-0.0.1/CHANGES.txt", b"older")
+            .add_file("foo-0.0.1/CHANGELOG.md", b"## 0.0.1\n\nInitial release")
+            .add_file("foo-0.0.1/docs/CHANGELOG.rst", b"nested")
+            .build();
+
+        let tarball_info = assert_ok!(process_tarball("foo-0.0.1", &*tarball, MAX_SIZE));
+        let changelog = assert_some!(tarball_info.changelog);
+        assert_eq!(changelog.path, "CHANGELOG.md");
+        assert_eq!(changelog.contents, "## 0.0.1\n\nInitial release");
+
+        let tarball = TarballBuilder::new()
+            .add_file("foo-0.0.1/Cargo.toml", MANIFEST)
+            .add_file("foo-0.0.1/CHANGELOG.md", b"\xff\xfe")
+            .build();
+
+        let tarball_info = assert_ok!(process_tarball("foo-0.0.1", &*tarball, MAX_SIZE));
+        assert_none!(tarball_info.changelog);
+    }
+
     #[test]
     fn process_tarball_test_manifest() {
         let manifest = br#"
@@ -0,0 +1,205 @@
+use crate::limit_reader::LimitErrorReader;
+use crate::{CargoVcsInfo, TarballError};
+use cargo_manifest::{Manifest, StringOrBool};
+use flate2::read::GzDecoder;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+/// The file names that cargo looks for if the manifest doesn't specify a
+/// README file, in order of preference.
+const DEFAULT_README_FILES: [&str; 3] = ["README.md", "README.txt", "README"];
+
+/// The README of an uploaded package, with the information that is needed to
+/// render it.
+#[derive(Debug)]
+pub struct PackageReadme {
+    /// The path of the README file within the package.
+    pub path: String,
+    pub contents: String,
+    /// The `package.repository` field of the manifest.
+    pub repository: Option<String>,
+    /// The path of the package within its repository, if known.
+    pub path_in_vcs: Option<String>,
+}
+
+/// Finds the README of the package in an already published tarball, as
+/// configured by the `package.readme` field of its manifest.
+///
+/// Returns `None` if the package doesn't have a README, or if it is not
+/// valid UTF-8. Unlike `process_tarball()`, this does not validate the
+/// contents of the tarball.
+pub fn find_readme(
+    pkg_name: &str,
+    tarball: &[u8],
+    max_unpack: u64,
+) -> Result<Option<PackageReadme>, TarballError> {
+    let pkg_root = Path::new(pkg_name);
+
+    let mut manifest = None;
+    let mut vcs_info = None;
+    for_each_entry(tarball, max_unpack, |path, contents| {
+        if path == pkg_root.join("Cargo.toml") {
+            manifest = Some(Manifest::from_str(&contents?)?);
+        } else if path == pkg_root.join(".cargo_vcs_info.json") {
+            vcs_info = CargoVcsInfo::from_contents(&contents?).ok();
+        }
+        Ok(())
+    })?;
+
+    let Some(package) = manifest.ok_or(TarballError::MissingManifest)?.package else {
+        return Ok(None);
+    };
+
+    let candidates = match package.readme.as_ref().and_then(|r| r.as_ref().as_local()) {
+        Some(StringOrBool::Bool(false)) => return Ok(None),
+        Some(StringOrBool::String(path)) => vec![normalize(path)],
+        _ => DEFAULT_README_FILES.iter().map(PathBuf::from).collect(),
+    };
+
+    let mut readmes = Vec::new();
+    for_each_entry(tarball, max_unpack, |path, contents| {
+        let Ok(path) = path.strip_prefix(pkg_root) else {
+            return Ok(());
+        };
+        if let Some(rank) = candidates.iter().position(|c| c == path) {
+            if let Ok(contents) = contents {
+                readmes.push((rank, path.to_path_buf(), contents));
+            }
+        }
+        Ok(())
+    })?;
+
+    let Some((_, path, contents)) = readmes.into_iter().min_by_key(|(rank, ..)| *rank) else {
+        return Ok(None);
+    };
+
+    let repository = package
+        .repository
+        .as_ref()
+        .and_then(|r| r.as_ref().as_local())
+        .cloned();
+
+    Ok(Some(PackageReadme {
+        path: path.to_string_lossy().into_owned(),
+        contents,
+        repository,
+        path_in_vcs: vcs_info.map(|info| info.path_in_vcs),
+    }))
+}
+
+/// Normalizes the `package.readme` path of a manifest to its path within the
+/// package. Cargo copies README files from outside of the package directory
+/// into the package root.
+fn normalize(path: &str) -> PathBuf {
+    let path = Path::new(path);
+    if path.components().any(|c| c == Component::ParentDir) {
+        return path.file_name().map(PathBuf::from).unwrap_or_default();
+    }
+
+    path.components()
+        .filter(|c| *c != Component::CurDir)
+        .collect()
+}
+
+/// Calls the callback with the path and the UTF-8 contents of every file in
+/// the tarball.
+fn for_each_entry<F>(tarball: &[u8], max_unpack: u64, mut f: F) -> Result<(), TarballError>
+where
+    F: FnMut(&Path, std::io::Result<String>) -> Result<(), TarballError>,
+{
+    let decoder = LimitErrorReader::new(GzDecoder::new(tarball), max_unpack);
+    let mut archive = tar::Archive::new(decoder);
+
+    for entry in archive.entries()? {
+        let mut entry = entry.map_err(TarballError::Malformed)?;
+        let path = entry.path()?.into_owned();
+
+        let mut contents = String::new();
+        let contents = entry.read_to_string(&mut contents).map(|_| contents);
+        f(&path, contents)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_readme;
+    use crate::TarballBuilder;
+
+    const MAX_SIZE: u64 = 512 * 1024 * 1024;
+
+    #[test]
+    fn default_readme() {
+        let tarball = TarballBuilder::new()
+            .add_file("foo-0.0.1/Cargo.toml", b"[package]\nname = \"foo\"\nversion = \"0.0.1\"\nrepository = \"https://github.com/foo/bar\"\n")
+            .add_file("foo-0.0.1/README", b"plain")
+            .add_file("foo-0.0.1/README.md", b"# foo")
+            .add_file("foo-0.0.1/.cargo_vcs_info.json", br#"{"path_in_vcs": "crates/foo"}"#)
+            .build();
+
+        let readme = assert_some!(assert_ok!(find_readme("foo-0.0.1", &tarball, MAX_SIZE)));
+        assert_eq!(readme.path, "README.md");
+        assert_eq!(readme.contents, "# foo");
+        assert_eq!(
+            readme.repository.as_deref(),
+            Some("https://github.com/foo/bar")
+        );
+        assert_eq!(readme.path_in_vcs.as_deref(), Some("crates/foo"));
+    }
+
+    #[test]
+    fn configured_readme() {
+        let tarball = TarballBuilder::new()
+            .add_file(
+                "foo-0.0.1/Cargo.toml",
+                b"[package]\nname = \"foo\"\nversion = \"0.0.1\"\nreadme = \"./docs/README.rst\"\n",
+            )
+            .add_file("foo-0.0.1/README.md", b"# foo")
+            .add_file("foo-0.0.1/docs/README.rst", b"foo\n===")
+            .build();
+
+        let readme = assert_some!(assert_ok!(find_readme("foo-0.0.1", &tarball, MAX_SIZE)));
+        assert_eq!(readme.path, "docs/README.rst");
+        assert_eq!(readme.contents, "foo\n===");
+        assert_none!(readme.repository);
+        assert_none!(readme.path_in_vcs);
+
+        let tarball = TarballBuilder::new()
+            .add_file(
+                "foo-0.0.1/Cargo.toml",
+                b"[package]\nname = \"foo\"\nversion = \"0.0.1\"\nreadme = \"../README.md\"\n",
+            )
+            .add_file("foo-0.0.1/README.md", b"# workspace")
+            .build();
+
+        let readme = assert_some!(assert_ok!(find_readme("foo-0.0.1", &tarball, MAX_SIZE)));
+        assert_eq!(readme.path, "README.md");
+    }
+
+    #[test]
+    fn missing_readme() {
+        let tarball = TarballBuilder::new()
+            .add_file(
+                "foo-0.0.1/Cargo.toml",
+                b"[package]\nname = \"foo\"\nversion = \"0.0.1\"\nreadme = false\n",
+            )
+            .add_file("foo-0.0.1/README.md", b"# foo")
+            .build();
+        assert_none!(assert_ok!(find_readme("foo-0.0.1", &tarball, MAX_SIZE)));
+
+        let tarball = TarballBuilder::new()
+            .add_file(
+                "foo-0.0.1/Cargo.toml",
+                b"[package]\nname = \"foo\"\nversion = \"0.0.1\"\n",
+            )
+            .build();
+        assert_none!(assert_ok!(find_readme("foo-0.0.1", &tarball, MAX_SIZE)));
+
+        let tarball = TarballBuilder::new()
+            .add_file("foo-0.0.1/README.md", b"# foo")
+            .build();
+        assert_err!(find_readme("foo-0.0.1", &tarball, MAX_SIZE));
+    }
+}
@@ -0,0 +1,23 @@
+use tokio_util::sync::CancellationToken;
+
+tokio::task_local! {
+    pub(crate) static CANCELLATION_TOKEN: CancellationToken;
+}
+
+/// Returns the cancellation token of the currently running job.
+///
+/// The token is cancelled shortly before the deadline of a
+/// [RunHandle::shutdown()](crate::RunHandle::shutdown) is reached. Long-running
+/// jobs can use it to stop early, in which case they should return a
+/// [JobCancelled](crate::JobCancelled) error, so that they are released back
+/// into the queue without counting as a failed attempt.
+///
+/// The token is stored in a task-local variable, so it is only available in
+/// the task running the job itself and not in tasks spawned by the job. If
+/// this function is called outside of a job, a token that is never cancelled
+/// is returned.
+pub fn cancellation_token() -> CancellationToken {
+    CANCELLATION_TOKEN
+        .try_with(|token| token.clone())
+        .unwrap_or_default()
+}
@@ -10,3 +10,13 @@ pub enum EnqueueError {
     #[error(transparent)]
     DatabaseError(#[from] diesel::result::Error),
 }
+
+/// Error that jobs should return if they stopped early because their
+/// [cancellation_token](crate::cancellation_token) was cancelled.
+///
+/// Jobs that fail with this error, or with an error that has it as its
+/// source or context, are released back into the queue without counting as a
+/// failed attempt.
+#[derive(Debug, thiserror::Error)]
+#[error("job was cancelled")]
+pub struct JobCancelled;
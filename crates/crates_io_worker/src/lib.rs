@@ -1,6 +1,8 @@
 mod background_job;
+mod cancellation;
 mod errors;
 mod job_registry;
+mod metrics;
 mod runner;
 pub mod schema;
 mod storage;
@@ -8,5 +10,7 @@ mod util;
 mod worker;
 
 pub use self::background_job::BackgroundJob;
-pub use self::errors::EnqueueError;
-pub use self::runner::Runner;
+pub use self::cancellation::cancellation_token;
+pub use self::errors::{EnqueueError, JobCancelled};
+pub use self::metrics::{JobMetrics, JobRun};
+pub use self::runner::{RunHandle, Runner};
@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+/// Information about a single job run, passed to [JobMetrics::record].
+#[derive(Debug, Clone)]
+pub struct JobRun<'a> {
+    /// The [JOB_NAME](crate::BackgroundJob::JOB_NAME) of the job.
+    pub job_type: &'a str,
+    /// How long the job was waiting in the queue before it was picked up.
+    ///
+    /// This is only available for the first attempt of a job, since the
+    /// delay of retries is mostly determined by the exponential backoff.
+    pub queue_wait: Option<Duration>,
+    /// How long it took to run the job.
+    pub duration: Duration,
+    /// How often this job had already failed before this attempt.
+    pub retries: i32,
+    /// Whether the job finished successfully.
+    pub success: bool,
+}
+
+/// A hook that is notified about every job run of a [Runner](crate::Runner).
+///
+/// This can be used to export job execution metrics without making this
+/// crate depend on any specific metrics implementation.
+pub trait JobMetrics: Send + Sync + 'static {
+    /// Record a finished job run.
+    ///
+    /// This is called while the job is still locked in the queue, so
+    /// implementations should not block for too long.
+    fn record(&self, run: &JobRun<'_>);
+}
@@ -1,26 +1,34 @@
 use crate::background_job::DEFAULT_QUEUE;
 use crate::job_registry::JobRegistry;
+use crate::metrics::JobMetrics;
 use crate::worker::Worker;
 use crate::{storage, BackgroundJob};
 use anyhow::anyhow;
 use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
 use diesel_async::pooled_connection::deadpool::Pool;
 use diesel_async::AsyncPgConnection;
-use futures_util::future::join_all;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::task::{spawn_blocking, JoinHandle};
+use tokio::time::timeout;
+use tokio_util::sync::CancellationToken;
 use tracing::{info, info_span, warn, Instrument};
 
 const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
+/// How long before the deadline of [RunHandle::shutdown()] the
+/// [cancellation_token](crate::cancellation_token) of the running jobs is
+/// cancelled, to give them time to stop.
+const CANCELLATION_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
 /// The core runner responsible for locking and running jobs
 pub struct Runner<Context> {
     connection_pool: Pool<AsyncPgConnection>,
     queues: HashMap<String, Queue<Context>>,
     context: Context,
     shutdown_when_queue_empty: bool,
+    metrics: Option<Arc<dyn JobMetrics>>,
 }
 
 impl<Context: Clone + Send + Sync + 'static> Runner<Context> {
@@ -30,6 +38,7 @@ impl<Context: Clone + Send + Sync + 'static> Runner<Context> {
             queues: HashMap::new(),
             context,
             shutdown_when_queue_empty: false,
+            metrics: None,
         }
     }
 
@@ -64,10 +73,20 @@ impl<Context: Clone + Send + Sync + 'static> Runner<Context> {
         self
     }
 
+    /// Set a hook that is notified about every job run, e.g. to export
+    /// job execution metrics.
+    pub fn with_metrics(mut self, metrics: impl JobMetrics) -> Self {
+        self.metrics = Some(Arc::new(metrics));
+        self
+    }
+
     /// Start the background workers.
     ///
-    /// This returns a `RunningRunner` which can be used to wait for the workers to shutdown.
+    /// This returns a [RunHandle] which can be used to wait for the workers to shutdown.
     pub fn start(&self) -> RunHandle {
+        let shutdown_token = CancellationToken::new();
+        let cancellation_token = CancellationToken::new();
+
         let mut handles = Vec::new();
         for (queue_name, queue) in &self.queues {
             for i in 1..=queue.num_workers {
@@ -80,6 +99,9 @@ impl<Context: Clone + Send + Sync + 'static> Runner<Context> {
                     job_registry: Arc::new(queue.job_registry.clone()),
                     shutdown_when_queue_empty: self.shutdown_when_queue_empty,
                     poll_interval: queue.poll_interval,
+                    metrics: self.metrics.clone(),
+                    shutdown_token: shutdown_token.clone(),
+                    cancellation_token: cancellation_token.clone(),
                 };
 
                 let span = info_span!("worker", worker.name = %name);
@@ -89,7 +111,11 @@ impl<Context: Clone + Send + Sync + 'static> Runner<Context> {
             }
         }
 
-        RunHandle { handles }
+        RunHandle {
+            handles,
+            shutdown_token,
+            cancellation_token,
+        }
     }
 
     /// Check if any jobs in the queue have failed.
@@ -115,16 +141,65 @@ impl<Context: Clone + Send + Sync + 'static> Runner<Context> {
 
 pub struct RunHandle {
     handles: Vec<JoinHandle<()>>,
+    /// Stops the workers from picking up new jobs.
+    shutdown_token: CancellationToken,
+    /// Asks the running jobs to stop, see [crate::cancellation_token].
+    cancellation_token: CancellationToken,
 }
 
 impl RunHandle {
     /// Wait for all background workers to shut down.
-    pub async fn wait_for_shutdown(self) {
-        join_all(self.handles).await.into_iter().for_each(|result| {
-            if let Err(error) = result {
+    ///
+    /// This future can be dropped and polled again, e.g. as part of a
+    /// `tokio::select!` loop, without losing track of the remaining workers.
+    pub async fn wait_for_shutdown(&mut self) {
+        while let Some(handle) = self.handles.last_mut() {
+            if let Err(error) = handle.await {
                 warn!(%error, "Background worker task panicked");
             }
-        });
+            self.handles.pop();
+        }
+    }
+
+    /// Gracefully shut down all background workers.
+    ///
+    /// The workers stop picking up new jobs immediately, and the running jobs
+    /// get until `deadline` to finish. Shortly before the deadline, the
+    /// [cancellation_token](crate::cancellation_token) of the running jobs is
+    /// cancelled, so that long-running jobs can stop early.
+    ///
+    /// Returns `false` if some jobs were still running after the deadline.
+    /// These jobs are still locked until the process exits and the database
+    /// connection is closed, after which they will be retried.
+    pub async fn shutdown(mut self, deadline: Duration) -> bool {
+        self.shutdown_token.cancel();
+
+        let abort_handles = self
+            .handles
+            .iter()
+            .map(|handle| handle.abort_handle())
+            .collect::<Vec<_>>();
+
+        let grace_period = CANCELLATION_GRACE_PERIOD.min(deadline);
+        let cancellation_token = self.cancellation_token.clone();
+
+        let mut wait_for_shutdown = std::pin::pin!(self.wait_for_shutdown());
+        if timeout(deadline - grace_period, &mut wait_for_shutdown)
+            .await
+            .is_ok()
+        {
+            return true;
+        }
+
+        info!("Cancelling running jobs…");
+        cancellation_token.cancel();
+        if timeout(grace_period, wait_for_shutdown).await.is_ok() {
+            return true;
+        }
+
+        warn!("Background workers did not shut down before the deadline");
+        abort_handles.iter().for_each(|handle| handle.abort());
+        false
     }
 }
 
@@ -1,4 +1,7 @@
+use crate::cancellation::CANCELLATION_TOKEN;
+use crate::errors::JobCancelled;
 use crate::job_registry::JobRegistry;
+use crate::metrics::{JobMetrics, JobRun};
 use crate::storage;
 use crate::util::{try_to_extract_panic_info, with_sentry_transaction};
 use anyhow::anyhow;
@@ -10,10 +13,11 @@ use futures_util::FutureExt;
 use sentry_core::{Hub, SentryFutureExt};
 use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 use tokio::runtime::Handle;
 use tokio::task::spawn_blocking;
 use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info_span, warn};
 
 pub struct Worker<Context> {
@@ -22,12 +26,24 @@ pub struct Worker<Context> {
     pub(crate) job_registry: Arc<JobRegistry<Context>>,
     pub(crate) shutdown_when_queue_empty: bool,
     pub(crate) poll_interval: Duration,
+    pub(crate) metrics: Option<Arc<dyn JobMetrics>>,
+    pub(crate) shutdown_token: CancellationToken,
+    /// The token that is handed to the running jobs, see
+    /// [cancellation_token](crate::cancellation_token).
+    pub(crate) cancellation_token: CancellationToken,
 }
 
 impl<Context: Clone + Send + Sync + 'static> Worker<Context> {
     /// Run background jobs forever, or until the queue is empty if `shutdown_when_queue_empty` is set.
+    ///
+    /// The worker also stops picking up new jobs once the `shutdown_token` is cancelled.
     pub async fn run(&self) {
         loop {
+            if self.shutdown_token.is_cancelled() {
+                debug!("Shutdown requested. Shutting down the worker…");
+                break;
+            }
+
             match self.run_next_job().await {
                 Ok(Some(_)) => {}
                 Ok(None) if self.shutdown_when_queue_empty => {
@@ -39,17 +55,25 @@ impl<Context: Clone + Send + Sync + 'static> Worker<Context> {
                         "No pending background worker jobs found. Polling again in {:?}…",
                         self.poll_interval
                     );
-                    sleep(self.poll_interval).await;
+                    self.sleep().await;
                 }
                 Err(error) => {
                     let error = format!("{error:#}");
                     error!(error, "Failed to run job");
-                    sleep(self.poll_interval).await;
+                    self.sleep().await;
                 }
             }
         }
     }
 
+    /// Sleep for the `poll_interval`, or until a shutdown is requested.
+    async fn sleep(&self) {
+        tokio::select! {
+            _ = sleep(self.poll_interval) => {},
+            _ = self.shutdown_token.cancelled() => {},
+        }
+    }
+
     /// Run the next job in the queue, if there is one.
     ///
     /// Returns:
@@ -59,6 +83,8 @@ impl<Context: Clone + Send + Sync + 'static> Worker<Context> {
     async fn run_next_job(&self) -> anyhow::Result<Option<i64>> {
         let context = self.context.clone();
         let job_registry = self.job_registry.clone();
+        let metrics = self.metrics.clone();
+        let cancellation_token = self.cancellation_token.clone();
         let conn = self.connection_pool.get().await?;
 
         spawn_blocking(move || {
@@ -78,12 +104,23 @@ impl<Context: Clone + Send + Sync + 'static> Worker<Context> {
                 let job_id = job.id;
                 debug!("Running job…");
 
+                // The queue wait time is only meaningful for the first attempt,
+                // since retries are delayed by the exponential backoff.
+                let queue_wait = match job.retries {
+                    0 => SystemTime::now().duration_since(job.created_at).ok(),
+                    _ => None,
+                };
+                let start = Instant::now();
+
                 let future = with_sentry_transaction(&job.job_type, || async {
                     let run_task_fn = job_registry
                         .get(&job.job_type)
                         .ok_or_else(|| anyhow!("Unknown job type {}", job.job_type))?;
 
-                    AssertUnwindSafe(run_task_fn(context, job.data))
+                    let job_future = run_task_fn(context, job.data);
+                    let job_future = CANCELLATION_TOKEN.scope(cancellation_token, job_future);
+
+                    AssertUnwindSafe(job_future)
                         .catch_unwind()
                         .await
                         .map_err(|e| try_to_extract_panic_info(&e))
@@ -92,12 +129,20 @@ impl<Context: Clone + Send + Sync + 'static> Worker<Context> {
                 });
 
                 let result = Handle::current().block_on(future.bind_hub(Hub::current()));
+                let duration = start.elapsed();
+                let success = result.is_ok();
 
                 match result {
                     Ok(_) => {
                         debug!("Deleting successful job…");
                         storage::delete_successful_job(conn, job_id)?
                     }
+                    Err(error) if error.chain().any(|e| e.is::<JobCancelled>()) => {
+                        // The job stopped because of the shutdown, so we
+                        // release it without counting it as a failed attempt.
+                        let error = format!("{error:#}");
+                        warn!(error, "Job was cancelled. Releasing job…");
+                    }
                     Err(error) => {
                         let error = format!("{error:#}");
                         warn!(error, "Failed to run job");
@@ -105,6 +150,16 @@ impl<Context: Clone + Send + Sync + 'static> Worker<Context> {
                     }
                 }
 
+                if let Some(metrics) = &metrics {
+                    metrics.record(&JobRun {
+                        job_type: &job.job_type,
+                        queue_wait,
+                        duration,
+                        retries: job.retries,
+                        success,
+                    });
+                }
+
                 Ok(Some(job_id))
             })
         })
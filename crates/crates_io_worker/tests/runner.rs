@@ -1,12 +1,15 @@
 use crates_io_test_db::TestDatabase;
 use crates_io_worker::schema::background_jobs;
-use crates_io_worker::{BackgroundJob, Runner};
+use crates_io_worker::{
+    cancellation_token, BackgroundJob, JobCancelled, JobMetrics, JobRun, Runner,
+};
 use diesel::prelude::*;
 use diesel_async::pooled_connection::deadpool::Pool;
 use diesel_async::pooled_connection::AsyncDieselConnectionManager;
 use diesel_async::AsyncPgConnection;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::sync::Barrier;
 
 fn job_exists(id: i64, conn: &mut PgConnection) -> bool {
@@ -68,7 +71,7 @@ async fn jobs_are_locked_when_fetched() {
     assert!(job_exists(job_id, &mut conn));
     assert!(!job_is_locked(job_id, &mut conn));
 
-    let runner = runner.start();
+    let mut runner = runner.start();
     test_context.job_started_barrier.wait().await;
 
     assert!(job_exists(job_id, &mut conn));
@@ -111,7 +114,7 @@ async fn jobs_are_deleted_when_successfully_run() {
     TestJob.enqueue(&mut conn).unwrap();
     assert_eq!(remaining_jobs(&mut conn), 1);
 
-    let runner = runner.start();
+    let mut runner = runner.start();
     runner.wait_for_shutdown().await;
     assert_eq!(remaining_jobs(&mut conn), 0);
 }
@@ -147,7 +150,7 @@ async fn failed_jobs_do_not_release_lock_before_updating_retry_time() {
     let mut conn = test_database.connect();
     TestJob.enqueue(&mut conn).unwrap();
 
-    let runner = runner.start();
+    let mut runner = runner.start();
     test_context.job_started_barrier.wait().await;
 
     // `SKIP LOCKED` is intentionally omitted here, so we block until
@@ -195,7 +198,7 @@ async fn panicking_in_jobs_updates_retry_counter() {
 
     let job_id = TestJob.enqueue(&mut conn).unwrap();
 
-    let runner = runner.start();
+    let mut runner = runner.start();
     runner.wait_for_shutdown().await;
 
     let tries = background_jobs::table
@@ -207,6 +210,223 @@ async fn panicking_in_jobs_updates_retry_counter() {
     assert_eq!(tries, 1);
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn metrics_hook_is_notified_about_job_runs() {
+    #[derive(Clone, Default)]
+    struct TestMetrics {
+        runs: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl JobMetrics for TestMetrics {
+        fn record(&self, run: &JobRun<'_>) {
+            let run = format!(
+                "{} success={} retries={} queue_wait={}",
+                run.job_type,
+                run.success,
+                run.retries,
+                run.queue_wait.is_some(),
+            );
+            self.runs.lock().unwrap().push(run);
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct SuccessfulJob;
+
+    impl BackgroundJob for SuccessfulJob {
+        const JOB_NAME: &'static str = "successful";
+        type Context = ();
+
+        async fn run(&self, _ctx: Self::Context) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct FailingJob;
+
+    impl BackgroundJob for FailingJob {
+        const JOB_NAME: &'static str = "failing";
+        type Context = ();
+
+        async fn run(&self, _ctx: Self::Context) -> anyhow::Result<()> {
+            Err(anyhow::anyhow!("nope"))
+        }
+    }
+
+    let test_database = TestDatabase::new();
+    let metrics = TestMetrics::default();
+
+    let runner = runner(test_database.url(), ())
+        .register_job_type::<SuccessfulJob>()
+        .register_job_type::<FailingJob>()
+        .with_metrics(metrics.clone());
+
+    let mut conn = test_database.connect();
+    SuccessfulJob.enqueue(&mut conn).unwrap();
+    FailingJob.enqueue(&mut conn).unwrap();
+
+    let mut runner = runner.start();
+    runner.wait_for_shutdown().await;
+
+    let mut runs = metrics.runs.lock().unwrap().clone();
+    runs.sort();
+    assert_eq!(
+        runs,
+        vec![
+            "failing success=false retries=0 queue_wait=true",
+            "successful success=true retries=0 queue_wait=true",
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn shutdown_cancels_running_jobs_and_releases_them() {
+    #[derive(Clone)]
+    struct TestContext {
+        job_started_barrier: Arc<Barrier>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct TestJob;
+
+    impl BackgroundJob for TestJob {
+        const JOB_NAME: &'static str = "test";
+        type Context = TestContext;
+
+        async fn run(&self, ctx: Self::Context) -> anyhow::Result<()> {
+            ctx.job_started_barrier.wait().await;
+            cancellation_token().cancelled().await;
+            Err(JobCancelled.into())
+        }
+    }
+
+    let test_database = TestDatabase::new();
+
+    let test_context = TestContext {
+        job_started_barrier: Arc::new(Barrier::new(2)),
+    };
+
+    let runner = runner(test_database.url(), test_context.clone())
+        .configure_default_queue(|queue| queue.num_workers(1))
+        .register_job_type::<TestJob>();
+
+    let mut conn = test_database.connect();
+    let job_id = TestJob.enqueue(&mut conn).unwrap();
+    let second_job_id = TestJob.enqueue(&mut conn).unwrap();
+
+    let runner = runner.start();
+    test_context.job_started_barrier.wait().await;
+
+    assert!(runner.shutdown(Duration::from_secs(10)).await);
+
+    // The cancelled job is released without counting as a failed attempt,
+    // and the second job was never picked up.
+    for id in [job_id, second_job_id] {
+        assert!(!job_is_locked(id, &mut conn));
+
+        let retries = background_jobs::table
+            .find(id)
+            .select(background_jobs::retries)
+            .get_result::<i32>(&mut conn)
+            .unwrap();
+        assert_eq!(retries, 0);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn shutdown_lets_running_jobs_finish_before_the_deadline() {
+    #[derive(Clone)]
+    struct TestContext {
+        job_started_barrier: Arc<Barrier>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct TestJob;
+
+    impl BackgroundJob for TestJob {
+        const JOB_NAME: &'static str = "test";
+        type Context = TestContext;
+
+        async fn run(&self, ctx: Self::Context) -> anyhow::Result<()> {
+            ctx.job_started_barrier.wait().await;
+            let cancellation_token = cancellation_token();
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_millis(500)) => Ok(()),
+                _ = cancellation_token.cancelled() => Err(JobCancelled.into()),
+            }
+        }
+    }
+
+    let test_database = TestDatabase::new();
+
+    let test_context = TestContext {
+        job_started_barrier: Arc::new(Barrier::new(2)),
+    };
+
+    let runner = runner(test_database.url(), test_context.clone())
+        .configure_default_queue(|queue| queue.num_workers(1))
+        .register_job_type::<TestJob>();
+
+    let mut conn = test_database.connect();
+    let job_id = TestJob.enqueue(&mut conn).unwrap();
+
+    let runner = runner.start();
+    test_context.job_started_barrier.wait().await;
+
+    assert!(runner.shutdown(Duration::from_secs(10)).await);
+
+    // The job was not cancelled, and finished successfully.
+    assert!(!job_exists(job_id, &mut conn));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn jobs_failing_during_shutdown_count_as_failed_attempts() {
+    #[derive(Clone)]
+    struct TestContext {
+        job_started_barrier: Arc<Barrier>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct TestJob;
+
+    impl BackgroundJob for TestJob {
+        const JOB_NAME: &'static str = "test";
+        type Context = TestContext;
+
+        async fn run(&self, ctx: Self::Context) -> anyhow::Result<()> {
+            ctx.job_started_barrier.wait().await;
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            Err(anyhow::anyhow!("job failed"))
+        }
+    }
+
+    let test_database = TestDatabase::new();
+
+    let test_context = TestContext {
+        job_started_barrier: Arc::new(Barrier::new(2)),
+    };
+
+    let runner = runner(test_database.url(), test_context.clone())
+        .configure_default_queue(|queue| queue.num_workers(1))
+        .register_job_type::<TestJob>();
+
+    let mut conn = test_database.connect();
+    let job_id = TestJob.enqueue(&mut conn).unwrap();
+
+    let runner = runner.start();
+    test_context.job_started_barrier.wait().await;
+
+    assert!(runner.shutdown(Duration::from_secs(10)).await);
+
+    let retries = background_jobs::table
+        .find(job_id)
+        .select(background_jobs::retries)
+        .get_result::<i32>(&mut conn)
+        .unwrap();
+    assert_eq!(retries, 1);
+}
+
 fn runner<Context: Clone + Send + Sync + 'static>(
     database_url: &str,
     context: Context,
@@ -0,0 +1,23 @@
+create table background_job_metrics
+(
+    job_type           text     not null
+        constraint background_job_metrics_pk
+            primary key,
+    successes          bigint   not null default 0,
+    failures           bigint   not null default 0,
+    retries            bigint   not null default 0,
+    queue_wait_sum     float8   not null default 0,
+    queue_wait_buckets bigint[] not null default '{}',
+    duration_sum       float8   not null default 0,
+    duration_buckets   bigint[] not null default '{}'
+);
+
+comment on table background_job_metrics is 'Aggregated execution metrics of the background worker, exported as service-level metrics.';
+comment on column background_job_metrics.job_type is 'Name of the background job type.';
+comment on column background_job_metrics.successes is 'Number of successful job runs.';
+comment on column background_job_metrics.failures is 'Number of failed job runs.';
+comment on column background_job_metrics.retries is 'Number of job runs that were retries of previously failed runs.';
+comment on column background_job_metrics.queue_wait_sum is 'Sum of the queue wait times of all first attempts, in seconds.';
+comment on column background_job_metrics.queue_wait_buckets is 'Non-cumulative histogram bucket counts of the queue wait times. The last element counts observations above the largest bucket.';
+comment on column background_job_metrics.duration_sum is 'Sum of the run durations of all job runs, in seconds.';
+comment on column background_job_metrics.duration_buckets is 'Non-cumulative histogram bucket counts of the run durations. The last element counts observations above the largest bucket.';
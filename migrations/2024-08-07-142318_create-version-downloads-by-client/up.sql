@@ -0,0 +1,18 @@
+create table version_downloads_by_client
+(
+    version_id integer not null
+        constraint version_downloads_by_client_version_id_fkey
+            references versions
+            on delete cascade,
+    date       date    not null default current_date,
+    client     text    not null,
+    downloads  integer not null default 0,
+    constraint version_downloads_by_client_pk
+        primary key (version_id, date, client)
+);
+
+comment on table version_downloads_by_client is 'Daily download counts of crate versions, broken down by the type of client that downloaded them. The totals are stored in the `version_downloads` table.';
+comment on column version_downloads_by_client.version_id is 'Reference to the version that was downloaded.';
+comment on column version_downloads_by_client.date is 'Date on which the downloads happened.';
+comment on column version_downloads_by_client.client is 'Type of client that downloaded the version, derived from the `User-Agent` header (e.g. `cargo`, `ci`, `browser`, `other` or `unknown`).';
+comment on column version_downloads_by_client.downloads is 'Number of downloads of the version by this type of client on this date.';
@@ -0,0 +1,3 @@
+alter table processed_log_files
+    drop column discarded_duplicates,
+    drop column discarded_blocked_user_agents;
@@ -0,0 +1,6 @@
+alter table processed_log_files
+    add column discarded_duplicates integer not null default 0,
+    add column discarded_blocked_user_agents integer not null default 0;
+
+comment on column processed_log_files.discarded_duplicates is 'Number of repeated downloads from the same client IP address that were not counted.';
+comment on column processed_log_files.discarded_blocked_user_agents is 'Number of downloads from blocked `User-Agent` headers that were not counted.';
@@ -0,0 +1,18 @@
+create table version_downloads_monthly
+(
+    version_id integer not null
+        constraint version_downloads_monthly_version_id_fkey
+            references versions
+            on delete cascade,
+    month      date    not null,
+    downloads  bigint  not null default 0,
+    constraint version_downloads_monthly_pk
+        primary key (version_id, month),
+    constraint version_downloads_monthly_month_check
+        check (extract(day from month) = 1)
+);
+
+comment on table version_downloads_monthly is 'Monthly download counts of crate versions, rolled up from the daily `version_downloads` data after it was archived to object storage.';
+comment on column version_downloads_monthly.version_id is 'Reference to the version that was downloaded.';
+comment on column version_downloads_monthly.month is 'First day of the month in which the downloads happened.';
+comment on column version_downloads_monthly.downloads is 'Number of downloads of the version on the archived dates of this month.';
@@ -0,0 +1 @@
+drop function fuzzy_crate_name(text);
@@ -0,0 +1,5 @@
+create function fuzzy_crate_name(text) returns text as $$
+    select replace(public.canon_crate_name($1), '_', '')
+$$ language sql;
+
+comment on function fuzzy_crate_name(text) is 'Normalizes a crate name for trigram similarity matching. Separators are removed, so that names like `foo_exact` and `other_exact` are not considered similar just because they share a word.';
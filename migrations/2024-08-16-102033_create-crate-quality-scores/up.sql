@@ -0,0 +1,16 @@
+create table crate_quality_scores
+(
+    crate_id    integer   not null
+        constraint crate_quality_scores_pk
+            primary key
+        constraint crate_quality_scores_crate_id_fkey
+            references crates
+            on delete cascade,
+    score       real      not null,
+    computed_at timestamp not null default now()
+);
+
+comment on table crate_quality_scores is 'Quality scores of crates, used for the `quality` sort of the crate search. The scores are periodically recomputed by the `update_quality_scores` background job.';
+comment on column crate_quality_scores.crate_id is 'Reference to the crate that the score belongs to.';
+comment on column crate_quality_scores.score is 'Score between 0 and 1, combining recent downloads, reverse dependencies, recency of the last release and the ratio of yanked versions.';
+comment on column crate_quality_scores.computed_at is 'Time at which the score was last computed.';
@@ -0,0 +1 @@
+drop index concurrently index_crates_name_prefix;
@@ -0,0 +1,2 @@
+create index concurrently if not exists index_crates_name_prefix
+    on crates (canon_crate_name(name) text_pattern_ops);
@@ -0,0 +1,4 @@
+alter table readme_renderings
+    add column toc jsonb;
+
+comment on column readme_renderings.toc is 'Table of contents of the rendered README, as a JSON array of headings with their `level`, `text` and `anchor`. `NULL` if the README was rendered before the table of contents was extracted.';
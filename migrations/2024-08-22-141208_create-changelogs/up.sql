@@ -0,0 +1,18 @@
+create table changelogs
+(
+    version_id  integer   not null
+        constraint changelogs_pk
+            primary key
+        constraint changelogs_version_id_fkey
+            references versions
+            on delete cascade,
+    path        varchar   not null,
+    sections    jsonb     not null,
+    rendered_at timestamp not null default now()
+);
+
+comment on table changelogs is 'Changelogs that were found in the root directory of published crate files. The rendered changelogs are uploaded to the `changelogs/` directory of the storage bucket.';
+comment on column changelogs.version_id is 'Reference to the version that the changelog belongs to.';
+comment on column changelogs.path is 'File name of the changelog, relative to the package root.';
+comment on column changelogs.sections is 'Sections of the rendered changelog, as a JSON array of objects with the `title`, `anchor` and `version` of each section, and the `start` and `end` byte offsets of the section within the uploaded HTML file.';
+comment on column changelogs.rendered_at is 'Time at which the changelog was last rendered.';
@@ -0,0 +1,16 @@
+create table notification_preferences
+(
+    user_id integer not null
+        constraint notification_preferences_user_id_fkey
+            references users
+            on delete cascade,
+    event   integer not null,
+    enabled boolean not null,
+    constraint notification_preferences_pk
+        primary key (user_id, event)
+);
+
+comment on table notification_preferences is 'Per-event notification settings of users. Events without a row in this table are enabled by default.';
+comment on column notification_preferences.user_id is 'Reference to the user that the setting belongs to.';
+comment on column notification_preferences.event is 'The `NotificationEvent` that the setting applies to (0 = publish, 1 = yank, 2 = ownership change, 3 = token expiry, 4 = typosquat, 5 = broken README links, 6 = new reverse dependency).';
+comment on column notification_preferences.enabled is 'Whether the user wants to receive notifications for the event.';
@@ -0,0 +1,63 @@
+create table webhooks
+(
+    id         serial
+        constraint webhooks_pk
+            primary key,
+    user_id    integer   not null
+        constraint webhooks_user_id_fkey
+            references users
+            on delete cascade,
+    crate_id   integer
+        constraint webhooks_crate_id_fkey
+            references crates
+            on delete cascade,
+    url        varchar   not null,
+    secret     varchar   not null,
+    events     integer[] not null,
+    created_at timestamp not null default now()
+);
+
+create index webhooks_user_id_index on webhooks (user_id);
+create index webhooks_crate_id_index on webhooks (crate_id);
+
+comment on table webhooks is 'Webhook endpoints that users registered to be notified about events of crates.';
+comment on column webhooks.id is 'Unique identifier of the webhook.';
+comment on column webhooks.user_id is 'Reference to the user that registered the webhook.';
+comment on column webhooks.crate_id is 'Reference to the crate that the webhook is subscribed to. If `NULL`, the webhook is subscribed to all crates that the user follows.';
+comment on column webhooks.url is 'URL that the event payloads are sent to.';
+comment on column webhooks.secret is 'Secret that is used to sign the event payloads with HMAC-SHA256.';
+comment on column webhooks.events is 'The `WebhookEvent`s that the webhook is subscribed to (0 = publish, 1 = yank, 2 = unyank, 3 = owner change).';
+comment on column webhooks.created_at is 'Time at which the webhook was registered.';
+
+create table webhook_deliveries
+(
+    id              bigserial
+        constraint webhook_deliveries_pk
+            primary key,
+    webhook_id      integer   not null
+        constraint webhook_deliveries_webhook_id_fkey
+            references webhooks
+            on delete cascade,
+    event           integer   not null,
+    payload         jsonb     not null,
+    attempts        integer   not null default 0,
+    status_code     integer,
+    error           varchar,
+    created_at      timestamp not null default now(),
+    last_attempt_at timestamp,
+    delivered_at    timestamp
+);
+
+create index webhook_deliveries_webhook_id_index on webhook_deliveries (webhook_id);
+
+comment on table webhook_deliveries is 'Log of the event payloads that were sent, or will be sent, to webhook endpoints.';
+comment on column webhook_deliveries.id is 'Unique identifier of the delivery. Sent to the webhook endpoint in the `X-Crates-Io-Delivery` header.';
+comment on column webhook_deliveries.webhook_id is 'Reference to the webhook that the payload is delivered to.';
+comment on column webhook_deliveries.event is 'The `WebhookEvent` that triggered the delivery.';
+comment on column webhook_deliveries.payload is 'The JSON payload that is sent to the webhook endpoint.';
+comment on column webhook_deliveries.attempts is 'Number of delivery attempts so far.';
+comment on column webhook_deliveries.status_code is 'HTTP status code of the response to the last delivery attempt, if any.';
+comment on column webhook_deliveries.error is 'Description of the error of the last delivery attempt, if it failed.';
+comment on column webhook_deliveries.created_at is 'Time at which the event was triggered.';
+comment on column webhook_deliveries.last_attempt_at is 'Time of the last delivery attempt.';
+comment on column webhook_deliveries.delivered_at is 'Time at which the payload was successfully delivered.';
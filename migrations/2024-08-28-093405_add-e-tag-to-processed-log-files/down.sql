@@ -0,0 +1,2 @@
+alter table processed_log_files
+    drop column e_tag;
@@ -0,0 +1,7 @@
+alter table processed_log_files
+    add column e_tag varchar;
+
+comment on column processed_log_files.e_tag is 'ETag of the log file, used to detect log files that were renamed after they were processed.';
+
+create index processed_log_files_e_tag_index
+    on processed_log_files (e_tag);
@@ -0,0 +1 @@
+drop table readme_link_reports;
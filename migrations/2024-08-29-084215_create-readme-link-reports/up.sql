@@ -0,0 +1,16 @@
+create table readme_link_reports
+(
+    crate_id     integer   not null
+        constraint readme_link_reports_pk
+            primary key
+        constraint readme_link_reports_crate_id_fkey
+            references crates
+            on delete cascade,
+    broken_links text[]    not null,
+    checked_at   timestamp not null default now()
+);
+
+comment on table readme_link_reports is 'Results of the last README link check of each crate. Used to only notify the crate owners if the set of broken links has changed.';
+comment on column readme_link_reports.crate_id is 'Reference to the crate whose README was checked.';
+comment on column readme_link_reports.broken_links is 'Sorted URLs of the links that were found to be broken.';
+comment on column readme_link_reports.checked_at is 'Time at which the README links were last checked.';
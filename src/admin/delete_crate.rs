@@ -103,6 +103,11 @@ pub fn run(opts: Opts) -> anyhow::Result<()> {
             warn!(%name, ?error, "Failed to delete readme files from S3");
         }
 
+        info!(%name, "Deleting changelog files from S3");
+        if let Err(error) = rt.block_on(store.delete_all_changelogs(name)) {
+            warn!(%name, ?error, "Failed to delete changelog files from S3");
+        }
+
         info!(%name, "Deleting RSS feed from S3");
         let feed_id = FeedId::Crate { name: name.clone() };
         if let Err(error) = rt.block_on(store.delete_feed(&feed_id)) {
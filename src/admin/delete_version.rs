@@ -104,6 +104,15 @@ pub fn run(opts: Opts) -> anyhow::Result<()> {
             }
             Ok(_) => {}
         }
+
+        debug!(%crate_name, %version, "Deleting changelog file from S3");
+        match rt.block_on(store.delete_changelog(crate_name, version)) {
+            Err(object_store::Error::NotFound { .. }) => {}
+            Err(error) => {
+                warn!(%crate_name, %version, ?error, "Failed to delete changelog file from S3")
+            }
+            Ok(_) => {}
+        }
     }
 
     Ok(())
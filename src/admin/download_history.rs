@@ -0,0 +1,92 @@
+use crate::db;
+use crate::models::{Crate, CrateDownloadsMonthly};
+use crate::schema::crates;
+use crate::util::dates::first_day_of_month;
+use crate::worker::jobs::RollupVersionDownloads;
+use anyhow::{anyhow, Context};
+use chrono::{Months, NaiveDate, Utc};
+use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+
+#[derive(clap::Parser, Debug)]
+#[command(
+    name = "download-history",
+    about = "Manage and inspect the monthly download history that is rolled up from the archived version downloads."
+)]
+pub enum Command {
+    /// Enqueue jobs to roll up the archived version downloads of the given
+    /// months into the `version_downloads_monthly` table.
+    Backfill {
+        /// The first month to roll up (`YYYY-MM`)
+        #[arg(long, value_parser = parse_month)]
+        from: NaiveDate,
+        /// The last month to roll up (`YYYY-MM`, default: current month)
+        #[arg(long, value_parser = parse_month)]
+        to: Option<NaiveDate>,
+    },
+    /// Print the monthly downloads of a crate.
+    Show {
+        /// Name of the crate
+        name: String,
+        /// The first month to show (`YYYY-MM`, default: 12 months ago)
+        #[arg(long, value_parser = parse_month)]
+        from: Option<NaiveDate>,
+        /// The last month to show (`YYYY-MM`, default: current month)
+        #[arg(long, value_parser = parse_month)]
+        to: Option<NaiveDate>,
+    },
+}
+
+pub fn run(command: Command) -> anyhow::Result<()> {
+    let mut conn = db::oneoff_connection().context("Failed to connect to the database")?;
+
+    let current_month = first_day_of_month(Utc::now().date_naive());
+
+    match command {
+        Command::Backfill { from, to } => {
+            let to = to.unwrap_or(current_month);
+            for month in months(from, to) {
+                println!("Enqueueing rollup of archived version downloads for {month}");
+                RollupVersionDownloads::month(month).enqueue(&mut conn)?;
+            }
+        }
+        Command::Show { name, from, to } => {
+            let to = to.unwrap_or(current_month);
+            let from = from.unwrap_or(to - Months::new(11));
+
+            let crate_id: i32 = Crate::by_name(&name)
+                .select(crates::id)
+                .first(&mut conn)
+                .optional()?
+                .ok_or_else(|| anyhow!("Crate `{name}` does not exist"))?;
+
+            let end_date = to + Months::new(1) - chrono::Duration::days(1);
+            let downloads = CrateDownloadsMonthly::query(crate_id, from, end_date)
+                .load::<CrateDownloadsMonthly>(&mut conn)
+                .context("Failed to load the monthly downloads")?;
+
+            for month in months(from, to) {
+                let downloads = downloads
+                    .iter()
+                    .find(|row| row.month == month)
+                    .map(|row| row.downloads)
+                    .unwrap_or_default();
+
+                println!("{}  {downloads}", month.format("%Y-%m"));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the first days of all months between `from` and `to` (inclusive).
+fn months(from: NaiveDate, to: NaiveDate) -> impl Iterator<Item = NaiveDate> {
+    std::iter::successors(Some(from), |month| Some(*month + Months::new(1)))
+        .take_while(move |month| *month <= to)
+}
+
+fn parse_month(month: &str) -> anyhow::Result<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{month}-01"), "%F")
+        .with_context(|| format!("Invalid month `{month}` (expected YYYY-MM)"))
+}
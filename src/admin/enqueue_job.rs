@@ -20,6 +20,7 @@ pub enum Command {
         before: Option<NaiveDate>,
     },
     UpdateDownloads,
+    UpdateQualityScores,
     CleanProcessedLogFiles,
     DumpDb,
     DailyDbMaintenance,
@@ -32,6 +33,15 @@ pub enum Command {
         #[arg()]
         name: String,
     },
+    /// Update the documents of the given crates in the external search
+    /// backend
+    SyncSearchIndex {
+        #[arg(required_unless_present = "all")]
+        names: Vec<String>,
+        /// Update the documents of all crates
+        #[arg(long, conflicts_with = "names")]
+        all: bool,
+    },
     ProcessCdnLogQueue(jobs::ProcessCdnLogQueue),
     SyncAdmins {
         /// Force a sync even if one is already in progress
@@ -69,6 +79,9 @@ pub fn run(command: Command) -> Result<()> {
                 jobs::UpdateDownloads.enqueue(conn)?;
             }
         }
+        Command::UpdateQualityScores => {
+            jobs::UpdateQualityScores.enqueue(conn)?;
+        }
         Command::CleanProcessedLogFiles => {
             jobs::CleanProcessedLogFiles.enqueue(conn)?;
         }
@@ -124,6 +137,16 @@ pub fn run(command: Command) -> Result<()> {
 
             jobs::CheckTyposquat::new(&name).enqueue(conn)?;
         }
+        Command::SyncSearchIndex { names, all } => {
+            let names = match all {
+                true => crates::table.select(crates::name).load(conn)?,
+                false => names,
+            };
+
+            for name in names {
+                jobs::SyncSearchIndex::new(name).enqueue(conn)?;
+            }
+        }
         Command::SendTokenExpiryNotifications => {
             jobs::SendTokenExpiryNotifications.enqueue(conn)?;
         }
@@ -2,6 +2,7 @@ pub mod default_versions;
 pub mod delete_crate;
 pub mod delete_version;
 pub mod dialoguer;
+pub mod download_history;
 pub mod enqueue_job;
 pub mod migrate;
 pub mod on_call;
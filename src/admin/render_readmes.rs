@@ -4,21 +4,24 @@ use crate::{
     schema::{crates, readme_renderings, versions},
 };
 use anyhow::{anyhow, Context};
-use std::path::PathBuf;
-use std::{io::Read, path::Path, sync::Arc, thread};
+use std::{sync::Arc, thread};
 
 use crate::storage::Storage;
 use chrono::{NaiveDateTime, Utc};
-use crates_io_markdown::text_to_html;
-use crates_io_tarball::{Manifest, StringOrBool};
+use crates_io_markdown::{
+    extract_toc, text_to_html_with_options, ImageProxy, RenderOptions, TocEntry,
+};
+use crates_io_tarball::find_readme;
 use diesel::prelude::*;
-use flate2::read::GzDecoder;
 use reqwest::{blocking::Client, header};
-use std::str::FromStr;
-use tar::{self, Archive};
+use url::Url;
 
 const USER_AGENT: &str = "crates-admin";
 
+/// The maximum size of the decompressed crate files, which matches the
+/// default of the `max_unpack_size` server setting.
+const MAX_UNPACK_SIZE: u64 = 512 * 1024 * 1024;
+
 #[derive(clap::Parser, Debug)]
 #[command(
     name = "render-readmes",
@@ -38,6 +41,18 @@ pub struct Opts {
     /// Only rerender readmes for the specified crate.
     #[arg(long = "crate")]
     crate_name: Option<String>,
+
+    /// Highlight the code blocks of the readmes on the server.
+    #[arg(long)]
+    syntax_highlighting: bool,
+
+    /// Load the images of the readmes through this camo-style image proxy.
+    #[arg(long, env = "README_IMAGE_PROXY_URL", requires = "image_proxy_key")]
+    image_proxy_url: Option<Url>,
+
+    /// The key to sign the URLs of the image proxy with.
+    #[arg(long, env = "README_IMAGE_PROXY_KEY", hide_env_values = true)]
+    image_proxy_key: Option<String>,
 }
 
 pub fn run(opts: Opts) -> anyhow::Result<()> {
@@ -78,6 +93,15 @@ pub fn run(opts: Opts) -> anyhow::Result<()> {
     println!("Rendering {total_versions} versions");
 
     let page_size = opts.page_size;
+    let image_proxy = opts
+        .image_proxy_url
+        .zip(opts.image_proxy_key)
+        .map(|(url, key)| ImageProxy::new(url, key));
+
+    let options = RenderOptions {
+        syntax_highlighting: opts.syntax_highlighting,
+        image_proxy,
+    };
 
     let total_pages = total_versions / page_size;
     let total_pages = if total_versions % page_size == 0 {
@@ -109,9 +133,12 @@ pub fn run(opts: Opts) -> anyhow::Result<()> {
 
             let client = client.clone();
             let storage = storage.clone();
-            let handle = thread::spawn::<_, anyhow::Result<()>>(move || {
+            let options = options.clone();
+            let version_id = version.id;
+            let handle = thread::spawn::<_, anyhow::Result<Vec<TocEntry>>>(move || {
                 println!("[{}-{}] Rendering README...", krate_name, version.num);
-                let readme = get_readme(&storage, &client, &version, &krate_name)?;
+                let readme = get_readme(&storage, &client, &version, &krate_name, &options)?;
+                let toc = extract_toc(&readme);
                 if !readme.is_empty() {
                     let rt = tokio::runtime::Builder::new_current_thread()
                         .enable_all()
@@ -122,15 +149,18 @@ pub fn run(opts: Opts) -> anyhow::Result<()> {
                         .context("Failed to upload rendered README file to S3")?;
                 }
 
-                Ok(())
+                Ok(toc)
             });
-            tasks.push(handle);
+            tasks.push((version_id, handle));
         }
-        for handle in tasks {
+        for (version_id, handle) in tasks {
             match handle.join() {
                 Err(err) => println!("Thread panicked: {err:?}"),
                 Ok(Err(err)) => println!("Thread failed: {err:?}"),
-                _ => {}
+                Ok(Ok(toc)) => {
+                    Version::record_readme_toc(version_id, &toc, conn)
+                        .context("Couldn't record table of contents")?;
+                }
             }
         }
     }
@@ -144,6 +174,7 @@ fn get_readme(
     client: &Client,
     version: &Version,
     krate_name: &str,
+    options: &RenderOptions,
 ) -> anyhow::Result<String> {
     let pkg_name = format!("{}-{}", krate_name, version.num);
 
@@ -164,83 +195,38 @@ fn get_readme(
         ));
     }
 
-    let reader = GzDecoder::new(response);
-    let archive = Archive::new(reader);
-    render_pkg_readme(archive, &pkg_name)
+    let tarball = response.bytes().context("Failed to read crate file")?;
+    render_pkg_readme(&tarball, &pkg_name, options)
 }
 
-fn render_pkg_readme<R: Read>(mut archive: Archive<R>, pkg_name: &str) -> anyhow::Result<String> {
-    let mut entries = archive.entries().context("Invalid tar archive entries")?;
-
-    let manifest: Manifest = {
-        let path = format!("{pkg_name}/Cargo.toml");
-        let contents = find_file_by_path(&mut entries, Path::new(&path))
-            .context("Failed to read Cargo.toml file")?;
-
-        Manifest::from_str(&contents).context("Failed to parse manifest file")?
-
-        // We don't call `validate_manifest()` here since the additional validation is not needed
-        // and it would prevent us from reading a couple of legacy crate files.
-    };
+/// Renders the README of the package in the given tarball, or returns an
+/// empty string if the package doesn't have a README.
+fn render_pkg_readme(
+    tarball: &[u8],
+    pkg_name: &str,
+    options: &RenderOptions,
+) -> anyhow::Result<String> {
+    let readme =
+        find_readme(pkg_name, tarball, MAX_UNPACK_SIZE).context("Failed to read crate file")?;
 
-    let rendered = {
-        let readme = manifest
-            .package
-            .as_ref()
-            .and_then(|p| p.readme.as_ref())
-            .and_then(|r| r.as_ref().as_local());
-
-        let readme_path = match readme {
-            Some(StringOrBool::Bool(bool)) if !(*bool) => return Ok("".to_string()),
-            Some(StringOrBool::String(path)) => PathBuf::from(path),
-            _ => PathBuf::from("README.md"),
-        };
-
-        let path = Path::new(pkg_name).join(&readme_path);
-        let contents = find_file_by_path(&mut entries, Path::new(&path))
-            .with_context(|| format!("Failed to read {} file", readme_path.display()))?;
-
-        // pkg_path_in_vcs Unsupported from admin::render_readmes. See #4095
-        // Would need access to cargo_vcs_info
-        let pkg_path_in_vcs = None;
-
-        let repository = manifest
-            .package
-            .as_ref()
-            .and_then(|p| p.repository.as_ref())
-            .and_then(|r| r.as_ref().as_local())
-            .map(|s| s.as_str());
-
-        text_to_html(&contents, &readme_path, repository, pkg_path_in_vcs)
+    let Some(readme) = readme else {
+        return Ok(String::new());
     };
-    Ok(rendered)
-}
 
-/// Search an entry by its path in a Tar archive.
-fn find_file_by_path<R: Read>(
-    entries: &mut tar::Entries<'_, R>,
-    path: &Path,
-) -> anyhow::Result<String> {
-    let mut file = entries
-        .filter_map(|entry| entry.ok())
-        .find(|file| match file.path() {
-            Ok(p) => p == path,
-            Err(_) => false,
-        })
-        .ok_or_else(|| anyhow!("Failed to find tarball entry: {}", path.display()))?;
-
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)
-        .context("Failed to read file contents")?;
-
-    Ok(contents)
+    Ok(text_to_html_with_options(
+        &readme.contents,
+        &readme.path,
+        readme.repository.as_deref(),
+        readme.path_in_vcs.as_ref(),
+        options,
+    ))
 }
 
 #[cfg(test)]
 pub mod tests {
     use crates_io_tarball::TarballBuilder;
 
-    use super::render_pkg_readme;
+    use super::{render_pkg_readme, RenderOptions};
 
     #[test]
     fn test_render_pkg_readme() {
@@ -255,10 +241,10 @@ readme = "README.md"
 "#,
             )
             .add_file("foo-0.0.1/README.md", b"readme")
-            .build_unzipped();
+            .build();
 
         let result =
-            render_pkg_readme(tar::Archive::new(&*serialized_archive), "foo-0.0.1").unwrap();
+            render_pkg_readme(&serialized_archive, "foo-0.0.1", &RenderOptions::default()).unwrap();
         assert!(result.contains("readme"))
     }
 
@@ -271,11 +257,12 @@ readme = "README.md"
 [package]
 "#,
             )
-            .build_unzipped();
+            .build();
 
         assert_err!(render_pkg_readme(
-            tar::Archive::new(&*serialized_archive),
-            "foo-0.0.1"
+            &serialized_archive,
+            "foo-0.0.1",
+            &RenderOptions::default()
         ));
     }
 
@@ -291,10 +278,10 @@ version = "0.0.1"
 "#,
             )
             .add_file("foo-0.0.1/README.md", b"readme")
-            .build_unzipped();
+            .build();
 
         let result =
-            render_pkg_readme(tar::Archive::new(&*serialized_archive), "foo-0.0.1").unwrap();
+            render_pkg_readme(&serialized_archive, "foo-0.0.1", &RenderOptions::default()).unwrap();
         assert!(result.contains("readme"))
     }
 
@@ -312,10 +299,10 @@ repository = "https://github.com/foo/foo"
 "#,
             )
             .add_file("foo-0.0.1/README.md", b"readme [link](./Other.md)")
-            .build_unzipped();
+            .build();
 
         let result =
-            render_pkg_readme(tar::Archive::new(&*serialized_archive), "foo-0.0.1").unwrap();
+            render_pkg_readme(&serialized_archive, "foo-0.0.1", &RenderOptions::default()).unwrap();
         assert!(result.contains("\"https://github.com/foo/foo/blob/HEAD/./Other.md\""))
     }
 
@@ -336,10 +323,10 @@ repository = "https://github.com/foo/foo"
                 "foo-0.0.1/docs/README.md",
                 b"docs/readme [link](./Other.md)",
             )
-            .build_unzipped();
+            .build();
 
         let result =
-            render_pkg_readme(tar::Archive::new(&*serialized_archive), "foo-0.0.1").unwrap();
+            render_pkg_readme(&serialized_archive, "foo-0.0.1", &RenderOptions::default()).unwrap();
         assert!(result.contains("docs/readme"));
         assert!(result.contains("\"https://github.com/foo/foo/blob/HEAD/docs/./Other.md\""))
     }
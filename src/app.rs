@@ -1,13 +1,16 @@
 //! Application-wide components in a struct accessible from each request
 
 use crate::config;
+use crate::controllers::krate::autocomplete::AutocompleteCache;
 use crate::db::{connection_url, make_manager_config, ConnectionConfig};
+use crate::dns::PublicResolver;
 use std::ops::Deref;
 use std::sync::Arc;
 
 use crate::email::Emails;
 use crate::metrics::{InstanceMetrics, ServiceMetrics};
 use crate::rate_limiter::RateLimiter;
+use crate::search::{self, SearchBackend};
 use crate::storage::Storage;
 use axum::extract::{FromRef, FromRequestParts, State};
 use crates_io_github::GitHubClient;
@@ -54,6 +57,17 @@ pub struct App {
 
     /// Rate limit select actions.
     pub rate_limiter: RateLimiter,
+
+    /// In-memory cache of the crate name autocomplete responses
+    pub autocomplete_cache: AutocompleteCache,
+
+    /// External search engine that the crate search is offloaded to, or
+    /// `None` if the database is used for searching
+    pub search_backend: Option<Arc<dyn SearchBackend>>,
+
+    /// DNS resolver that is used to check that user-provided URLs, like
+    /// webhook endpoints, only point to public addresses
+    pub dns_resolver: PublicResolver,
 }
 
 impl App {
@@ -135,6 +149,12 @@ impl App {
             service_metrics: ServiceMetrics::new().expect("could not initialize service metrics"),
             instance_metrics,
             rate_limiter: RateLimiter::new(config.rate_limiter.clone()),
+            autocomplete_cache: AutocompleteCache::builder()
+                .max_capacity(config.autocomplete_cache_size)
+                .time_to_live(config.autocomplete_cache_ttl)
+                .build(),
+            search_backend: search::from_config(&config.search_backend),
+            dns_resolver: PublicResolver::default(),
             config: Arc::new(config),
         }
     }
@@ -7,21 +7,27 @@
 //! the worker thread), we will rebuild the runner and try again up to 5 times.
 //! After the 5th occurrence, we will panic.
 //!
+//! On `SIGTERM` or `SIGINT` the workers stop picking up new jobs, and the
+//! running jobs get `WORKER_SHUTDOWN_TIMEOUT_SECONDS` to finish before the
+//! process exits.
+//!
 //! Usage:
 //!      cargo run --bin background-worker
 
 #[macro_use]
 extern crate tracing;
 
-use anyhow::Context;
+use anyhow::{anyhow, Context};
 use crates_io::cloudfront::CloudFront;
 use crates_io::db::make_manager_config;
 use crates_io::fastly::Fastly;
+use crates_io::link_checker::{LinkChecker, LinkCheckerImpl};
 use crates_io::storage::Storage;
 use crates_io::team_repo::TeamRepoImpl;
-use crates_io::worker::{Environment, RunnerExt};
+use crates_io::webhooks::WebhookClientImpl;
+use crates_io::worker::{DatabaseJobMetrics, Environment, RunnerExt};
 use crates_io::{config, Emails};
-use crates_io::{db, ssh};
+use crates_io::{db, search, ssh};
 use crates_io_env_vars::var;
 use crates_io_index::RepositoryConfig;
 use crates_io_worker::Runner;
@@ -34,6 +40,7 @@ use secrecy::ExposeSecret;
 use std::sync::Arc;
 use std::thread::sleep;
 use std::time::Duration;
+use tokio::signal::unix::{signal, SignalKind};
 
 fn main() -> anyhow::Result<()> {
     let _sentry = crates_io::sentry::init();
@@ -84,11 +91,18 @@ fn main() -> anyhow::Result<()> {
     let emails = Emails::from_environment(&config);
     let fastly = Fastly::from_environment(client.clone());
     let team_repo = TeamRepoImpl::default();
+    let search_backend = search::from_config(&config.search_backend);
+    let link_checker = config
+        .readme_link_checks
+        .then(|| Box::new(LinkCheckerImpl::default()) as Box<dyn LinkChecker + Send + Sync>);
+    let webhook_client = WebhookClientImpl::default();
 
     let manager_config = make_manager_config(config.db.enforce_tls);
     let manager = AsyncDieselConnectionManager::new_with_config(db_url, manager_config);
     let deadpool = Pool::builder(manager).max_size(10).build().unwrap();
 
+    let shutdown_timeout = config.worker_shutdown_timeout;
+
     let environment = Environment::builder()
         .config(Arc::new(config))
         .repository_config(repository_config)
@@ -99,6 +113,9 @@ fn main() -> anyhow::Result<()> {
         .deadpool(deadpool.clone())
         .emails(emails)
         .team_repo(Box::new(team_repo))
+        .search_backend(search_backend)
+        .link_checker(link_checker)
+        .webhook_client(Some(Box::new(webhook_client)))
         .build()?;
 
     let environment = Arc::new(environment);
@@ -112,18 +129,59 @@ fn main() -> anyhow::Result<()> {
         }
     });
 
+    let job_metrics = DatabaseJobMetrics::new(deadpool.clone());
+
     let runner = Runner::new(deadpool, environment.clone())
+        .with_metrics(job_metrics)
         .configure_default_queue(|queue| queue.num_workers(5))
         .configure_queue("downloads", |queue| queue.num_workers(1))
         .configure_queue("repository", |queue| queue.num_workers(1))
+        .configure_queue("webhooks", |queue| queue.num_workers(2))
         .register_crates_io_job_types();
 
-    runtime.block_on(async {
-        let handle = runner.start();
+    let result = runtime.block_on(async {
+        let mut handle = runner.start();
 
         info!("Runner booted, running jobs");
-        handle.wait_for_shutdown().await
+        tokio::select! {
+            _ = shutdown_signal() => {}
+            _ = handle.wait_for_shutdown() => {
+                return Err(anyhow!("All background workers stopped unexpectedly"));
+            }
+        }
+
+        info!(?shutdown_timeout, "Shutting down runner…");
+        if handle.shutdown(shutdown_timeout).await {
+            info!("Runner has gracefully shutdown!");
+        }
+
+        Ok(())
     });
 
-    Ok(())
+    // Jobs that did not finish before the deadline are still blocking threads
+    // of the runtime, so we don't wait for them to finish.
+    runtime.shutdown_background();
+
+    result
+}
+
+async fn shutdown_signal() {
+    let interrupt = async {
+        signal(SignalKind::interrupt())
+            .expect("failed to install signal handler")
+            .recv()
+            .await;
+    };
+
+    let terminate = async {
+        signal(SignalKind::terminate())
+            .expect("failed to install signal handler")
+            .recv()
+            .await;
+    };
+
+    tokio::select! {
+        _ = interrupt => {},
+        _ = terminate => {},
+    }
 }
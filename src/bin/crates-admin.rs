@@ -2,8 +2,9 @@
 extern crate tracing;
 
 use crates_io::admin::{
-    default_versions, delete_crate, delete_version, enqueue_job, migrate, populate, render_readmes,
-    test_pagerduty, transfer_crates, upload_index, verify_token, yank_version,
+    default_versions, delete_crate, delete_version, download_history, enqueue_job, migrate,
+    populate, render_readmes, test_pagerduty, transfer_crates, upload_index, verify_token,
+    yank_version,
 };
 
 #[derive(clap::Parser, Debug)]
@@ -23,6 +24,8 @@ enum Command {
     EnqueueJob(enqueue_job::Command),
     #[clap(subcommand)]
     DefaultVersions(default_versions::Command),
+    #[clap(subcommand)]
+    DownloadHistory(download_history::Command),
 }
 
 fn main() -> anyhow::Result<()> {
@@ -50,6 +53,7 @@ fn main() -> anyhow::Result<()> {
         Command::YankVersion(opts) => yank_version::run(opts),
         Command::EnqueueJob(command) => enqueue_job::run(command),
         Command::DefaultVersions(opts) => default_versions::run(opts),
+        Command::DownloadHistory(command) => download_history::run(command),
     }
 }
 
@@ -1,6 +1,12 @@
-use crates_io_env_vars::{required_var, var};
+use crates_io_env_vars::{required_var, var, var_parsed};
+use regex::Regex;
 use secrecy::SecretString;
 
+/// Matches the log files that nginx and Caddy have finished writing to, once
+/// they have been rotated and compressed by logrotate, as well as the log
+/// files uploaded by CloudFront and Fastly.
+const DEFAULT_POLL_PATTERN: &str = r"\.gz$";
+
 #[derive(Debug, Clone)]
 pub enum CdnLogQueueConfig {
     SQS {
@@ -9,6 +15,24 @@ pub enum CdnLogQueueConfig {
         queue_url: String,
         region: String,
     },
+    /// Periodically lists the CDN log storage for new log files instead of
+    /// receiving S3 event notifications from SQS.
+    ///
+    /// This is intended for self-hosted deployments that don't run on AWS.
+    Polling {
+        /// Only log files below this path prefix are considered.
+        prefix: Option<String>,
+        /// Only log files with a path matching this pattern are considered.
+        ///
+        /// The pattern must only match log files that are no longer written
+        /// to or compressed afterwards, since the content of a log file
+        /// changes in both cases, and it would be counted again.
+        pattern: Regex,
+        /// The region of the S3 bucket, if the CDN log storage is using S3.
+        region: String,
+        /// The name of the S3 bucket, if the CDN log storage is using S3.
+        bucket: String,
+    },
     Mock,
 }
 
@@ -27,6 +51,22 @@ impl CdnLogQueueConfig {
             });
         }
 
+        if let Some(prefix) = var("CDN_LOG_QUEUE_POLL_PREFIX")? {
+            let prefix = Some(prefix).filter(|prefix| !prefix.is_empty());
+            let region = var("CDN_LOG_QUEUE_POLL_REGION")?.unwrap_or_default();
+            let bucket = var("CDN_LOG_QUEUE_POLL_BUCKET")?.unwrap_or_default();
+            let pattern = var_parsed("CDN_LOG_QUEUE_POLL_PATTERN")?;
+            let pattern = pattern.unwrap_or_else(|| Regex::new(DEFAULT_POLL_PATTERN).unwrap());
+
+            info!("Polling the CDN log storage for new log files");
+            return Ok(Self::Polling {
+                prefix,
+                pattern,
+                region,
+                bucket,
+            });
+        }
+
         warn!("Falling back to mocked CDN log queue");
         Ok(Self::Mock)
     }
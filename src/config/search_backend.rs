@@ -0,0 +1,22 @@
+use crates_io_env_vars::var;
+
+#[derive(Debug, Clone)]
+pub enum SearchBackendConfig {
+    /// Use the built-in full-text search of the database.
+    Database,
+    /// Use an OpenSearch or Elasticsearch compatible search engine.
+    OpenSearch { url: String, index: String },
+    /// Use an in-memory search index, which is only useful for testing.
+    Memory,
+}
+
+impl SearchBackendConfig {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let Some(url) = var("SEARCH_BACKEND_URL")? else {
+            return Ok(Self::Database);
+        };
+
+        let index = var("SEARCH_BACKEND_INDEX")?.unwrap_or_else(|| "crates".into());
+        Ok(Self::OpenSearch { url, index })
+    }
+}
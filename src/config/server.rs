@@ -8,10 +8,13 @@ use crate::Env;
 use super::base::Base;
 use super::database_pools::DatabasePools;
 use crate::config::cdn_log_storage::CdnLogStorageConfig;
+use crate::config::search_backend::SearchBackendConfig;
 use crate::config::CdnLogQueueConfig;
 use crate::middleware::cargo_compat::StatusCodeConfig;
 use crate::storage::StorageConfig;
+use crates_io_cdn_logs::FilterConfig;
 use crates_io_env_vars::{list, list_parsed, required_var, var, var_parsed};
+use crates_io_markdown::ImageProxy;
 use http::HeaderValue;
 use std::collections::{HashMap, HashSet};
 use std::net::IpAddr;
@@ -20,6 +23,9 @@ use std::time::Duration;
 
 const DEFAULT_VERSION_ID_CACHE_SIZE: u64 = 10_000;
 const DEFAULT_VERSION_ID_CACHE_TTL: u64 = 5 * 60; // 5 minutes
+const DEFAULT_AUTOCOMPLETE_CACHE_SIZE: u64 = 10_000;
+const DEFAULT_AUTOCOMPLETE_CACHE_TTL: u64 = 5 * 60; // 5 minutes
+const DEFAULT_WORKER_SHUTDOWN_TIMEOUT: u64 = 25; // Heroku sends SIGKILL after 30 seconds
 
 /// Maximum number of features a crate can have or that a feature itself can
 /// enable. This value can be overridden in the database on a per-crate basis.
@@ -37,6 +43,11 @@ pub struct Server {
     pub storage: StorageConfig,
     pub cdn_log_storage: CdnLogStorageConfig,
     pub cdn_log_queue: CdnLogQueueConfig,
+    /// The rules for discarding requests when counting downloads in CDN
+    /// log files.
+    pub cdn_log_filter: FilterConfig,
+    /// The search engine that the crate search is offloaded to.
+    pub search_backend: SearchBackendConfig,
     pub session_key: cookie::Key,
     pub gh_client_id: ClientId,
     pub gh_client_secret: ClientSecret,
@@ -61,8 +72,31 @@ pub struct Server {
     pub blocked_routes: HashSet<String>,
     pub version_id_cache_size: u64,
     pub version_id_cache_ttl: Duration,
+
+    /// Maximum number of cached responses of the crate name autocomplete
+    /// endpoint.
+    pub autocomplete_cache_size: u64,
+    /// How long responses of the crate name autocomplete endpoint are cached.
+    pub autocomplete_cache_ttl: Duration,
+
+    /// Whether code blocks in READMEs are syntax highlighted when the READMEs
+    /// are rendered, instead of leaving the highlighting to the frontend.
+    pub readme_syntax_highlighting: bool,
+
+    /// The camo-style proxy that images in READMEs are loaded through, to
+    /// avoid leaking the IP addresses of visitors to third-party hosts.
+    pub readme_image_proxy: Option<ImageProxy>,
+
+    /// Whether the links in READMEs are checked after rendering, so that
+    /// the crate owners can be notified about broken links.
+    pub readme_link_checks: bool,
+
     pub cdn_user_agent: String,
 
+    /// How long the background worker waits for running jobs to finish
+    /// after receiving a shutdown signal.
+    pub worker_shutdown_timeout: Duration,
+
     /// Instructs the `cargo_compat` middleware whether to adjust response
     /// status codes to `200 OK` for all endpoints that are relevant for cargo.
     pub cargo_compat_status_code_config: StatusCodeConfig,
@@ -109,6 +143,19 @@ impl Server {
     ///   endpoint even with a healthy database pool.
     /// - `BLOCKED_ROUTES`: A comma separated list of HTTP route patterns that are manually blocked
     ///   by an operator (e.g. `/crates/:crate_id/:version/download`).
+    /// - `WORKER_SHUTDOWN_TIMEOUT_SECONDS`: How long the background worker waits for running jobs
+    ///   to finish after receiving a shutdown signal. Defaults to 25 seconds.
+    /// - `CDN_LOG_DEDUP_WINDOW_SECONDS`: Repeated downloads of the same crate version from the same
+    ///   client IP address within this window are only counted once. Disabled by default.
+    /// - `CDN_LOG_BLOCKED_USER_AGENTS`: A comma separated list of case-insensitive `User-Agent`
+    ///   substrings of known scrapers, whose downloads are not counted. Defaults to an empty list.
+    /// - `README_SYNTAX_HIGHLIGHTING`: Whether code blocks in READMEs are syntax highlighted on the
+    ///   server when the READMEs are rendered. Defaults to `false`.
+    /// - `README_IMAGE_PROXY_URL`, `README_IMAGE_PROXY_KEY`: The base URL of a camo-style image
+    ///   proxy and the key to sign the image URLs with. Images in READMEs are only proxied if both
+    ///   are set.
+    /// - `README_LINK_CHECKS`: Whether the links in READMEs are checked after publishing, and the
+    ///   crate owners notified about broken links. Defaults to `false`.
     ///
     /// # Panics
     ///
@@ -157,6 +204,14 @@ impl Server {
 
         let storage = StorageConfig::from_environment();
 
+        let readme_image_proxy = match (
+            var_parsed("README_IMAGE_PROXY_URL")?,
+            var("README_IMAGE_PROXY_KEY")?,
+        ) {
+            (Some(url), Some(key)) => Some(ImageProxy::new(url, key)),
+            _ => None,
+        };
+
         // `sha256-dbf9FMl76C7BnK1CC3eWb3pvsQAUaTYSHAlBy9tNTG0=` refers to
         // the `script` in `public/github-redirect.html`
         let content_security_policy = format!(
@@ -175,6 +230,8 @@ impl Server {
             storage,
             cdn_log_storage: CdnLogStorageConfig::from_env()?,
             cdn_log_queue: CdnLogQueueConfig::from_env()?,
+            cdn_log_filter: cdn_log_filter()?,
+            search_backend: SearchBackendConfig::from_env()?,
             base,
             ip,
             port,
@@ -208,8 +265,20 @@ impl Server {
             version_id_cache_ttl: Duration::from_secs(
                 var_parsed("VERSION_ID_CACHE_TTL")?.unwrap_or(DEFAULT_VERSION_ID_CACHE_TTL),
             ),
+            autocomplete_cache_size: var_parsed("AUTOCOMPLETE_CACHE_SIZE")?
+                .unwrap_or(DEFAULT_AUTOCOMPLETE_CACHE_SIZE),
+            autocomplete_cache_ttl: Duration::from_secs(
+                var_parsed("AUTOCOMPLETE_CACHE_TTL")?.unwrap_or(DEFAULT_AUTOCOMPLETE_CACHE_TTL),
+            ),
+            readme_syntax_highlighting: var_parsed("README_SYNTAX_HIGHLIGHTING")?.unwrap_or(false),
+            readme_image_proxy,
+            readme_link_checks: var_parsed("README_LINK_CHECKS")?.unwrap_or(false),
             cdn_user_agent: var("WEB_CDN_USER_AGENT")?
                 .unwrap_or_else(|| "Amazon CloudFront".into()),
+            worker_shutdown_timeout: Duration::from_secs(
+                var_parsed("WORKER_SHUTDOWN_TIMEOUT_SECONDS")?
+                    .unwrap_or(DEFAULT_WORKER_SHUTDOWN_TIMEOUT),
+            ),
             cargo_compat_status_code_config: var_parsed("CARGO_COMPAT_STATUS_CODES")?
                 .unwrap_or(StatusCodeConfig::AdjustAll),
             serve_dist: true,
@@ -251,6 +320,13 @@ fn parse_cidr_block(block: &str) -> anyhow::Result<IpNetwork> {
     Ok(cidr)
 }
 
+fn cdn_log_filter() -> anyhow::Result<FilterConfig> {
+    Ok(FilterConfig {
+        dedup_window: var_parsed("CDN_LOG_DEDUP_WINDOW_SECONDS")?.map(Duration::from_secs),
+        blocked_user_agents: list("CDN_LOG_BLOCKED_USER_AGENTS")?,
+    })
+}
+
 fn blocked_traffic() -> Vec<(String, Vec<String>)> {
     let pattern_list = dotenvy::var("BLOCKED_TRAFFIC").unwrap_or_default();
     parse_traffic_patterns(&pattern_list)
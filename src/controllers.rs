@@ -65,6 +65,7 @@ pub mod util;
 
 pub mod category;
 pub mod crate_owner_invitation;
+pub mod email_preview;
 pub mod git;
 pub mod github;
 pub mod keyword;
@@ -76,3 +77,4 @@ pub mod team;
 pub mod token;
 pub mod user;
 pub mod version;
+pub mod webhook;
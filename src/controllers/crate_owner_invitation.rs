@@ -3,7 +3,7 @@ use super::frontend_prelude::*;
 use crate::auth::AuthCheck;
 use crate::auth::Authentication;
 use crate::controllers::helpers::pagination::{Page, PaginationOptions};
-use crate::models::{Crate, CrateOwnerInvitation, Rights, User};
+use crate::models::{Crate, CrateOwnerInvitation, Rights, User, Webhook, WebhookEvent};
 use crate::schema::{crate_owner_invitations, crates, users};
 use crate::util::diesel::Conn;
 use crate::util::errors::{forbidden, internal};
@@ -11,7 +11,9 @@ use crate::views::{
     EncodableCrateOwnerInvitation, EncodableCrateOwnerInvitationV1, EncodablePublicUser,
     InvitationResponse,
 };
+use crate::worker::jobs::{SendOwnershipChangeNotifications, TriggerWebhooks};
 use chrono::{Duration, Utc};
+use crates_io_worker::BackgroundJob;
 use diesel::{pg::Pg, sql_types::Bool};
 use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
 use indexmap::IndexMap;
@@ -285,6 +287,13 @@ pub async fn handle_invite(state: AppState, req: BytesRequest) -> AppResult<Json
         let invitation = CrateOwnerInvitation::find_by_id(user_id, crate_invite.crate_id, conn)?;
         if crate_invite.accepted {
             invitation.accept(conn, config)?;
+
+            let crate_id = crate_invite.crate_id;
+            let login = &auth.user().gh_login;
+            SendOwnershipChangeNotifications::new(crate_id, login, true).enqueue(conn)?;
+            if Webhook::any_subscribed_to(crate_id, WebhookEvent::OwnerChange, conn)? {
+                TriggerWebhooks::owner_change(crate_id, login, true).enqueue(conn)?;
+            }
         } else {
             invitation.decline(conn)?;
         }
@@ -307,8 +316,15 @@ pub async fn handle_invite_with_token(
 
         let invitation = CrateOwnerInvitation::find_by_token(&token, conn)?;
         let crate_id = invitation.crate_id;
+        let user_id = invitation.invited_user_id;
         invitation.accept(conn, config)?;
 
+        let user = User::find(conn, user_id)?;
+        SendOwnershipChangeNotifications::new(crate_id, &user.gh_login, true).enqueue(conn)?;
+        if Webhook::any_subscribed_to(crate_id, WebhookEvent::OwnerChange, conn)? {
+            TriggerWebhooks::owner_change(crate_id, &user.gh_login, true).enqueue(conn)?;
+        }
+
         Ok(Json(json!({
             "crate_owner_invitation": {
                 "crate_id": crate_id,
@@ -0,0 +1,161 @@
+//! Previews of all emails with example data, to make it easier to work on
+//! the email templates in `src/email/templates`.
+//!
+//! These endpoints are only available in development mode.
+
+use super::frontend_prelude::*;
+
+use crate::controllers::github::secret_scanning::TokenExposedEmail;
+use crate::controllers::user::me::UserConfirmEmail;
+use crate::email::{EmailError, Emails, RenderedEmail};
+use crate::models::krate::OwnerInviteEmail;
+use crate::util::errors::not_found;
+use crate::worker::jobs::{
+    AdminAccountEmail, BrokenLink, BrokenReadmeLinksEmail, ExpiryNotificationEmail,
+    NewReverseDependencyEmail, OwnershipChangeEmail, PossibleTyposquatEmail, VersionActionDetails,
+    VersionPublishedEmail, VersionYankedEmail,
+};
+use axum::response::{Html, IntoResponse};
+use chrono::{TimeZone, Utc};
+use secrecy::SecretString;
+use typomania::checks::Squat;
+
+/// Names of all emails that can be previewed.
+const EMAILS: &[&str] = &[
+    "admin_accounts",
+    "broken_readme_links",
+    "new_reverse_dependency",
+    "owner_invite",
+    "owner_added",
+    "owner_removed",
+    "possible_typosquat",
+    "token_expiry",
+    "token_exposed",
+    "user_confirm",
+    "version_published",
+    "version_yanked",
+];
+
+/// Handles the `GET /api/private/email_previews` route.
+pub async fn list() -> Json<Value> {
+    Json(json!({ "email_previews": EMAILS }))
+}
+
+/// Handles the `GET /api/private/email_previews/:name` route.
+///
+/// Returns the HTML body of the email, or the plain text body if the
+/// `?format=text` query parameter is used.
+pub async fn show(state: AppState, Path(name): Path<String>, req: Parts) -> AppResult<Response> {
+    let email = render_example(&name, &state.emails).ok_or_else(not_found)??;
+
+    let response = match req.query().get("format").map(String::as_str) {
+        None | Some("html") => Html(email.html).into_response(),
+        Some("text") => email.text.into_response(),
+        Some(_) => return Err(bad_request("invalid `format` parameter")),
+    };
+
+    Ok(response)
+}
+
+/// Renders the email with the given name using example data, or returns
+/// `None` if there is no such email.
+fn render_example(name: &str, emails: &Emails) -> Option<Result<RenderedEmail, EmailError>> {
+    let version_action = VersionActionDetails {
+        crate_name: "foo",
+        version: "1.2.3",
+        actor: "alice",
+        token_name: Some("ci"),
+    };
+
+    let rendered = match name {
+        "admin_accounts" => emails.render(&AdminAccountEmail::new(
+            vec!["new-admin".into()],
+            vec!["obsolete-admin".into()],
+        )),
+        "broken_readme_links" => emails.render(&BrokenReadmeLinksEmail {
+            crate_name: "foo",
+            version: "1.2.3",
+            broken_links: &[
+                BrokenLink {
+                    url: "https://example.com/missing".into(),
+                    reason: "HTTP status 404".into(),
+                },
+                BrokenLink {
+                    url: "https://unreachable.example.com/".into(),
+                    reason: "connection failed".into(),
+                },
+            ],
+        }),
+        "new_reverse_dependency" => emails.render(&NewReverseDependencyEmail {
+            crate_name: "foo",
+            dependent: "bar",
+            version: "0.1.0",
+        }),
+        "owner_invite" => emails.render(&OwnerInviteEmail {
+            user_name: "alice",
+            crate_name: "foo",
+            token: SecretString::new("secret-invite-token".into()),
+        }),
+        "owner_added" => emails.render(&OwnershipChangeEmail {
+            crate_name: "foo",
+            owner: "github:rust-lang:owners",
+            added: true,
+        }),
+        "owner_removed" => emails.render(&OwnershipChangeEmail {
+            crate_name: "foo",
+            owner: "bob",
+            added: false,
+        }),
+        "possible_typosquat" => emails.render(&PossibleTyposquatEmail {
+            crate_name: "sered",
+            squats: &[
+                Squat::OmittedCharacter("serde".into()),
+                Squat::SwappedCharacters("serde".into()),
+            ],
+        }),
+        "token_expiry" => emails.render(&ExpiryNotificationEmail {
+            name: "alice",
+            token_id: 42,
+            token_name: "ci",
+            expiry_date: Utc.with_ymd_and_hms(2024, 9, 1, 12, 0, 0).unwrap(),
+        }),
+        "token_exposed" => emails.render(&TokenExposedEmail {
+            reporter: "GitHub",
+            source: "commit",
+            token_name: "ci",
+            url: "https://github.com/rust-lang/crates.io/blob/main/.env",
+        }),
+        "user_confirm" => emails.render(&UserConfirmEmail {
+            user_name: "alice",
+            token: SecretString::new("secret-confirmation-token".into()),
+        }),
+        "version_published" => emails.render(&VersionPublishedEmail(version_action)),
+        "version_yanked" => emails.render(&VersionYankedEmail(version_action)),
+        _ => return None,
+    };
+
+    Some(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn email_previews() {
+        let emails = Emails::new_in_memory();
+
+        for name in EMAILS {
+            let email = render_example(name, &emails).unwrap().unwrap();
+            let snapshot = format!("Subject: {}\n\n{}", email.subject, email.text);
+            assert_snapshot!(format!("{name}.txt"), snapshot);
+            assert_snapshot!(format!("{name}.html"), email.html);
+        }
+    }
+
+    #[test]
+    fn unknown_email() {
+        assert!(render_example("unknown", &Emails::new_in_memory()).is_none());
+    }
+}
@@ -184,7 +184,6 @@ fn send_notification_email(
     };
 
     let email = TokenExposedEmail {
-        domain: &state.config.domain_name,
         reporter: "GitHub",
         source: &alert.source,
         token_name: &token.name,
@@ -196,37 +195,25 @@ fn send_notification_email(
     Ok(())
 }
 
-struct TokenExposedEmail<'a> {
-    domain: &'a str,
-    reporter: &'a str,
-    source: &'a str,
-    token_name: &'a str,
-    url: &'a str,
+pub struct TokenExposedEmail<'a> {
+    pub reporter: &'a str,
+    pub source: &'a str,
+    pub token_name: &'a str,
+    /// The URL where the token was found, or an empty string if unknown.
+    pub url: &'a str,
 }
 
 impl Email for TokenExposedEmail<'_> {
     const SUBJECT: &'static str = "Exposed API token found";
-
-    fn body(&self) -> String {
-        let mut body = format!(
-            "{reporter} has notified us that your crates.io API token {token_name}\n
-has been exposed publicly. We have revoked this token as a precaution.\n
-Please review your account at https://{domain} to confirm that no\n
-unexpected changes have been made to your settings or crates.\n
-\n
-Source type: {source}\n",
-            domain = self.domain,
-            reporter = self.reporter,
-            source = self.source,
-            token_name = self.token_name,
-        );
-        if self.url.is_empty() {
-            body.push_str("\nWe were not informed of the URL where the token was found.\n");
-        } else {
-            body.push_str(&format!("\nURL where the token was found: {}\n", self.url));
+    const TEMPLATE: &'static str = "token_exposed";
+
+    fn context(&self) -> minijinja::Value {
+        minijinja::context! {
+            reporter => self.reporter,
+            source => self.source,
+            token_name => self.token_name,
+            url => self.url,
         }
-
-        body
     }
 }
 
@@ -1,3 +1,4 @@
+pub mod autocomplete;
 pub mod downloads;
 pub mod follow;
 pub mod metadata;
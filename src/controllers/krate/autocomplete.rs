@@ -0,0 +1,90 @@
+//! Endpoint for looking up crate names while the user is typing
+//!
+//! In contrast to the full search endpoint in `krate::search`, this endpoint
+//! only matches name prefixes, returns a small number of results and caches
+//! the responses in memory, so that it can be called on every keystroke.
+
+use std::sync::Arc;
+
+use crate::controllers::frontend_prelude::*;
+
+use crate::models::krate::MAX_NAME_LENGTH;
+use crate::models::Crate;
+use crate::schema::{crate_downloads, crates, default_versions, versions};
+use crate::util::errors::bad_request;
+use diesel_async::RunQueryDsl;
+
+/// The number of crates that are returned if no `per_page` parameter is
+/// given.
+const DEFAULT_PER_PAGE: usize = 10;
+
+/// The maximum number of crates that can be requested with `per_page`.
+const MAX_PER_PAGE: usize = 25;
+
+/// Cache of the autocomplete results, keyed by the canonical name prefix
+/// and the number of requested crates.
+pub type AutocompleteCache = moka::future::Cache<(String, usize), Arc<Vec<AutocompleteCrate>>>;
+
+#[derive(Debug, Clone, Serialize, Queryable)]
+pub struct AutocompleteCrate {
+    name: String,
+    default_version: String,
+}
+
+/// Handles the `GET /api/private/crates/autocomplete` route.
+///
+/// Returns the names of the most downloaded crates whose name starts with
+/// the `q` query parameter, treating `-` and `_` as equivalent.
+pub async fn autocomplete(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    let params = req.query();
+
+    let per_page = match params.get("per_page") {
+        Some(per_page) => per_page
+            .parse::<usize>()
+            .ok()
+            .filter(|per_page| (1..=MAX_PER_PAGE).contains(per_page))
+            .ok_or_else(|| {
+                bad_request(format_args!(
+                    "invalid `per_page` parameter (expected a number between 1 and {MAX_PER_PAGE})"
+                ))
+            })?,
+        None => DEFAULT_PER_PAGE,
+    };
+
+    let prefix = params.get("q").map(|q| q.trim()).unwrap_or_default();
+
+    // Prefixes that can not be part of a valid crate name can not match
+    // anything, so there is no need to hit the database for them.
+    let is_valid_prefix = prefix.len() <= MAX_NAME_LENGTH
+        && prefix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if prefix.is_empty() || !is_valid_prefix {
+        return Ok(Json(json!({ "crates": [] })));
+    }
+
+    let key = (prefix.to_lowercase().replace('-', "_"), per_page);
+    let crates = match app.autocomplete_cache.get(&key).await {
+        Some(crates) => crates,
+        None => {
+            let mut conn = app.db_read().await?;
+
+            let crates: Vec<AutocompleteCrate> = crates::table
+                .inner_join(crate_downloads::table)
+                .inner_join(default_versions::table.inner_join(versions::table))
+                .filter(Crate::name_starts_with(&key.0))
+                .order((crate_downloads::downloads.desc(), crates::name.asc()))
+                .select((crates::name, versions::num))
+                .limit(per_page as i64)
+                .load(&mut conn)
+                .await?;
+
+            let crates = Arc::new(crates);
+            app.autocomplete_cache.insert(key, crates.clone()).await;
+            crates
+        }
+    };
+
+    Ok(Json(json!({ "crates": *crates })))
+}
@@ -4,23 +4,45 @@
 //! download counts are located in `version::downloads`.
 
 use std::cmp;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
 
 use crate::controllers::frontend_prelude::*;
 
-use crate::models::{Crate, Version, VersionDownload};
-use crate::schema::{crates, version_downloads, versions};
+use crate::models::{
+    Crate, CrateDownloadsMonthly, Version, VersionDownload, VersionDownloadByClient,
+};
+use crate::schema::{crates, version_downloads, version_downloads_by_client, versions};
 use crate::sql::to_char;
-use crate::util::errors::crate_not_found;
-use crate::views::EncodableVersionDownload;
+use crate::util::dates::first_day_of_month;
+use crate::util::errors::{bad_request, crate_not_found};
+use crate::views::{EncodableVersionClientDownload, EncodableVersionDownload};
+use chrono::{Duration, Months, NaiveDate, Utc};
 use diesel_async::RunQueryDsl;
 
 /// Handles the `GET /crates/:crate_id/downloads` route.
-pub async fn downloads(state: AppState, Path(crate_name): Path<String>) -> AppResult<Json<Value>> {
+///
+/// With `?include=clients`, the response additionally contains the daily
+/// downloads broken down by the type of client (e.g. `cargo`, `ci` or
+/// `browser`).
+pub async fn downloads(
+    state: AppState,
+    Path(crate_name): Path<String>,
+    req: Parts,
+) -> AppResult<Json<Value>> {
     let mut conn = state.db_read().await?;
 
     use diesel::dsl::*;
     use diesel::sql_types::BigInt;
 
+    let include = req
+        .query()
+        .get("include")
+        .map(|mode| DownloadsIncludeMode::from_str(mode))
+        .transpose()?
+        .unwrap_or_default();
+
     let crate_id: i32 = Crate::by_name(&crate_name)
         .select(crates::id)
         .first(&mut conn)
@@ -66,10 +88,337 @@ pub async fn downloads(state: AppState, Path(crate_name): Path<String>) -> AppRe
         downloads: i64,
     }
 
+    if !include.clients {
+        return Ok(Json(json!({
+            "version_downloads": downloads,
+            "meta": {
+                "extra_downloads": extra,
+            },
+        })));
+    }
+
+    let client_downloads = VersionDownloadByClient::belonging_to(latest_five)
+        .select(VersionDownloadByClient::as_select())
+        .filter(version_downloads_by_client::date.gt(date(now - 90.days())))
+        .order((
+            version_downloads_by_client::date.asc(),
+            version_downloads_by_client::version_id.desc(),
+            version_downloads_by_client::client.asc(),
+        ))
+        .load(&mut conn)
+        .await?
+        .into_iter()
+        .map(VersionDownloadByClient::into)
+        .collect::<Vec<EncodableVersionClientDownload>>();
+
+    let sum_downloads = sql::<BigInt>("SUM(version_downloads_by_client.downloads)");
+    let extra_clients: Vec<ExtraClientDownload> = VersionDownloadByClient::belonging_to(rest)
+        .select((
+            to_char(version_downloads_by_client::date, "YYYY-MM-DD"),
+            version_downloads_by_client::client,
+            sum_downloads,
+        ))
+        .filter(version_downloads_by_client::date.gt(date(now - 90.days())))
+        .group_by((
+            version_downloads_by_client::date,
+            version_downloads_by_client::client,
+        ))
+        .order((
+            version_downloads_by_client::date.asc(),
+            version_downloads_by_client::client.asc(),
+        ))
+        .load(&mut conn)
+        .await?;
+
+    #[derive(Serialize, Queryable)]
+    struct ExtraClientDownload {
+        date: String,
+        client: String,
+        downloads: i64,
+    }
+
     Ok(Json(json!({
         "version_downloads": downloads,
+        "version_client_downloads": client_downloads,
         "meta": {
             "extra_downloads": extra,
+            "extra_client_downloads": extra_clients,
         },
     })))
 }
+
+/// The default and maximum number of days covered by the
+/// [`release_lines`] endpoint, matching the retention period of the
+/// `version_downloads` table.
+const MAX_RELEASE_LINE_DAYS: i64 = 90;
+
+/// Handles the `GET /crates/:crate_id/downloads/release_lines` route.
+///
+/// Aggregates the downloads of all versions per semver-compatible release
+/// line (e.g. `1.x` or `0.4.x`), so that maintainers can see which older
+/// release lines are still in use.
+///
+/// The covered date range ends at `?before_date=` (defaults to today) and
+/// spans the number of days given by `?days=` (defaults to 90).
+pub async fn release_lines(
+    state: AppState,
+    Path(crate_name): Path<String>,
+    req: Parts,
+) -> AppResult<Json<Value>> {
+    use diesel::dsl::sql;
+    use diesel::sql_types::BigInt;
+
+    let query = req.query();
+
+    let end_date = query
+        .get("before_date")
+        .map(|date| NaiveDate::parse_from_str(date, "%F"))
+        .transpose()
+        .map_err(|_| bad_request("invalid `before_date` parameter (expected YYYY-MM-DD)"))?
+        .unwrap_or_else(|| Utc::now().date_naive());
+
+    let days = match query.get("days") {
+        None => MAX_RELEASE_LINE_DAYS,
+        Some(days) => days
+            .parse::<i64>()
+            .ok()
+            .filter(|days| (1..=MAX_RELEASE_LINE_DAYS).contains(days))
+            .ok_or_else(|| {
+                bad_request(format!(
+                    "invalid `days` parameter (expected a number between 1 and {MAX_RELEASE_LINE_DAYS})"
+                ))
+            })?,
+    };
+
+    let start_date = end_date - Duration::days(days - 1);
+
+    let mut conn = state.db_read().await?;
+
+    let crate_id: i32 = Crate::by_name(&crate_name)
+        .select(crates::id)
+        .first(&mut conn)
+        .await
+        .optional()?
+        .ok_or_else(|| crate_not_found(&crate_name))?;
+
+    let versions: Vec<String> = versions::table
+        .filter(versions::crate_id.eq(crate_id))
+        .select(versions::num)
+        .load(&mut conn)
+        .await?;
+
+    let downloads: HashMap<String, i64> = version_downloads::table
+        .inner_join(versions::table)
+        .filter(versions::crate_id.eq(crate_id))
+        .filter(version_downloads::date.between(start_date, end_date))
+        .group_by(versions::num)
+        .select((
+            versions::num,
+            sql::<BigInt>("SUM(version_downloads.downloads)"),
+        ))
+        .load(&mut conn)
+        .await?
+        .into_iter()
+        .collect();
+
+    #[derive(Serialize)]
+    struct ReleaseLineDownloads {
+        line: String,
+        downloads: i64,
+        num_versions: usize,
+        latest_version: String,
+    }
+
+    let mut lines: BTreeMap<ReleaseLine, (i64, Vec<semver::Version>)> = BTreeMap::new();
+    for num in versions {
+        let Ok(version) = semver::Version::parse(&num) else {
+            continue;
+        };
+
+        let (sum, versions) = lines.entry(ReleaseLine::of(&version)).or_default();
+        *sum += downloads.get(&num).copied().unwrap_or_default();
+        versions.push(version);
+    }
+
+    // Newest release lines first, like the versions list.
+    let release_lines = lines
+        .into_iter()
+        .rev()
+        .map(|(line, (downloads, versions))| ReleaseLineDownloads {
+            line: line.to_string(),
+            downloads,
+            num_versions: versions.len(),
+            latest_version: versions
+                .iter()
+                .max()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Json(json!({
+        "release_lines": release_lines,
+        "meta": {
+            "start_date": start_date,
+            "end_date": end_date,
+        },
+    })))
+}
+
+/// The default number of months covered by the [`history`] endpoint.
+const DEFAULT_HISTORY_MONTHS: u32 = 12;
+
+/// The maximum number of months covered by the [`history`] endpoint.
+const MAX_HISTORY_MONTHS: u32 = 120;
+
+/// Handles the `GET /crates/:crate_id/downloads/history` route.
+///
+/// Returns the monthly downloads of the crate, including the months that
+/// have already been archived from the daily `version_downloads` data.
+///
+/// The covered months are given by `?from=` and `?to=` in `YYYY-MM` format
+/// and default to the last 12 months.
+pub async fn history(
+    state: AppState,
+    Path(crate_name): Path<String>,
+    req: Parts,
+) -> AppResult<Json<Value>> {
+    let query = req.query();
+
+    let parse_month = |name: &str| {
+        query
+            .get(name)
+            .map(|month| NaiveDate::parse_from_str(&format!("{month}-01"), "%F"))
+            .transpose()
+            .map_err(|_| bad_request(format!("invalid `{name}` parameter (expected YYYY-MM)")))
+    };
+
+    // The months are given by the user, so they can be close to the limits
+    // of `NaiveDate`, which would make the unchecked arithmetic panic.
+    let out_of_range = || bad_request("the requested months are out of range");
+
+    let end_month =
+        parse_month("to")?.unwrap_or_else(|| first_day_of_month(Utc::now().date_naive()));
+    let start_month = match parse_month("from")? {
+        Some(start_month) => start_month,
+        None => end_month
+            .checked_sub_months(Months::new(DEFAULT_HISTORY_MONTHS - 1))
+            .ok_or_else(out_of_range)?,
+    };
+
+    if start_month > end_month {
+        return Err(bad_request("`from` must not be after `to`"));
+    }
+
+    let next_month = |month: &NaiveDate| month.checked_add_months(Months::new(1));
+    let months = std::iter::successors(Some(start_month), next_month)
+        .take_while(|month| *month <= end_month)
+        .take(MAX_HISTORY_MONTHS as usize + 1)
+        .collect::<Vec<_>>();
+
+    if months.len() > MAX_HISTORY_MONTHS as usize {
+        let detail = format!("the requested range must not exceed {MAX_HISTORY_MONTHS} months");
+        return Err(bad_request(detail));
+    }
+
+    let end_date = next_month(&end_month).ok_or_else(out_of_range)? - Duration::days(1);
+
+    let mut conn = state.db_read().await?;
+
+    let crate_id: i32 = Crate::by_name(&crate_name)
+        .select(crates::id)
+        .first(&mut conn)
+        .await
+        .optional()?
+        .ok_or_else(|| crate_not_found(&crate_name))?;
+
+    let downloads: HashMap<NaiveDate, i64> =
+        CrateDownloadsMonthly::query(crate_id, start_month, end_date)
+            .load::<CrateDownloadsMonthly>(&mut conn)
+            .await?
+            .into_iter()
+            .map(|row| (row.month, row.downloads))
+            .collect();
+
+    #[derive(Serialize)]
+    struct MonthlyDownloads {
+        month: String,
+        downloads: i64,
+    }
+
+    // Months without any downloads are included with a count of zero.
+    let monthly_downloads = months
+        .into_iter()
+        .map(|month| MonthlyDownloads {
+            month: month.format("%Y-%m").to_string(),
+            downloads: downloads.get(&month).copied().unwrap_or_default(),
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Json(json!({
+        "monthly_downloads": monthly_downloads,
+        "meta": {
+            "from": start_month.format("%Y-%m").to_string(),
+            "to": end_month.format("%Y-%m").to_string(),
+        },
+    })))
+}
+
+/// A semver-compatible release line of a crate, as defined by the cargo
+/// resolver: versions are compatible if their left-most non-zero component
+/// matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ReleaseLine {
+    /// `0.0.z` versions are only compatible with themselves.
+    Patch(u64),
+    /// `0.y.z` versions are compatible if their minor version matches.
+    Minor(u64),
+    /// `x.y.z` versions with `x > 0` are compatible if their major version
+    /// matches.
+    Major(u64),
+}
+
+impl ReleaseLine {
+    fn of(version: &semver::Version) -> Self {
+        match (version.major, version.minor) {
+            (0, 0) => ReleaseLine::Patch(version.patch),
+            (0, minor) => ReleaseLine::Minor(minor),
+            (major, _) => ReleaseLine::Major(major),
+        }
+    }
+}
+
+impl Display for ReleaseLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ReleaseLine::Patch(patch) => write!(f, "0.0.{patch}"),
+            ReleaseLine::Minor(minor) => write!(f, "0.{minor}.x"),
+            ReleaseLine::Major(major) => write!(f, "{major}.x"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct DownloadsIncludeMode {
+    clients: bool,
+}
+
+impl DownloadsIncludeMode {
+    const INVALID_COMPONENT: &'static str = "invalid component for ?include= (expected 'clients')";
+}
+
+impl FromStr for DownloadsIncludeMode {
+    type Err = BoxedAppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut mode = Self::default();
+        for component in s.split(',') {
+            match component {
+                "" => {}
+                "clients" => mode.clients = true,
+                _ => return Err(bad_request(Self::INVALID_COMPONENT)),
+            }
+        }
+        Ok(mode)
+    }
+}
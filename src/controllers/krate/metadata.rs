@@ -211,17 +211,37 @@ impl FromStr for ShowIncludeMode {
 }
 
 /// Handles the `GET /crates/:crate_id/:version/readme` route.
+///
+/// JSON responses include the table of contents of the rendered README, or
+/// `null` if it is not known.
 pub async fn readme(
     app: AppState,
     Path((crate_name, version)): Path<(String, String)>,
     req: Parts,
-) -> Response {
+) -> AppResult<Response> {
     let redirect_url = app.storage.readme_location(&crate_name, &version);
-    if req.wants_json() {
-        Json(json!({ "url": redirect_url })).into_response()
-    } else {
-        redirect(redirect_url)
+    if !req.wants_json() {
+        return Ok(redirect(redirect_url));
     }
+
+    let conn = app.db_read().await?;
+    let toc = spawn_blocking(move || {
+        let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+        let toc: Option<Option<Value>> = versions::table
+            .inner_join(crates::table)
+            .inner_join(readme_renderings::table)
+            .filter(crates::name.eq(&crate_name))
+            .filter(versions::num.eq(&version))
+            .select(readme_renderings::toc)
+            .first(conn)
+            .optional()?;
+
+        Ok::<_, BoxedAppError>(toc.flatten())
+    })
+    .await?;
+
+    Ok(Json(json!({ "url": redirect_url, "toc": toc })).into_response())
 }
 
 /// Handles the `GET /crates/:crate_id/reverse_dependencies` route.
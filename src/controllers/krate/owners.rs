@@ -3,9 +3,11 @@
 use crate::auth::AuthCheck;
 use crate::controllers::prelude::*;
 use crate::models::token::EndpointScope;
-use crate::models::{Crate, Owner, Rights, Team, User};
+use crate::models::{Crate, Owner, Rights, Team, User, Webhook, WebhookEvent};
 use crate::util::errors::{bad_request, crate_not_found, custom};
 use crate::views::EncodableOwner;
+use crate::worker::jobs::{SendOwnershipChangeNotifications, TriggerWebhooks};
+use crates_io_worker::BackgroundJob;
 use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
 use tokio::runtime::Handle;
 
@@ -154,11 +156,26 @@ async fn modify_owners(
                     }
                     let msg = krate.owner_add(&app, conn, user, login)?;
                     msgs.push(msg);
+
+                    // Users are only invited, and become owners once they
+                    // accept the invitation.
+                    if login.contains(':') {
+                        SendOwnershipChangeNotifications::new(krate.id, login, true)
+                            .enqueue(conn)?;
+
+                        if Webhook::any_subscribed_to(krate.id, WebhookEvent::OwnerChange, conn)? {
+                            TriggerWebhooks::owner_change(krate.id, login, true).enqueue(conn)?;
+                        }
+                    }
                 }
                 msgs.join(",")
             } else {
                 for login in &logins {
                     krate.owner_remove(conn, login)?;
+                    SendOwnershipChangeNotifications::new(krate.id, login, false).enqueue(conn)?;
+                    if Webhook::any_subscribed_to(krate.id, WebhookEvent::OwnerChange, conn)? {
+                        TriggerWebhooks::owner_change(krate.id, login, false).enqueue(conn)?;
+                    }
                 }
                 if User::owning(&krate, conn)?.is_empty() {
                     return Err(bad_request(
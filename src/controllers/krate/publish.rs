@@ -1,7 +1,10 @@
 //! Functionality related to publishing a new crate or version of a crate.
 
 use crate::auth::AuthCheck;
-use crate::worker::jobs::{self, CheckTyposquat, UpdateDefaultVersion};
+use crate::worker::jobs::{
+    self, CheckTyposquat, SendReverseDependencyNotifications, SendVersionNotifications,
+    TriggerWebhooks, UpdateDefaultVersion,
+};
 use axum::body::Bytes;
 use cargo_manifest::{Dependency, DepsSet, TargetDepsSet};
 use crates_io_tarball::{process_tarball, TarballError};
@@ -19,7 +22,7 @@ use url::Url;
 use crate::controllers::cargo_prelude::*;
 use crate::models::{
     insert_version_owner_action, Category, Crate, DependencyKind, Keyword, NewCrate, NewVersion,
-    Rights, VersionAction,
+    Rights, VersionAction, Webhook, WebhookEvent,
 };
 
 use crate::licenses::parse_license_expr;
@@ -369,7 +372,7 @@ pub async fn publish(app: AppState, req: BytesRequest) -> AppResult<Json<GoodCra
                 .map_err(|error| internal(error.to_string()))?
                 .save(conn, &verified_email_address)?;
 
-            insert_version_owner_action(
+            let action = insert_version_owner_action(
                 conn,
                 version.id,
                 user.id,
@@ -420,6 +423,19 @@ pub async fn publish(app: AppState, req: BytesRequest) -> AppResult<Json<GoodCra
                         metadata
                             .readme_file
                             .unwrap_or_else(|| String::from("README.md")),
+                        repository.clone(),
+                        pkg_path_in_vcs.clone(),
+                    )
+                    .enqueue(conn)?;
+                }
+            }
+
+            if let Some(changelog) = tarball_info.changelog {
+                if !changelog.contents.trim().is_empty() {
+                    jobs::RenderAndUploadChangelog::new(
+                        version.id,
+                        changelog.contents,
+                        changelog.path,
                         repository,
                         pkg_path_in_vcs,
                     )
@@ -444,11 +460,20 @@ pub async fn publish(app: AppState, req: BytesRequest) -> AppResult<Json<GoodCra
                 UpdateDefaultVersion::new(krate.id).enqueue(conn)?;
             }
 
+            SendVersionNotifications::new(action.id).enqueue(conn)?;
+            if Webhook::any_subscribed_to(krate.id, WebhookEvent::Publish, conn)? {
+                TriggerWebhooks::version_action(action.id).enqueue(conn)?;
+            }
+
             // Experiment: check new crates for potential typosquatting.
             if existing_crate.is_none() {
                 CheckTyposquat::new(&krate.name).enqueue(conn)?;
             }
 
+            if existing_crate.is_none() && !deps.is_empty() {
+                SendReverseDependencyNotifications::new(version.id).enqueue(conn)?;
+            }
+
             let job = jobs::rss::SyncCrateFeed::new(krate.name.clone());
             if let Err(error) = job.enqueue(conn) {
                 error!("Failed to enqueue `rss::SyncCrateFeed` job: {error}");
@@ -1,8 +1,16 @@
 //! Endpoint for searching and discovery functionality
 
+mod external;
+mod facets;
+mod query;
+
+use self::facets::Facets;
+use self::query::{Comparison, SearchQuery};
 use crate::auth::AuthCheck;
+use chrono::{NaiveDate, NaiveTime};
 use diesel::dsl::*;
-use diesel::sql_types::{Array, Bool, Text};
+use diesel::expression::SqlLiteral;
+use diesel::sql_types::{Array, Bool, Float, Integer, Text};
 use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
 use diesel_full_text_search::*;
 use std::cell::OnceCell;
@@ -16,7 +24,7 @@ use crate::views::EncodableCrate;
 
 use crate::controllers::helpers::pagination::{Page, Paginated, PaginationOptions};
 use crate::models::krate::ALL_COLUMNS;
-use crate::sql::{array_agg, canon_crate_name, lower};
+use crate::sql::{array_agg, canon_crate_name, cardinality, escape_like, lower};
 use crate::util::diesel::Conn;
 
 /// Handles the `GET /crates` route.
@@ -41,6 +49,17 @@ use crate::util::diesel::Conn;
 /// function out to cover the different use cases, and create unit tests
 /// for them.
 pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    if let Some(search_backend) = app.search_backend.clone() {
+        if let Some((request, pagination)) = external::search_request(&req)? {
+            match search_backend.search(&request).await {
+                Ok(response) => {
+                    return external::load_results(&app, request, response, pagination, req).await
+                }
+                Err(error) => warn!("Search backend failed, falling back to the database: {error}"),
+            }
+        }
+    }
+
     let conn = app.db_read().await?;
     spawn_blocking(move || {
         let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
@@ -54,15 +73,28 @@ pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
         let include_yanked = option_param("include_yanked")
             .map(|s| s == "yes")
             .unwrap_or(true);
+        let include_facets = option_param("include_facets").is_some_and(|s| s == "yes");
 
         // Remove 0x00 characters from the query string because Postgres can not
         // handle them and will return an error, which would cause us to throw
         // an Internal Server Error ourselves.
         let q_string = option_param("q").map(|q| q.replace('\u{0}', ""));
 
+        // The `q` parameter may contain filter terms like `keyword:async`,
+        // which are split off the free text that is used for the full-text
+        // search.
+        let search_query = q_string
+            .as_deref()
+            .map(SearchQuery::parse)
+            .transpose()
+            .map_err(bad_request)?;
+        let q_string = search_query.as_ref().map(|query| query.text.as_str());
+
         let filter_params = FilterParams {
-            q_string: q_string.as_deref(),
-            include_yanked,
+            q_string,
+            include_yanked: include_yanked
+                && !search_query.as_ref().is_some_and(|q| q.exclude_yanked),
+            search_query: search_query.as_ref(),
             category: option_param("category"),
             all_keywords: option_param("all_keywords"),
             keyword: option_param("keyword"),
@@ -89,7 +121,7 @@ pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
             .left_join(recent_crate_downloads::table)
             .select(selection);
 
-        if let Some(q_string) = &q_string {
+        if let Some(q_string) = q_string {
             if !q_string.is_empty() {
                 let sort = sort.unwrap_or("relevance");
 
@@ -99,7 +131,8 @@ pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
                     let q = sql::<TsQuery>("plainto_tsquery('english', ")
                         .bind::<Text, _>(q_string)
                         .sql(")");
-                    let rank = ts_rank_cd(crates::textsearchable_index_col, q);
+                    let rank = ts_rank_cd(crates::textsearchable_index_col, q)
+                        + Crate::name_similarity(q_string) * NAME_SIMILARITY_WEIGHT;
                     query = query.select((
                         ALL_COLUMNS,
                         Crate::with_name(q_string),
@@ -139,6 +172,27 @@ pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
         } else if sort == Some("recent-updates") {
             seek = Some(Seek::RecentUpdates);
             query = query.order((crates::updated_at.desc(), crates::id.desc()));
+        } else if sort == Some("quality") {
+            seek = Some(Seek::Quality);
+            // The quality score takes the place of the relevance rank in the
+            // selection, so that it is available for seek-based pagination.
+            query = match q_string.filter(|q| !q.is_empty()) {
+                Some(q_string) => query.select((
+                    ALL_COLUMNS,
+                    Crate::with_name(q_string),
+                    crate_downloads::downloads,
+                    recent_crate_downloads::downloads.nullable(),
+                    quality_score(),
+                )),
+                None => query.select((
+                    ALL_COLUMNS,
+                    false.into_sql::<Bool>(),
+                    crate_downloads::downloads,
+                    recent_crate_downloads::downloads.nullable(),
+                    quality_score(),
+                )),
+            };
+            query = query.order((quality_score().desc(), crates::id.desc()));
         } else if sort == Some("new") {
             seek = Some(Seek::New);
             query = query.order((crates::created_at.desc(), crates::id.desc()));
@@ -207,55 +261,112 @@ pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
             )
         };
 
-        let perfect_matches = data.iter().map(|&(_, b, _, _, _)| b).collect::<Vec<_>>();
-        let downloads = data
-            .iter()
-            .map(|&(_, _, total, recent, _)| (total, recent.unwrap_or(0)))
-            .collect::<Vec<_>>();
-        let crates = data
+        let data = data
             .into_iter()
-            .map(|(c, _, _, _, _)| c)
-            .collect::<Vec<_>>();
-
-        let versions: Vec<Version> = info_span!("db.query", message = "SELECT ... FROM versions")
-            .in_scope(|| crates.versions().load(conn))?;
-        let versions = versions
-            .grouped_by(&crates)
-            .into_iter()
-            .map(TopVersions::from_versions);
-
-        let crates = versions
-            .zip(crates)
-            .zip(perfect_matches)
-            .zip(downloads)
-            .map(|(((max_version, krate), perfect_match), (total, recent))| {
-                EncodableCrate::from_minimal(
-                    krate,
-                    Some(&max_version),
-                    Some(vec![]),
-                    perfect_match,
-                    total,
-                    Some(recent),
-                )
-            })
-            .collect::<Vec<_>>();
+            .map(|(krate, perfect_match, total, recent, _)| (krate, perfect_match, total, recent))
+            .collect();
+        let crates = encode_crates(data, conn)?;
+
+        // If nothing was found, suggest the crate with the most similar name,
+        // since the search query might contain a misspelled crate name.
+        let suggestion = match q_string {
+            Some(q_string) if total == 0 && q_string.len() > 2 => {
+                info_span!("db.query", message = "SELECT name FROM crates").in_scope(|| {
+                    crates::table
+                        .filter(Crate::may_be_suggested_for(q_string))
+                        .select(crates::name)
+                        .order((Crate::name_similarity(q_string).desc(), crates::name))
+                        .first::<String>(conn)
+                        .optional()
+                })?
+            }
+            _ => None,
+        };
 
-        Ok(Json(json!({
+        let mut response = json!({
             "crates": crates,
             "meta": {
                 "total": total,
                 "next_page": next_page,
                 "prev_page": prev_page,
+                "suggestion": suggestion,
             },
-        })))
+        });
+
+        if include_facets {
+            let facets = info_span!("db.query", message = "SELECT ..., COUNT(*) FROM crates_*")
+                .in_scope(|| Facets::load(conn, |conn| filter_params.make_query(&req, conn)))?;
+            response["facets"] = json!(facets);
+        }
+
+        Ok(Json(response))
     })
     .await
 }
 
+/// Loads the versions of the crates and converts them into their JSON
+/// representation.
+///
+/// The tuples contain the crate, whether its name is an exact match of the
+/// search query, and its total and recent downloads.
+fn encode_crates(
+    data: Vec<(Crate, bool, i64, Option<i64>)>,
+    conn: &mut impl Conn,
+) -> QueryResult<Vec<EncodableCrate>> {
+    let perfect_matches = data.iter().map(|&(_, b, _, _)| b).collect::<Vec<_>>();
+    let downloads = data
+        .iter()
+        .map(|&(_, _, total, recent)| (total, recent.unwrap_or(0)))
+        .collect::<Vec<_>>();
+    let crates = data.into_iter().map(|(c, _, _, _)| c).collect::<Vec<_>>();
+
+    let versions: Vec<Version> = info_span!("db.query", message = "SELECT ... FROM versions")
+        .in_scope(|| crates.versions().load(conn))?;
+    let versions = versions
+        .grouped_by(&crates)
+        .into_iter()
+        .map(TopVersions::from_versions);
+
+    let crates = versions
+        .zip(crates)
+        .zip(perfect_matches)
+        .zip(downloads)
+        .map(|(((max_version, krate), perfect_match), (total, recent))| {
+            EncodableCrate::from_minimal(
+                krate,
+                Some(&max_version),
+                Some(vec![]),
+                perfect_match,
+                total,
+                Some(recent),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    Ok(crates)
+}
+
+/// The quality score of the crate, as computed by the `UpdateQualityScores`
+/// background job, or `0` if it has not been computed yet.
+fn quality_score() -> SqlLiteral<Float> {
+    sql::<Float>(
+        "coalesce((SELECT score FROM crate_quality_scores WHERE crate_quality_scores.crate_id = crates.id), 0)",
+    )
+}
+
+/// The weight of the trigram similarity between the crate name and the search
+/// query in the relevance ranking.
+///
+/// With the default weights of `ts_rank_cd()` this ranks a very similar crate
+/// name a bit above a full-text match in the keywords, but below a full-text
+/// match in the crate name.
+const NAME_SIMILARITY_WEIGHT: f32 = 0.5;
+
 #[derive(Default)]
 struct FilterParams<'a> {
     q_string: Option<&'a str>,
     include_yanked: bool,
+    search_query: Option<&'a SearchQuery<'a>>,
     category: Option<&'a str>,
     all_keywords: Option<&'a str>,
     keyword: Option<&'a str>,
@@ -311,10 +422,16 @@ impl<'a> FilterParams<'a> {
                 let q = sql::<TsQuery>("plainto_tsquery('english', ")
                     .bind::<Text, _>(q_string)
                     .sql(")");
-                query = query.filter(
+                let mut condition: Box<dyn BoxableExpression<_, _, SqlType = Bool>> = Box::new(
                     q.matches(crates::textsearchable_index_col)
                         .or(Crate::loosly_matches_name(q_string)),
                 );
+                // Short strings are similar to too many crate names to be
+                // useful for typo tolerance.
+                if q_string.len() > 2 {
+                    condition = Box::new(condition.or(Crate::fuzzy_matches_name(q_string)));
+                }
+                query = query.filter(condition);
             }
         }
 
@@ -327,7 +444,7 @@ impl<'a> FilterParams<'a> {
                         .filter(
                             categories::slug
                                 .eq(cat)
-                                .or(categories::slug.like(format!("{cat}::%"))),
+                                .or(categories::slug.like(format!("{}::%", escape_like(cat)))),
                         ),
                 ),
             );
@@ -400,6 +517,110 @@ impl<'a> FilterParams<'a> {
             query = query.filter(crates::name.eq_any(self.ids(req).unwrap()));
         }
 
+        if let Some(search_query) = self.search_query {
+            for &kw in &search_query.keywords {
+                query = query.filter(
+                    crates::id.eq_any(
+                        crates_keywords::table
+                            .select(crates_keywords::crate_id)
+                            .inner_join(keywords::table)
+                            .filter(lower(keywords::keyword).eq(lower(kw))),
+                    ),
+                );
+            }
+
+            for &cat in &search_query.categories {
+                query = query.filter(
+                    crates::id.eq_any(
+                        crates_categories::table
+                            .select(crates_categories::crate_id)
+                            .inner_join(categories::table)
+                            .filter(
+                                categories::slug
+                                    .eq(cat)
+                                    .or(categories::slug.like(format!("{}::%", escape_like(cat)))),
+                            ),
+                    ),
+                );
+            }
+
+            // Conditions on the default version of the crates are collected
+            // into a single subquery.
+            let mut default_version = default_versions::table
+                .inner_join(versions::table)
+                .select(default_versions::crate_id)
+                .into_boxed();
+            let mut filter_default_version = false;
+
+            for &license in &search_query.licenses {
+                // Matches the license on its own and as part of SPDX
+                // expressions like `MIT OR Apache-2.0` or `(MIT/Apache-2.0)`.
+                let condition = sql::<Bool>("lower(")
+                    .bind::<Text, _>(license)
+                    .sql(r") = ANY(regexp_split_to_array(lower(versions.license), '[\s()/]+'))");
+
+                default_version = default_version.filter(condition);
+                filter_default_version = true;
+            }
+
+            for &(comparison, version) in &search_query.msrv {
+                // Pads the `rust-version` to three components, so that e.g.
+                // `1.70` and `1.70.0` compare as equal. Legacy values that
+                // are not plain version numbers never match.
+                let condition = sql::<Bool>(
+                    "CASE WHEN versions.rust_version ~ '^[0-9]+(\\.[0-9]+){0,2}$' \
+                     THEN (string_to_array(versions.rust_version, '.')::int[] || '{0,0}'::int[])[1:3] \
+                     END ",
+                )
+                .sql(comparison.as_sql())
+                .bind::<Array<Integer>, _>(version.to_vec());
+
+                default_version = default_version.filter(condition);
+                filter_default_version = true;
+            }
+
+            for &feature in &search_query.features {
+                // Only matches explicitly declared features, not the implicit
+                // features of optional dependencies.
+                let condition = sql::<Bool>("versions.features ? ").bind::<Text, _>(feature);
+
+                default_version = default_version.filter(condition);
+                filter_default_version = true;
+            }
+
+            if search_query.has_lib {
+                default_version = default_version.filter(versions::has_lib.eq(true));
+                filter_default_version = true;
+            }
+
+            if search_query.has_bin {
+                default_version = default_version.filter(cardinality(versions::bin_names).gt(0));
+                filter_default_version = true;
+            }
+
+            if filter_default_version {
+                query = query.filter(crates::id.eq_any(default_version));
+            }
+
+            for &(comparison, date) in &search_query.updated {
+                let start = date.and_time(NaiveTime::MIN);
+                let end = date
+                    .succ_opt()
+                    .unwrap_or(NaiveDate::MAX)
+                    .and_time(NaiveTime::MIN);
+
+                query = match comparison {
+                    Comparison::Lt => query.filter(crates::updated_at.lt(start)),
+                    Comparison::Le => query.filter(crates::updated_at.lt(end)),
+                    Comparison::Eq => {
+                        query.filter(crates::updated_at.ge(start).and(crates::updated_at.lt(end)))
+                    }
+                    Comparison::Ge => query.filter(crates::updated_at.ge(start)),
+                    Comparison::Gt => query.filter(crates::updated_at.ge(end)),
+                };
+            }
+        }
+
         if !self.include_yanked {
             query = query.filter(exists(
                 versions::table
@@ -503,6 +724,14 @@ impl<'a> FilterParams<'a> {
                     Box::new(crate_downloads::downloads.lt(downloads).nullable()),
                 ]
             }
+            SeekPayload::Quality(Quality { score, id }) => {
+                // Equivalent of:
+                // `WHERE (score = score' AND id < id') OR score < score'`
+                vec![
+                    Box::new(quality_score().eq(score).and(crates::id.lt(id)).nullable()),
+                    Box::new(quality_score().lt(score).nullable()),
+                ]
+            }
             SeekPayload::Query(Query { exact_match, id }) => {
                 // Equivalent of:
                 // `WHERE (exact_match = exact_match' AND name < name') OR exact_match <
@@ -532,7 +761,8 @@ impl<'a> FilterParams<'a> {
                 let q = sql::<TsQuery>("plainto_tsquery('english', ")
                     .bind::<Text, _>(q_string)
                     .sql(")");
-                let rank = ts_rank_cd(crates::textsearchable_index_col, q);
+                let rank = ts_rank_cd(crates::textsearchable_index_col, q)
+                    + Crate::name_similarity(q_string) * NAME_SIMILARITY_WEIGHT;
                 let name_exact_match = Crate::with_name(q_string);
                 vec![
                     Box::new(
@@ -600,6 +830,10 @@ mod seek {
                 rank: f32,
                 id: i32,
             },
+            Quality {
+                score: f32,
+                id: i32,
+            },
         }
     );
 
@@ -636,6 +870,7 @@ mod seek {
                     rank,
                     id,
                 }),
+                Seek::Quality => SeekPayload::Quality(Quality { score: rank, id }),
             }
         }
     }
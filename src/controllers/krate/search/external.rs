@@ -0,0 +1,133 @@
+//! Crate search via an external search backend
+//!
+//! If an external search backend is configured, plain text searches that are
+//! sorted by relevance are forwarded to it. All other searches, e.g. with
+//! filter terms, a different sort order or seek-based pagination, are still
+//! handled by the database.
+
+use super::encode_crates;
+use super::query::SearchQuery;
+use crate::controllers::cargo_prelude::*;
+use crate::controllers::helpers::pagination::{Page, PaginationOptions};
+use crate::models::krate::ALL_COLUMNS;
+use crate::models::Crate;
+use crate::schema::{crate_downloads, crates, recent_crate_downloads};
+use crate::search::{SearchRequest, SearchResponse};
+use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
+use indexmap::IndexMap;
+
+/// The query parameters that the external search backend supports.
+const SUPPORTED_PARAMS: &[&str] = &["q", "sort", "include_yanked", "page", "per_page"];
+
+/// Returns the request for the external search backend, or `None` if the
+/// search has to be handled by the database.
+pub fn search_request(req: &Parts) -> AppResult<Option<(SearchRequest, PaginationOptions)>> {
+    let params = req.query();
+
+    if params
+        .keys()
+        .any(|key| !SUPPORTED_PARAMS.contains(&key.as_str()))
+    {
+        return Ok(None);
+    }
+
+    if params.get("sort").is_some_and(|sort| sort != "relevance") {
+        return Ok(None);
+    }
+
+    let Some(q) = params.get("q") else {
+        return Ok(None);
+    };
+
+    // Invalid filter terms are reported by the database search.
+    let q = q.replace('\u{0}', "");
+    let Ok(query) = SearchQuery::parse(&q) else {
+        return Ok(None);
+    };
+
+    let text_only = SearchQuery {
+        text: query.text.clone(),
+        exclude_yanked: query.exclude_yanked,
+        ..Default::default()
+    };
+    if query.text.is_empty() || query != text_only {
+        return Ok(None);
+    }
+
+    let include_yanked = params
+        .get("include_yanked")
+        .map(|s| s == "yes")
+        .unwrap_or(true)
+        && !query.exclude_yanked;
+
+    let pagination = PaginationOptions::builder()
+        .limit_page_numbers()
+        .gather(req)?;
+
+    let request = SearchRequest {
+        text: query.text,
+        include_yanked,
+        offset: pagination.offset().unwrap_or_default(),
+        limit: pagination.per_page,
+    };
+
+    Ok(Some((request, pagination)))
+}
+
+/// Loads the crates that were found by the external search backend from the
+/// database.
+pub async fn load_results(
+    app: &AppState,
+    request: SearchRequest,
+    response: SearchResponse,
+    pagination: PaginationOptions,
+    req: Parts,
+) -> AppResult<Json<Value>> {
+    let conn = app.db_read().await?;
+    spawn_blocking(move || {
+        let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+        let mut data: Vec<(Crate, bool, i64, Option<i64>)> = crates::table
+            .inner_join(crate_downloads::table)
+            .left_join(recent_crate_downloads::table)
+            .filter(crates::name.eq_any(&response.names))
+            .select((
+                ALL_COLUMNS,
+                Crate::with_name(&request.text),
+                crate_downloads::downloads,
+                recent_crate_downloads::downloads.nullable(),
+            ))
+            .load(conn)?;
+
+        // Keep the order of the search backend. Crates that have been
+        // deleted since they were indexed are skipped.
+        data.sort_by_key(|(krate, ..)| response.names.iter().position(|name| *name == krate.name));
+
+        let crates = encode_crates(data, conn)?;
+
+        let has_next_page = request.offset + request.limit < response.total;
+        let next_page = match pagination.page {
+            Page::Numeric(page) if has_next_page => Some(page + 1),
+            Page::Unspecified if has_next_page => Some(2),
+            _ => None,
+        };
+        let prev_page = match pagination.page {
+            Page::Numeric(page) if page > 1 => Some(page - 1),
+            _ => None,
+        };
+
+        let page_params =
+            |page: u32| req.query_with_params(IndexMap::from([("page".into(), page.to_string())]));
+
+        Ok(Json(json!({
+            "crates": crates,
+            "meta": {
+                "total": response.total,
+                "next_page": next_page.map(page_params),
+                "prev_page": prev_page.map(page_params),
+                "suggestion": null,
+            },
+        })))
+    })
+    .await
+}
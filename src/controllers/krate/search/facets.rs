@@ -0,0 +1,103 @@
+//! Facet counts for the crate search
+//!
+//! If requested via `include_facets=yes`, the search response contains the
+//! number of matching crates per category, keyword and license, so that the
+//! search results can be narrowed down further.
+
+use crate::schema::{
+    categories, crates, crates_categories, crates_keywords, default_versions, keywords, versions,
+};
+use crate::util::diesel::Conn;
+use crate::util::errors::AppResult;
+use diesel::dsl::{count_star, exists};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+
+/// The maximum number of matching crates that the facet counts are computed
+/// for.
+///
+/// Broad queries can match most of the crates table, and counting the
+/// categories, keywords and licenses of all of them would be too expensive.
+/// If more crates match the query, the counts are only computed for a subset
+/// of them and the facets are marked as `approximate`.
+const MAX_FACET_CRATES: i64 = 10_000;
+
+/// The maximum number of values that are returned per facet.
+const MAX_FACET_VALUES: i64 = 10;
+
+#[derive(Debug, Serialize)]
+pub struct Facets {
+    categories: Vec<FacetCount>,
+    keywords: Vec<FacetCount>,
+    licenses: Vec<FacetCount>,
+    /// Whether the counts were computed for a subset of the matching crates.
+    approximate: bool,
+}
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct FacetCount {
+    value: String,
+    count: i64,
+}
+
+impl Facets {
+    /// Computes the facet counts for the crates that are matched by the
+    /// query that `make_query` builds.
+    ///
+    /// The query is used as a subquery for every facet, so it has to be
+    /// built once per facet. If it matches more than [`MAX_FACET_CRATES`]
+    /// crates, the counts are computed for the ones with the lowest ids.
+    pub fn load<'a, C: Conn>(
+        conn: &mut C,
+        make_query: impl Fn(&mut C) -> AppResult<crates::BoxedQuery<'a, Pg>>,
+    ) -> AppResult<Self> {
+        let crate_ids = |conn: &mut C| -> AppResult<_> {
+            Ok(make_query(conn)?
+                .select(crates::id)
+                .order(crates::id)
+                .limit(MAX_FACET_CRATES))
+        };
+
+        let approximate = diesel::select(exists(
+            make_query(conn)?
+                .select(crates::id)
+                .offset(MAX_FACET_CRATES),
+        ))
+        .get_result(conn)?;
+
+        let categories = crates_categories::table
+            .inner_join(categories::table)
+            .filter(crates_categories::crate_id.eq_any(crate_ids(conn)?))
+            .group_by(categories::slug)
+            .select((categories::slug, count_star()))
+            .order((count_star().desc(), categories::slug))
+            .limit(MAX_FACET_VALUES)
+            .load(conn)?;
+
+        let keywords = crates_keywords::table
+            .inner_join(keywords::table)
+            .filter(crates_keywords::crate_id.eq_any(crate_ids(conn)?))
+            .group_by(keywords::keyword)
+            .select((keywords::keyword, count_star()))
+            .order((count_star().desc(), keywords::keyword))
+            .limit(MAX_FACET_VALUES)
+            .load(conn)?;
+
+        let licenses = default_versions::table
+            .inner_join(versions::table)
+            .filter(default_versions::crate_id.eq_any(crate_ids(conn)?))
+            .filter(versions::license.is_not_null())
+            .group_by(versions::license)
+            .select((versions::license.assume_not_null(), count_star()))
+            .order((count_star().desc(), versions::license))
+            .limit(MAX_FACET_VALUES)
+            .load(conn)?;
+
+        Ok(Self {
+            categories,
+            keywords,
+            licenses,
+            approximate,
+        })
+    }
+}
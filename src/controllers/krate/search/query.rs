@@ -0,0 +1,261 @@
+//! Parser for the advanced search syntax of the `q` query parameter
+//!
+//! In addition to free text, the search query may contain the following
+//! terms, separated by whitespace:
+//!
+//! - `keyword:<keyword>`: crates with the given keyword
+//! - `category:<slug>`: crates in the given category or its subcategories
+//! - `license:<license>`: crates whose default version uses the given
+//!   license, either on its own or as part of an SPDX expression
+//! - `msrv:<op><version>`: crates whose default version declares a
+//!   `rust-version` that matches the comparison, e.g. `msrv:<=1.70`
+//! - `updated:<op><date>`: crates that were updated at a date that matches
+//!   the comparison, e.g. `updated:>2024-01-01`
+//! - `feature:<name>`: crates whose default version declares the given
+//!   feature
+//! - `has:lib` / `has:bin`: crates whose default version contains a library
+//!   or at least one binary
+//! - `-yanked`: exclude crates where all versions are yanked
+//!
+//! Supported comparison operators are `<`, `<=`, `=`, `>=` and `>`, with `=`
+//! being the default if the operator is omitted. All terms have to match.
+//!
+//! Anything that does not look like one of these terms, including unknown
+//! `name:value` pairs, is treated as free text.
+
+use chrono::NaiveDate;
+
+/// The parsed representation of a search query.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SearchQuery<'a> {
+    /// The free text parts of the search query, separated by single spaces.
+    pub text: String,
+    pub keywords: Vec<&'a str>,
+    pub categories: Vec<&'a str>,
+    pub licenses: Vec<&'a str>,
+    /// `rust-version` comparisons, with the version padded to
+    /// `[major, minor, patch]`.
+    pub msrv: Vec<(Comparison, [i32; 3])>,
+    pub updated: Vec<(Comparison, NaiveDate)>,
+    pub features: Vec<&'a str>,
+    pub has_lib: bool,
+    pub has_bin: bool,
+    pub exclude_yanked: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+impl Comparison {
+    /// Splits the comparison operator off the start of the given value.
+    fn split(value: &str) -> (Self, &str) {
+        if let Some(rest) = value.strip_prefix("<=") {
+            (Self::Le, rest)
+        } else if let Some(rest) = value.strip_prefix(">=") {
+            (Self::Ge, rest)
+        } else if let Some(rest) = value.strip_prefix('<') {
+            (Self::Lt, rest)
+        } else if let Some(rest) = value.strip_prefix('>') {
+            (Self::Gt, rest)
+        } else if let Some(rest) = value.strip_prefix('=') {
+            (Self::Eq, rest)
+        } else {
+            (Self::Eq, value)
+        }
+    }
+
+    /// Returns the corresponding SQL operator.
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Eq => "=",
+            Self::Ge => ">=",
+            Self::Gt => ">",
+        }
+    }
+}
+
+/// The supported names of `name:value` search terms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Keyword,
+    Category,
+    License,
+    Msrv,
+    Updated,
+    Feature,
+    Has,
+}
+
+impl Key {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "keyword" => Some(Self::Keyword),
+            "category" => Some(Self::Category),
+            "license" => Some(Self::License),
+            "msrv" => Some(Self::Msrv),
+            "updated" => Some(Self::Updated),
+            "feature" => Some(Self::Feature),
+            "has" => Some(Self::Has),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum QueryError {
+    #[error("missing value for the `{0}:` search term")]
+    MissingValue(String),
+    #[error("invalid search term `{0}` (expected a Rust version like `msrv:<=1.70`)")]
+    InvalidMsrv(String),
+    #[error("invalid search term `{0}` (expected a date like `updated:>2024-01-01`)")]
+    InvalidDate(String),
+    #[error("invalid search term `{0}` (expected `has:lib` or `has:bin`)")]
+    InvalidHas(String),
+}
+
+impl<'a> SearchQuery<'a> {
+    pub fn parse(q: &'a str) -> Result<Self, QueryError> {
+        let mut query = SearchQuery::default();
+        let mut text = Vec::new();
+
+        for term in q.split_whitespace() {
+            if term == "-yanked" {
+                query.exclude_yanked = true;
+                continue;
+            }
+
+            let Some((name, value)) = term.split_once(':') else {
+                text.push(term);
+                continue;
+            };
+
+            let Some(key) = Key::parse(name) else {
+                text.push(term);
+                continue;
+            };
+
+            if value.is_empty() {
+                return Err(QueryError::MissingValue(name.to_string()));
+            }
+
+            match key {
+                Key::Keyword => query.keywords.push(value),
+                Key::Category => query.categories.push(value),
+                Key::License => query.licenses.push(value),
+                Key::Msrv => {
+                    let (comparison, version) = Comparison::split(value);
+                    let version = parse_rust_version(version)
+                        .ok_or_else(|| QueryError::InvalidMsrv(term.to_string()))?;
+                    query.msrv.push((comparison, version));
+                }
+                Key::Updated => {
+                    let (comparison, date) = Comparison::split(value);
+                    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                        .map_err(|_| QueryError::InvalidDate(term.to_string()))?;
+                    query.updated.push((comparison, date));
+                }
+                Key::Feature => query.features.push(value),
+                Key::Has => match value {
+                    "lib" => query.has_lib = true,
+                    "bin" => query.has_bin = true,
+                    _ => return Err(QueryError::InvalidHas(term.to_string())),
+                },
+            }
+        }
+
+        query.text = text.join(" ");
+        Ok(query)
+    }
+}
+
+/// Parses a `rust-version` like `1.70` into `[1, 70, 0]`.
+fn parse_rust_version(version: &str) -> Option<[i32; 3]> {
+    let parts = version
+        .split('.')
+        .map(|part| match part.bytes().all(|b| b.is_ascii_digit()) {
+            true => part.parse().ok(),
+            false => None,
+        })
+        .collect::<Option<Vec<i32>>>()?;
+
+    if parts.len() > 3 {
+        return None;
+    }
+
+    let mut version = [0; 3];
+    version[..parts.len()].copy_from_slice(&parts);
+    Some(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_free_text() {
+        let query = SearchQuery::parse("  serde   json ").unwrap();
+        assert_eq!(query.text, "serde json");
+        assert_eq!(
+            query,
+            SearchQuery {
+                text: "serde json".into(),
+                ..Default::default()
+            }
+        );
+
+        // unknown terms are treated as free text
+        let query = SearchQuery::parse("serde::Serialize foo:bar -sys").unwrap();
+        assert_eq!(query.text, "serde::Serialize foo:bar -sys");
+    }
+
+    #[test]
+    fn test_terms() {
+        let q = "keyword:async tokio category:network-programming license:MIT msrv:<=1.70 updated:>2024-01-01 -yanked keyword:io feature:serde has:lib has:bin";
+        let query = SearchQuery::parse(q).unwrap();
+        assert_eq!(
+            query,
+            SearchQuery {
+                text: "tokio".into(),
+                keywords: vec!["async", "io"],
+                categories: vec!["network-programming"],
+                licenses: vec!["MIT"],
+                msrv: vec![(Comparison::Le, [1, 70, 0])],
+                updated: vec![(Comparison::Gt, NaiveDate::from_ymd_opt(2024, 1, 1).unwrap())],
+                features: vec!["serde"],
+                has_lib: true,
+                has_bin: true,
+                exclude_yanked: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let msrv = |q| SearchQuery::parse(q).unwrap().msrv;
+        assert_eq!(msrv("msrv:1"), vec![(Comparison::Eq, [1, 0, 0])]);
+        assert_eq!(msrv("msrv:=1.70.1"), vec![(Comparison::Eq, [1, 70, 1])]);
+        assert_eq!(msrv("msrv:<1.70"), vec![(Comparison::Lt, [1, 70, 0])]);
+        assert_eq!(msrv("msrv:>=1.70"), vec![(Comparison::Ge, [1, 70, 0])]);
+        assert_eq!(msrv("msrv:>1.70"), vec![(Comparison::Gt, [1, 70, 0])]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |q| SearchQuery::parse(q).unwrap_err().to_string();
+        insta::assert_snapshot!(error("foo keyword:"), @"missing value for the `keyword:` search term");
+        insta::assert_snapshot!(error("msrv:<="), @"invalid search term `msrv:<=` (expected a Rust version like `msrv:<=1.70`)");
+        insta::assert_snapshot!(error("msrv:1.70.0.1"), @"invalid search term `msrv:1.70.0.1` (expected a Rust version like `msrv:<=1.70`)");
+        insta::assert_snapshot!(error("msrv:^1.70"), @"invalid search term `msrv:^1.70` (expected a Rust version like `msrv:<=1.70`)");
+        insta::assert_snapshot!(error("msrv:1..70"), @"invalid search term `msrv:1..70` (expected a Rust version like `msrv:<=1.70`)");
+        insta::assert_snapshot!(error("has:docs"), @"invalid search term `has:docs` (expected `has:lib` or `has:bin`)");
+        insta::assert_snapshot!(error("updated:>yesterday"), @"invalid search term `updated:>yesterday` (expected a date like `updated:>2024-01-01`)");
+    }
+}
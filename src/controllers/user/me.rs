@@ -9,10 +9,15 @@ use crate::controllers::helpers::*;
 
 use crate::controllers::helpers::pagination::{Paginated, PaginationOptions};
 use crate::models::{
-    CrateOwner, Email, Follow, NewEmail, OwnerKind, User, Version, VersionOwnerAction,
+    CrateOwner, Email, Follow, NewEmail, NotificationEvent, OwnerKind, User, Version,
+    VersionOwnerAction,
 };
 use crate::schema::{crate_owners, crates, emails, follows, users, versions};
-use crate::views::{EncodableMe, EncodablePrivateUser, EncodableVersion, OwnedCrate};
+use crate::util::diesel::Conn;
+use crate::views::{
+    EncodableMe, EncodableNotificationSetting, EncodableNotificationSettings, EncodablePrivateUser,
+    EncodableVersion, OwnedCrate,
+};
 
 /// Handles the `GET /me` route.
 pub async fn me(app: AppState, req: Parts) -> AppResult<Json<EncodableMe>> {
@@ -34,19 +39,7 @@ pub async fn me(app: AppState, req: Parts) -> AppResult<Json<EncodableMe>> {
                 ))
                 .first(conn)?;
 
-        let owned_crates = CrateOwner::by_owner_kind(OwnerKind::User)
-            .inner_join(crates::table)
-            .filter(crate_owners::owner_id.eq(user_id))
-            .select((crates::id, crates::name, crate_owners::email_notifications))
-            .order(crates::name.asc())
-            .load(conn)?
-            .into_iter()
-            .map(|(id, name, email_notifications)| OwnedCrate {
-                id,
-                name,
-                email_notifications,
-            })
-            .collect();
+        let owned_crates = owned_crates(user_id, conn)?;
 
         let verified = verified.unwrap_or(false);
         let verification_sent = verified || verification_sent;
@@ -58,6 +51,26 @@ pub async fn me(app: AppState, req: Parts) -> AppResult<Json<EncodableMe>> {
     .await
 }
 
+/// Returns the crates owned by the user, along with whether the user wants to
+/// receive email notifications for each of them.
+fn owned_crates(user_id: i32, conn: &mut impl Conn) -> QueryResult<Vec<OwnedCrate>> {
+    let owned_crates = CrateOwner::by_owner_kind(OwnerKind::User)
+        .inner_join(crates::table)
+        .filter(crate_owners::owner_id.eq(user_id))
+        .select((crates::id, crates::name, crate_owners::email_notifications))
+        .order(crates::name.asc())
+        .load(conn)?
+        .into_iter()
+        .map(|(id, name, email_notifications)| OwnedCrate {
+            id,
+            name,
+            email_notifications,
+        })
+        .collect();
+
+    Ok(owned_crates)
+}
+
 /// Handles the `GET /me/updates` route.
 pub async fn updates(app: AppState, req: Parts) -> AppResult<Json<Value>> {
     let conn = app.db_read_prefer_primary().await?;
@@ -167,7 +180,6 @@ pub async fn update_user(
             // email. They'll then have to provide a valid email address.
             let email = UserConfirmEmail {
                 user_name: &user.gh_login,
-                domain: &state.emails.domain,
                 token,
             };
 
@@ -232,7 +244,6 @@ pub async fn regenerate_token_and_send(
 
             let email1 = UserConfirmEmail {
                 user_name: &user.gh_login,
-                domain: &state.emails.domain,
                 token: email.token,
             };
 
@@ -244,20 +255,63 @@ pub async fn regenerate_token_and_send(
     .await
 }
 
-/// Handles `PUT /me/email_notifications` route
-pub async fn update_email_notifications(app: AppState, req: BytesRequest) -> AppResult<Response> {
+/// Handles the `GET /me/notification_settings` route.
+pub async fn notification_settings(
+    app: AppState,
+    req: Parts,
+) -> AppResult<Json<EncodableNotificationSettings>> {
+    let conn = app.db_read_prefer_primary().await?;
+    spawn_blocking(move || {
+        let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+        let user_id = AuthCheck::only_cookie().check(&req, conn)?.user_id();
+
+        let events = NotificationEvent::settings_for(user_id, conn)?
+            .into_iter()
+            .map(|(event, enabled)| EncodableNotificationSetting { event, enabled })
+            .collect();
+
+        let crates = owned_crates(user_id, conn)?;
+
+        Ok(Json(EncodableNotificationSettings { events, crates }))
+    })
+    .await
+}
+
+/// Handles the `PUT /me/notification_settings` route.
+///
+/// Both the per-event settings and the per-crate `email_notifications` flags
+/// are optional. Events and crates that are not part of the request are left
+/// untouched.
+pub async fn update_notification_settings(app: AppState, req: BytesRequest) -> AppResult<Response> {
     #[derive(Deserialize)]
     struct CrateEmailNotifications {
         id: i32,
         email_notifications: bool,
     }
 
-    let updates: HashMap<i32, bool> =
-        serde_json::from_slice::<Vec<CrateEmailNotifications>>(req.body())
-            .map_err(|_| bad_request("invalid json request"))?
-            .iter()
-            .map(|c| (c.id, c.email_notifications))
-            .collect();
+    #[derive(Deserialize)]
+    struct NotificationSettingsUpdate {
+        #[serde(default)]
+        events: Vec<EncodableNotificationSetting>,
+        #[serde(default)]
+        crates: Vec<CrateEmailNotifications>,
+    }
+
+    let update: NotificationSettingsUpdate =
+        serde_json::from_slice(req.body()).map_err(|_| bad_request("invalid json request"))?;
+
+    let events = update
+        .events
+        .iter()
+        .map(|setting| (setting.event, setting.enabled))
+        .collect::<Vec<_>>();
+
+    let updates: HashMap<i32, bool> = update
+        .crates
+        .iter()
+        .map(|c| (c.id, c.email_notifications))
+        .collect();
 
     let conn = app.db_write().await?;
     spawn_blocking(move || {
@@ -267,42 +321,56 @@ pub async fn update_email_notifications(app: AppState, req: BytesRequest) -> App
 
         let user_id = AuthCheck::default().check(&req, conn)?.user_id();
 
-        // Build inserts from existing crates belonging to the current user
-        let to_insert = CrateOwner::by_owner_kind(OwnerKind::User)
-            .filter(crate_owners::owner_id.eq(user_id))
-            .select((
-                crate_owners::crate_id,
-                crate_owners::owner_id,
-                crate_owners::owner_kind,
-                crate_owners::email_notifications,
-            ))
-            .load(conn)?
-            .into_iter()
-            // Remove records whose `email_notifications` will not change from their current value
-            .map(
-                |(c_id, o_id, o_kind, e_notifications): (i32, i32, i32, bool)| {
-                    let current_e_notifications = *updates.get(&c_id).unwrap_or(&e_notifications);
-                    (
-                        crate_owners::crate_id.eq(c_id),
-                        crate_owners::owner_id.eq(o_id),
-                        crate_owners::owner_kind.eq(o_kind),
-                        crate_owners::email_notifications.eq(current_e_notifications),
-                    )
-                },
-            )
-            .collect::<Vec<_>>();
+        conn.transaction(|conn| -> QueryResult<_> {
+            NotificationEvent::update_settings(user_id, &events, conn)?;
 
-        // Upsert crate owners; this should only actually execute updates
-        diesel::insert_into(crate_owners::table)
-            .values(&to_insert)
-            .on_conflict((
-                crate_owners::crate_id,
-                crate_owners::owner_id,
-                crate_owners::owner_kind,
-            ))
-            .do_update()
-            .set(crate_owners::email_notifications.eq(excluded(crate_owners::email_notifications)))
-            .execute(conn)?;
+            if updates.is_empty() {
+                return Ok(());
+            }
+
+            // Build inserts from existing crates belonging to the current user
+            let to_insert = CrateOwner::by_owner_kind(OwnerKind::User)
+                .filter(crate_owners::owner_id.eq(user_id))
+                .select((
+                    crate_owners::crate_id,
+                    crate_owners::owner_id,
+                    crate_owners::owner_kind,
+                    crate_owners::email_notifications,
+                ))
+                .load(conn)?
+                .into_iter()
+                // Remove records whose `email_notifications` will not change from their current value
+                .map(
+                    |(c_id, o_id, o_kind, e_notifications): (i32, i32, i32, bool)| {
+                        let current_e_notifications =
+                            *updates.get(&c_id).unwrap_or(&e_notifications);
+                        (
+                            crate_owners::crate_id.eq(c_id),
+                            crate_owners::owner_id.eq(o_id),
+                            crate_owners::owner_kind.eq(o_kind),
+                            crate_owners::email_notifications.eq(current_e_notifications),
+                        )
+                    },
+                )
+                .collect::<Vec<_>>();
+
+            // Upsert crate owners; this should only actually execute updates
+            diesel::insert_into(crate_owners::table)
+                .values(&to_insert)
+                .on_conflict((
+                    crate_owners::crate_id,
+                    crate_owners::owner_id,
+                    crate_owners::owner_kind,
+                ))
+                .do_update()
+                .set(
+                    crate_owners::email_notifications
+                        .eq(excluded(crate_owners::email_notifications)),
+                )
+                .execute(conn)?;
+
+            Ok(())
+        })?;
 
         ok_true()
     })
@@ -311,25 +379,20 @@ pub async fn update_email_notifications(app: AppState, req: BytesRequest) -> App
 
 pub struct UserConfirmEmail<'a> {
     pub user_name: &'a str,
-    pub domain: &'a str,
     pub token: SecretString,
 }
 
 impl crate::email::Email for UserConfirmEmail<'_> {
     const SUBJECT: &'static str = "Please confirm your email address";
-
-    fn body(&self) -> String {
-        // Create a URL with token string as path to send to user
-        // If user clicks on path, look email/user up in database,
-        // make sure tokens match
-
-        format!(
-            "Hello {user_name}! Welcome to crates.io. Please click the
-link below to verify your email address. Thank you!\n
-https://{domain}/confirm/{token}",
-            user_name = self.user_name,
-            domain = self.domain,
-            token = self.token.expose_secret(),
-        )
+    const TEMPLATE: &'static str = "user_confirm";
+
+    fn context(&self) -> minijinja::Value {
+        // The email contains a URL with the token string as path. If the
+        // user clicks on it, we look the email/user up in the database and
+        // make sure that the tokens match.
+        minijinja::context! {
+            user_name => self.user_name,
+            token => self.token.expose_secret(),
+        }
     }
 }
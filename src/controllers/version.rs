@@ -1,5 +1,7 @@
+pub mod changelog;
 pub mod downloads;
 pub mod metadata;
+pub mod readme;
 pub mod yank;
 
 use super::prelude::*;
@@ -0,0 +1,75 @@
+//! Endpoint for the rendered changelogs of crate versions.
+
+use super::version_and_crate;
+use crate::controllers::frontend_prelude::*;
+use crate::schema::changelogs;
+use crate::util::errors::custom;
+use crates_io_markdown::ChangelogSection;
+use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
+
+/// Handles the `GET /crates/:crate_id/:version/changelog` route.
+///
+/// Returns the sections of the changelog that was published with the
+/// version. If the `since` query parameter is set to a version number, only
+/// the sections about newer versions are returned, i.e. what's new when
+/// upgrading from that version.
+///
+/// The rendered changelog itself is available at the returned `url`, and the
+/// `start` and `end` byte offsets of each section can be used to request or
+/// slice out only the relevant parts of it.
+pub async fn changelog(
+    app: AppState,
+    Path((crate_name, version)): Path<(String, String)>,
+    req: Parts,
+) -> AppResult<Json<Value>> {
+    let since = req
+        .query()
+        .get("since")
+        .map(|since| semver::Version::parse(since.trim_start_matches('v')))
+        .transpose()
+        .map_err(|_| bad_request("invalid `since` version"))?;
+
+    let conn = app.db_read().await?;
+    spawn_blocking(move || {
+        let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+        let (version, krate) = version_and_crate(conn, &crate_name, &version)?;
+
+        let (path, sections): (String, Value) = changelogs::table
+            .find(version.id)
+            .select((changelogs::path, changelogs::sections))
+            .first(conn)
+            .optional()?
+            .ok_or_else(|| {
+                let detail = format!(
+                    "version `{}` of crate `{}` does not have a changelog",
+                    version.num, krate.name
+                );
+                custom(StatusCode::NOT_FOUND, detail)
+            })?;
+
+        let mut sections: Vec<ChangelogSection> =
+            serde_json::from_value(sections).map_err(server_error)?;
+
+        if let Some(since) = since {
+            sections.retain(|section| {
+                section
+                    .version
+                    .as_deref()
+                    .and_then(|version| semver::Version::parse(version).ok())
+                    .is_some_and(|version| version > since)
+            });
+        }
+
+        let url = app.storage.changelog_location(&krate.name, &version.num);
+
+        Ok(Json(json!({
+            "changelog": {
+                "path": path,
+                "url": url,
+                "sections": sections,
+            }
+        })))
+    })
+    .await
+}
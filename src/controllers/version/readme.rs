@@ -0,0 +1,61 @@
+//! Endpoint for re-rendering the README of a specific version of a crate
+
+use super::version_and_crate;
+use crate::auth::AuthCheck;
+use crate::controllers::cargo_prelude::*;
+use crate::models::token::EndpointScope;
+use crate::models::Rights;
+use crate::rate_limiter::LimitedAction;
+use crate::util::errors::{custom, version_not_found};
+use crate::worker::jobs::RerenderReadme;
+use crates_io_worker::BackgroundJob;
+use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
+use tokio::runtime::Handle;
+
+/// Handles the `POST /crates/:crate_id/:version/rerender_readme` route.
+///
+/// This enqueues a background job that reads the README from the uploaded
+/// crate file and renders it again, e.g. to pick up fixes to the renderer.
+pub async fn rerender_readme(
+    app: AppState,
+    Path((crate_name, version)): Path<(String, String)>,
+    req: Parts,
+) -> AppResult<Response> {
+    if semver::Version::parse(&version).is_err() {
+        return Err(version_not_found(&crate_name, &version));
+    }
+
+    let conn = app.db_write().await?;
+    spawn_blocking(move || {
+        let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+        let auth = AuthCheck::default()
+            .with_endpoint_scope(EndpointScope::PublishUpdate)
+            .for_crate(&crate_name)
+            .check(&req, conn)?;
+
+        app.rate_limiter
+            .check_rate_limit(auth.user_id(), LimitedAction::RenderReadme, conn)?;
+
+        let (version, krate) = version_and_crate(conn, &crate_name, &version)?;
+        let user = auth.user();
+        let owners = krate.owners(conn)?;
+
+        if Handle::current().block_on(user.rights(&app, &owners))? < Rights::Publish {
+            return Err(custom(
+                StatusCode::FORBIDDEN,
+                "must already be an owner to re-render a README",
+            ));
+        }
+
+        info!(
+            "User {} requested a re-rendering of the README of {}@{}",
+            user.gh_login, krate.name, version.num
+        );
+
+        RerenderReadme::new(version.id).enqueue(conn)?;
+
+        ok_true()
+    })
+    .await
+}
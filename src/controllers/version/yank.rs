@@ -5,12 +5,12 @@ use crate::auth::AuthCheck;
 use crate::controllers::cargo_prelude::*;
 use crate::models::token::EndpointScope;
 use crate::models::Rights;
-use crate::models::{insert_version_owner_action, VersionAction};
+use crate::models::{insert_version_owner_action, VersionAction, Webhook, WebhookEvent};
 use crate::rate_limiter::LimitedAction;
 use crate::schema::versions;
 use crate::util::errors::{custom, version_not_found};
 use crate::worker::jobs;
-use crate::worker::jobs::UpdateDefaultVersion;
+use crate::worker::jobs::{SendVersionNotifications, TriggerWebhooks, UpdateDefaultVersion};
 use crates_io_worker::BackgroundJob;
 use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
 use tokio::runtime::Handle;
@@ -104,12 +104,25 @@ async fn modify_yank(
             VersionAction::Unyank
         };
 
-        insert_version_owner_action(conn, version.id, user.id, api_token_id, action)?;
+        let action = insert_version_owner_action(conn, version.id, user.id, api_token_id, action)?;
 
         jobs::enqueue_sync_to_index(&krate.name, conn)?;
 
         UpdateDefaultVersion::new(krate.id).enqueue(conn)?;
 
+        if yanked {
+            SendVersionNotifications::new(action.id).enqueue(conn)?;
+        }
+
+        let event = if yanked {
+            WebhookEvent::Yank
+        } else {
+            WebhookEvent::Unyank
+        };
+        if Webhook::any_subscribed_to(krate.id, event, conn)? {
+            TriggerWebhooks::version_action(action.id).enqueue(conn)?;
+        }
+
         ok_true()
     })
     .await
@@ -0,0 +1,169 @@
+//! Endpoints for managing the webhooks of the current user.
+//!
+//! Webhooks can either be subscribed to the events of a specific crate, or to
+//! the events of all crates that the user follows. Since crate events are
+//! public anyway, users don't need to own a crate to subscribe to it.
+
+use super::frontend_prelude::*;
+
+use crate::auth::AuthCheck;
+use crate::models::{Crate, Webhook, WebhookDelivery, WebhookEvent};
+use crate::schema::{crates, webhook_deliveries, webhooks};
+use crate::util::errors::{crate_not_found, not_found};
+use crate::views::{EncodableWebhook, EncodableWebhookWithSecret};
+use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
+use tokio::runtime::Handle;
+use url::Url;
+
+/// The maximum number of webhooks that a user can register.
+const MAX_WEBHOOKS_PER_USER: i64 = 20;
+
+/// Handles the `GET /me/webhooks` route.
+pub async fn list(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    let conn = app.db_read_prefer_primary().await?;
+    spawn_blocking(move || {
+        let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user = auth.user();
+
+        let webhooks = Webhook::belonging_to(user)
+            .left_join(crates::table)
+            .select((Webhook::as_select(), crates::name.nullable()))
+            .order(webhooks::id)
+            .load(conn)?
+            .into_iter()
+            .map(|(webhook, crate_name)| EncodableWebhook::from(webhook, crate_name))
+            .collect::<Vec<_>>();
+
+        Ok(Json(json!({ "webhooks": webhooks })))
+    })
+    .await
+}
+
+/// Handles the `PUT /me/webhooks` route.
+pub async fn new(app: AppState, req: BytesRequest) -> AppResult<Json<Value>> {
+    #[derive(Deserialize)]
+    struct NewWebhook {
+        url: String,
+        #[serde(rename = "crate")]
+        krate: Option<String>,
+        events: Vec<WebhookEvent>,
+    }
+
+    #[derive(Deserialize)]
+    struct NewWebhookRequest {
+        webhook: NewWebhook,
+    }
+
+    let new: NewWebhookRequest = serde_json::from_slice(req.body())
+        .map_err(|e| bad_request(format!("invalid new webhook request: {e}")))?;
+    let new = new.webhook;
+
+    let url = Url::parse(&new.url)
+        .ok()
+        .filter(|url| url.scheme() == "https")
+        .ok_or_else(|| bad_request("webhook URL must be a valid `https` URL"))?;
+
+    let mut events = new.events;
+    events.sort_by_key(|event| *event as i32);
+    events.dedup();
+    if events.is_empty() {
+        return Err(bad_request(
+            "webhook must be subscribed to at least one event",
+        ));
+    }
+
+    let conn = app.db_write().await?;
+    spawn_blocking(move || {
+        let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user = auth.user();
+
+        // The host is resolved again for every delivery, see
+        // `WebhookClientImpl`. This check only gives users early feedback.
+        if Handle::current()
+            .block_on(app.dns_resolver.check_url(&url))
+            .is_err()
+        {
+            return Err(bad_request(
+                "webhook URL must resolve to a public IP address",
+            ));
+        }
+
+        let count: i64 = Webhook::belonging_to(user).count().get_result(conn)?;
+        if count >= MAX_WEBHOOKS_PER_USER {
+            return Err(bad_request(format!(
+                "maximum webhooks per user is: {MAX_WEBHOOKS_PER_USER}"
+            )));
+        }
+
+        let crate_id = match &new.krate {
+            Some(name) => Some(
+                Crate::by_name(name)
+                    .select(crates::id)
+                    .first::<i32>(conn)
+                    .optional()?
+                    .ok_or_else(|| crate_not_found(name))?,
+            ),
+            None => None,
+        };
+
+        let webhook = Webhook::insert(conn, user.id, crate_id, &new.url, &events)?;
+        let secret = webhook.secret.clone();
+        let webhook = EncodableWebhookWithSecret {
+            webhook: EncodableWebhook::from(webhook, new.krate),
+            secret,
+        };
+
+        Ok(Json(json!({ "webhook": webhook })))
+    })
+    .await
+}
+
+/// Handles the `DELETE /me/webhooks/:id` route.
+pub async fn delete(app: AppState, Path(id): Path<i32>, req: Parts) -> AppResult<Response> {
+    let conn = app.db_write().await?;
+    spawn_blocking(move || {
+        let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user = auth.user();
+
+        let deleted = diesel::delete(Webhook::belonging_to(user).find(id)).execute(conn)?;
+        if deleted == 0 {
+            return Err(not_found());
+        }
+
+        ok_true()
+    })
+    .await
+}
+
+/// Handles the `GET /me/webhooks/:id/deliveries` route.
+///
+/// Returns the most recent deliveries of the webhook, including failed ones.
+pub async fn deliveries(app: AppState, Path(id): Path<i32>, req: Parts) -> AppResult<Json<Value>> {
+    let conn = app.db_read_prefer_primary().await?;
+    spawn_blocking(move || {
+        let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user = auth.user();
+
+        let webhook: Webhook = Webhook::belonging_to(user)
+            .find(id)
+            .select(Webhook::as_select())
+            .first(conn)?;
+
+        let deliveries: Vec<WebhookDelivery> = WebhookDelivery::belonging_to(&webhook)
+            .select(WebhookDelivery::as_select())
+            .order(webhook_deliveries::id.desc())
+            .limit(WebhookDelivery::MAX_PER_WEBHOOK)
+            .load(conn)?;
+
+        Ok(Json(json!({ "deliveries": deliveries })))
+    })
+    .await
+}
@@ -0,0 +1,232 @@
+//! DNS resolution for requests to URLs that were provided by users.
+//!
+//! Links in READMEs and webhook endpoints can point anywhere, including the
+//! internal network that crates.io is running in. The [PublicResolver] only
+//! resolves host names to public IP addresses. It is plugged into the HTTP
+//! clients that send requests to such URLs, so that the check applies to the
+//! addresses that are actually connected to, and not just to the result of
+//! an earlier lookup.
+//!
+//! Since `reqwest` does not resolve IP addresses that are used as hosts,
+//! these have to be checked separately with [is_public_host()].
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+use url::{Host, Url};
+
+/// Error that is returned if a host does not resolve to public addresses
+/// only.
+#[derive(Debug, thiserror::Error)]
+#[error("`{0}` does not resolve to a public address")]
+pub struct NonPublicAddress(String);
+
+/// A DNS resolver that fails for host names that resolve to loopback,
+/// private, link-local or otherwise non-public addresses.
+///
+/// If any of the resolved addresses is not public, the resolution fails as a
+/// whole, instead of only skipping these addresses.
+#[derive(Clone)]
+pub struct PublicResolver {
+    inner: Arc<dyn Resolve>,
+}
+
+impl PublicResolver {
+    /// Creates a resolver that filters the results of the given resolver.
+    pub fn new(inner: Arc<dyn Resolve>) -> Self {
+        Self { inner }
+    }
+
+    /// Checks that the host of the URL only resolves to public addresses.
+    pub async fn check_url(&self, url: &Url) -> Result<(), NonPublicAddress> {
+        let non_public = || NonPublicAddress(url.host_str().unwrap_or_default().to_string());
+
+        match url.host() {
+            Some(Host::Domain(domain)) => {
+                let name = domain.parse().map_err(|_| non_public())?;
+                match self.resolve(name).await {
+                    Ok(_) => Ok(()),
+                    Err(_) => Err(non_public()),
+                }
+            }
+            Some(_) if is_public_host(url) => Ok(()),
+            _ => Err(non_public()),
+        }
+    }
+}
+
+impl Default for PublicResolver {
+    /// Creates a resolver that filters the results of the system resolver.
+    fn default() -> Self {
+        Self::new(Arc::new(SystemResolver))
+    }
+}
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        let resolving = self.inner.resolve(name);
+
+        Box::pin(async move {
+            let addrs = resolving.await?.collect::<Vec<_>>();
+            if addrs.is_empty() || !addrs.iter().all(|addr| is_public_ip(addr.ip())) {
+                return Err(NonPublicAddress(host).into());
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Resolves host names with `getaddrinfo`, like `reqwest` does by default.
+struct SystemResolver;
+
+impl Resolve for SystemResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((host, 0)).await?;
+            Ok(Box::new(addrs) as Addrs)
+        })
+    }
+}
+
+/// Returns `false` if the host of the URL is a non-public IP address, or if
+/// the URL has no host at all.
+///
+/// Host names are not resolved by this function.
+pub fn is_public_host(url: &Url) -> bool {
+    match url.host() {
+        Some(Host::Domain(_)) => true,
+        Some(Host::Ipv4(ip)) => is_public_ip(ip.into()),
+        Some(Host::Ipv6(ip)) => is_public_ip(ip.into()),
+        None => false,
+    }
+}
+
+/// Returns `true` if the IP address is globally reachable, i.e. it is not a
+/// loopback, private, link-local, unique local or otherwise special-purpose
+/// address.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ipv4(ip),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // `0.0.0.0/8` ("this network")
+        || a == 0
+        // `100.64.0.0/10` (shared address space)
+        || (a == 100 && (b & 0b1100_0000) == 64)
+        // `198.18.0.0/15` (benchmarking)
+        || (a == 198 && (b & 0b1111_1110) == 18)
+        // `240.0.0.0/4` (reserved)
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // `fc00::/7` (unique local)
+        || (first & 0xfe00) == 0xfc00
+        // `fe80::/10` (link-local)
+        || (first & 0xffc0) == 0xfe80
+        // `2001:db8::/32` (documentation)
+        || (first == 0x2001 && ip.segments()[1] == 0x0db8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+
+    /// Resolves all host names to the given addresses.
+    struct StaticResolver(Vec<IpAddr>);
+
+    impl Resolve for StaticResolver {
+        fn resolve(&self, _name: Name) -> Resolving {
+            let addrs = self.0.iter().map(|ip| SocketAddr::new(*ip, 0));
+            let addrs = addrs.collect::<Vec<_>>().into_iter();
+            Box::pin(async move { Ok(Box::new(addrs) as Addrs) })
+        }
+    }
+
+    fn resolver(ips: &[&str]) -> PublicResolver {
+        let ips = ips.iter().map(|ip| ip.parse().unwrap()).collect();
+        PublicResolver::new(Arc::new(StaticResolver(ips)))
+    }
+
+    #[test]
+    fn test_is_public_ip() {
+        let public = ["1.1.1.1", "93.184.215.14", "2606:4700::1111"];
+        for ip in public {
+            assert!(is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+
+        let non_public = [
+            "0.0.0.0",
+            "0.1.2.3",
+            "127.0.0.1",
+            "10.0.0.1",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "198.18.0.1",
+            "224.0.0.1",
+            "255.255.255.255",
+            "::",
+            "::1",
+            "::ffff:127.0.0.1",
+            "::ffff:10.0.0.1",
+            "fc00::1",
+            "fd00::1",
+            "fe80::1",
+            "ff02::1",
+            "2001:db8::1",
+        ];
+        for ip in non_public {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[test]
+    fn test_is_public_host() {
+        let is_public = |url: &str| is_public_host(&Url::parse(url).unwrap());
+
+        assert!(is_public("https://example.com/"));
+        assert!(is_public("https://1.1.1.1/"));
+        assert!(!is_public("https://127.0.0.1/"));
+        assert!(!is_public("https://[::1]/"));
+        assert!(!is_public("https://[fd00::1]:8080/"));
+        assert!(!is_public("mailto:foo@example.com"));
+    }
+
+    #[tokio::test]
+    async fn test_check_url() {
+        let url = Url::parse("https://example.com/hook").unwrap();
+        assert!(resolver(&["1.1.1.1"]).check_url(&url).await.is_ok());
+        assert!(resolver(&["10.0.0.1"]).check_url(&url).await.is_err());
+        assert!(resolver(&["1.1.1.1", "::1"]).check_url(&url).await.is_err());
+        assert!(resolver(&[]).check_url(&url).await.is_err());
+
+        let url = Url::parse("https://169.254.169.254/latest").unwrap();
+        assert!(resolver(&["1.1.1.1"]).check_url(&url).await.is_err());
+    }
+}
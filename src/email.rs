@@ -1,20 +1,97 @@
 use crate::config;
 use crate::Env;
 use lettre::address::Envelope;
-use lettre::message::header::ContentType;
-use lettre::message::Mailbox;
+use lettre::message::{Mailbox, MultiPart};
 use lettre::transport::file::FileTransport;
 use lettre::transport::smtp::authentication::{Credentials, Mechanism};
 use lettre::transport::smtp::SmtpTransport;
 use lettre::transport::stub::StubTransport;
 use lettre::{Message, Transport};
+use minijinja::{context, Environment, Value};
 use rand::distributions::{Alphanumeric, DistString};
+use std::sync::LazyLock;
 
+/// An email that can be sent through [`Emails::send()`].
+///
+/// Emails are sent as multipart messages with both a plain text and an HTML
+/// body, which are rendered from the `{TEMPLATE}.txt.j2` and
+/// `{TEMPLATE}.html.j2` files in `src/email/templates`. The templates extend
+/// the shared `base.txt.j2` and `base.html.j2` layouts and have access to the
+/// [`context()`](Email::context) of the email, the `subject` and the `domain`
+/// of the crates.io instance.
+///
+/// Senders of emails about one of the
+/// [`NotificationEvent`](crate::models::NotificationEvent)s are responsible
+/// for checking the notification settings of the recipient first. All other
+/// emails (e.g. email address confirmations or security alerts) are always
+/// sent.
 pub trait Email {
     const SUBJECT: &'static str;
-    fn body(&self) -> String;
+
+    /// Name of the templates of the email, without the file extensions.
+    const TEMPLATE: &'static str;
+
+    /// Variables that are passed to the templates of the email.
+    fn context(&self) -> Value;
 }
 
+/// The subject and the bodies of an [`Email`], as returned by
+/// [`Emails::render()`].
+#[derive(Debug, Clone)]
+pub struct RenderedEmail {
+    pub subject: &'static str,
+    pub text: String,
+    pub html: String,
+}
+
+/// Embeds the given files of the `src/email/templates` directory.
+macro_rules! template_files {
+    ($($name:literal),* $(,)?) => {
+        &[$(($name, include_str!(concat!("email/templates/", $name)))),*]
+    };
+}
+
+/// Template files that are embedded into the binary, see [`Email`].
+const TEMPLATE_FILES: &[(&str, &str)] = template_files![
+    "base.html.j2",
+    "base.txt.j2",
+    "macros.html.j2",
+    "admin_accounts.html.j2",
+    "admin_accounts.txt.j2",
+    "broken_readme_links.html.j2",
+    "broken_readme_links.txt.j2",
+    "new_reverse_dependency.html.j2",
+    "new_reverse_dependency.txt.j2",
+    "owner_invite.html.j2",
+    "owner_invite.txt.j2",
+    "ownership_change.html.j2",
+    "ownership_change.txt.j2",
+    "possible_typosquat.html.j2",
+    "possible_typosquat.txt.j2",
+    "token_expiry.html.j2",
+    "token_expiry.txt.j2",
+    "token_exposed.html.j2",
+    "token_exposed.txt.j2",
+    "user_confirm.html.j2",
+    "user_confirm.txt.j2",
+    "version_action.html.j2",
+    "version_action.txt.j2",
+];
+
+static TEMPLATES: LazyLock<Environment<'static>> = LazyLock::new(|| {
+    let mut env = Environment::new();
+    env.set_trim_blocks(true);
+    env.set_lstrip_blocks(true);
+
+    // HTML templates are escaped automatically based on their file extension.
+    for (name, source) in TEMPLATE_FILES {
+        env.add_template(name, source)
+            .unwrap_or_else(|error| panic!("Failed to load email template {name}: {error}"));
+    }
+
+    env
+});
+
 #[derive(Debug, Clone)]
 pub struct Emails {
     backend: EmailBackend,
@@ -97,19 +174,38 @@ impl Emails {
             self.domain,
         );
 
-        let subject = E::SUBJECT;
-        let body = email.body();
+        let email = self.render(&email)?;
 
         let email = Message::builder()
             .message_id(Some(message_id.clone()))
             .to(recipient.parse()?)
             .from(self.from.clone())
-            .subject(subject)
-            .header(ContentType::TEXT_PLAIN)
-            .body(body)?;
+            .subject(email.subject)
+            .multipart(MultiPart::alternative_plain_html(email.text, email.html))?;
 
         self.backend.send(email).map_err(EmailError::TransportError)
     }
+
+    /// Renders the subject and the bodies of an email without sending it.
+    pub fn render<E: Email>(&self, email: &E) -> Result<RenderedEmail, EmailError> {
+        let subject = E::SUBJECT;
+        let context = context! {
+            subject,
+            domain => self.domain,
+            ..email.context()
+        };
+
+        let render = |extension: &str| {
+            let name = format!("{}.{extension}.j2", E::TEMPLATE);
+            TEMPLATES.get_template(&name)?.render(&context)
+        };
+
+        Ok(RenderedEmail {
+            subject,
+            text: render("txt")?,
+            html: render("html")?,
+        })
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -119,6 +215,8 @@ pub enum EmailError {
     #[error(transparent)]
     MessageBuilderError(#[from] lettre::error::Error),
     #[error(transparent)]
+    TemplateError(#[from] minijinja::Error),
+    #[error(transparent)]
     TransportError(anyhow::Error),
 }
 
@@ -161,9 +259,10 @@ mod tests {
 
     impl Email for TestEmail {
         const SUBJECT: &'static str = "test";
+        const TEMPLATE: &'static str = "user_confirm";
 
-        fn body(&self) -> String {
-            "test".into()
+        fn context(&self) -> Value {
+            context! { user_name => "test", token => "test" }
         }
     }
 
@@ -183,4 +282,22 @@ mod tests {
 
         assert_ok!(emails.send("someone@example.com", TestEmail));
     }
+
+    #[test]
+    fn only_html_bodies_are_escaped() {
+        struct UnsafeEmail;
+
+        impl Email for UnsafeEmail {
+            const SUBJECT: &'static str = "test";
+            const TEMPLATE: &'static str = "user_confirm";
+
+            fn context(&self) -> Value {
+                context! { user_name => "<b>test</b>", token => "test" }
+            }
+        }
+
+        let email = assert_ok!(Emails::new_in_memory().render(&UnsafeEmail));
+        assert!(email.text.contains("Hello <b>test</b>!"));
+        assert!(email.html.contains("Hello &lt;b&gt;test&lt;&#x2f;b&gt;!"));
+    }
 }
@@ -38,11 +38,13 @@ pub mod cloudfront;
 pub mod config;
 pub mod controllers;
 pub mod db;
+pub mod dns;
 pub mod email;
 pub mod external_urls;
 pub mod fastly;
 pub mod headers;
 mod licenses;
+pub mod link_checker;
 pub mod metrics;
 pub mod middleware;
 pub mod models;
@@ -50,6 +52,7 @@ pub mod rate_limiter;
 mod real_ip;
 mod router;
 pub mod schema;
+pub mod search;
 pub mod sentry;
 pub mod sql;
 pub mod sqs;
@@ -61,6 +64,7 @@ mod test_util;
 pub mod typosquat;
 pub mod util;
 pub mod views;
+pub mod webhooks;
 pub mod worker;
 
 /// Used for setting different values depending on whether the app is being run in production,
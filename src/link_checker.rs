@@ -0,0 +1,93 @@
+//! The code in this module checks whether the links in READMEs still work.
+//!
+//! The [LinkChecker] trait is used to abstract away the HTTP client for
+//! testing purposes. The [LinkCheckerImpl] struct is the actual
+//! implementation of the trait.
+//!
+//! Since the links are chosen by the crate authors, [LinkCheckerImpl] only
+//! connects to public IP addresses, see [crate::dns].
+
+use crate::dns::{self, PublicResolver};
+use async_trait::async_trait;
+use mockall::automock;
+use reqwest::redirect::Policy;
+use reqwest::{Client, StatusCode};
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+/// The maximum number of redirects that are followed for a link.
+const MAX_REDIRECTS: usize = 10;
+
+#[automock]
+#[async_trait]
+pub trait LinkChecker {
+    /// Checks the target of a link, and returns the reason if the link is
+    /// broken.
+    ///
+    /// Links whose state can't be determined reliably, e.g. because of
+    /// timeouts or server errors, are not considered broken.
+    async fn check(&self, url: &str) -> Option<String>;
+}
+
+pub struct LinkCheckerImpl {
+    client: Client,
+}
+
+impl LinkCheckerImpl {
+    fn new(client: Client) -> Self {
+        LinkCheckerImpl { client }
+    }
+}
+
+impl Default for LinkCheckerImpl {
+    fn default() -> Self {
+        let client = Client::builder()
+            .user_agent("crates.io link checker (https://crates.io)")
+            .timeout(Duration::from_secs(10))
+            .dns_resolver(Arc::new(PublicResolver::default()))
+            .redirect(redirect_policy())
+            .build()
+            .unwrap();
+
+        LinkCheckerImpl::new(client)
+    }
+}
+
+/// Follows up to [MAX_REDIRECTS] redirects, unless they point to a non-public
+/// IP address.
+///
+/// Host names of the redirect targets are checked by the [PublicResolver]
+/// when connecting to them.
+fn redirect_policy() -> Policy {
+    Policy::custom(|attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else if !dns::is_public_host(attempt.url()) {
+            attempt.error("redirect to a non-public address")
+        } else {
+            attempt.follow()
+        }
+    })
+}
+
+#[async_trait]
+impl LinkChecker for LinkCheckerImpl {
+    async fn check(&self, url: &str) -> Option<String> {
+        if !Url::parse(url).is_ok_and(|url| dns::is_public_host(&url)) {
+            return None;
+        }
+
+        // Some servers don't support `HEAD` requests, so we fall back to
+        // `GET` for them.
+        let response = match self.client.head(url).send().await {
+            Ok(response) if response.status() == StatusCode::METHOD_NOT_ALLOWED => {
+                self.client.get(url).send().await
+            }
+            result => result,
+        };
+
+        let status = response.ok()?.status();
+        matches!(status, StatusCode::NOT_FOUND | StatusCode::GONE).then(|| status.to_string())
+    }
+}
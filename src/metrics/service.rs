@@ -11,11 +11,13 @@
 //! instance-level metric, and you should add it to `src/metrics/instance.rs`.
 
 use crate::metrics::macros::metrics;
-use crate::schema::{background_jobs, crates, versions};
+use crate::schema::{background_job_metrics, background_jobs, crates, versions};
 use crate::util::errors::AppResult;
+use crate::worker::JOB_HISTOGRAM_BUCKETS;
 use diesel::{dsl::count_star, prelude::*};
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
-use prometheus::{proto::MetricFamily, IntGauge, IntGaugeVec};
+use prometheus::proto::{Bucket, Histogram, LabelPair, Metric, MetricFamily, MetricType};
+use prometheus::{IntCounterVec, IntGauge, IntGaugeVec};
 
 metrics! {
     pub struct ServiceMetrics {
@@ -25,6 +27,10 @@ metrics! {
         versions_total: IntGauge,
         /// Number of queued up background jobs
         background_jobs: IntGaugeVec["priority", "job"],
+        /// Number of finished background job runs
+        background_job_runs_total: IntCounterVec["job", "outcome"],
+        /// Number of background job runs that were retries of failed runs
+        background_job_retries_total: IntCounterVec["job"],
     }
 
     // All service metrics will be prefixed with this namespace.
@@ -59,6 +65,114 @@ impl ServiceMetrics {
                 .set(count);
         }
 
-        Ok(self.registry.gather())
+        let job_metrics = background_job_metrics::table
+            .select(JobMetrics::as_select())
+            .load::<JobMetrics>(conn)
+            .await?;
+
+        self.background_job_runs_total.reset();
+        self.background_job_retries_total.reset();
+        for metrics in &job_metrics {
+            let job = metrics.job_type.as_str();
+            self.background_job_runs_total
+                .get_metric_with_label_values(&[job, "success"])?
+                .inc_by(metrics.successes as u64);
+            self.background_job_runs_total
+                .get_metric_with_label_values(&[job, "failure"])?
+                .inc_by(metrics.failures as u64);
+            self.background_job_retries_total
+                .get_metric_with_label_values(&[job])?
+                .inc_by(metrics.retries as u64);
+        }
+
+        let mut families = self.registry.gather();
+        families.extend(histogram_family(
+            "background_job_queue_wait_seconds",
+            "Time background jobs spent in the queue before their first attempt",
+            job_metrics
+                .iter()
+                .map(|m| (&*m.job_type, m.queue_wait_sum, &*m.queue_wait_buckets)),
+        ));
+        families.extend(histogram_family(
+            "background_job_duration_seconds",
+            "Time it took to run background jobs",
+            job_metrics
+                .iter()
+                .map(|m| (&*m.job_type, m.duration_sum, &*m.duration_buckets)),
+        ));
+
+        Ok(families)
     }
 }
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = background_job_metrics, check_for_backend(diesel::pg::Pg))]
+struct JobMetrics {
+    job_type: String,
+    successes: i64,
+    failures: i64,
+    retries: i64,
+    queue_wait_sum: f64,
+    queue_wait_buckets: Vec<Option<i64>>,
+    duration_sum: f64,
+    duration_buckets: Vec<Option<i64>>,
+}
+
+/// Builds a histogram metric family from the non-cumulative bucket counts
+/// aggregated by the background worker (see `src/worker/metrics.rs`).
+///
+/// The `prometheus` histogram types can only be filled by observing
+/// individual values, so we have to build the metric family manually. Returns
+/// `None` if there are no jobs, since empty metric families can't be encoded.
+fn histogram_family<'a>(
+    name: &str,
+    help: &str,
+    jobs: impl Iterator<Item = (&'a str, f64, &'a [Option<i64>])>,
+) -> Option<MetricFamily> {
+    let metrics = jobs
+        .map(|(job, sum, counts)| {
+            let mut cumulative_count = 0;
+            let buckets = JOB_HISTOGRAM_BUCKETS
+                .iter()
+                .enumerate()
+                .map(|(i, upper_bound)| {
+                    cumulative_count += counts.get(i).copied().flatten().unwrap_or(0) as u64;
+
+                    let mut bucket = Bucket::default();
+                    bucket.set_upper_bound(*upper_bound);
+                    bucket.set_cumulative_count(cumulative_count);
+                    bucket
+                })
+                .collect();
+
+            // The last element counts all observations above the largest bucket.
+            let overflow = counts.get(JOB_HISTOGRAM_BUCKETS.len()).copied().flatten();
+            cumulative_count += overflow.unwrap_or(0) as u64;
+
+            let mut histogram = Histogram::default();
+            histogram.set_sample_count(cumulative_count);
+            histogram.set_sample_sum(sum);
+            histogram.set_bucket(buckets);
+
+            let mut label = LabelPair::default();
+            label.set_name("job".into());
+            label.set_value(job.into());
+
+            let mut metric = Metric::default();
+            metric.set_label(vec![label]);
+            metric.set_histogram(histogram);
+            metric
+        })
+        .collect::<Vec<_>>();
+
+    if metrics.is_empty() {
+        return None;
+    }
+
+    let mut family = MetricFamily::default();
+    family.set_name(format!("cratesio_service_{name}"));
+    family.set_help(help.into());
+    family.set_field_type(MetricType::HISTOGRAM);
+    family.set_metric(metrics);
+    Some(family)
+}
@@ -3,17 +3,19 @@ pub use self::category::{Category, CrateCategory, NewCategory};
 pub use self::crate_owner_invitation::{CrateOwnerInvitation, NewCrateOwnerInvitationOutcome};
 pub use self::default_versions::{update_default_version, verify_default_version};
 pub use self::dependency::{Dependency, DependencyKind, ReverseDependency};
-pub use self::download::VersionDownload;
+pub use self::download::{CrateDownloadsMonthly, VersionDownload, VersionDownloadByClient};
 pub use self::email::{Email, NewEmail};
 pub use self::follow::Follow;
 pub use self::keyword::{CrateKeyword, Keyword};
 pub use self::krate::{Crate, CrateVersions, NewCrate, RecentCrateDownloads};
+pub use self::notification::NotificationEvent;
 pub use self::owner::{CrateOwner, Owner, OwnerKind};
 pub use self::rights::Rights;
 pub use self::team::{NewTeam, Team};
 pub use self::token::{ApiToken, CreatedApiToken};
 pub use self::user::{NewUser, User};
 pub use self::version::{NewVersion, TopVersions, Version};
+pub use self::webhook::{Webhook, WebhookDelivery, WebhookEvent};
 
 pub mod helpers;
 
@@ -27,9 +29,11 @@ mod email;
 mod follow;
 mod keyword;
 pub mod krate;
+mod notification;
 mod owner;
 mod rights;
 mod team;
 pub mod token;
 pub mod user;
 pub mod version;
+mod webhook;
@@ -1,6 +1,9 @@
 use crate::models::Version;
-use crate::schema::version_downloads;
+use crate::schema::{version_downloads, version_downloads_by_client};
 use chrono::NaiveDate;
+use diesel::pg::Pg;
+use diesel::query_builder::{BoxedSqlQuery, SqlQuery};
+use diesel::sql_types::{BigInt, Date, Integer};
 
 #[derive(Queryable, Identifiable, Associations, Debug, Clone, Copy)]
 #[diesel(primary_key(version_id, date), belongs_to(Version))]
@@ -11,3 +14,70 @@ pub struct VersionDownload {
     pub date: NaiveDate,
     pub processed: bool,
 }
+
+/// The downloads of a version on a specific date by a specific type of client
+/// (e.g. `cargo`, `ci` or `browser`).
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug, Clone)]
+#[diesel(
+    table_name = version_downloads_by_client,
+    primary_key(version_id, date, client),
+    belongs_to(Version)
+)]
+pub struct VersionDownloadByClient {
+    pub version_id: i32,
+    pub date: NaiveDate,
+    pub client: String,
+    pub downloads: i32,
+}
+
+/// The downloads of all versions of a crate in a specific month.
+#[derive(QueryableByName, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrateDownloadsMonthly {
+    /// The first day of the month.
+    #[diesel(sql_type = Date)]
+    pub month: NaiveDate,
+    #[diesel(sql_type = BigInt)]
+    pub downloads: i64,
+}
+
+impl CrateDownloadsMonthly {
+    /// Builds a query for the monthly downloads of the given crate between
+    /// the `start` and `end` dates (inclusive).
+    ///
+    /// The archived months are read from the `version_downloads_monthly`
+    /// rollup table, while the not yet archived days are summed up from the
+    /// `version_downloads` table. Since archived days are deleted from the
+    /// `version_downloads` table, the two sources don't overlap.
+    ///
+    /// The `start` date is expected to be the first day of a month.
+    pub fn query(
+        crate_id: i32,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> BoxedSqlQuery<'static, Pg, SqlQuery> {
+        diesel::sql_query(
+            r#"
+                SELECT month, SUM(downloads)::BIGINT AS downloads
+                FROM (
+                    SELECT version_downloads_monthly.month, version_downloads_monthly.downloads
+                    FROM version_downloads_monthly
+                    INNER JOIN versions ON versions.id = version_downloads_monthly.version_id
+                    WHERE versions.crate_id = $1
+                      AND version_downloads_monthly.month BETWEEN $2 AND $3
+                    UNION ALL
+                    SELECT date_trunc('month', version_downloads.date)::DATE, version_downloads.downloads
+                    FROM version_downloads
+                    INNER JOIN versions ON versions.id = version_downloads.version_id
+                    WHERE versions.crate_id = $1
+                      AND version_downloads.date BETWEEN $2 AND $3
+                ) AS downloads
+                GROUP BY month
+                ORDER BY month
+            "#,
+        )
+        .into_boxed()
+        .bind::<Integer, _>(crate_id)
+        .bind::<Date, _>(start)
+        .bind::<Date, _>(end)
+    }
+}
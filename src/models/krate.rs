@@ -20,7 +20,7 @@ use crate::util::errors::{version_not_found, AppResult};
 
 use crate::models::helpers::with_count::*;
 use crate::schema::*;
-use crate::sql::canon_crate_name;
+use crate::sql::{canon_crate_name, escape_like, fuzzy_crate_name, similarity};
 use crate::util::diesel::Conn;
 
 #[derive(Debug, Queryable, Identifiable, Associations, Clone, Copy)]
@@ -80,8 +80,30 @@ pub const ALL_COLUMNS: AllColumns = (
 
 pub const MAX_NAME_LENGTH: usize = 64;
 
+/// The minimum trigram similarity of two crate names to be considered a
+/// fuzzy match.
+///
+/// This is a bit stricter than the `pg_trgm.similarity_threshold` default,
+/// which would also match unrelated names that only share a longer word.
+const FUZZY_NAME_THRESHOLD: f32 = 0.4;
+
+/// The minimum trigram similarity of two crate names for one of them to be
+/// suggested as a replacement for the other.
+///
+/// This is looser than [`FUZZY_NAME_THRESHOLD`], since suggestions are only
+/// made when nothing matched the search query at all.
+const SUGGESTED_NAME_THRESHOLD: f32 = 0.3;
+
 type All = diesel::dsl::Select<crates::table, diesel::dsl::AsSelect<Crate, diesel::pg::Pg>>;
 type WithName<'a> = diesel::dsl::Eq<canon_crate_name<crates::name>, canon_crate_name<&'a str>>;
+type FuzzyMatchesName<'a> = dsl::And<
+    IsSimilar<canon_crate_name<crates::name>, canon_crate_name<&'a str>>,
+    dsl::GtEq<NameSimilarity<'a>, f32>,
+>;
+type NameSimilarity<'a> = similarity<fuzzy_crate_name<crates::name>, fuzzy_crate_name<&'a str>>;
+type NameStartsWith = dsl::Like<canon_crate_name<crates::name>, String>;
+
+diesel::infix_operator!(IsSimilar, " % ");
 
 #[derive(Insertable, AsChangeset, Default, Debug)]
 #[diesel(
@@ -165,6 +187,49 @@ impl Crate {
         }
     }
 
+    /// SQL filter based on whether the trigram similarity of the crate's
+    /// name and the given string is at least [`FUZZY_NAME_THRESHOLD`].
+    ///
+    /// This allows finding crates even if their name was misspelled.
+    pub fn fuzzy_matches_name(name: &str) -> FuzzyMatchesName<'_> {
+        Self::name_similar_to(name, FUZZY_NAME_THRESHOLD)
+    }
+
+    /// SQL filter based on whether the trigram similarity of the crate's
+    /// name and the given string is at least [`SUGGESTED_NAME_THRESHOLD`].
+    ///
+    /// This is used to suggest a crate name if a search query did not match
+    /// any crates, not even by [`Crate::fuzzy_matches_name`].
+    pub fn may_be_suggested_for(name: &str) -> FuzzyMatchesName<'_> {
+        Self::name_similar_to(name, SUGGESTED_NAME_THRESHOLD)
+    }
+
+    /// The candidates are found by the `%` operator on the canonical names,
+    /// which allows using the `index_crates_name_tgrm` index. The similarity
+    /// is then compared on the names normalized by the `fuzzy_crate_name()`
+    /// SQL function.
+    fn name_similar_to(name: &str, threshold: f32) -> FuzzyMatchesName<'_> {
+        IsSimilar::new(canon_crate_name(crates::name), canon_crate_name(name))
+            .and(Self::name_similarity(name).ge(threshold))
+    }
+
+    /// SQL expression for the trigram similarity of the crate's name and
+    /// the given string, ranging from `0` to `1`.
+    pub fn name_similarity(name: &str) -> NameSimilarity<'_> {
+        similarity(fuzzy_crate_name(crates::name), fuzzy_crate_name(name))
+    }
+
+    /// SQL filter based on whether the crate's canonical name starts with
+    /// the canonical form of the given prefix.
+    ///
+    /// The `LIKE` pattern is built on the Rust side, so that the
+    /// `index_crates_name_prefix` index can be used for the lookup.
+    pub fn name_starts_with(prefix: &str) -> NameStartsWith {
+        let prefix = prefix.to_lowercase().replace('-', "_");
+        let pattern = format!("{}%", escape_like(&prefix));
+        canon_crate_name(crates::name).like(pattern)
+    }
+
     /// SQL filter with the = binary operator
     pub fn with_name(name: &str) -> WithName<'_> {
         canon_crate_name(crates::name).eq(canon_crate_name(name))
@@ -377,7 +442,6 @@ impl Crate {
                             // invitation when they visit https://crates.io/me/pending-invites/.
                             let email = OwnerInviteEmail {
                                 user_name: &req_user.gh_login,
-                                domain: &app.emails.domain,
                                 crate_name: &self.name,
                                 token: plaintext_token,
                             };
@@ -533,26 +597,23 @@ impl Crate {
     }
 }
 
-struct OwnerInviteEmail<'a> {
-    user_name: &'a str,
-    domain: &'a str,
-    crate_name: &'a str,
-    token: SecretString,
+pub struct OwnerInviteEmail<'a> {
+    /// The user who sent the invitation.
+    pub user_name: &'a str,
+    pub crate_name: &'a str,
+    pub token: SecretString,
 }
 
 impl Email for OwnerInviteEmail<'_> {
     const SUBJECT: &'static str = "Crate ownership invitation";
+    const TEMPLATE: &'static str = "owner_invite";
 
-    fn body(&self) -> String {
-        format!(
-            "{user_name} has invited you to become an owner of the crate {crate_name}!\n
-Visit https://{domain}/accept-invite/{token} to accept this invitation,
-or go to https://{domain}/me/pending-invites to manage all of your crate ownership invitations.",
-            user_name = self.user_name,
-            domain = self.domain,
-            crate_name = self.crate_name,
-            token = self.token.expose_secret(),
-        )
+    fn context(&self) -> minijinja::Value {
+        minijinja::context! {
+            user_name => self.user_name,
+            crate_name => self.crate_name,
+            token => self.token.expose_secret(),
+        }
     }
 }
 
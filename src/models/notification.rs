@@ -0,0 +1,110 @@
+use diesel::dsl::{exists, not};
+use diesel::prelude::*;
+use std::collections::HashMap;
+
+use crate::schema::{emails, notification_preferences};
+use crate::sql::pg_enum;
+use crate::util::diesel::Conn;
+
+pg_enum! {
+    /// Events that users can individually opt out of receiving notifications
+    /// for.
+    ///
+    /// Emails that are not tied to one of these events (e.g. email address
+    /// confirmations or exposed API token alerts) are always sent.
+    pub enum NotificationEvent {
+        Publish = 0,
+        Yank = 1,
+        OwnershipChange = 2,
+        TokenExpiry = 3,
+        Typosquat = 4,
+        BrokenReadmeLinks = 5,
+        NewReverseDependency = 6,
+    }
+}
+
+impl NotificationEvent {
+    /// Returns whether the user wants to be notified about this event.
+    ///
+    /// Events that the user has not configured yet are enabled by default.
+    pub fn is_enabled_for(self, user_id: i32, conn: &mut impl Conn) -> QueryResult<bool> {
+        notification_preferences::table
+            .find((user_id, self))
+            .select(notification_preferences::enabled)
+            .first(conn)
+            .optional()
+            .map(|enabled| enabled.unwrap_or(true))
+    }
+
+    /// Returns whether notifications about this event should be sent to the
+    /// given email address.
+    ///
+    /// Email addresses that don't belong to any user (e.g. the configured
+    /// typosquatting alert recipients) are always notified.
+    pub fn is_enabled_for_email(self, email: &str, conn: &mut impl Conn) -> QueryResult<bool> {
+        let disabled = notification_preferences::table
+            .inner_join(emails::table.on(emails::user_id.eq(notification_preferences::user_id)))
+            .filter(emails::email.eq(email))
+            .filter(notification_preferences::event.eq(self))
+            .filter(notification_preferences::enabled.eq(false));
+
+        diesel::select(not(exists(disabled))).get_result(conn)
+    }
+
+    /// Returns the notification settings of the user for all events, in the
+    /// order of [`NotificationEvent::VARIANTS`].
+    pub fn settings_for(
+        user_id: i32,
+        conn: &mut impl Conn,
+    ) -> QueryResult<Vec<(NotificationEvent, bool)>> {
+        let stored: HashMap<NotificationEvent, bool> = notification_preferences::table
+            .filter(notification_preferences::user_id.eq(user_id))
+            .select((
+                notification_preferences::event,
+                notification_preferences::enabled,
+            ))
+            .load(conn)?
+            .into_iter()
+            .collect();
+
+        let settings = Self::VARIANTS
+            .iter()
+            .map(|event| (*event, stored.get(event).copied().unwrap_or(true)))
+            .collect();
+
+        Ok(settings)
+    }
+
+    /// Stores the notification settings of the user for the given events.
+    /// Events that are not part of `settings` are left untouched.
+    pub fn update_settings(
+        user_id: i32,
+        settings: &[(NotificationEvent, bool)],
+        conn: &mut impl Conn,
+    ) -> QueryResult<()> {
+        use diesel::pg::upsert::excluded;
+
+        let values = settings
+            .iter()
+            .map(|(event, enabled)| {
+                (
+                    notification_preferences::user_id.eq(user_id),
+                    notification_preferences::event.eq(*event),
+                    notification_preferences::enabled.eq(*enabled),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        diesel::insert_into(notification_preferences::table)
+            .values(&values)
+            .on_conflict((
+                notification_preferences::user_id,
+                notification_preferences::event,
+            ))
+            .do_update()
+            .set(notification_preferences::enabled.eq(excluded(notification_preferences::enabled)))
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
@@ -1,11 +1,12 @@
+use diesel::dsl::{exists, not};
 use diesel::pg::Pg;
 use diesel::prelude::*;
 
 use crate::app::App;
 use crate::util::errors::{bad_request, AppResult};
 
-use crate::models::{Crate, Team, User};
-use crate::schema::crate_owners;
+use crate::models::{Crate, NotificationEvent, Team, User};
+use crate::schema::{crate_owners, emails, notification_preferences};
 use crate::sql::pg_enum;
 use crate::util::diesel::Conn;
 
@@ -37,6 +38,29 @@ impl CrateOwner {
             .filter(crate_owners::owner_kind.eq(kind))
             .into_boxed()
     }
+
+    /// Returns the verified email addresses of all users that own the crate
+    /// and have neither disabled the email notifications for it nor the
+    /// notifications for the given `event`.
+    pub fn notification_emails(
+        crate_id: i32,
+        event: NotificationEvent,
+        conn: &mut impl Conn,
+    ) -> QueryResult<Vec<String>> {
+        let disabled_event = notification_preferences::table
+            .filter(notification_preferences::user_id.eq(crate_owners::owner_id))
+            .filter(notification_preferences::event.eq(event))
+            .filter(notification_preferences::enabled.eq(false));
+
+        Self::by_owner_kind(OwnerKind::User)
+            .filter(crate_owners::crate_id.eq(crate_id))
+            .filter(crate_owners::email_notifications)
+            .filter(not(exists(disabled_event)))
+            .inner_join(emails::table.on(emails::user_id.eq(crate_owners::owner_id)))
+            .filter(emails::verified)
+            .select(emails::email)
+            .load(conn)
+    }
 }
 
 pg_enum! {
@@ -106,7 +106,6 @@ impl<'a> NewUser<'a> {
                     // Swallows any error. Some users might insert an invalid email address here.
                     let email = UserConfirmEmail {
                         user_name: &user.gh_login,
-                        domain: &emails.domain,
                         token,
                     };
                     let _ = emails.send(user_email, email);
@@ -1,6 +1,7 @@
 use std::collections::BTreeMap;
 
 use chrono::NaiveDateTime;
+use crates_io_markdown::TocEntry;
 use derive_builder::Builder;
 use diesel::prelude::*;
 
@@ -54,6 +55,19 @@ impl Version {
             .execute(conn)
     }
 
+    /// Stores the table of contents of the rendered README of a version.
+    ///
+    /// The rendering has to be recorded via `record_readme_rendering()` first.
+    pub fn record_readme_toc(
+        version_id: i32,
+        toc: &[TocEntry],
+        conn: &mut impl Conn,
+    ) -> QueryResult<usize> {
+        diesel::update(readme_renderings::table.find(version_id))
+            .set(readme_renderings::toc.eq(serde_json::json!(toc)))
+            .execute(conn)
+    }
+
     /// Gets the User who ran `cargo publish` for this version, if recorded.
     /// Not for use when you have a group of versions you need the publishers for.
     pub fn published_by(&self, conn: &mut impl Conn) -> Option<User> {
@@ -0,0 +1,160 @@
+use chrono::NaiveDateTime;
+use diesel::dsl::exists;
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use rand::distributions::{Alphanumeric, DistString};
+use rand::rngs::OsRng;
+
+use crate::models::User;
+use crate::schema::{follows, webhook_deliveries, webhooks};
+use crate::sql::pg_enum;
+use crate::util::diesel::Conn;
+use crate::util::rfc3339;
+
+/// The length of the generated webhook secrets.
+const SECRET_LENGTH: usize = 32;
+
+pg_enum! {
+    pub enum WebhookEvent {
+        Publish = 0,
+        Yank = 1,
+        Unyank = 2,
+        OwnerChange = 3,
+    }
+}
+
+impl From<WebhookEvent> for &'static str {
+    fn from(event: WebhookEvent) -> Self {
+        match event {
+            WebhookEvent::Publish => "publish",
+            WebhookEvent::Yank => "yank",
+            WebhookEvent::Unyank => "unyank",
+            WebhookEvent::OwnerChange => "owner_change",
+        }
+    }
+}
+
+/// The model representing a row in the `webhooks` database table.
+#[derive(Debug, Identifiable, Queryable, Selectable, Associations)]
+#[diesel(belongs_to(User))]
+pub struct Webhook {
+    pub id: i32,
+    pub user_id: i32,
+    /// The crate that the webhook is subscribed to, or `None` for all crates
+    /// that the user follows.
+    pub crate_id: Option<i32>,
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<WebhookEvent>,
+    pub created_at: NaiveDateTime,
+}
+
+impl Webhook {
+    /// Registers a new webhook for a user with a newly generated secret.
+    pub fn insert(
+        conn: &mut impl Conn,
+        user_id: i32,
+        crate_id: Option<i32>,
+        url: &str,
+        events: &[WebhookEvent],
+    ) -> QueryResult<Webhook> {
+        let secret = Alphanumeric.sample_string(&mut OsRng, SECRET_LENGTH);
+
+        diesel::insert_into(webhooks::table)
+            .values((
+                webhooks::user_id.eq(user_id),
+                webhooks::crate_id.eq(crate_id),
+                webhooks::url.eq(url),
+                webhooks::secret.eq(secret),
+                webhooks::events.eq(events.to_vec()),
+            ))
+            .returning(Webhook::as_returning())
+            .get_result(conn)
+    }
+
+    /// Returns all webhooks that are subscribed to the `event` of the given
+    /// crate, either directly or because their user follows the crate.
+    pub fn subscribed_to(
+        crate_id: i32,
+        event: WebhookEvent,
+        conn: &mut impl Conn,
+    ) -> QueryResult<Vec<Webhook>> {
+        subscribed_to(crate_id, event)
+            .select(Webhook::as_select())
+            .order(webhooks::id)
+            .load(conn)
+    }
+
+    /// Checks whether any webhooks are subscribed to the `event` of the given
+    /// crate, to avoid enqueueing jobs for the vast majority of crates that
+    /// have no webhooks at all.
+    pub fn any_subscribed_to(
+        crate_id: i32,
+        event: WebhookEvent,
+        conn: &mut impl Conn,
+    ) -> QueryResult<bool> {
+        diesel::select(exists(subscribed_to(crate_id, event))).get_result(conn)
+    }
+}
+
+fn subscribed_to(crate_id: i32, event: WebhookEvent) -> webhooks::BoxedQuery<'static, Pg> {
+    let followed = follows::table
+        .filter(follows::user_id.eq(webhooks::user_id))
+        .filter(follows::crate_id.eq(crate_id));
+
+    webhooks::table
+        .filter(
+            webhooks::crate_id
+                .eq(crate_id)
+                .or(webhooks::crate_id.is_null().and(exists(followed))),
+        )
+        .filter(webhooks::events.contains(vec![event]))
+        .into_boxed()
+}
+
+/// The model representing a row in the `webhook_deliveries` database table.
+#[derive(Debug, Identifiable, Queryable, Selectable, Associations, Serialize)]
+#[diesel(table_name = webhook_deliveries, belongs_to(Webhook))]
+pub struct WebhookDelivery {
+    pub id: i64,
+    #[serde(skip)]
+    pub webhook_id: i32,
+    pub event: WebhookEvent,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    #[serde(with = "rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(with = "rfc3339::option")]
+    pub last_attempt_at: Option<NaiveDateTime>,
+    #[serde(with = "rfc3339::option")]
+    pub delivered_at: Option<NaiveDateTime>,
+}
+
+impl WebhookDelivery {
+    /// The number of deliveries that are kept per webhook.
+    pub const MAX_PER_WEBHOOK: i64 = 100;
+
+    /// Deletes all but the newest [`Self::MAX_PER_WEBHOOK`] deliveries of the
+    /// webhook.
+    pub fn prune(webhook_id: i32, conn: &mut impl Conn) -> QueryResult<usize> {
+        let oldest_kept_id = webhook_deliveries::table
+            .filter(webhook_deliveries::webhook_id.eq(webhook_id))
+            .select(webhook_deliveries::id)
+            .order(webhook_deliveries::id.desc())
+            .offset(Self::MAX_PER_WEBHOOK - 1)
+            .first::<i64>(conn)
+            .optional()?;
+
+        let Some(oldest_kept_id) = oldest_kept_id else {
+            return Ok(0);
+        };
+
+        let outdated = webhook_deliveries::table
+            .filter(webhook_deliveries::webhook_id.eq(webhook_id))
+            .filter(webhook_deliveries::id.lt(oldest_kept_id));
+
+        diesel::delete(outdated).execute(conn)
+    }
+}
@@ -15,6 +15,7 @@ pg_enum! {
         PublishNew = 0,
         PublishUpdate = 1,
         YankUnyank = 2,
+        RenderReadme = 3,
     }
 }
 
@@ -24,6 +25,7 @@ impl LimitedAction {
             LimitedAction::PublishNew => 10 * 60, // 10 minutes
             LimitedAction::PublishUpdate => 60,   // 1 minute
             LimitedAction::YankUnyank => 60,      // 1 minute
+            LimitedAction::RenderReadme => 60,    // 1 minute
         }
     }
 
@@ -32,6 +34,7 @@ impl LimitedAction {
             LimitedAction::PublishNew => 5,
             LimitedAction::PublishUpdate => 30,
             LimitedAction::YankUnyank => 100,
+            LimitedAction::RenderReadme => 10,
         }
     }
 
@@ -40,6 +43,7 @@ impl LimitedAction {
             LimitedAction::PublishNew => "PUBLISH_NEW",
             LimitedAction::PublishUpdate => "PUBLISH_UPDATE",
             LimitedAction::YankUnyank => "YANK_UNYANK",
+            LimitedAction::RenderReadme => "RENDER_README",
         }
     }
 
@@ -54,6 +58,9 @@ impl LimitedAction {
             LimitedAction::YankUnyank => {
                 "You have yanked or unyanked too many versions in a short period of time"
             }
+            LimitedAction::RenderReadme => {
+                "You have requested too many README renderings in a short period of time"
+            }
         }
     }
 }
@@ -50,6 +50,14 @@ pub fn build_axum_router(state: AppState) -> Router<()> {
             "/api/v1/crates/:crate_id/:version/readme",
             get(krate::metadata::readme),
         )
+        .route(
+            "/api/v1/crates/:crate_id/:version/rerender_readme",
+            post(version::readme::rerender_readme),
+        )
+        .route(
+            "/api/v1/crates/:crate_id/:version/changelog",
+            get(version::changelog::changelog),
+        )
         .route(
             "/api/v1/crates/:crate_id/:version/dependencies",
             get(version::metadata::dependencies),
@@ -66,6 +74,14 @@ pub fn build_axum_router(state: AppState) -> Router<()> {
             "/api/v1/crates/:crate_id/downloads",
             get(krate::downloads::downloads),
         )
+        .route(
+            "/api/v1/crates/:crate_id/downloads/release_lines",
+            get(krate::downloads::release_lines),
+        )
+        .route(
+            "/api/v1/crates/:crate_id/downloads/history",
+            get(krate::downloads::history),
+        )
         .route(
             "/api/v1/crates/:crate_id/versions",
             get(krate::versions::versions),
@@ -109,6 +125,12 @@ pub fn build_axum_router(state: AppState) -> Router<()> {
             get(token::show).delete(token::revoke),
         )
         .route("/api/v1/tokens/current", delete(token::revoke_current))
+        .route("/api/v1/me/webhooks", get(webhook::list).put(webhook::new))
+        .route("/api/v1/me/webhooks/:id", delete(webhook::delete))
+        .route(
+            "/api/v1/me/webhooks/:id/deliveries",
+            get(webhook::deliveries),
+        )
         .route(
             "/api/v1/me/crate_owner_invitations",
             get(crate_owner_invitation::list),
@@ -122,8 +144,8 @@ pub fn build_axum_router(state: AppState) -> Router<()> {
             put(crate_owner_invitation::handle_invite_with_token),
         )
         .route(
-            "/api/v1/me/email_notifications",
-            put(user::me::update_email_notifications),
+            "/api/v1/me/notification_settings",
+            get(user::me::notification_settings).put(user::me::update_notification_settings),
         )
         .route("/api/v1/summary", get(summary::summary))
         .route(
@@ -145,6 +167,11 @@ pub fn build_axum_router(state: AppState) -> Router<()> {
             get(user::session::authorize),
         )
         .route("/api/private/session", delete(user::session::logout))
+        // Route used by the frontend for the search input
+        .route(
+            "/api/private/crates/autocomplete",
+            get(krate::autocomplete::autocomplete),
+        )
         // Metrics
         .route("/api/private/metrics/:kind", get(metrics::prometheus))
         // Crate ownership invitations management in the frontend
@@ -167,6 +194,15 @@ pub fn build_axum_router(state: AppState) -> Router<()> {
             "/git/index/*path",
             get(git::http_backend).post(git::http_backend),
         );
+
+        // Previews of the emails that are sent by crates.io, to make it
+        // easier to work on the email templates.
+        router = router
+            .route("/api/private/email_previews", get(email_preview::list))
+            .route(
+                "/api/private/email_previews/:name",
+                get(email_preview::show),
+            );
     }
 
     router
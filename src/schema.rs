@@ -79,6 +79,28 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    /// Aggregated execution metrics of the background worker, exported as service-level metrics.
+    background_job_metrics (job_type) {
+        /// Name of the background job type.
+        job_type -> Text,
+        /// Number of successful job runs.
+        successes -> Int8,
+        /// Number of failed job runs.
+        failures -> Int8,
+        /// Number of job runs that were retries of previously failed runs.
+        retries -> Int8,
+        /// Sum of the queue wait times of all first attempts, in seconds.
+        queue_wait_sum -> Float8,
+        /// Non-cumulative histogram bucket counts of the queue wait times. The last element counts observations above the largest bucket.
+        queue_wait_buckets -> Array<Nullable<Int8>>,
+        /// Sum of the run durations of all job runs, in seconds.
+        duration_sum -> Float8,
+        /// Non-cumulative histogram bucket counts of the run durations. The last element counts observations above the largest bucket.
+        duration_buckets -> Array<Nullable<Int8>>,
+    }
+}
+
 diesel::table! {
     /// Representation of the `background_jobs` table.
     ///
@@ -176,6 +198,20 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    /// Changelogs that were found in the root directory of published crate files. The rendered changelogs are uploaded to the `changelogs/` directory of the storage bucket.
+    changelogs (version_id) {
+        /// Reference to the version that the changelog belongs to.
+        version_id -> Int4,
+        /// File name of the changelog, relative to the package root.
+        path -> Varchar,
+        /// Sections of the rendered changelog, as a JSON array of objects with the `title`, `anchor` and `version` of each section, and the `start` and `end` byte offsets of the section within the uploaded HTML file.
+        sections -> Jsonb,
+        /// Time at which the changelog was last rendered.
+        rendered_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     /// Number of downloads per crate. This was extracted from the `crates` table for performance reasons.
     crate_downloads (crate_id) {
@@ -278,6 +314,32 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    /// Representation of the `crate_quality_scores` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    crate_quality_scores (crate_id) {
+        /// The `crate_id` column of the `crate_quality_scores` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        crate_id -> Int4,
+        /// The `score` column of the `crate_quality_scores` table.
+        ///
+        /// Its SQL type is `Float4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        score -> Float4,
+        /// The `computed_at` column of the `crate_quality_scores` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        computed_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::Tsvector;
@@ -589,6 +651,18 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    /// Per-event notification settings of users. Events without a row in this table are enabled by default.
+    notification_preferences (user_id, event) {
+        /// Reference to the user that the setting belongs to.
+        user_id -> Int4,
+        /// The `NotificationEvent` that the setting applies to (0 = publish, 1 = yank, 2 = ownership change, 3 = token expiry, 4 = typosquat, 5 = broken README links, 6 = new reverse dependency).
+        event -> Int4,
+        /// Whether the user wants to receive notifications for the event.
+        enabled -> Bool,
+    }
+}
+
 diesel::table! {
     /// List of all processed CDN log files, used to avoid processing the same file multiple times.
     processed_log_files (path) {
@@ -596,6 +670,12 @@ diesel::table! {
         path -> Varchar,
         /// Time when the log file was processed
         time -> Timestamptz,
+        /// Number of repeated downloads from the same client IP address that were not counted.
+        discarded_duplicates -> Int4,
+        /// Number of downloads from blocked `User-Agent` headers that were not counted.
+        discarded_blocked_user_agents -> Int4,
+        /// ETag of the log file, used to detect log files that were renamed after they were processed.
+        e_tag -> Nullable<Varchar>,
     }
 }
 
@@ -663,6 +743,18 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    /// Results of the last README link check of each crate. Used to only notify the crate owners if the set of broken links has changed.
+    readme_link_reports (crate_id) {
+        /// Reference to the crate whose README was checked.
+        crate_id -> Int4,
+        /// Sorted URLs of the links that were found to be broken.
+        broken_links -> Array<Text>,
+        /// Time at which the README links were last checked.
+        checked_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     /// Representation of the `readme_renderings` table.
     ///
@@ -680,6 +772,8 @@ diesel::table! {
         ///
         /// (Automatically generated by Diesel.)
         rendered_at -> Timestamp,
+        /// Table of contents of the rendered README, as a JSON array of headings with their `level`, `text` and `anchor`. `NULL` if the README was rendered before the table of contents was extracted.
+        toc -> Nullable<Jsonb>,
     }
 }
 
@@ -847,6 +941,64 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    /// Representation of the `version_downloads_by_client` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    version_downloads_by_client (version_id, date, client) {
+        /// The `version_id` column of the `version_downloads_by_client` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        version_id -> Int4,
+        /// The `date` column of the `version_downloads_by_client` table.
+        ///
+        /// Its SQL type is `Date`.
+        ///
+        /// (Automatically generated by Diesel.)
+        date -> Date,
+        /// The `client` column of the `version_downloads_by_client` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        client -> Text,
+        /// The `downloads` column of the `version_downloads_by_client` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        downloads -> Int4,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `version_downloads_monthly` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    version_downloads_monthly (version_id, month) {
+        /// The `version_id` column of the `version_downloads_monthly` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        version_id -> Int4,
+        /// The `month` column of the `version_downloads_monthly` table.
+        ///
+        /// Its SQL type is `Date`.
+        ///
+        /// (Automatically generated by Diesel.)
+        month -> Date,
+        /// The `downloads` column of the `version_downloads_monthly` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        downloads -> Int8,
+    }
+}
+
 diesel::table! {
     /// Representation of the `version_owner_actions` table.
     ///
@@ -1008,12 +1160,60 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    /// Log of the event payloads that were sent, or will be sent, to webhook endpoints.
+    webhook_deliveries (id) {
+        /// Unique identifier of the delivery. Sent to the webhook endpoint in the `X-Crates-Io-Delivery` header.
+        id -> Int8,
+        /// Reference to the webhook that the payload is delivered to.
+        webhook_id -> Int4,
+        /// The `WebhookEvent` that triggered the delivery.
+        event -> Int4,
+        /// The JSON payload that is sent to the webhook endpoint.
+        payload -> Jsonb,
+        /// Number of delivery attempts so far.
+        attempts -> Int4,
+        /// HTTP status code of the response to the last delivery attempt, if any.
+        status_code -> Nullable<Int4>,
+        /// Description of the error of the last delivery attempt, if it failed.
+        error -> Nullable<Varchar>,
+        /// Time at which the event was triggered.
+        created_at -> Timestamp,
+        /// Time of the last delivery attempt.
+        last_attempt_at -> Nullable<Timestamp>,
+        /// Time at which the payload was successfully delivered.
+        delivered_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    /// Webhook endpoints that users registered to be notified about events of crates.
+    webhooks (id) {
+        /// Unique identifier of the webhook.
+        id -> Int4,
+        /// Reference to the user that registered the webhook.
+        user_id -> Int4,
+        /// Reference to the crate that the webhook is subscribed to. If `NULL`, the webhook is subscribed to all crates that the user follows.
+        crate_id -> Nullable<Int4>,
+        /// URL that the event payloads are sent to.
+        url -> Varchar,
+        /// Secret that is used to sign the event payloads with HMAC-SHA256.
+        secret -> Varchar,
+        /// The `WebhookEvent`s that the webhook is subscribed to (0 = publish, 1 = yank, 2 = unyank, 3 = owner change).
+        events -> Array<Int4>,
+        /// Time at which the webhook was registered.
+        created_at -> Timestamp,
+    }
+}
+
 diesel::joinable!(api_tokens -> users (user_id));
+diesel::joinable!(changelogs -> versions (version_id));
 diesel::joinable!(crate_downloads -> crates (crate_id));
 diesel::joinable!(crate_owner_invitations -> crates (crate_id));
 diesel::joinable!(crate_owners -> crates (crate_id));
 diesel::joinable!(crate_owners -> teams (owner_id));
 diesel::joinable!(crate_owners -> users (owner_id));
+diesel::joinable!(crate_quality_scores -> crates (crate_id));
 diesel::joinable!(crates_categories -> categories (category_id));
 diesel::joinable!(crates_categories -> crates (crate_id));
 diesel::joinable!(crates_keywords -> crates (crate_id));
@@ -1025,25 +1225,35 @@ diesel::joinable!(dependencies -> versions (version_id));
 diesel::joinable!(emails -> users (user_id));
 diesel::joinable!(follows -> crates (crate_id));
 diesel::joinable!(follows -> users (user_id));
+diesel::joinable!(notification_preferences -> users (user_id));
 diesel::joinable!(publish_limit_buckets -> users (user_id));
 diesel::joinable!(publish_rate_overrides -> users (user_id));
+diesel::joinable!(readme_link_reports -> crates (crate_id));
 diesel::joinable!(readme_renderings -> versions (version_id));
 diesel::joinable!(recent_crate_downloads -> crates (crate_id));
 diesel::joinable!(version_downloads -> versions (version_id));
+diesel::joinable!(version_downloads_by_client -> versions (version_id));
+diesel::joinable!(version_downloads_monthly -> versions (version_id));
 diesel::joinable!(version_owner_actions -> api_tokens (api_token_id));
 diesel::joinable!(version_owner_actions -> users (user_id));
 diesel::joinable!(version_owner_actions -> versions (version_id));
 diesel::joinable!(versions -> crates (crate_id));
 diesel::joinable!(versions -> users (published_by));
 diesel::joinable!(versions_published_by -> versions (version_id));
+diesel::joinable!(webhook_deliveries -> webhooks (webhook_id));
+diesel::joinable!(webhooks -> crates (crate_id));
+diesel::joinable!(webhooks -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     api_tokens,
+    background_job_metrics,
     background_jobs,
     categories,
+    changelogs,
     crate_downloads,
     crate_owner_invitations,
     crate_owners,
+    crate_quality_scores,
     crates,
     crates_categories,
     crates_keywords,
@@ -1053,16 +1263,22 @@ diesel::allow_tables_to_appear_in_same_query!(
     follows,
     keywords,
     metadata,
+    notification_preferences,
     processed_log_files,
     publish_limit_buckets,
     publish_rate_overrides,
+    readme_link_reports,
     readme_renderings,
     recent_crate_downloads,
     reserved_crate_names,
     teams,
     users,
     version_downloads,
+    version_downloads_by_client,
+    version_downloads_monthly,
     version_owner_actions,
     versions,
     versions_published_by,
+    webhook_deliveries,
+    webhooks,
 );
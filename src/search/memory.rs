@@ -0,0 +1,79 @@
+use super::{canon_crate_name, CrateDocument, SearchBackend, SearchRequest, SearchResponse};
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+/// An in-memory search backend, used in tests instead of an actual search
+/// engine.
+///
+/// A document matches if each word of the search text is contained in its
+/// name, description or keywords. Exact name matches come first, followed
+/// by the other matches ordered by their number of downloads.
+#[derive(Debug, Default)]
+pub struct InMemorySearch {
+    documents: RwLock<BTreeMap<String, CrateDocument>>,
+}
+
+impl InMemorySearch {
+    /// Returns the document of the given crate, if it was indexed.
+    pub fn document(&self, name: &str) -> Option<CrateDocument> {
+        self.documents.read().get(name).cloned()
+    }
+}
+
+#[async_trait]
+impl SearchBackend for InMemorySearch {
+    async fn search(&self, request: &SearchRequest) -> anyhow::Result<SearchResponse> {
+        let text = canon_crate_name(&request.text);
+        let words = text.split_whitespace().collect::<Vec<_>>();
+
+        let documents = self.documents.read();
+        let mut matches = documents
+            .values()
+            .filter(|document| request.include_yanked || !document.yanked)
+            .filter(|document| {
+                let name = canon_crate_name(&document.name);
+                let description = document.description.as_deref().unwrap_or_default();
+                let description = canon_crate_name(description);
+
+                words.iter().all(|word| {
+                    name.contains(word)
+                        || description.contains(word)
+                        || document
+                            .keywords
+                            .iter()
+                            .any(|keyword| canon_crate_name(keyword) == *word)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        matches.sort_by_key(|document| {
+            let exact_match = canon_crate_name(&document.name) == text;
+            (Reverse(exact_match), Reverse(document.downloads))
+        });
+
+        let names = matches
+            .iter()
+            .skip(request.offset as usize)
+            .take(request.limit as usize)
+            .map(|document| document.name.clone())
+            .collect();
+
+        Ok(SearchResponse {
+            total: matches.len() as i64,
+            names,
+        })
+    }
+
+    async fn upsert(&self, document: &CrateDocument) -> anyhow::Result<()> {
+        let mut documents = self.documents.write();
+        documents.insert(document.name.clone(), document.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, name: &str) -> anyhow::Result<()> {
+        self.documents.write().remove(name);
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+//! External search backends for the crate search
+//!
+//! By default, the crate search is implemented with full-text search queries
+//! against the Postgres database (see `controllers::krate::search`). For large
+//! deployments, the text search can be offloaded to an external search engine
+//! instead, which is kept in sync with the database by the
+//! [`SyncSearchIndex`](crate::worker::jobs::SyncSearchIndex) background job.
+
+mod memory;
+mod opensearch;
+
+pub use self::memory::InMemorySearch;
+pub use self::opensearch::OpenSearch;
+
+use crate::config::SearchBackendConfig;
+use crate::models::Crate;
+use crate::schema::{
+    categories, crate_downloads, crates, crates_categories, crates_keywords, default_versions,
+    keywords, versions,
+};
+use crate::util::diesel::Conn;
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use std::sync::Arc;
+
+/// A search engine that crates can be indexed into and searched in.
+#[async_trait]
+pub trait SearchBackend: Send + Sync {
+    /// Returns the names of the crates matching the request, ordered by
+    /// relevance, together with the total number of matches.
+    async fn search(&self, request: &SearchRequest) -> anyhow::Result<SearchResponse>;
+
+    /// Adds the document to the index, replacing any previous document for
+    /// the same crate.
+    async fn upsert(&self, document: &CrateDocument) -> anyhow::Result<()>;
+
+    /// Removes the document of the given crate from the index, if it exists.
+    async fn delete(&self, name: &str) -> anyhow::Result<()>;
+}
+
+/// Creates the search backend for the given configuration, or `None` if the
+/// built-in database search should be used.
+pub fn from_config(config: &SearchBackendConfig) -> Option<Arc<dyn SearchBackend>> {
+    match config {
+        SearchBackendConfig::Database => None,
+        SearchBackendConfig::OpenSearch { url, index } => {
+            let client = reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()
+                .expect("Couldn't build client");
+
+            Some(Arc::new(OpenSearch::new(
+                client,
+                url.clone(),
+                index.clone(),
+            )))
+        }
+        SearchBackendConfig::Memory => Some(Arc::new(InMemorySearch::default())),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchRequest {
+    /// The free text of the search query.
+    pub text: String,
+    /// Whether to include crates where all versions are yanked.
+    pub include_yanked: bool,
+    pub offset: i64,
+    pub limit: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResponse {
+    /// The total number of matching crates.
+    pub total: i64,
+    /// The names of the crates on the requested page.
+    pub names: Vec<String>,
+}
+
+/// The searchable representation of a crate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateDocument {
+    pub name: String,
+    pub description: Option<String>,
+    pub keywords: Vec<String>,
+    /// The slugs of the categories of the crate.
+    pub categories: Vec<String>,
+    /// The license of the default version.
+    pub license: Option<String>,
+    /// The total number of downloads. Only refreshed periodically for the
+    /// most downloaded crates, see
+    /// [`SyncSearchIndexDownloads`](crate::worker::jobs::SyncSearchIndexDownloads).
+    pub downloads: i64,
+    /// Whether the default version is yanked, which means that all versions
+    /// are yanked.
+    pub yanked: bool,
+    pub updated_at: NaiveDateTime,
+}
+
+impl CrateDocument {
+    /// Loads the document of the given crate from the database, or `None` if
+    /// the crate does not exist (anymore).
+    pub fn load(name: &str, conn: &mut impl Conn) -> QueryResult<Option<Self>> {
+        let Some((crate_id, name, description, updated_at, downloads)) = crates::table
+            .inner_join(crate_downloads::table)
+            .filter(Crate::with_name(name))
+            .select((
+                crates::id,
+                crates::name,
+                crates::description,
+                crates::updated_at,
+                crate_downloads::downloads,
+            ))
+            .first::<(i32, String, Option<String>, NaiveDateTime, i64)>(conn)
+            .optional()?
+        else {
+            return Ok(None);
+        };
+
+        let keywords = crates_keywords::table
+            .inner_join(keywords::table)
+            .filter(crates_keywords::crate_id.eq(crate_id))
+            .select(keywords::keyword)
+            .order(keywords::keyword)
+            .load(conn)?;
+
+        let categories = crates_categories::table
+            .inner_join(categories::table)
+            .filter(crates_categories::crate_id.eq(crate_id))
+            .select(categories::slug)
+            .order(categories::slug)
+            .load(conn)?;
+
+        let (license, yanked) = default_versions::table
+            .inner_join(versions::table)
+            .filter(default_versions::crate_id.eq(crate_id))
+            .select((versions::license, versions::yanked))
+            .first::<(Option<String>, bool)>(conn)
+            .optional()?
+            .unwrap_or((None, true));
+
+        Ok(Some(Self {
+            name,
+            description,
+            keywords,
+            categories,
+            license,
+            downloads,
+            yanked,
+            updated_at,
+        }))
+    }
+}
+
+/// Normalizes a crate name the same way as the `canon_crate_name()` SQL
+/// function does.
+fn canon_crate_name(name: &str) -> String {
+    name.to_lowercase().replace('-', "_")
+}
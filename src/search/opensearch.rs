@@ -0,0 +1,220 @@
+use super::{canon_crate_name, CrateDocument, SearchBackend, SearchRequest, SearchResponse};
+use anyhow::Context;
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde_json::{json, Value};
+
+/// A search backend for OpenSearch or Elasticsearch compatible search
+/// engines.
+///
+/// Each crate is stored as a document in the configured index, using the
+/// crate name as the document ID. The index relies on dynamic mappings, so
+/// it does not need to be created upfront.
+#[derive(Debug)]
+pub struct OpenSearch {
+    client: Client,
+    url: String,
+    index: String,
+}
+
+impl OpenSearch {
+    pub fn new(client: Client, url: String, index: String) -> Self {
+        let url = url.trim_end_matches('/').to_string();
+        Self { client, url, index }
+    }
+
+    /// Crate names only consist of ASCII alphanumeric characters, `-` and
+    /// `_`, so they don't need to be escaped in the URL.
+    fn document_url(&self, name: &str) -> String {
+        format!("{}/{}/_doc/{name}", self.url, self.index)
+    }
+}
+
+#[async_trait]
+impl SearchBackend for OpenSearch {
+    #[instrument(skip(self))]
+    async fn search(&self, request: &SearchRequest) -> anyhow::Result<SearchResponse> {
+        #[derive(Deserialize)]
+        struct Response {
+            hits: Hits,
+        }
+
+        #[derive(Deserialize)]
+        struct Hits {
+            total: Total,
+            hits: Vec<Hit>,
+        }
+
+        #[derive(Deserialize)]
+        struct Total {
+            value: i64,
+        }
+
+        #[derive(Deserialize)]
+        struct Hit {
+            #[serde(rename = "_id")]
+            id: String,
+        }
+
+        let url = format!("{}/{}/_search", self.url, self.index);
+        let response: Response = self
+            .client
+            .post(&url)
+            .json(&search_body(request))
+            .send()
+            .await
+            .context("failed to send search request")?
+            .error_for_status()?
+            .json()
+            .await
+            .context("failed to parse search response")?;
+
+        Ok(SearchResponse {
+            total: response.hits.total.value,
+            names: response.hits.hits.into_iter().map(|hit| hit.id).collect(),
+        })
+    }
+
+    #[instrument(skip_all, fields(krate.name = %document.name))]
+    async fn upsert(&self, document: &CrateDocument) -> anyhow::Result<()> {
+        let mut body = serde_json::to_value(document)?;
+        body["canonical_name"] = canon_crate_name(&document.name).into();
+
+        self.client
+            .put(self.document_url(&document.name))
+            .json(&body)
+            .send()
+            .await
+            .context("failed to send index request")?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn delete(&self, name: &str) -> anyhow::Result<()> {
+        let response = self
+            .client
+            .delete(self.document_url(name))
+            .send()
+            .await
+            .context("failed to send delete request")?;
+
+        if response.status() != StatusCode::NOT_FOUND {
+            response.error_for_status()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds the body of a `_search` request.
+///
+/// The search text is matched against the name, keywords and description of
+/// the crates, with exact name matches being ranked first. Crates with the
+/// same relevance score are ordered by their number of downloads.
+fn search_body(request: &SearchRequest) -> Value {
+    let mut filter = Vec::new();
+    if !request.include_yanked {
+        filter.push(json!({ "term": { "yanked": false } }));
+    }
+
+    json!({
+        "from": request.offset,
+        "size": request.limit,
+        "track_total_hits": true,
+        "_source": false,
+        "query": {
+            "bool": {
+                "must": {
+                    "multi_match": {
+                        "query": request.text,
+                        "fields": ["name^3", "keywords^2", "description"],
+                        "fuzziness": "AUTO",
+                    },
+                },
+                "should": {
+                    "term": {
+                        "canonical_name.keyword": {
+                            "value": canon_crate_name(&request.text),
+                            "boost": 100,
+                        },
+                    },
+                },
+                "filter": filter,
+            },
+        },
+        "sort": ["_score", { "downloads": "desc" }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn test_search_body() {
+        let request = SearchRequest {
+            text: "Serde-JSON".into(),
+            include_yanked: false,
+            offset: 20,
+            limit: 10,
+        };
+
+        assert_json_snapshot!(search_body(&request), @r###"
+        {
+          "_source": false,
+          "from": 20,
+          "query": {
+            "bool": {
+              "filter": [
+                {
+                  "term": {
+                    "yanked": false
+                  }
+                }
+              ],
+              "must": {
+                "multi_match": {
+                  "fields": [
+                    "name^3",
+                    "keywords^2",
+                    "description"
+                  ],
+                  "fuzziness": "AUTO",
+                  "query": "Serde-JSON"
+                }
+              },
+              "should": {
+                "term": {
+                  "canonical_name.keyword": {
+                    "boost": 100,
+                    "value": "serde_json"
+                  }
+                }
+              }
+            }
+          },
+          "size": 10,
+          "sort": [
+            "_score",
+            {
+              "downloads": "desc"
+            }
+          ],
+          "track_total_hits": true
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_document_url() {
+        let client = Client::new();
+        let backend = OpenSearch::new(client, "http://localhost:9200/".into(), "crates".into());
+        assert_eq!(
+            backend.document_url("serde_json"),
+            "http://localhost:9200/crates/_doc/serde_json"
+        );
+    }
+}
@@ -1,4 +1,6 @@
-use diesel::sql_types::{Date, Double, Integer, Interval, SingleValue, Text, Timestamp};
+use diesel::sql_types::{
+    Array, Date, Double, Integer, Interval, Nullable, SingleValue, Text, Timestamp,
+};
 
 mod semver;
 
@@ -6,6 +8,7 @@ pub use semver::SemverVersion;
 
 define_sql_function!(#[aggregate] fn array_agg<T: SingleValue>(x: T) -> Array<T>);
 define_sql_function!(fn canon_crate_name(x: Text) -> Text);
+define_sql_function!(fn fuzzy_crate_name(x: Text) -> Text);
 define_sql_function!(fn to_char(a: Date, b: Text) -> Text);
 define_sql_function!(fn lower(x: Text) -> Text);
 define_sql_function!(fn date_part(x: Text, y: Timestamp) -> Double);
@@ -17,9 +20,24 @@ define_sql_function!(fn floor(x: Double) -> Integer);
 define_sql_function!(fn greatest<T: SingleValue>(x: T, y: T) -> T);
 define_sql_function!(fn least<T: SingleValue>(x: T, y: T) -> T);
 define_sql_function!(fn split_part(string: Text, delimiter: Text, n: Integer) -> Text);
+define_sql_function!(fn similarity(a: Text, b: Text) -> Float);
+define_sql_function!(fn cardinality<T: SingleValue>(x: Nullable<Array<T>>) -> Nullable<Integer>);
+
+/// Escapes the special characters of a `LIKE` pattern.
+pub fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
 
 macro_rules! pg_enum {
     (
+        $(#[$attr:meta])*
         $vis:vis enum $name:ident {
             $($item:ident = $int:expr,)*
         }
@@ -28,6 +46,7 @@ macro_rules! pg_enum {
         #[diesel(sql_type = diesel::sql_types::Integer)]
         #[serde(rename_all = "snake_case")]
         #[repr(i32)]
+        $(#[$attr])*
         $vis enum $name {
             $($item = $int,)*
         }
@@ -57,3 +76,16 @@ macro_rules! pg_enum {
 }
 
 pub(crate) use pg_enum;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!(escape_like("serde"), "serde");
+        assert_eq!(escape_like("serde_json"), "serde\\_json");
+        assert_eq!(escape_like("100%"), "100\\%");
+        assert_eq!(escape_like("a\\b"), "a\\\\b");
+    }
+}
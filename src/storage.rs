@@ -18,6 +18,7 @@ use tokio::io::AsyncWriteExt;
 
 const PREFIX_CRATES: &str = "crates";
 const PREFIX_READMES: &str = "readmes";
+const PREFIX_CHANGELOGS: &str = "changelogs";
 const DEFAULT_REGION: &str = "us-west-1";
 const CONTENT_TYPE_CRATE: &str = "application/gzip";
 const CONTENT_TYPE_GZIP: &str = "application/gzip";
@@ -204,6 +205,13 @@ impl Storage {
         apply_cdn_prefix(&self.cdn_prefix, &readme_path(name, version)).replace('+', "%2B")
     }
 
+    /// Returns the URL of an uploaded crate's version changelog.
+    ///
+    /// The function doesn't check for the existence of the file.
+    pub fn changelog_location(&self, name: &str, version: &str) -> String {
+        apply_cdn_prefix(&self.cdn_prefix, &changelog_path(name, version)).replace('+', "%2B")
+    }
+
     /// Returns the URL of an uploaded RSS feed.
     pub fn feed_url(&self, feed_id: &FeedId) -> String {
         apply_cdn_prefix(&self.cdn_prefix, &feed_id.into()).replace('+', "%2B")
@@ -221,6 +229,12 @@ impl Storage {
         self.delete_all_with_prefix(&prefix).await
     }
 
+    #[instrument(skip(self))]
+    pub async fn delete_all_changelogs(&self, name: &str) -> Result<()> {
+        let prefix = format!("{PREFIX_CHANGELOGS}/{name}").into();
+        self.delete_all_with_prefix(&prefix).await
+    }
+
     #[instrument(skip(self))]
     pub async fn delete_crate_file(&self, name: &str, version: &str) -> Result<()> {
         let path = crate_file_path(name, version);
@@ -233,12 +247,25 @@ impl Storage {
         self.store.delete(&path).await
     }
 
+    #[instrument(skip(self))]
+    pub async fn delete_changelog(&self, name: &str, version: &str) -> Result<()> {
+        let path = changelog_path(name, version);
+        self.store.delete(&path).await
+    }
+
     #[instrument(skip(self))]
     pub async fn delete_feed(&self, feed_id: &FeedId) -> Result<()> {
         let path = feed_id.into();
         self.store.delete(&path).await
     }
 
+    /// Downloads the uploaded archive of a crate version.
+    #[instrument(skip(self))]
+    pub async fn download_crate_file(&self, name: &str, version: &str) -> Result<Bytes> {
+        let path = crate_file_path(name, version);
+        self.store.get(&path).await?.bytes().await
+    }
+
     #[instrument(skip(self, bytes))]
     pub async fn upload_crate_file(&self, name: &str, version: &str, bytes: Bytes) -> Result<()> {
         let path = crate_file_path(name, version);
@@ -263,6 +290,18 @@ impl Storage {
         Ok(())
     }
 
+    #[instrument(skip(self, bytes))]
+    pub async fn upload_changelog(&self, name: &str, version: &str, bytes: Bytes) -> Result<()> {
+        let path = changelog_path(name, version);
+        let attributes = self.attrs([
+            (Attribute::ContentType, CONTENT_TYPE_README),
+            (Attribute::CacheControl, CACHE_CONTROL_README),
+        ]);
+        let opts = attributes.into();
+        self.store.put_opts(&path, bytes.into(), opts).await?;
+        Ok(())
+    }
+
     #[instrument(skip(self, channel))]
     pub async fn upload_feed(
         &self,
@@ -370,6 +409,10 @@ fn readme_path(name: &str, version: &str) -> Path {
     format!("{PREFIX_READMES}/{name}/{name}-{version}.html").into()
 }
 
+fn changelog_path(name: &str, version: &str) -> Path {
+    format!("{PREFIX_CHANGELOGS}/{name}/{name}-{version}.html").into()
+}
+
 fn apply_cdn_prefix(cdn_prefix: &Option<String>, path: &Path) -> String {
     match cdn_prefix {
         Some(cdn_prefix) if !cdn_prefix.starts_with("https://") => {
@@ -461,6 +504,18 @@ mod tests {
         for (name, version, expected) in readme_tests {
             assert_eq!(storage.readme_location(name, version), expected);
         }
+
+        let changelog_tests = vec![
+            ("foo", "1.2.3", "https://static.crates.io/changelogs/foo/foo-1.2.3.html"),
+            (
+                "some-long-crate-name",
+                "42.0.5-beta.1+foo",
+                "https://static.crates.io/changelogs/some-long-crate-name/some-long-crate-name-42.0.5-beta.1%2Bfoo.html",
+            ),
+        ];
+        for (name, version, expected) in changelog_tests {
+            assert_eq!(storage.changelog_location(name, version), expected);
+        }
     }
 
     #[test]
@@ -1,3 +1,4 @@
+use crates_io::models::DependencyKind;
 use crates_io::views::krate_publish as u;
 
 /// A builder for constructing a dependency of another crate.
@@ -7,6 +8,7 @@ pub struct DependencyBuilder {
     features: Vec<String>,
     registry: Option<String>,
     version_req: String,
+    kind: Option<DependencyKind>,
 }
 
 impl DependencyBuilder {
@@ -18,6 +20,7 @@ impl DependencyBuilder {
             features: vec![],
             registry: None,
             version_req: "> 0".to_string(),
+            kind: None,
         }
     }
 
@@ -44,6 +47,12 @@ impl DependencyBuilder {
         self
     }
 
+    /// Set the kind of this dependency, e.g. a dev-dependency.
+    pub fn kind(mut self, kind: DependencyKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
     pub fn add_feature<T: Into<String>>(mut self, feature: T) -> Self {
         self.features.push(feature.into());
         self
@@ -59,7 +68,7 @@ impl DependencyBuilder {
             features: self.features,
             version_req: self.version_req,
             target: None,
-            kind: None,
+            kind: self.kind,
             explicit_name_in_toml: self.explicit_name_in_toml,
             registry: self.registry,
         }
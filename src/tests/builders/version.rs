@@ -21,6 +21,8 @@ pub struct VersionBuilder {
     checksum: String,
     links: Option<String>,
     rust_version: Option<String>,
+    has_lib: Option<bool>,
+    bin_names: Option<Vec<String>>,
 }
 
 #[allow(dead_code)]
@@ -47,6 +49,8 @@ impl VersionBuilder {
             checksum: String::new(),
             links: None,
             rust_version: None,
+            has_lib: None,
+            bin_names: None,
         }
     }
 
@@ -91,6 +95,25 @@ impl VersionBuilder {
         self
     }
 
+    /// Adds a feature to this version.
+    pub fn feature(mut self, name: &str, values: &[&str]) -> Self {
+        let values = values.iter().map(|value| value.to_string()).collect();
+        self.features.insert(name.to_string(), values);
+        self
+    }
+
+    /// Sets the version's `has_lib` value.
+    pub fn has_lib(mut self, has_lib: bool) -> Self {
+        self.has_lib = Some(has_lib);
+        self
+    }
+
+    /// Sets the version's `bin_names` value.
+    pub fn bin_names(mut self, bin_names: &[&str]) -> Self {
+        self.bin_names = Some(bin_names.iter().map(|name| name.to_string()).collect());
+        self
+    }
+
     pub fn build(
         self,
         crate_id: i32,
@@ -99,14 +122,25 @@ impl VersionBuilder {
     ) -> AppResult<Version> {
         use diesel::{insert_into, update};
 
-        let new_version = NewVersion::builder(crate_id, self.num.to_string())
+        let mut builder = NewVersion::builder(crate_id, self.num.to_string());
+        builder
             .features(&self.features)?
             .license(self.license)
             .size(self.size)
             .published_by(published_by)
             .checksum(self.checksum)
             .links(self.links)
-            .rust_version(self.rust_version)
+            .rust_version(self.rust_version);
+
+        if let Some(has_lib) = self.has_lib {
+            builder.has_lib(has_lib);
+        }
+
+        if let Some(bin_names) = self.bin_names {
+            builder.bin_names(bin_names);
+        }
+
+        let new_version = builder
             .build()
             .map_err(|error| internal(error.to_string()))?;
 
@@ -63,6 +63,8 @@ async fn test_dump_db_job() {
         "YYYY-MM-DD-HHMMSS/data/default_versions.csv",
         "YYYY-MM-DD-HHMMSS/data/dependencies.csv",
         "YYYY-MM-DD-HHMMSS/data/version_downloads.csv",
+        "YYYY-MM-DD-HHMMSS/data/version_downloads_by_client.csv",
+        "YYYY-MM-DD-HHMMSS/data/version_downloads_monthly.csv",
     ]
     "###);
 
@@ -95,6 +97,8 @@ async fn test_dump_db_job() {
         "data/default_versions.csv",
         "data/dependencies.csv",
         "data/version_downloads.csv",
+        "data/version_downloads_by_client.csv",
+        "data/version_downloads_monthly.csv",
     ]
     "###);
 }
@@ -6,7 +6,7 @@ use crate::{
     TestApp,
 };
 use crates_io::{
-    models::Crate,
+    models::{Crate, NotificationEvent},
     views::{
         EncodableCrateOwnerInvitationV1, EncodableOwner, EncodablePublicUser, InvitationResponse,
     },
@@ -174,7 +174,7 @@ async fn create_and_add_owner(
 /// a user can still remove their own login as an owner
 #[tokio::test(flavor = "multi_thread")]
 async fn owners_can_remove_self() {
-    let (app, _, user, token) = TestApp::init().with_token();
+    let (app, _, user, token) = TestApp::full().with_token();
     let username = &user.as_model().gh_login;
 
     let krate = app
@@ -216,7 +216,7 @@ async fn owners_can_remove_self() {
 /// Verify consistency when adidng or removing multiple owners in a single request.
 #[tokio::test(flavor = "multi_thread")]
 async fn modify_multiple_owners() {
-    let (app, _, user, token) = TestApp::init().with_token();
+    let (app, _, user, token) = TestApp::full().with_token();
     let username = &user.as_model().gh_login;
 
     let krate =
@@ -487,7 +487,7 @@ async fn invitations_list_does_not_include_expired_invites_v1() {
 /// inserted into the table for the given crate.
 #[tokio::test(flavor = "multi_thread")]
 async fn test_accept_invitation() {
-    let (app, anon, owner, owner_token) = TestApp::init().with_token();
+    let (app, anon, owner, owner_token) = TestApp::full().with_token();
     let owner = owner.as_model();
     let invited_user = app.db_new_user("user_bar");
     let krate = app.db(|conn| CrateBuilder::new("accept_invitation", owner.id).expect_build(conn));
@@ -545,7 +545,7 @@ async fn test_decline_invitation() {
 
 #[tokio::test(flavor = "multi_thread")]
 async fn test_accept_invitation_by_mail() {
-    let (app, anon, owner, owner_token) = TestApp::init().with_token();
+    let (app, anon, owner, owner_token) = TestApp::full().with_token();
     let owner = owner.as_model();
     let invited_user = app.db_new_user("user_bar");
     let _krate = app.db(|conn| CrateBuilder::new("accept_invitation", owner.id).expect_build(conn));
@@ -756,6 +756,66 @@ async fn highest_gh_id_is_most_recent_account_we_know_of() {
     assert_eq!(json.crate_owner_invitations.len(), 1);
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn owners_are_notified_about_ownership_changes() {
+    let (app, _, owner, owner_token) = TestApp::full().with_token();
+    let krate = app.db(|conn| CrateBuilder::new("foo", owner.as_model().id).expect_build(conn));
+
+    let invited_user = app.db_new_user("bar");
+    owner_token.add_named_owner("foo", "bar").await.good();
+    invited_user
+        .accept_ownership_invitation("foo", krate.id)
+        .await;
+    app.run_pending_background_jobs().await;
+
+    owner_token.remove_named_owner("foo", "bar").await.good();
+    app.run_pending_background_jobs().await;
+
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    let subjects = emails
+        .iter()
+        .filter_map(|(_envelope, message)| message.lines().find(|l| l.starts_with("Subject:")))
+        .collect::<Vec<_>>();
+
+    // Both owners are notified about the new owner, but only the remaining
+    // owner is notified about the removal.
+    assert_snapshot!(subjects.join("\n"), @r###"
+    Subject: Crate ownership invitation
+    Subject: The owners of your crate have changed
+    Subject: The owners of your crate have changed
+    Subject: The owners of your crate have changed
+    "###);
+
+    let text = app.emails_snapshot().pop().unwrap();
+    assert!(text.contains("bar has been removed as an owner of your crate foo."));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn ownership_change_notifications_respect_user_settings() {
+    let (app, _, owner, owner_token) = TestApp::full().with_token();
+    let krate = app.db(|conn| CrateBuilder::new("foo", owner.as_model().id).expect_build(conn));
+
+    app.db(|conn| {
+        NotificationEvent::update_settings(
+            owner.as_model().id,
+            &[(NotificationEvent::OwnershipChange, false)],
+            conn,
+        )
+        .unwrap();
+    });
+
+    let invited_user = app.db_new_user("bar");
+    owner_token.add_named_owner("foo", "bar").await.good();
+    invited_user
+        .accept_ownership_invitation("foo", krate.id)
+        .await;
+    app.run_pending_background_jobs().await;
+
+    // Only the invitation and the notification of the new owner are sent.
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    assert_eq!(emails.len(), 2);
+}
+
 fn extract_token_from_invite_email(emails: &Emails) -> String {
     let emails = emails.mails_in_memory().unwrap();
 
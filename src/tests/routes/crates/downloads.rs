@@ -1,7 +1,9 @@
 use crate::builders::{CrateBuilder, VersionBuilder};
 use crate::util::{MockAnonymousUser, RequestHelper, TestApp};
-use chrono::{Duration, Utc};
-use crates_io::schema::{crates, version_downloads, versions};
+use chrono::{Duration, NaiveDate, Utc};
+use crates_io::schema::{
+    crates, version_downloads, version_downloads_by_client, version_downloads_monthly, versions,
+};
 use crates_io::views::EncodableVersionDownload;
 use diesel::prelude::*;
 use http::StatusCode;
@@ -35,6 +37,31 @@ fn save_version_downloads(
         .unwrap();
 }
 
+fn save_version_downloads_by_client(
+    crate_name: &str,
+    version: &str,
+    client: &str,
+    num_downloads: i32,
+    conn: &mut PgConnection,
+) {
+    let version_id = versions::table
+        .select(versions::id)
+        .left_join(crates::table)
+        .filter(crates::name.eq(crate_name))
+        .filter(versions::num.eq(version))
+        .first::<i32>(conn)
+        .unwrap();
+
+    diesel::insert_into(version_downloads_by_client::table)
+        .values((
+            version_downloads_by_client::version_id.eq(version_id),
+            version_downloads_by_client::client.eq(client),
+            version_downloads_by_client::downloads.eq(num_downloads),
+        ))
+        .execute(conn)
+        .unwrap();
+}
+
 pub async fn assert_dl_count(
     anon: &MockAnonymousUser,
     name_and_version: &str,
@@ -156,6 +183,225 @@ async fn test_crate_downloads() {
     assert_eq!(response.json(), json);
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn test_crate_downloads_by_client() {
+    let (app, anon, cookie) = TestApp::init().with_user();
+
+    app.db(|conn| {
+        let user_id = cookie.as_model().id;
+        CrateBuilder::new("foo", user_id)
+            .version("1.0.0")
+            .version("1.1.0")
+            .version("1.2.0")
+            .version("1.3.0")
+            .version("1.4.0")
+            .version("1.5.0")
+            .expect_build(conn);
+    });
+
+    app.db(|conn| {
+        save_version_downloads("foo", "1.0.0", 3, conn);
+        save_version_downloads_by_client("foo", "1.0.0", "cargo", 2, conn);
+        save_version_downloads_by_client("foo", "1.0.0", "browser", 1, conn);
+        save_version_downloads("foo", "1.5.0", 4, conn);
+        save_version_downloads_by_client("foo", "1.5.0", "cargo", 3, conn);
+        save_version_downloads_by_client("foo", "1.5.0", "ci", 1, conn);
+    });
+
+    // The breakdown is only included if explicitly requested
+    let response = anon.get::<()>("/api/v1/crates/foo/downloads").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = response.json();
+    assert!(json.get("version_client_downloads").is_none());
+    assert!(json["meta"].get("extra_client_downloads").is_none());
+
+    let response = anon
+        .get_with_query::<()>("/api/v1/crates/foo/downloads", "include=clients")
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_json_snapshot!(response.json(), {
+        ".version_downloads[].date" => "[date]",
+        ".version_client_downloads[].date" => "[date]",
+        ".meta.extra_downloads[].date" => "[date]",
+        ".meta.extra_client_downloads[].date" => "[date]",
+    });
+
+    let response = anon
+        .get_with_query::<()>("/api/v1/crates/foo/downloads", "include=foo")
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_snapshot!(
+        response.text(),
+        @r###"{"errors":[{"detail":"invalid component for ?include= (expected 'clients')"}]}"###
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_crate_downloads_by_release_line() {
+    let (app, anon, cookie) = TestApp::init().with_user();
+
+    app.db(|conn| {
+        let user_id = cookie.as_model().id;
+        CrateBuilder::new("foo", user_id)
+            .version("0.0.3")
+            .version("0.1.0")
+            .version("0.1.1")
+            .version("0.2.0")
+            .version("1.0.0")
+            .version("1.1.0")
+            .version("2.0.0-beta.1")
+            .expect_build(conn);
+    });
+
+    app.db(|conn| {
+        save_version_downloads("foo", "0.0.3", 1, conn);
+        save_version_downloads("foo", "0.1.0", 2, conn);
+        save_version_downloads("foo", "0.1.1", 3, conn);
+        save_version_downloads("foo", "1.0.0", 4, conn);
+        save_version_downloads("foo", "1.1.0", 5, conn);
+        save_version_downloads("foo", "2.0.0-beta.1", 6, conn);
+    });
+
+    let url = "/api/v1/crates/foo/downloads/release_lines";
+    let response = anon.get::<()>(url).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_json_snapshot!(response.json(), {
+        ".meta.start_date" => "[date]",
+        ".meta.end_date" => "[date]",
+    });
+
+    // Downloads outside of the date range are not counted
+    let before_date = (Utc::now() - Duration::days(1)).date_naive();
+    let query = format!("before_date={before_date}&days=30");
+    let response = anon.get_with_query::<()>(url, &query).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = response.json();
+    let downloads = json["release_lines"].as_array().unwrap().iter();
+    assert!(downloads.map(|line| &line["downloads"]).all(|d| d == 0));
+    let start_date = (Utc::now() - Duration::days(30)).date_naive();
+    assert_eq!(json["meta"]["start_date"], start_date.to_string());
+    assert_eq!(json["meta"]["end_date"], before_date.to_string());
+
+    let response = anon.get_with_query::<()>(url, "days=91").await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_snapshot!(
+        response.text(),
+        @r###"{"errors":[{"detail":"invalid `days` parameter (expected a number between 1 and 90)"}]}"###
+    );
+
+    let response = anon.get_with_query::<()>(url, "before_date=foo").await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_snapshot!(
+        response.text(),
+        @r###"{"errors":[{"detail":"invalid `before_date` parameter (expected YYYY-MM-DD)"}]}"###
+    );
+
+    let response = anon
+        .get::<()>("/api/v1/crates/bar/downloads/release_lines")
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_crate_downloads_history() {
+    let (app, anon, cookie) = TestApp::init().with_user();
+
+    app.db(|conn| {
+        let user_id = cookie.as_model().id;
+        CrateBuilder::new("foo", user_id)
+            .version("1.0.0")
+            .version("1.1.0")
+            .expect_build(conn);
+    });
+
+    app.db(|conn| {
+        let version_ids: Vec<i32> = versions::table
+            .select(versions::id)
+            .order(versions::num)
+            .load(conn)
+            .unwrap();
+
+        let rollups = [
+            (version_ids[0], "2023-11-01", 100_i64),
+            (version_ids[0], "2024-01-01", 200),
+            (version_ids[1], "2024-01-01", 300),
+        ];
+        for (version_id, month, downloads) in rollups {
+            diesel::insert_into(version_downloads_monthly::table)
+                .values((
+                    version_downloads_monthly::version_id.eq(version_id),
+                    version_downloads_monthly::month.eq(month.parse::<NaiveDate>().unwrap()),
+                    version_downloads_monthly::downloads.eq(downloads),
+                ))
+                .execute(conn)
+                .unwrap();
+        }
+
+        // Not yet archived daily downloads are included too
+        diesel::insert_into(version_downloads::table)
+            .values((
+                version_downloads::version_id.eq(version_ids[1]),
+                version_downloads::date.eq("2024-01-31".parse::<NaiveDate>().unwrap()),
+                version_downloads::downloads.eq(5),
+            ))
+            .execute(conn)
+            .unwrap();
+    });
+
+    let url = "/api/v1/crates/foo/downloads/history";
+    let response = anon
+        .get_with_query::<()>(url, "from=2023-11&to=2024-02")
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_json_snapshot!(response.json());
+
+    // The last 12 months are returned by default
+    let response = anon.get::<()>(url).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = response.json();
+    assert_eq!(json["monthly_downloads"].as_array().unwrap().len(), 12);
+
+    let response = anon.get_with_query::<()>(url, "from=2024-13").await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_snapshot!(
+        response.text(),
+        @r###"{"errors":[{"detail":"invalid `from` parameter (expected YYYY-MM)"}]}"###
+    );
+
+    let response = anon
+        .get_with_query::<()>(url, "from=2024-02&to=2024-01")
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_snapshot!(
+        response.text(),
+        @r###"{"errors":[{"detail":"`from` must not be after `to`"}]}"###
+    );
+
+    let response = anon
+        .get_with_query::<()>(url, "from=2000-01&to=2024-01")
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_snapshot!(
+        response.text(),
+        @r###"{"errors":[{"detail":"the requested range must not exceed 120 months"}]}"###
+    );
+
+    // Months at the limits of the supported dates don't cause an overflow
+    let response = anon.get_with_query::<()>(url, "to=%2B262142-12").await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_snapshot!(
+        response.text(),
+        @r###"{"errors":[{"detail":"the requested months are out of range"}]}"###
+    );
+
+    let response = anon.get_with_query::<()>(url, "to=-262143-01").await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_snapshot!(
+        response.text(),
+        @r###"{"errors":[{"detail":"the requested months are out of range"}]}"###
+    );
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_version_downloads() {
     let (app, anon, cookie) = TestApp::init().with_user();
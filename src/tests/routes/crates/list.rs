@@ -684,6 +684,314 @@ async fn loose_search_order() {
     }
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn search_by_features_and_targets() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        CrateBuilder::new("lib_only", user.id)
+            .version(
+                VersionBuilder::new("1.0.0")
+                    .feature("default", &["std"])
+                    .feature("std", &[])
+                    .feature("serde", &["dep:serde"])
+                    .has_lib(true)
+                    .bin_names(&[]),
+            )
+            .expect_build(conn);
+
+        CrateBuilder::new("bin_only", user.id)
+            .version(
+                VersionBuilder::new("1.0.0")
+                    .feature("serde", &[])
+                    .has_lib(false)
+                    .bin_names(&["bin_only"]),
+            )
+            // features of older versions are ignored
+            .version(VersionBuilder::new("0.1.0").feature("std", &[]))
+            .expect_build(conn);
+
+        CrateBuilder::new("lib_and_bin", user.id)
+            .version(
+                VersionBuilder::new("1.0.0")
+                    .has_lib(true)
+                    .bin_names(&["foo", "bar"]),
+            )
+            .expect_build(conn);
+
+        // crates published before `has_lib` and `bin_names` were recorded
+        CrateBuilder::new("legacy", user.id)
+            .version(VersionBuilder::new("1.0.0").feature("std", &[]))
+            .expect_build(conn);
+    });
+
+    for names in search_names(&anon, "feature:serde").await {
+        assert_eq!(names, ["bin_only", "lib_only"]);
+    }
+    for names in search_names(&anon, "feature:std").await {
+        assert_eq!(names, ["legacy", "lib_only"]);
+    }
+    for names in search_names(&anon, "feature:std%20feature:serde").await {
+        assert_eq!(names, ["lib_only"]);
+    }
+    for names in search_names(&anon, "feature:dep:serde").await {
+        assert_eq!(names, Vec::<String>::new());
+    }
+    for names in search_names(&anon, "has:lib").await {
+        assert_eq!(names, ["lib_and_bin", "lib_only"]);
+    }
+    for names in search_names(&anon, "has:bin").await {
+        assert_eq!(names, ["bin_only", "lib_and_bin"]);
+    }
+    for names in search_names(&anon, "has:lib%20has:bin").await {
+        assert_eq!(names, ["lib_and_bin"]);
+    }
+    for names in search_names(&anon, "has:lib%20feature:serde").await {
+        assert_eq!(names, ["lib_only"]);
+    }
+
+    let response = anon
+        .get_with_query::<()>("/api/v1/crates", "q=has:tests")
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.json(),
+        json!({ "errors": [{ "detail": "invalid search term `has:tests` (expected `has:lib` or `has:bin`)" }] })
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fuzzy_search() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        CrateBuilder::new("serde", user.id).expect_build(conn);
+        CrateBuilder::new("serde_json", user.id).expect_build(conn);
+        CrateBuilder::new("reqwest", user.id)
+            .keyword("http")
+            .expect_build(conn);
+        CrateBuilder::new("evalrs", user.id).expect_build(conn);
+    });
+
+    // misspelled crate names are found, ordered by their similarity
+    for json in search_both(&anon, "q=serde-jsno").await {
+        let names = json.crates.iter().map(|c| &c.name).collect::<Vec<_>>();
+        assert_eq!(names, ["serde_json", "serde"]);
+    }
+    for json in search_both(&anon, "q=reqwets").await {
+        let names = json.crates.iter().map(|c| &c.name).collect::<Vec<_>>();
+        assert_eq!(names, ["reqwest"]);
+    }
+
+    // short strings are not matched fuzzily
+    for json in search_both(&anon, "q=tk").await {
+        assert_eq!(json.meta.total, 0);
+    }
+
+    let suggestion = |json: serde_json::Value| json["meta"]["suggestion"].clone();
+
+    // a suggestion is only included if nothing was found
+    let response = anon
+        .get_with_query::<()>("/api/v1/crates", "q=reqwets")
+        .await;
+    assert_eq!(suggestion(response.json()), json!(null));
+
+    // names that are too different to be found by the search are still
+    // suggested
+    let response = anon
+        .get_with_query::<()>("/api/v1/crates", "q=reqwset")
+        .await;
+    assert_eq!(response.json()["meta"]["total"], json!(0));
+    assert_eq!(suggestion(response.json()), json!("reqwest"));
+
+    let response = anon.get_with_query::<()>("/api/v1/crates", "q=hyper").await;
+    assert_eq!(response.json()["meta"]["total"], json!(0));
+    assert_eq!(suggestion(response.json()), json!(null));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn search_facets() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        new_category("Network programming", "network-programming", "")
+            .create_or_update(conn)
+            .unwrap();
+        new_category("Asynchronous", "asynchronous", "")
+            .create_or_update(conn)
+            .unwrap();
+
+        CrateBuilder::new("async_net", user.id)
+            .keyword("async")
+            .keyword("net")
+            .category("network-programming")
+            .category("asynchronous")
+            .version(VersionBuilder::new("1.0.0").license("MIT OR Apache-2.0"))
+            .expect_build(conn);
+
+        CrateBuilder::new("async_fs", user.id)
+            .keyword("async")
+            .category("asynchronous")
+            .version(VersionBuilder::new("1.0.0").license("MIT OR Apache-2.0"))
+            .expect_build(conn);
+
+        CrateBuilder::new("sync_net", user.id)
+            .keyword("net")
+            .category("network-programming")
+            .version(VersionBuilder::new("1.0.0").license("MIT"))
+            .expect_build(conn);
+
+        CrateBuilder::new("no_license", user.id)
+            .version(VersionBuilder::new("1.0.0"))
+            .expect_build(conn);
+    });
+
+    // Facets are only included if requested
+    let response = anon.get_with_query::<()>("/api/v1/crates", "").await;
+    assert_eq!(response.json()["facets"], json!(null));
+
+    let response = anon
+        .get_with_query::<()>("/api/v1/crates", "include_facets=yes&per_page=1")
+        .await;
+    assert_json_snapshot!(response.json()["facets"], @r###"
+    {
+      "approximate": false,
+      "categories": [
+        {
+          "count": 2,
+          "value": "asynchronous"
+        },
+        {
+          "count": 2,
+          "value": "network-programming"
+        }
+      ],
+      "keywords": [
+        {
+          "count": 2,
+          "value": "async"
+        },
+        {
+          "count": 2,
+          "value": "net"
+        }
+      ],
+      "licenses": [
+        {
+          "count": 2,
+          "value": "MIT OR Apache-2.0"
+        },
+        {
+          "count": 1,
+          "value": "MIT"
+        }
+      ]
+    }
+    "###);
+
+    // The counts are computed for the current filter set
+    let response = anon
+        .get_with_query::<()>("/api/v1/crates", "include_facets=yes&q=keyword:net")
+        .await;
+    assert_json_snapshot!(response.json()["facets"], @r###"
+    {
+      "approximate": false,
+      "categories": [
+        {
+          "count": 2,
+          "value": "network-programming"
+        },
+        {
+          "count": 1,
+          "value": "asynchronous"
+        }
+      ],
+      "keywords": [
+        {
+          "count": 2,
+          "value": "net"
+        },
+        {
+          "count": 1,
+          "value": "async"
+        }
+      ],
+      "licenses": [
+        {
+          "count": 1,
+          "value": "MIT"
+        },
+        {
+          "count": 1,
+          "value": "MIT OR Apache-2.0"
+        }
+      ]
+    }
+    "###);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn quality_sort() {
+    use crates_io::schema::crate_quality_scores;
+
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        let scores = [
+            ("foo_quality", Some(0.5_f32)),
+            ("bar_quality", Some(0.9)),
+            ("baz_quality", None),
+            ("other_quality", Some(0.5)),
+        ];
+
+        for (name, score) in scores {
+            let krate = CrateBuilder::new(name, user.id).expect_build(conn);
+            if let Some(score) = score {
+                diesel::insert_into(crate_quality_scores::table)
+                    .values((
+                        crate_quality_scores::crate_id.eq(krate.id),
+                        crate_quality_scores::score.eq(score),
+                    ))
+                    .execute(conn)
+                    .unwrap();
+            }
+        }
+    });
+
+    // Crates with the same score are ordered by descending ID and crates
+    // without a score come last
+    let expected = ["bar_quality", "other_quality", "foo_quality", "baz_quality"];
+
+    for json in search_both(&anon, "sort=quality").await {
+        assert_eq!(json.meta.total, 4);
+        let names = json.crates.iter().map(|c| &c.name).collect::<Vec<_>>();
+        assert_eq!(names, expected);
+    }
+
+    let (resp, calls) = page_with_seek(&anon, "sort=quality").await;
+    let names = resp
+        .iter()
+        .flat_map(|json| &json.crates)
+        .map(|c| &c.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, expected);
+    assert_eq!(calls, 5);
+
+    // the sort can be combined with a search query
+    let (resp, calls) = page_with_seek(&anon, "sort=quality&q=quality").await;
+    let names = resp
+        .iter()
+        .flat_map(|json| &json.crates)
+        .map(|c| &c.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, expected);
+    assert_eq!(calls, 5);
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn index_include_yanked() {
     let (app, anon, user) = TestApp::init().with_user();
@@ -729,6 +1037,134 @@ async fn index_include_yanked() {
     }
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn advanced_search_syntax() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    let updated_at = |date: &str| {
+        chrono::NaiveDate::parse_from_str(date, "%F")
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    };
+
+    app.db(|conn| {
+        new_category("Network programming", "network-programming", "")
+            .create_or_update(conn)
+            .unwrap();
+        new_category("HTTP server", "network-programming::http-server", "")
+            .create_or_update(conn)
+            .unwrap();
+
+        CrateBuilder::new("async_net", user.id)
+            .keyword("async")
+            .keyword("net")
+            .category("network-programming::http-server")
+            .version(
+                VersionBuilder::new("1.0.0")
+                    .license("MIT OR Apache-2.0")
+                    .rust_version("1.70"),
+            )
+            .updated_at(updated_at("2024-03-01"))
+            .expect_build(conn);
+
+        CrateBuilder::new("async_fs", user.id)
+            .keyword("Async")
+            .version(
+                VersionBuilder::new("1.0.0")
+                    .license("Apache-2.0")
+                    .rust_version("1.74.1"),
+            )
+            .updated_at(updated_at("2024-01-01"))
+            .expect_build(conn);
+
+        CrateBuilder::new("legacy_net", user.id)
+            .keyword("net")
+            .category("network-programming")
+            .version(VersionBuilder::new("0.1.0").license("MIT").yanked(true))
+            .updated_at(updated_at("2015-05-15"))
+            .expect_build(conn);
+    });
+
+    for names in search_names(&anon, "keyword:async").await {
+        assert_eq!(names, ["async_fs", "async_net"]);
+    }
+    for names in search_names(&anon, "keyword:async%20keyword:net").await {
+        assert_eq!(names, ["async_net"]);
+    }
+    for names in search_names(&anon, "category:network-programming").await {
+        assert_eq!(names, ["async_net", "legacy_net"]);
+    }
+    // `_` is not a wildcard in category slugs
+    for names in search_names(&anon, "category:network_programming").await {
+        assert!(names.is_empty());
+    }
+    for names in search_names(&anon, "license:mit").await {
+        assert_eq!(names, ["async_net", "legacy_net"]);
+    }
+    for names in search_names(&anon, "license:Apache-2.0%20keyword:net").await {
+        assert_eq!(names, ["async_net"]);
+    }
+    for names in search_names(&anon, "msrv:%3C%3D1.70").await {
+        assert_eq!(names, ["async_net"]);
+    }
+    for names in search_names(&anon, "msrv:%3E1.70.0").await {
+        assert_eq!(names, ["async_fs"]);
+    }
+    for names in search_names(&anon, "msrv:1.70.0").await {
+        assert_eq!(names, ["async_net"]);
+    }
+    for names in search_names(&anon, "updated:%3E2024-01-01").await {
+        assert_eq!(names, ["async_net"]);
+    }
+    for names in search_names(&anon, "updated:%3E%3D2024-01-01").await {
+        assert_eq!(names, ["async_fs", "async_net"]);
+    }
+    for names in search_names(&anon, "updated:2024-01-01").await {
+        assert_eq!(names, ["async_fs"]);
+    }
+    for names in search_names(&anon, "updated:%3C2024-01-01").await {
+        assert_eq!(names, ["legacy_net"]);
+    }
+    for names in search_names(&anon, "keyword:net").await {
+        assert_eq!(names, ["async_net", "legacy_net"]);
+    }
+    for names in search_names(&anon, "keyword:net%20-yanked").await {
+        assert_eq!(names, ["async_net"]);
+    }
+    for names in search_names(&anon, "net%20-yanked%20license:MIT").await {
+        assert_eq!(names, ["async_net"]);
+    }
+
+    let response = anon
+        .get_with_query::<()>("/api/v1/crates", "q=keyword:%20async")
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.json(),
+        json!({ "errors": [{ "detail": "missing value for the `keyword:` search term" }] })
+    );
+
+    let response = anon
+        .get_with_query::<()>("/api/v1/crates", "q=msrv:%3Clatest")
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.json(),
+        json!({ "errors": [{ "detail": "invalid search term `msrv:<latest` (expected a Rust version like `msrv:<=1.70`)" }] })
+    );
+
+    let response = anon
+        .get_with_query::<()>("/api/v1/crates", "q=updated:%3E2024-13-01")
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.json(),
+        json!({ "errors": [{ "detail": "invalid search term `updated:>2024-13-01` (expected a date like `updated:>2024-01-01`)" }] })
+    );
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn yanked_versions_are_not_considered_for_max_version() {
     let (app, anon, user) = TestApp::init().with_user();
@@ -1111,6 +1547,16 @@ async fn search_both<U: RequestHelper>(anon: &U, query: &str) -> [crate::CrateLi
     [offset, seek]
 }
 
+// search with both pagination methods and only return the names of the found crates
+async fn search_names<U: RequestHelper>(anon: &U, q: &str) -> [Vec<String>; 2] {
+    search_both(anon, &format!("sort=alphabetical&q={q}"))
+        .await
+        .map(|json| {
+            assert_eq!(json.meta.total as usize, json.crates.len());
+            json.crates.into_iter().map(|c| c.name).collect()
+        })
+}
+
 async fn search_both_by_user_id<U: RequestHelper>(anon: &U, id: i32) -> [crate::CrateList; 2] {
     let url = format!("user_id={id}");
     search_both(anon, &url).await
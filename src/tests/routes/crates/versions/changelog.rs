@@ -0,0 +1,123 @@
+use crate::builders::PublishBuilder;
+use crate::util::{RequestHelper, TestApp};
+use http::StatusCode;
+use insta::assert_json_snapshot;
+
+const CHANGELOG: &str = "# Changelog\n\n## Unreleased\n\n## [1.1.0] - 2024-08-01\n\n- Added `foo()`\n\n## [1.0.0] - 2024-07-01\n\nInitial release\n";
+
+#[tokio::test(flavor = "multi_thread")]
+async fn changelog() {
+    let (_app, anon, _, token) = TestApp::full().with_token();
+
+    let crate_to_publish = PublishBuilder::new("foo_changes", "1.1.0")
+        .add_file("foo_changes-1.1.0/CHANGELOG.md", CHANGELOG);
+    token.publish_crate(crate_to_publish).await.good();
+
+    let response = anon
+        .get::<()>("/api/v1/crates/foo_changes/1.1.0/changelog")
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_json_snapshot!(response.json(), @r###"
+    {
+      "changelog": {
+        "path": "CHANGELOG.md",
+        "sections": [
+          {
+            "anchor": "user-content-unreleased",
+            "end": 216,
+            "start": 107,
+            "title": "Unreleased",
+            "version": null
+          },
+          {
+            "anchor": "user-content-110---2024-08-01",
+            "end": 393,
+            "start": 217,
+            "title": "[1.1.0] - 2024-08-01",
+            "version": "1.1.0"
+          },
+          {
+            "anchor": "user-content-100---2024-07-01",
+            "end": 548,
+            "start": 394,
+            "title": "[1.0.0] - 2024-07-01",
+            "version": "1.0.0"
+          }
+        ],
+        "url": "https://static.crates.io/changelogs/foo_changes/foo_changes-1.1.0.html"
+      }
+    }
+    "###);
+
+    let response = anon
+        .get::<()>("/api/v1/crates/foo_changes/1.1.0/changelog?since=1.0.0")
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_json_snapshot!(response.json(), @r###"
+    {
+      "changelog": {
+        "path": "CHANGELOG.md",
+        "sections": [
+          {
+            "anchor": "user-content-110---2024-08-01",
+            "end": 393,
+            "start": 217,
+            "title": "[1.1.0] - 2024-08-01",
+            "version": "1.1.0"
+          }
+        ],
+        "url": "https://static.crates.io/changelogs/foo_changes/foo_changes-1.1.0.html"
+      }
+    }
+    "###);
+
+    let response = anon
+        .get::<()>("/api/v1/crates/foo_changes/1.1.0/changelog?since=foo")
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_json_snapshot!(response.json(), @r###"
+    {
+      "errors": [
+        {
+          "detail": "invalid `since` version"
+        }
+      ]
+    }
+    "###);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn no_changelog() {
+    let (_app, anon, _, token) = TestApp::full().with_token();
+
+    let crate_to_publish = PublishBuilder::new("foo_changes", "1.0.0");
+    token.publish_crate(crate_to_publish).await.good();
+
+    let response = anon
+        .get::<()>("/api/v1/crates/foo_changes/1.0.0/changelog")
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_json_snapshot!(response.json(), @r###"
+    {
+      "errors": [
+        {
+          "detail": "version `1.0.0` of crate `foo_changes` does not have a changelog"
+        }
+      ]
+    }
+    "###);
+
+    let response = anon
+        .get::<()>("/api/v1/crates/foo_changes/2.0.0/changelog")
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_json_snapshot!(response.json(), @r###"
+    {
+      "errors": [
+        {
+          "detail": "crate `foo_changes` does not have a version `2.0.0`"
+        }
+      ]
+    }
+    "###);
+}
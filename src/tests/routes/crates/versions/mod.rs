@@ -1,6 +1,9 @@
 mod authors;
+mod changelog;
 pub mod dependencies;
 pub mod download;
 mod list;
 mod read;
+mod readme;
+mod rerender_readme;
 pub mod yank_unyank;
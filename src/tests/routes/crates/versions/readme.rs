@@ -0,0 +1,52 @@
+use crate::builders::PublishBuilder;
+use crate::util::{MockRequestExt, RequestHelper, TestApp};
+use http::{header, StatusCode};
+use insta::assert_json_snapshot;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn readme_with_toc() {
+    let (_app, anon, _, token) = TestApp::full().with_token();
+
+    let readme = "# foo_readme\n\nHello world\n\n## Usage\n\n### `cargo add`\n";
+    let crate_to_publish = PublishBuilder::new("foo_readme", "1.0.0").readme(readme);
+    token.publish_crate(crate_to_publish).await.good();
+
+    let mut request = anon.get_request("/api/v1/crates/foo_readme/1.0.0/readme");
+    request.header(header::ACCEPT, "application/json");
+    let response = anon.run::<()>(request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_json_snapshot!(response.json(), @r###"
+    {
+      "toc": [
+        {
+          "anchor": "user-content-foo_readme",
+          "level": 1,
+          "text": "foo_readme"
+        },
+        {
+          "anchor": "user-content-usage",
+          "level": 2,
+          "text": "Usage"
+        },
+        {
+          "anchor": "user-content-cargo-add",
+          "level": 3,
+          "text": "cargo add"
+        }
+      ],
+      "url": "https://static.crates.io/readmes/foo_readme/foo_readme-1.0.0.html"
+    }
+    "###);
+
+    // Missing versions still return the URL, but no table of contents.
+    let mut request = anon.get_request("/api/v1/crates/foo_readme/2.0.0/readme");
+    request.header(header::ACCEPT, "application/json");
+    let response = anon.run::<()>(request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_json_snapshot!(response.json(), @r###"
+    {
+      "toc": null,
+      "url": "https://static.crates.io/readmes/foo_readme/foo_readme-2.0.0.html"
+    }
+    "###);
+}
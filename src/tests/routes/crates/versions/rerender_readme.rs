@@ -0,0 +1,174 @@
+use crate::builders::PublishBuilder;
+use crate::util::{MockRequestExt, RequestHelper, Response, TestApp};
+use chrono::Utc;
+use crates_io::rate_limiter::LimitedAction;
+use crates_io::schema::publish_limit_buckets;
+use diesel::prelude::*;
+use http::{header, StatusCode};
+use insta::assert_json_snapshot;
+use std::time::Duration;
+
+const README: &str = "# foo\n\n## Usage\n";
+
+async fn rerender(user: &impl RequestHelper, version: &str) -> Response<()> {
+    let url = format!("/api/v1/crates/foo/{version}/rerender_readme");
+    user.run(user.post_request(&url)).await
+}
+
+async fn readme_toc(user: &impl RequestHelper) -> serde_json::Value {
+    let mut request = user.get_request("/api/v1/crates/foo/1.0.0/readme");
+    request.header(header::ACCEPT, "application/json");
+    let response = user.run::<()>(request).await;
+    response.json()["toc"].clone()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rerender_readme() {
+    let (app, anon, _, token) = TestApp::full().with_token();
+
+    // The README in the tarball differs from the one in the publish metadata,
+    // so that we can tell which one was rendered.
+    let crate_to_publish = PublishBuilder::new("foo", "1.0.0")
+        .readme("# old\n")
+        .add_file("foo-1.0.0/README.md", README);
+    token.publish_crate(crate_to_publish).await.good();
+
+    assert_json_snapshot!(readme_toc(&anon).await, @r###"
+    [
+      {
+        "anchor": "user-content-old",
+        "level": 1,
+        "text": "old"
+      }
+    ]
+    "###);
+
+    let response = rerender(&token, "1.0.0").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_json_snapshot!(response.json(), @r###"
+    {
+      "ok": true
+    }
+    "###);
+
+    app.run_pending_background_jobs().await;
+
+    assert_json_snapshot!(readme_toc(&anon).await, @r###"
+    [
+      {
+        "anchor": "user-content-foo",
+        "level": 1,
+        "text": "foo"
+      },
+      {
+        "anchor": "user-content-usage",
+        "level": 2,
+        "text": "Usage"
+      }
+    ]
+    "###);
+}
+
+/// If the tarball doesn't contain a README file, the README that was rendered
+/// from the publish metadata is kept.
+#[tokio::test(flavor = "multi_thread")]
+async fn rerender_readme_without_readme_file() {
+    let (app, anon, _, token) = TestApp::full().with_token();
+
+    let crate_to_publish = PublishBuilder::new("foo", "1.0.0").readme(README);
+    token.publish_crate(crate_to_publish).await.good();
+
+    let response = rerender(&token, "1.0.0").await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    app.run_pending_background_jobs().await;
+
+    assert_json_snapshot!(readme_toc(&anon).await, @r###"
+    [
+      {
+        "anchor": "user-content-foo",
+        "level": 1,
+        "text": "foo"
+      },
+      {
+        "anchor": "user-content-usage",
+        "level": 2,
+        "text": "Usage"
+      }
+    ]
+    "###);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rerender_readme_errors() {
+    let (app, anon, _, token) = TestApp::full().with_token();
+
+    let crate_to_publish = PublishBuilder::new("foo", "1.0.0").readme(README);
+    token.publish_crate(crate_to_publish).await.good();
+
+    let response = rerender(&anon, "1.0.0").await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_json_snapshot!(response.json(), @r###"
+    {
+      "errors": [
+        {
+          "detail": "this action requires authentication"
+        }
+      ]
+    }
+    "###);
+
+    let other_user = app.db_new_user("bar");
+    let response = rerender(&other_user, "1.0.0").await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_json_snapshot!(response.json(), @r###"
+    {
+      "errors": [
+        {
+          "detail": "must already be an owner to re-render a README"
+        }
+      ]
+    }
+    "###);
+
+    let response = rerender(&token, "2.0.0").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_json_snapshot!(response.json(), @r###"
+    {
+      "errors": [
+        {
+          "detail": "crate `foo` does not have a version `2.0.0`"
+        }
+      ]
+    }
+    "###);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rerender_readme_ratelimit_hit() {
+    let (app, _, _, token) = TestApp::full()
+        .with_rate_limit(LimitedAction::RenderReadme, Duration::from_millis(500), 1)
+        .with_token();
+
+    let crate_to_publish =
+        PublishBuilder::new("foo", "1.0.0").add_file("foo-1.0.0/README.md", README);
+    token.publish_crate(crate_to_publish).await.good();
+
+    app.db(|conn| {
+        // Ratelimit bucket should next refill in about a year
+        let far_future = Utc::now().naive_utc() + Duration::from_secs(60 * 60 * 24 * 365);
+        diesel::insert_into(publish_limit_buckets::table)
+            .values((
+                publish_limit_buckets::user_id.eq(token.as_model().user_id),
+                publish_limit_buckets::action.eq(LimitedAction::RenderReadme),
+                publish_limit_buckets::tokens.eq(0),
+                publish_limit_buckets::last_refill.eq(far_future),
+            ))
+            .execute(conn)
+            .expect("Failed to set fake ratelimit")
+    });
+
+    rerender(&token, "1.0.0")
+        .await
+        .assert_rate_limited(LimitedAction::RenderReadme);
+}
@@ -1,4 +1,5 @@
-mod email_notifications;
 pub mod get;
+mod notification_settings;
 pub mod tokens;
 mod updates;
+mod webhooks;
@@ -0,0 +1,318 @@
+use crate::builders::CrateBuilder;
+use crate::new_user;
+use crate::util::{RequestHelper, TestApp};
+use crates_io::schema::{crate_owner_invitations, crate_owners};
+use diesel::prelude::*;
+use http::StatusCode;
+use insta::assert_json_snapshot;
+
+#[derive(Serialize)]
+struct EmailNotificationsUpdate {
+    id: i32,
+    email_notifications: bool,
+}
+
+impl crate::util::MockCookieUser {
+    async fn update_notification_settings(&self, body: serde_json::Value) {
+        let response = self
+            .put::<()>("/api/v1/me/notification_settings", body.to_string())
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.json(), json!({ "ok": true }));
+    }
+
+    async fn update_email_notifications(&self, updates: Vec<EmailNotificationsUpdate>) {
+        self.update_notification_settings(json!({ "crates": updates }))
+            .await;
+    }
+
+    async fn notification_settings(&self) -> serde_json::Value {
+        let response = self.get::<()>("/api/v1/me/notification_settings").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        response.json()
+    }
+}
+
+/// All events should be enabled by default, and the crates owned by the user should be listed
+/// with their `email_notifications` value.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_notification_settings_defaults() {
+    let (app, _, user) = TestApp::init().with_user();
+
+    app.db(|conn| {
+        CrateBuilder::new("test_package", user.as_model().id).expect_build(conn);
+    });
+
+    assert_json_snapshot!(user.notification_settings().await, {
+        ".crates[].id" => "[id]",
+    }, @r###"
+    {
+      "crates": [
+        {
+          "email_notifications": true,
+          "id": "[id]",
+          "name": "test_package"
+        }
+      ],
+      "events": [
+        {
+          "enabled": true,
+          "event": "publish"
+        },
+        {
+          "enabled": true,
+          "event": "yank"
+        },
+        {
+          "enabled": true,
+          "event": "ownership_change"
+        },
+        {
+          "enabled": true,
+          "event": "token_expiry"
+        },
+        {
+          "enabled": true,
+          "event": "typosquat"
+        },
+        {
+          "enabled": true,
+          "event": "broken_readme_links"
+        },
+        {
+          "enabled": true,
+          "event": "new_reverse_dependency"
+        }
+      ]
+    }
+    "###);
+}
+
+/// A user should be able to disable and re-enable notifications for individual events. Events that
+/// are not part of the request should keep their current value.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_update_notification_events() {
+    let (_, _, user) = TestApp::init().with_user();
+
+    user.update_notification_settings(json!({
+        "events": [
+            { "event": "yank", "enabled": false },
+            { "event": "typosquat", "enabled": false },
+        ],
+    }))
+    .await;
+
+    user.update_notification_settings(json!({
+        "events": [{ "event": "typosquat", "enabled": true }],
+    }))
+    .await;
+
+    assert_json_snapshot!(user.notification_settings().await["events"], @r###"
+    [
+      {
+        "enabled": true,
+        "event": "publish"
+      },
+      {
+        "enabled": false,
+        "event": "yank"
+      },
+      {
+        "enabled": true,
+        "event": "ownership_change"
+      },
+      {
+        "enabled": true,
+        "event": "token_expiry"
+      },
+      {
+        "enabled": true,
+        "event": "typosquat"
+      },
+      {
+        "enabled": true,
+        "event": "broken_readme_links"
+      },
+      {
+        "enabled": true,
+        "event": "new_reverse_dependency"
+      }
+    ]
+    "###);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_update_notification_settings_errors() {
+    let (_, anon, user) = TestApp::init().with_user();
+
+    let body = json!({ "events": [{ "event": "yank", "enabled": false }] }).to_string();
+    let response = anon
+        .put::<()>("/api/v1/me/notification_settings", body)
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = anon.get::<()>("/api/v1/me/notification_settings").await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let body = json!({ "events": [{ "event": "unknown", "enabled": false }] }).to_string();
+    let response = user
+        .put::<()>("/api/v1/me/notification_settings", body)
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_json_snapshot!(response.json(), @r###"
+    {
+      "errors": [
+        {
+          "detail": "invalid json request"
+        }
+      ]
+    }
+    "###);
+}
+
+/// Ownership invitations have to be accepted by the invited user, so they are emailed regardless
+/// of the ownership change notification setting.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_owner_invite_ignores_notification_settings() {
+    let (app, _, owner, owner_token) = TestApp::init().with_token();
+    let invited_user = app.db_new_user("user_bar");
+    app.db(|conn| CrateBuilder::new("foo", owner.as_model().id).expect_build(conn));
+
+    invited_user
+        .update_notification_settings(json!({
+            "events": [{ "event": "ownership_change", "enabled": false }],
+        }))
+        .await;
+
+    owner_token.add_named_owner("foo", "user_bar").await.good();
+
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    assert_eq!(emails.len(), 1);
+
+    let invitations: i64 = app.db(|conn| {
+        crate_owner_invitations::table
+            .count()
+            .get_result(conn)
+            .unwrap()
+    });
+    assert_eq!(invitations, 1);
+}
+
+/// A user should be able to update the email notifications for crates they own. Only the crates that
+/// were sent in the request should be updated to the corresponding `email_notifications` value.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_update_email_notifications() {
+    let (app, _, user) = TestApp::init().with_user();
+
+    let my_crates = app.db(|conn| {
+        vec![
+            CrateBuilder::new("test_package", user.as_model().id).expect_build(conn),
+            CrateBuilder::new("another_package", user.as_model().id).expect_build(conn),
+        ]
+    });
+
+    let a_id = my_crates.first().unwrap().id;
+    let b_id = my_crates.get(1).unwrap().id;
+
+    // Update crate_a: email_notifications = false
+    // crate_a should be false, crate_b should be true
+    user.update_email_notifications(vec![EmailNotificationsUpdate {
+        id: a_id,
+        email_notifications: false,
+    }])
+    .await;
+    let json = user.show_me().await;
+
+    assert!(
+        !json
+            .owned_crates
+            .iter()
+            .find(|c| c.id == a_id)
+            .unwrap()
+            .email_notifications
+    );
+    assert!(
+        json.owned_crates
+            .iter()
+            .find(|c| c.id == b_id)
+            .unwrap()
+            .email_notifications
+    );
+
+    // Update crate_b: email_notifications = false
+    // Both should be false now
+    user.update_email_notifications(vec![EmailNotificationsUpdate {
+        id: b_id,
+        email_notifications: false,
+    }])
+    .await;
+    let json = user.show_me().await;
+
+    assert!(
+        !json
+            .owned_crates
+            .iter()
+            .find(|c| c.id == a_id)
+            .unwrap()
+            .email_notifications
+    );
+    assert!(
+        !json
+            .owned_crates
+            .iter()
+            .find(|c| c.id == b_id)
+            .unwrap()
+            .email_notifications
+    );
+
+    // Update crate_a and crate_b: email_notifications = true
+    // Both should be true
+    user.update_email_notifications(vec![
+        EmailNotificationsUpdate {
+            id: a_id,
+            email_notifications: true,
+        },
+        EmailNotificationsUpdate {
+            id: b_id,
+            email_notifications: true,
+        },
+    ])
+    .await;
+    let json = user.show_me().await;
+
+    json.owned_crates.iter().for_each(|c| {
+        assert!(c.email_notifications);
+    })
+}
+
+/// A user should not be able to update the `email_notifications` value for a crate that is not
+/// owned by them.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_update_email_notifications_not_owned() {
+    let (app, _, user) = TestApp::init().with_user();
+
+    let not_my_crate = app.db(|conn| {
+        let u = new_user("arbitrary_username")
+            .create_or_update(None, &app.as_inner().emails, conn)
+            .unwrap();
+        CrateBuilder::new("test_package", u.id).expect_build(conn)
+    });
+
+    user.update_email_notifications(vec![EmailNotificationsUpdate {
+        id: not_my_crate.id,
+        email_notifications: false,
+    }])
+    .await;
+
+    let email_notifications: bool = app
+        .db(|conn| {
+            crate_owners::table
+                .select(crate_owners::email_notifications)
+                .filter(crate_owners::crate_id.eq(not_my_crate.id))
+                .first(conn)
+        })
+        .unwrap();
+
+    // There should be no change to the `email_notifications` value for a crate not belonging to me
+    assert!(email_notifications);
+}
@@ -0,0 +1,228 @@
+use crate::builders::CrateBuilder;
+use crate::util::{MockCookieUser, RequestHelper, Response, TestApp};
+use http::StatusCode;
+use insta::assert_json_snapshot;
+
+async fn create_webhook(user: &MockCookieUser, webhook: serde_json::Value) -> Response<()> {
+    let body = json!({ "webhook": webhook }).to_string();
+    user.put("/api/v1/me/webhooks", body).await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_list_and_delete_webhooks() {
+    let (app, _, user) = TestApp::init().with_user();
+    app.db(|conn| CrateBuilder::new("foo", user.as_model().id).expect_build(conn));
+
+    let response = create_webhook(
+        &user,
+        json!({ "url": "https://example.com/hook", "crate": "foo", "events": ["publish", "yank", "publish"] }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let json = response.json();
+    let secret = json["webhook"]["secret"].as_str().unwrap();
+    assert_eq!(secret.len(), 32);
+    assert_json_snapshot!(json, {
+        ".webhook.id" => "[id]",
+        ".webhook.created_at" => "[datetime]",
+        ".webhook.secret" => "[secret]",
+    }, @r###"
+    {
+      "webhook": {
+        "crate": "foo",
+        "created_at": "[datetime]",
+        "events": [
+          "publish",
+          "yank"
+        ],
+        "id": "[id]",
+        "secret": "[secret]",
+        "url": "https://example.com/hook"
+      }
+    }
+    "###);
+
+    let response = create_webhook(
+        &user,
+        json!({ "url": "https://example.com/followed", "events": ["owner_change"] }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = user.get::<()>("/api/v1/me/webhooks").await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let json = response.json();
+    let id = json["webhooks"][0]["id"].as_i64().unwrap();
+    assert_json_snapshot!(json, {
+        ".webhooks[].id" => "[id]",
+        ".webhooks[].created_at" => "[datetime]",
+    }, @r###"
+    {
+      "webhooks": [
+        {
+          "crate": "foo",
+          "created_at": "[datetime]",
+          "events": [
+            "publish",
+            "yank"
+          ],
+          "id": "[id]",
+          "url": "https://example.com/hook"
+        },
+        {
+          "crate": null,
+          "created_at": "[datetime]",
+          "events": [
+            "owner_change"
+          ],
+          "id": "[id]",
+          "url": "https://example.com/followed"
+        }
+      ]
+    }
+    "###);
+
+    let response = user
+        .delete::<()>(&format!("/api/v1/me/webhooks/{id}"))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.json(), json!({ "ok": true }));
+
+    let response = user.get::<()>("/api/v1/me/webhooks").await;
+    assert_eq!(response.json()["webhooks"].as_array().unwrap().len(), 1);
+
+    let response = user
+        .delete::<()>(&format!("/api/v1/me/webhooks/{id}"))
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_webhook_errors() {
+    let (_, anon, user) = TestApp::init().with_user();
+
+    let body = json!({ "webhook": { "url": "https://example.com/hook", "events": ["publish"] } });
+    let response = anon
+        .put::<()>("/api/v1/me/webhooks", body.to_string())
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Anonymous users can't probe the DNS resolution of the server
+    let body =
+        json!({ "webhook": { "url": "https://hooks.internal/hook", "events": ["publish"] } });
+    let response = anon
+        .put::<()>("/api/v1/me/webhooks", body.to_string())
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = create_webhook(
+        &user,
+        json!({ "url": "http://example.com/hook", "events": ["publish"] }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_json_snapshot!(response.json(), @r###"
+    {
+      "errors": [
+        {
+          "detail": "webhook URL must be a valid `https` URL"
+        }
+      ]
+    }
+    "###);
+
+    let response = create_webhook(
+        &user,
+        json!({ "url": "https://hooks.internal/hook", "events": ["publish"] }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_json_snapshot!(response.json(), @r###"
+    {
+      "errors": [
+        {
+          "detail": "webhook URL must resolve to a public IP address"
+        }
+      ]
+    }
+    "###);
+
+    let response = create_webhook(
+        &user,
+        json!({ "url": "https://127.0.0.1/hook", "events": ["publish"] }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = create_webhook(
+        &user,
+        json!({ "url": "https://[fd00::1]:8443/hook", "events": ["publish"] }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = create_webhook(
+        &user,
+        json!({ "url": "https://example.com/hook", "events": [] }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_json_snapshot!(response.json(), @r###"
+    {
+      "errors": [
+        {
+          "detail": "webhook must be subscribed to at least one event"
+        }
+      ]
+    }
+    "###);
+
+    let response = create_webhook(
+        &user,
+        json!({ "url": "https://example.com/hook", "events": ["download"] }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = create_webhook(
+        &user,
+        json!({ "url": "https://example.com/hook", "crate": "missing", "events": ["publish"] }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_json_snapshot!(response.json(), @r###"
+    {
+      "errors": [
+        {
+          "detail": "crate `missing` does not exist"
+        }
+      ]
+    }
+    "###);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn webhooks_of_other_users_are_not_accessible() {
+    let (app, _, user) = TestApp::init().with_user();
+    let other_user = app.db_new_user("bar");
+
+    let response = create_webhook(
+        &user,
+        json!({ "url": "https://example.com/hook", "events": ["publish"] }),
+    )
+    .await;
+    let id = response.json()["webhook"]["id"].as_i64().unwrap();
+
+    let url = format!("/api/v1/me/webhooks/{id}/deliveries");
+    let response = other_user.get::<()>(&url).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let url = format!("/api/v1/me/webhooks/{id}");
+    let response = other_user.delete::<()>(&url).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = other_user.get::<()>("/api/v1/me/webhooks").await;
+    assert_eq!(response.json(), json!({ "webhooks": [] }));
+}
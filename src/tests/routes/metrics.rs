@@ -1,5 +1,7 @@
 use crate::util::{MockAnonymousUser, MockRequestExt, Response};
 use crate::{RequestHelper, TestApp};
+use crates_io::schema::background_job_metrics;
+use diesel::prelude::*;
 use http::StatusCode;
 
 #[tokio::test(flavor = "multi_thread")]
@@ -18,6 +20,48 @@ async fn metrics_endpoint_works() {
     assert_eq!(resp.status(), StatusCode::NOT_FOUND);
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn service_metrics_include_background_jobs() {
+    let (app, anon) = TestApp::init()
+        .with_config(|config| config.metrics_authorization_token = Some("foobar".into()))
+        .empty();
+
+    app.db(|conn| {
+        diesel::insert_into(background_job_metrics::table)
+            .values((
+                background_job_metrics::job_type.eq("render_readme"),
+                background_job_metrics::successes.eq(3),
+                background_job_metrics::failures.eq(1),
+                background_job_metrics::retries.eq(1),
+                background_job_metrics::duration_sum.eq(2.5),
+                background_job_metrics::duration_buckets.eq(vec![Some(0), Some(2), Some(2)]),
+            ))
+            .execute(conn)
+            .unwrap();
+    });
+
+    let resp = request_metrics(&anon, "service", Some("foobar")).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let text = resp.text();
+    let expected_lines = [
+        r#"cratesio_service_background_job_runs_total{job="render_readme",outcome="success"} 3"#,
+        r#"cratesio_service_background_job_runs_total{job="render_readme",outcome="failure"} 1"#,
+        r#"cratesio_service_background_job_retries_total{job="render_readme"} 1"#,
+        r#"cratesio_service_background_job_duration_seconds_bucket{job="render_readme",le="0.05"} 2"#,
+        r#"cratesio_service_background_job_duration_seconds_bucket{job="render_readme",le="0.1"} 4"#,
+        r#"cratesio_service_background_job_duration_seconds_sum{job="render_readme"} 2.5"#,
+        r#"cratesio_service_background_job_duration_seconds_count{job="render_readme"} 4"#,
+        r#"cratesio_service_background_job_queue_wait_seconds_count{job="render_readme"} 0"#,
+    ];
+    for line in expected_lines {
+        assert!(
+            text.lines().any(|l| l == line),
+            "missing `{line}` in:\n{text}"
+        );
+    }
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn metrics_endpoint_wrong_auth() {
     let (_, anon) = TestApp::init()
@@ -0,0 +1,143 @@
+use crate::builders::CrateBuilder;
+use crate::util::{RequestHelper, Response, TestApp};
+use http::StatusCode;
+use insta::assert_json_snapshot;
+use serde_json::json;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn autocomplete() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        CrateBuilder::new("serde", user.id)
+            .downloads(1000)
+            .expect_build(conn);
+        CrateBuilder::new("serde_json", user.id)
+            .downloads(500)
+            .expect_build(conn);
+        CrateBuilder::new("serde-yaml", user.id)
+            .downloads(800)
+            .expect_build(conn);
+        CrateBuilder::new("serdex", user.id)
+            .downloads(800)
+            .expect_build(conn);
+        CrateBuilder::new("tokio", user.id)
+            .downloads(2000)
+            .expect_build(conn);
+    });
+
+    let names = |json: serde_json::Value| {
+        json["crates"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|krate| krate["name"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let response = get_autocomplete(&anon, "q=serde").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_json_snapshot!(response.json(), @r###"
+    {
+      "crates": [
+        {
+          "default_version": "0.99.0",
+          "name": "serde"
+        },
+        {
+          "default_version": "0.99.0",
+          "name": "serde-yaml"
+        },
+        {
+          "default_version": "0.99.0",
+          "name": "serdex"
+        },
+        {
+          "default_version": "0.99.0",
+          "name": "serde_json"
+        }
+      ]
+    }
+    "###);
+
+    // `-` and `_` are equivalent and the lookup is case-insensitive
+    let response = get_autocomplete(&anon, "q=SERDE-").await;
+    assert_eq!(names(response.json()), ["serde-yaml", "serde_json"]);
+
+    // `_` is not treated as a wildcard
+    let response = get_autocomplete(&anon, "q=serde_y").await;
+    assert_eq!(names(response.json()), ["serde-yaml"]);
+    let response = get_autocomplete(&anon, "q=serde_").await;
+    assert_eq!(names(response.json()), ["serde-yaml", "serde_json"]);
+
+    let response = get_autocomplete(&anon, "q=serde&per_page=2").await;
+    assert_eq!(names(response.json()), ["serde", "serde-yaml"]);
+
+    // Empty prefixes and prefixes that are not valid crate names don't
+    // match anything
+    for query in ["q=", "q=%25", "q=serde%20json", "per_page=5"] {
+        let response = get_autocomplete(&anon, query).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.json(), json!({ "crates": [] }));
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn autocomplete_is_cached() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_cached", user.id).expect_build(conn);
+    });
+
+    let response = get_autocomplete(&anon, "q=foo").await;
+    assert_eq!(response.json()["crates"].as_array().unwrap().len(), 1);
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_new", user.id).expect_build(conn);
+    });
+
+    // The cached response is returned for the same prefix
+    let response = get_autocomplete(&anon, "q=foo").await;
+    assert_eq!(response.json()["crates"].as_array().unwrap().len(), 1);
+
+    // ... even if it is written differently
+    let response = get_autocomplete(&anon, "q=FOO").await;
+    assert_eq!(response.json()["crates"].as_array().unwrap().len(), 1);
+
+    // Other prefixes are looked up separately
+    let response = get_autocomplete(&anon, "q=foo_").await;
+    assert_eq!(response.json()["crates"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn autocomplete_per_page_limit() {
+    let (_, anon) = TestApp::init().empty();
+
+    for query in ["q=foo&per_page=0", "q=foo&per_page=26", "q=foo&per_page=a"] {
+        let response = get_autocomplete(&anon, query).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let detail = "invalid `per_page` parameter (expected a number between 1 and 25)";
+        assert_eq!(response.json(), json!({ "errors": [{ "detail": detail }] }));
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn crate_named_autocomplete_is_not_shadowed() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        CrateBuilder::new("autocomplete", user.id).expect_build(conn);
+    });
+
+    let json = anon.show_crate("autocomplete").await;
+    assert_eq!(json.krate.name, "autocomplete");
+}
+
+async fn get_autocomplete<U: RequestHelper>(anon: &U, query: &str) -> Response<()> {
+    anon.get_with_query("/api/private/crates/autocomplete", query)
+        .await
+}
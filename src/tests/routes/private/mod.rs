@@ -1 +1,2 @@
 mod crate_owner_invitations;
+mod crates;
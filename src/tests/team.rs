@@ -104,7 +104,7 @@ async fn add_nonexistent_team() {
 /// Test adding a renamed team
 #[tokio::test(flavor = "multi_thread")]
 async fn add_renamed_team() {
-    let (app, anon) = TestApp::init().empty();
+    let (app, anon) = TestApp::full().empty();
     let user = app.db_new_user("user-all-teams");
     let token = user.db_new_token("arbitrary token name");
     let owner_id = user.as_model().id;
@@ -142,7 +142,7 @@ async fn add_renamed_team() {
 /// Test adding team names with mixed case, when on the team
 #[tokio::test(flavor = "multi_thread")]
 async fn add_team_mixed_case() {
-    let (app, anon) = TestApp::init().empty();
+    let (app, anon) = TestApp::full().empty();
     let user = app.db_new_user("user-all-teams");
     let token = user.db_new_token("arbitrary token name");
 
@@ -170,7 +170,7 @@ async fn add_team_mixed_case() {
 
 #[tokio::test(flavor = "multi_thread")]
 async fn add_team_as_org_owner() {
-    let (app, anon) = TestApp::init().empty();
+    let (app, anon) = TestApp::full().empty();
     let user = app.db_new_user("user-org-owner");
     let token = user.db_new_token("arbitrary token name");
 
@@ -261,7 +261,7 @@ async fn remove_team_as_named_owner() {
 
 #[tokio::test(flavor = "multi_thread")]
 async fn remove_team_as_team_owner() {
-    let (app, _) = TestApp::init().empty();
+    let (app, _) = TestApp::full().empty();
     let user_on_both_teams = app.db_new_user("user-all-teams");
     let token_on_both_teams = user_on_both_teams.db_new_token("arbitrary token name");
 
@@ -301,7 +301,7 @@ async fn remove_team_as_team_owner() {
 
 #[tokio::test(flavor = "multi_thread")]
 async fn remove_nonexistent_team() {
-    let (app, _, user, token) = TestApp::init().with_token();
+    let (app, _, user, token) = TestApp::full().with_token();
 
     app.db(|conn| {
         CrateBuilder::new("foo_remove_nonexistent", user.as_model().id).expect_build(conn);
@@ -404,7 +404,7 @@ async fn publish_owned() {
 /// Test trying to change owners (when only on an owning team)
 #[tokio::test(flavor = "multi_thread")]
 async fn add_owners_as_org_owner() {
-    let (app, _) = TestApp::init().empty();
+    let (app, _) = TestApp::full().empty();
     let user_on_both_teams = app.db_new_user("user-all-teams");
     let token_on_both_teams = user_on_both_teams.db_new_token("arbitrary token name");
 
@@ -432,7 +432,7 @@ async fn add_owners_as_org_owner() {
 
 #[tokio::test(flavor = "multi_thread")]
 async fn add_owners_as_team_owner() {
-    let (app, _) = TestApp::init().empty();
+    let (app, _) = TestApp::full().empty();
     let user_on_both_teams = app.db_new_user("user-all-teams");
     let token_on_both_teams = user_on_both_teams.db_new_token("arbitrary token name");
 
@@ -3,12 +3,16 @@ use crate::util::chaosproxy::ChaosProxy;
 use crate::util::github::{MockGitHubClient, MOCK_GITHUB_DATA};
 use crates_io::config::{
     self, Base, CdnLogQueueConfig, CdnLogStorageConfig, DatabasePools, DbPoolConfig,
+    SearchBackendConfig,
 };
+use crates_io::dns::PublicResolver;
+use crates_io::link_checker::{LinkChecker, MockLinkChecker};
 use crates_io::middleware::cargo_compat::StatusCodeConfig;
 use crates_io::models::token::{CrateScope, EndpointScope};
 use crates_io::rate_limiter::{LimitedAction, RateLimiterConfig};
 use crates_io::storage::StorageConfig;
 use crates_io::team_repo::MockTeamRepo;
+use crates_io::webhooks::{MockWebhookClient, WebhookClient};
 use crates_io::worker::{Environment, RunnerExt};
 use crates_io::{App, Emails, Env};
 use crates_io_index::testing::UpstreamIndex;
@@ -18,7 +22,10 @@ use crates_io_worker::Runner;
 use diesel::PgConnection;
 use futures_util::TryStreamExt;
 use oauth2::{ClientId, ClientSecret};
+use regex::Regex;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
 use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::{rc::Rc, sync::Arc, time::Duration};
 use tokio::runtime::Handle;
 use tokio::task::block_in_place;
@@ -46,19 +53,30 @@ impl Drop for TestAppInner {
             return;
         }
 
-        // Lazily run any remaining jobs
+        let mut conn = self.test_database.connect();
+
+        // Lazily run any remaining jobs, including the ones that they enqueue
+        // for queues whose workers have already shut down
         if let Some(runner) = &self.runner {
-            block_in_place(move || {
-                Handle::current().block_on(async {
-                    let handle = runner.start();
-                    handle.wait_for_shutdown().await;
-                })
-            });
+            loop {
+                block_in_place(|| {
+                    Handle::current().block_on(async {
+                        let mut handle = runner.start();
+                        handle.wait_for_shutdown().await;
+                    })
+                });
+
+                // Failed jobs are retried later, so don't wait for them here
+                let pending_jobs = background_jobs::table.filter(background_jobs::retries.eq(0));
+                let pending_jobs: i64 = pending_jobs.count().get_result(&mut conn).unwrap();
+                if pending_jobs == 0 {
+                    break;
+                }
+            }
         }
 
         // Manually verify that all jobs have completed successfully
         // This will catch any tests that enqueued a job but forgot to initialize the runner
-        let mut conn = self.test_database.connect();
         let job_count: i64 = background_jobs::table
             .count()
             .get_result(&mut conn)
@@ -96,6 +114,8 @@ impl TestApp {
             build_job_runner: false,
             use_chaos_proxy: false,
             team_repo: MockTeamRepo::new(),
+            link_checker: None,
+            webhook_client: None,
         }
     }
 
@@ -166,15 +186,54 @@ impl TestApp {
             .collect()
     }
 
+    /// Obtain the raw messages of all emails that were sent so far, with the
+    /// headers and multipart boundaries that change between test runs
+    /// replaced, so that they can be used in snapshots.
+    pub fn emails_snapshot(&self) -> Vec<String> {
+        let header_regex = Regex::new(r"(Message-ID|Date): [^\r\n]+\r\n").unwrap();
+        let boundary_regex = Regex::new(r#"boundary="([^"]+)""#).unwrap();
+
+        let emails = self.as_inner().emails.mails_in_memory().unwrap();
+        emails
+            .into_iter()
+            .map(|(_envelope, email)| {
+                let email = header_regex.replace_all(&email, "");
+                match boundary_regex.captures(&email) {
+                    Some(captures) => email.replace(&captures[1], "[boundary]"),
+                    None => email.into_owned(),
+                }
+            })
+            .collect()
+    }
+
     pub async fn run_pending_background_jobs(&self) {
         let runner = &self.0.runner;
         let runner = runner.as_ref().expect("Index has not been initialized");
 
-        let handle = runner.start();
-        handle.wait_for_shutdown().await;
+        // Jobs can enqueue jobs on other queues, whose workers might have
+        // already shut down, so keep going until the queue is empty.
+        loop {
+            let mut handle = runner.start();
+            handle.wait_for_shutdown().await;
+
+            let result = runner.check_for_failed_jobs().await;
+            result.expect("Could not determine if jobs failed");
 
-        let result = runner.check_for_failed_jobs().await;
-        result.expect("Could not determine if jobs failed");
+            if self.pending_job_count() == 0 {
+                break;
+            }
+        }
+    }
+
+    fn pending_job_count(&self) -> i64 {
+        use crates_io::schema::background_jobs;
+        use diesel::prelude::*;
+
+        let mut conn = self.0.test_database.connect();
+        background_jobs::table
+            .count()
+            .get_result(&mut conn)
+            .unwrap()
     }
 
     /// Obtain a reference to the inner `App` value
@@ -208,6 +267,8 @@ pub struct TestAppBuilder {
     build_job_runner: bool,
     use_chaos_proxy: bool,
     team_repo: MockTeamRepo,
+    link_checker: Option<MockLinkChecker>,
+    webhook_client: Option<MockWebhookClient>,
 }
 
 impl TestAppBuilder {
@@ -265,6 +326,14 @@ impl TestAppBuilder {
                 credentials: Credentials::Missing,
             };
 
+            let link_checker = self
+                .link_checker
+                .map(|link_checker| Box::new(link_checker) as Box<dyn LinkChecker + Send + Sync>);
+
+            let webhook_client = self.webhook_client.map(|webhook_client| {
+                Box::new(webhook_client) as Box<dyn WebhookClient + Send + Sync>
+            });
+
             let environment = Environment::builder()
                 .config(app.config.clone())
                 .repository_config(repository_config)
@@ -272,6 +341,9 @@ impl TestAppBuilder {
                 .deadpool(app.primary_database.clone())
                 .emails(app.emails.clone())
                 .team_repo(Box::new(self.team_repo))
+                .search_backend(app.search_backend.clone())
+                .link_checker(link_checker)
+                .webhook_client(webhook_client)
                 .build()
                 .unwrap();
 
@@ -359,6 +431,16 @@ impl TestAppBuilder {
         self
     }
 
+    pub fn with_link_checker(mut self, link_checker: MockLinkChecker) -> Self {
+        self.link_checker = Some(link_checker);
+        self
+    }
+
+    pub fn with_webhook_client(mut self, webhook_client: MockWebhookClient) -> Self {
+        self.webhook_client = Some(webhook_client);
+        self
+    }
+
     pub fn with_replica(mut self) -> Self {
         let primary = &self.config.db.primary;
 
@@ -406,6 +488,8 @@ fn simple_config() -> config::Server {
         storage,
         cdn_log_queue: CdnLogQueueConfig::Mock,
         cdn_log_storage: CdnLogStorageConfig::memory(),
+        cdn_log_filter: Default::default(),
+        search_backend: SearchBackendConfig::Database,
         session_key: cookie::Key::derive_from("test this has to be over 32 bytes long".as_bytes()),
         gh_client_id: ClientId::new(dotenvy::var("GH_CLIENT_ID").unwrap_or_default()),
         gh_client_secret: ClientSecret::new(dotenvy::var("GH_CLIENT_SECRET").unwrap_or_default()),
@@ -430,7 +514,13 @@ fn simple_config() -> config::Server {
         blocked_routes: HashSet::new(),
         version_id_cache_size: 10000,
         version_id_cache_ttl: Duration::from_secs(5 * 60),
+        autocomplete_cache_size: 1000,
+        autocomplete_cache_ttl: Duration::from_secs(5 * 60),
+        readme_syntax_highlighting: false,
+        readme_image_proxy: None,
+        readme_link_checks: false,
         cdn_user_agent: "Amazon CloudFront".to_string(),
+        worker_shutdown_timeout: Duration::from_secs(5),
 
         // The middleware has its own unit tests to verify its functionality.
         // Here, we can test what would happen if we toggled the status code
@@ -453,9 +543,28 @@ fn build_app(config: config::Server) -> (Arc<App>, axum::Router) {
     // organizations without actually having to create GitHub accounts.
     let github = Box::new(MockGitHubClient::new(&MOCK_GITHUB_DATA));
 
-    let app = App::new(config, emails, github);
+    let mut app = App::new(config, emails, github);
+
+    // Use a mock DNS resolver, so that the tests don't depend on the network.
+    app.dns_resolver = PublicResolver::new(Arc::new(MockResolver));
 
     let app = Arc::new(app);
     let router = crates_io::build_handler(Arc::clone(&app));
     (app, router)
 }
+
+/// Resolves host names ending with `.internal` to a private IP address, and
+/// all other host names to a public one.
+struct MockResolver;
+
+impl Resolve for MockResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let ip = match name.as_str().ends_with(".internal") {
+            true => Ipv4Addr::new(10, 0, 0, 1),
+            false => Ipv4Addr::new(93, 184, 215, 14),
+        };
+
+        let addr = SocketAddr::new(IpAddr::V4(ip), 0);
+        Box::pin(async move { Ok(Box::new(std::iter::once(addr)) as Addrs) })
+    }
+}
@@ -1,3 +1,8 @@
 mod git;
+mod readme_links;
+mod reverse_dependency_notifications;
 mod rss;
+mod search_index;
 mod sync_admins;
+mod version_notifications;
+mod webhooks;
@@ -0,0 +1,154 @@
+use crate::builders::PublishBuilder;
+use crate::util::{RequestHelper, TestApp};
+use crates_io::link_checker::MockLinkChecker;
+use insta::assert_snapshot;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn broken_links_are_reported_to_owners() {
+    let mut link_checker = MockLinkChecker::new();
+    link_checker
+        .expect_check()
+        .times(3)
+        .returning(|url| url.contains("broken").then(|| "404 Not Found".to_string()));
+
+    let (app, _, _, token) = TestApp::full().with_link_checker(link_checker).with_token();
+
+    let readme = "# foo\n\n[docs](https://docs.rs/foo), [broken](https://example.com/broken), [also broken](https://example.com/broken?page=2), [usage](#usage) and [docs again](https://docs.rs/foo)\n";
+    let crate_to_publish = PublishBuilder::new("foo", "1.0.0").readme(readme);
+    token.publish_crate(crate_to_publish).await.good();
+
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    let emails = emails
+        .iter()
+        .filter(|(_, email)| email.contains("Subject: Broken links"))
+        .collect::<Vec<_>>();
+    assert_eq!(emails.len(), 1);
+
+    let (envelope, _) = emails[0];
+    assert_eq!(envelope.to()[0].to_string(), "something@example.com");
+
+    let emails = app.emails_snapshot();
+    let email = emails
+        .iter()
+        .find(|email| email.contains("Subject: Broken links"))
+        .unwrap();
+    assert_snapshot!(email, @r###"
+    To: something@example.com
+    From: noreply@crates.io
+    Subject: Broken links in the README of your crate
+    MIME-Version: 1.0
+    Content-Type: multipart/alternative;
+     boundary="[boundary]"
+
+    --[boundary]
+    Content-Type: text/plain; charset=utf-8
+    Content-Transfer-Encoding: quoted-printable
+
+    The README of version 1.0.0 of your crate foo contains links that appear to=
+     be broken:
+
+    - https://example.com/broken (404 Not Found)
+    - https://example.com/broken?page=3D2 (404 Not Found)
+
+    Visit https://crates.io/crates/foo/1.0.0 to see the README.
+
+    You are receiving this email because you have email notifications enabled f=
+    or foo. You can disable them in your account settings.
+
+    --[boundary]
+    Content-Type: text/html; charset=utf-8
+    Content-Transfer-Encoding: quoted-printable
+
+    <!DOCTYPE html>
+    <html lang=3D"en">
+    <head>
+      <meta charset=3D"utf-8">
+      <meta name=3D"viewport" content=3D"width=3Ddevice-width, initial-scale=3D=
+    1">
+      <title>Broken links in the README of your crate</title>
+    </head>
+    <body style=3D"margin: 0; padding: 0; background-color: #f9f7ec;">
+      <div style=3D"max-width: 600px; margin: 0 auto; padding: 24px; font-famil=
+    y: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Helvetica, Arial,=
+     sans-serif; font-size: 16px; line-height: 1.5; color: #383838;">
+        <p style=3D"margin: 0 0 16px; font-size: 20px; font-weight: bold;">
+          <a href=3D"https://crates.io" style=3D"color: #383838; text-decoratio=
+    n: none;">crates.io</a>
+        </p>
+        <div style=3D"padding: 24px; background-color: #ffffff; border-radius: =
+    8px;">
+          <p style=3D"margin: 0;">The README of version <strong>1.0.0</strong> =
+    of your crate <strong>foo</strong> contains links that appear to be broken:=
+    </p>
+          <ul style=3D"margin: 8px 0 0;">
+            <li><a href=3D"https:&#x2f;&#x2f;example.com&#x2f;broken">https:&#x=
+    2f;&#x2f;example.com&#x2f;broken</a> (404 Not Found)</li>
+            <li><a href=3D"https:&#x2f;&#x2f;example.com&#x2f;broken?page=3D2">=
+    https:&#x2f;&#x2f;example.com&#x2f;broken?page=3D2</a> (404 Not Found)</li>
+          </ul>
+          <p style=3D"margin: 24px 0;">
+            <a href=3D"https:&#x2f;&#x2f;crates.io&#x2f;crates&#x2f;foo&#x2f;1.=
+    0.0" style=3D"display: inline-block; padding: 10px 20px; background-color: =
+    #3b6837; border-radius: 4px; color: #ffffff; font-weight: bold; text-decora=
+    tion: none;">View the README</a>
+          </p>
+        </div>
+        <div style=3D"margin-top: 16px; font-size: 13px; color: #6b6b6b;">
+          <p style=3D"margin: 0;">You are receiving this email because you have=
+     email notifications enabled for foo. You can disable them in your <a href=
+    =3D"https://crates.io/settings/email-notifications" style=3D"color: #6b6b6b=
+    ;">account settings</a>.</p>
+        </div>
+      </div>
+    </body>
+    </html>
+    --[boundary]--
+    "###);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn links_are_not_checked_without_link_checker() {
+    let (app, _, _, token) = TestApp::full().with_token();
+
+    let readme = "[broken](https://example.com/broken)\n";
+    let crate_to_publish = PublishBuilder::new("foo", "1.0.0").readme(readme);
+    token.publish_crate(crate_to_publish).await.good();
+
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    assert!(!emails
+        .iter()
+        .any(|(_, email)| email.contains("Subject: Broken links")));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn unchanged_broken_links_are_only_reported_once() {
+    let mut link_checker = MockLinkChecker::new();
+    link_checker
+        .expect_check()
+        .returning(|url| url.contains("broken").then(|| "404 Not Found".to_string()));
+
+    let (app, _, _, token) = TestApp::full().with_link_checker(link_checker).with_token();
+
+    let count_emails = || {
+        let emails = app.as_inner().emails.mails_in_memory().unwrap();
+        emails
+            .iter()
+            .filter(|(_, email)| email.contains("Subject: Broken links"))
+            .count()
+    };
+
+    let readme = "[broken](https://example.com/broken)\n";
+    let crate_to_publish = PublishBuilder::new("foo", "1.0.0").readme(readme);
+    token.publish_crate(crate_to_publish).await.good();
+    assert_eq!(count_emails(), 1);
+
+    let crate_to_publish = PublishBuilder::new("foo", "1.0.1").readme(readme);
+    token.publish_crate(crate_to_publish).await.good();
+    assert_eq!(count_emails(), 1);
+
+    let readme =
+        "[broken](https://example.com/broken) [also broken](https://example.com/broken?page=2)\n";
+    let crate_to_publish = PublishBuilder::new("foo", "1.0.2").readme(readme);
+    token.publish_crate(crate_to_publish).await.good();
+    assert_eq!(count_emails(), 2);
+}
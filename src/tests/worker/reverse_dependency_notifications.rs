@@ -0,0 +1,86 @@
+use crate::builders::{DependencyBuilder, PublishBuilder};
+use crate::util::{RequestHelper, TestApp};
+use crates_io::models::{DependencyKind, NotificationEvent};
+use insta::assert_snapshot;
+
+fn subjects(app: &TestApp) -> String {
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    emails
+        .iter()
+        .filter_map(|(_envelope, message)| message.lines().find(|l| l.starts_with("Subject:")))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn owners_are_notified_about_new_reverse_dependencies() {
+    let (app, _, _, token) = TestApp::full().with_token();
+    token
+        .publish_crate(PublishBuilder::new("foo", "1.0.0"))
+        .await
+        .good();
+
+    let other_user = app.db_new_user("bar");
+    let other_token = other_user.db_new_token("bar");
+    let crate_to_publish =
+        PublishBuilder::new("bar", "1.0.0").dependency(DependencyBuilder::new("foo"));
+    other_token.publish_crate(crate_to_publish).await.good();
+
+    assert_snapshot!(subjects(&app), @r###"
+    Subject: A new version of your crate has been published
+    Subject: A new version of your crate has been published
+    Subject: A new crate depends on your crate
+    "###);
+
+    let text = app.emails_snapshot().pop().unwrap();
+    assert!(text.contains("Version 1.0.0 of the new crate bar depends on your crate foo."));
+
+    // New versions of existing crates don't send notifications
+    let crate_to_publish =
+        PublishBuilder::new("bar", "1.1.0").dependency(DependencyBuilder::new("foo"));
+    other_token.publish_crate(crate_to_publish).await.good();
+
+    // Owners are not notified about dependencies on their own crates
+    let crate_to_publish =
+        PublishBuilder::new("foo-derive", "1.0.0").dependency(DependencyBuilder::new("foo"));
+    token.publish_crate(crate_to_publish).await.good();
+
+    // Dev-dependencies don't send notifications
+    let crate_to_publish = PublishBuilder::new("baz", "1.0.0")
+        .dependency(DependencyBuilder::new("foo").kind(DependencyKind::Dev));
+    other_token.publish_crate(crate_to_publish).await.good();
+
+    assert_snapshot!(subjects(&app), @r###"
+    Subject: A new version of your crate has been published
+    Subject: A new version of your crate has been published
+    Subject: A new crate depends on your crate
+    Subject: A new version of your crate has been published
+    Subject: A new version of your crate has been published
+    Subject: A new version of your crate has been published
+    "###);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reverse_dependency_notifications_respect_user_settings() {
+    let (app, _, user, token) = TestApp::full().with_token();
+    token
+        .publish_crate(PublishBuilder::new("foo", "1.0.0"))
+        .await
+        .good();
+
+    app.db(|conn| {
+        let settings = [(NotificationEvent::NewReverseDependency, false)];
+        NotificationEvent::update_settings(user.as_model().id, &settings, conn).unwrap();
+    });
+
+    let other_user = app.db_new_user("bar");
+    let other_token = other_user.db_new_token("bar");
+    let crate_to_publish =
+        PublishBuilder::new("bar", "1.0.0").dependency(DependencyBuilder::new("foo"));
+    other_token.publish_crate(crate_to_publish).await.good();
+
+    assert_snapshot!(subjects(&app), @r###"
+    Subject: A new version of your crate has been published
+    Subject: A new version of your crate has been published
+    "###);
+}
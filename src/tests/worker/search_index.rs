@@ -0,0 +1,130 @@
+use crate::builders::{CrateBuilder, PublishBuilder};
+use crate::routes::crates::versions::yank_unyank::YankRequestHelper;
+use crate::util::{RequestHelper, TestApp};
+use crates_io::config::SearchBackendConfig;
+use crates_io::schema::{crates, version_downloads, versions};
+use crates_io::worker::jobs::UpdateDownloads;
+use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn text_search_uses_search_backend() {
+    let (app, anon, _, token) = TestApp::full()
+        .with_config(|config| config.search_backend = SearchBackendConfig::Memory)
+        .with_token();
+
+    let user_id = token.as_model().user_id;
+
+    let names = |json: crate::CrateList| {
+        let names = json.crates.into_iter().map(|c| c.name).collect::<Vec<_>>();
+        (names, json.meta.total)
+    };
+
+    // Publishing a crate adds it to the search index
+    let pb = PublishBuilder::new("foo_search", "1.0.0").keyword("testing");
+    token.publish_crate(pb).await.good();
+    let pb = PublishBuilder::new("bar_search", "1.0.0").description("Also for testing");
+    token.publish_crate(pb).await.good();
+
+    // Crates that were not indexed are not found by text searches
+    app.db(|conn| {
+        CrateBuilder::new("baz_search", user_id).expect_build(conn);
+    });
+
+    let json = anon.search("q=testing").await;
+    assert_eq!(
+        names(json),
+        (vec!["bar_search".into(), "foo_search".into()], 2)
+    );
+
+    let json = anon.search("q=foo_search").await;
+    assert_eq!(names(json), (vec!["foo_search".into()], 1));
+    let json = anon.search("q=baz_search").await;
+    assert_eq!(names(json), (vec![], 0));
+
+    // Other searches still use the database
+    let json = anon.search("q=search&sort=alpha").await;
+    let expected = vec![
+        "bar_search".into(),
+        "baz_search".into(),
+        "foo_search".into(),
+    ];
+    assert_eq!(names(json), (expected, 3));
+
+    // Pagination works with page numbers
+    let json = anon.search("q=testing&per_page=1").await;
+    assert_eq!(
+        json.meta.next_page.as_deref(),
+        Some("?q=testing&per_page=1&page=2")
+    );
+    assert_eq!(json.meta.prev_page, None);
+    assert_eq!(names(json), (vec!["bar_search".into()], 2));
+
+    let json = anon.search("q=testing&per_page=1&page=2").await;
+    assert_eq!(json.meta.next_page, None);
+    assert_eq!(
+        json.meta.prev_page.as_deref(),
+        Some("?q=testing&per_page=1&page=1")
+    );
+    assert_eq!(names(json), (vec!["foo_search".into()], 2));
+
+    // Yanking a crate updates the search index
+    token.yank("foo_search", "1.0.0").await.good();
+
+    let json = anon.search("q=testing&include_yanked=no").await;
+    assert_eq!(names(json), (vec!["bar_search".into()], 1));
+
+    token.unyank("foo_search", "1.0.0").await.good();
+
+    let json = anon.search("q=testing&include_yanked=no").await;
+    assert_eq!(
+        names(json),
+        (vec!["bar_search".into(), "foo_search".into()], 2)
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn download_counts_are_synced_after_updating_downloads() {
+    let (app, anon, _, token) = TestApp::full()
+        .with_config(|config| config.search_backend = SearchBackendConfig::Memory)
+        .with_token();
+
+    let names = |json: crate::CrateList| {
+        json.crates
+            .into_iter()
+            .map(|c| c.name)
+            .collect::<Vec<String>>()
+    };
+
+    let pb = PublishBuilder::new("foo_search", "1.0.0").keyword("testing");
+    token.publish_crate(pb).await.good();
+    let pb = PublishBuilder::new("bar_search", "1.0.0").keyword("testing");
+    token.publish_crate(pb).await.good();
+
+    let json = anon.search("q=testing").await;
+    assert_eq!(names(json), vec!["bar_search", "foo_search"]);
+
+    app.db(|conn| {
+        let version_id: i32 = versions::table
+            .inner_join(crates::table)
+            .filter(crates::name.eq("foo_search"))
+            .select(versions::id)
+            .first(conn)
+            .unwrap();
+
+        diesel::insert_into(version_downloads::table)
+            .values((
+                version_downloads::version_id.eq(version_id),
+                version_downloads::downloads.eq(100),
+            ))
+            .execute(conn)
+            .unwrap();
+
+        UpdateDownloads.enqueue(conn).unwrap();
+    });
+
+    app.run_pending_background_jobs().await;
+
+    let json = anon.search("q=testing").await;
+    assert_eq!(names(json), vec!["foo_search", "bar_search"]);
+}
@@ -0,0 +1,197 @@
+use crate::builders::PublishBuilder;
+use crate::routes::crates::versions::yank_unyank::YankRequestHelper;
+use crate::util::{RequestHelper, TestApp};
+use crates_io::models::NotificationEvent;
+use crates_io::schema::notification_preferences;
+use diesel::prelude::*;
+use insta::assert_snapshot;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn owners_are_notified_about_publishes_and_yanks() {
+    let (app, _, _, token) = TestApp::full().with_token();
+
+    let crate_to_publish = PublishBuilder::new("foo", "1.0.0");
+    token.publish_crate(crate_to_publish).await.good();
+
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    assert_eq!(emails.len(), 1);
+
+    let (envelope, _) = &emails[0];
+    assert_eq!(envelope.to()[0].to_string(), "something@example.com");
+    assert_snapshot!(app.emails_snapshot()[0], @r###"
+    To: something@example.com
+    From: noreply@crates.io
+    Subject: A new version of your crate has been published
+    MIME-Version: 1.0
+    Content-Type: multipart/alternative;
+     boundary="[boundary]"
+
+    --[boundary]
+    Content-Type: text/plain; charset=utf-8
+    Content-Transfer-Encoding: quoted-printable
+
+    Version 1.0.0 of your crate foo has been published by foo using the API tok=
+    en "bar".
+
+    Visit https://crates.io/crates/foo/1.0.0 for more details.
+
+    If you did not expect this, please check the API tokens of all owners of th=
+    e crate and revoke any that might have been leaked, then contact help@crate=
+    s.io.
+
+    You are receiving this email because you have email notifications enabled f=
+    or foo. You can disable them in your account settings.
+
+    --[boundary]
+    Content-Type: text/html; charset=utf-8
+    Content-Transfer-Encoding: quoted-printable
+
+    <!DOCTYPE html>
+    <html lang=3D"en">
+    <head>
+      <meta charset=3D"utf-8">
+      <meta name=3D"viewport" content=3D"width=3Ddevice-width, initial-scale=3D=
+    1">
+      <title>A new version of your crate has been published</title>
+    </head>
+    <body style=3D"margin: 0; padding: 0; background-color: #f9f7ec;">
+      <div style=3D"max-width: 600px; margin: 0 auto; padding: 24px; font-famil=
+    y: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Helvetica, Arial,=
+     sans-serif; font-size: 16px; line-height: 1.5; color: #383838;">
+        <p style=3D"margin: 0 0 16px; font-size: 20px; font-weight: bold;">
+          <a href=3D"https://crates.io" style=3D"color: #383838; text-decoratio=
+    n: none;">crates.io</a>
+        </p>
+        <div style=3D"padding: 24px; background-color: #ffffff; border-radius: =
+    8px;">
+          <p style=3D"margin: 0;">Version <strong>1.0.0</strong> of your crate =
+    <strong>foo</strong> has been published by <a href=3D"https://crates.io/use=
+    rs/foo">foo</a> using the API token "bar".</p>
+          <p style=3D"margin: 24px 0;">
+            <a href=3D"https:&#x2f;&#x2f;crates.io&#x2f;crates&#x2f;foo&#x2f;1.=
+    0.0" style=3D"display: inline-block; padding: 10px 20px; background-color: =
+    #3b6837; border-radius: 4px; color: #ffffff; font-weight: bold; text-decora=
+    tion: none;">View version 1.0.0</a>
+          </p>
+          <p style=3D"margin: 0;">If you did not expect this, please check the =
+    API tokens of all owners of the crate and revoke any that might have been l=
+    eaked, then contact <a href=3D"mailto:help@crates.io">help@crates.io</a>.</=
+    p>
+        </div>
+        <div style=3D"margin-top: 16px; font-size: 13px; color: #6b6b6b;">
+          <p style=3D"margin: 0;">You are receiving this email because you have=
+     email notifications enabled for foo. You can disable them in your <a href=
+    =3D"https://crates.io/settings/email-notifications" style=3D"color: #6b6b6b=
+    ;">account settings</a>.</p>
+        </div>
+      </div>
+    </body>
+    </html>
+    --[boundary]--
+    "###);
+
+    token.yank("foo", "1.0.0").await.good();
+    token.unyank("foo", "1.0.0").await.good();
+
+    // Unyanking does not send a notification
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    assert_eq!(emails.len(), 2);
+    assert_snapshot!(app.emails_snapshot()[1], @r###"
+    To: something@example.com
+    From: noreply@crates.io
+    Subject: A version of your crate has been yanked
+    MIME-Version: 1.0
+    Content-Type: multipart/alternative;
+     boundary="[boundary]"
+
+    --[boundary]
+    Content-Type: text/plain; charset=utf-8
+    Content-Transfer-Encoding: quoted-printable
+
+    Version 1.0.0 of your crate foo has been yanked by foo using the API token =
+    "bar".
+
+    Visit https://crates.io/crates/foo/1.0.0 for more details.
+
+    If you did not expect this, please check the API tokens of all owners of th=
+    e crate and revoke any that might have been leaked, then contact help@crate=
+    s.io.
+
+    You are receiving this email because you have email notifications enabled f=
+    or foo. You can disable them in your account settings.
+
+    --[boundary]
+    Content-Type: text/html; charset=utf-8
+    Content-Transfer-Encoding: quoted-printable
+
+    <!DOCTYPE html>
+    <html lang=3D"en">
+    <head>
+      <meta charset=3D"utf-8">
+      <meta name=3D"viewport" content=3D"width=3Ddevice-width, initial-scale=3D=
+    1">
+      <title>A version of your crate has been yanked</title>
+    </head>
+    <body style=3D"margin: 0; padding: 0; background-color: #f9f7ec;">
+      <div style=3D"max-width: 600px; margin: 0 auto; padding: 24px; font-famil=
+    y: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Helvetica, Arial,=
+     sans-serif; font-size: 16px; line-height: 1.5; color: #383838;">
+        <p style=3D"margin: 0 0 16px; font-size: 20px; font-weight: bold;">
+          <a href=3D"https://crates.io" style=3D"color: #383838; text-decoratio=
+    n: none;">crates.io</a>
+        </p>
+        <div style=3D"padding: 24px; background-color: #ffffff; border-radius: =
+    8px;">
+          <p style=3D"margin: 0;">Version <strong>1.0.0</strong> of your crate =
+    <strong>foo</strong> has been yanked by <a href=3D"https://crates.io/users/=
+    foo">foo</a> using the API token "bar".</p>
+          <p style=3D"margin: 24px 0;">
+            <a href=3D"https:&#x2f;&#x2f;crates.io&#x2f;crates&#x2f;foo&#x2f;1.=
+    0.0" style=3D"display: inline-block; padding: 10px 20px; background-color: =
+    #3b6837; border-radius: 4px; color: #ffffff; font-weight: bold; text-decora=
+    tion: none;">View version 1.0.0</a>
+          </p>
+          <p style=3D"margin: 0;">If you did not expect this, please check the =
+    API tokens of all owners of the crate and revoke any that might have been l=
+    eaked, then contact <a href=3D"mailto:help@crates.io">help@crates.io</a>.</=
+    p>
+        </div>
+        <div style=3D"margin-top: 16px; font-size: 13px; color: #6b6b6b;">
+          <p style=3D"margin: 0;">You are receiving this email because you have=
+     email notifications enabled for foo. You can disable them in your <a href=
+    =3D"https://crates.io/settings/email-notifications" style=3D"color: #6b6b6b=
+    ;">account settings</a>.</p>
+        </div>
+      </div>
+    </body>
+    </html>
+    --[boundary]--
+    "###);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn notifications_respect_user_settings() {
+    let (app, _, user, token) = TestApp::full().with_token();
+
+    app.db(|conn| {
+        diesel::insert_into(notification_preferences::table)
+            .values((
+                notification_preferences::user_id.eq(user.as_model().id),
+                notification_preferences::event.eq(NotificationEvent::Publish),
+                notification_preferences::enabled.eq(false),
+            ))
+            .execute(conn)
+            .unwrap();
+    });
+
+    let crate_to_publish = PublishBuilder::new("foo", "1.0.0");
+    token.publish_crate(crate_to_publish).await.good();
+
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    assert_eq!(emails.len(), 0);
+
+    token.yank("foo", "1.0.0").await.good();
+
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    assert_eq!(emails.len(), 1);
+}
@@ -0,0 +1,334 @@
+use crate::builders::PublishBuilder;
+use crate::routes::crates::versions::yank_unyank::YankRequestHelper;
+use crate::util::{MockCookieUser, RequestHelper, TestApp};
+use crates_io::models::{WebhookDelivery, WebhookEvent};
+use crates_io::schema::webhook_deliveries;
+use crates_io::webhooks::{sign, MockWebhookClient, WebhookRequest};
+use diesel::prelude::*;
+use http::StatusCode;
+use insta::assert_json_snapshot;
+use std::sync::{Arc, Mutex};
+
+/// Returns a webhook client that responds with the given status code, and the
+/// list of requests that it received.
+fn webhook_client(status: u16) -> (MockWebhookClient, Arc<Mutex<Vec<WebhookRequest>>>) {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let mut webhook_client = MockWebhookClient::new();
+    webhook_client.expect_deliver().returning({
+        let requests = requests.clone();
+        move |request| {
+            requests.lock().unwrap().push(request);
+            Ok(status)
+        }
+    });
+
+    (webhook_client, requests)
+}
+
+/// Registers a webhook for the user and returns its ID and secret.
+async fn create_webhook(user: &MockCookieUser, webhook: serde_json::Value) -> (i64, String) {
+    let body = json!({ "webhook": webhook }).to_string();
+    let response = user.put::<()>("/api/v1/me/webhooks", body).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let json = response.json();
+    let id = json["webhook"]["id"].as_i64().unwrap();
+    let secret = json["webhook"]["secret"].as_str().unwrap().to_string();
+    (id, secret)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn version_events_are_delivered() {
+    let (webhook_client, requests) = webhook_client(200);
+    let (_, _, user, token) = TestApp::full()
+        .with_webhook_client(webhook_client)
+        .with_token();
+
+    token
+        .publish_crate(PublishBuilder::new("foo", "1.0.0"))
+        .await
+        .good();
+
+    let webhook = json!({ "url": "https://example.com/hook", "crate": "foo", "events": ["publish", "unyank"] });
+    let (id, secret) = create_webhook(&user, webhook).await;
+
+    token
+        .publish_crate(PublishBuilder::new("foo", "1.1.0"))
+        .await
+        .good();
+    token.yank("foo", "1.1.0").await.good();
+    token.unyank("foo", "1.1.0").await.good();
+
+    let requests = requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 2);
+
+    for request in &requests {
+        assert_eq!(request.url, "https://example.com/hook");
+        assert_eq!(request.signature, sign(&secret, &request.body));
+    }
+
+    let payloads = requests
+        .iter()
+        .map(|request| serde_json::from_slice::<serde_json::Value>(&request.body).unwrap())
+        .collect::<Vec<_>>();
+    assert_json_snapshot!(payloads, { "[].time" => "[datetime]" }, @r###"
+    [
+      {
+        "actor": "foo",
+        "crate": "foo",
+        "event": "publish",
+        "time": "[datetime]",
+        "version": "1.1.0"
+      },
+      {
+        "actor": "foo",
+        "crate": "foo",
+        "event": "unyank",
+        "time": "[datetime]",
+        "version": "1.1.0"
+      }
+    ]
+    "###);
+
+    let response = user
+        .get::<()>(&format!("/api/v1/me/webhooks/{id}/deliveries"))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_json_snapshot!(response.json(), {
+        ".deliveries[].id" => "[id]",
+        ".deliveries[].payload.time" => "[datetime]",
+        ".deliveries[].created_at" => "[datetime]",
+        ".deliveries[].last_attempt_at" => "[datetime]",
+        ".deliveries[].delivered_at" => "[datetime]",
+    }, @r###"
+    {
+      "deliveries": [
+        {
+          "attempts": 1,
+          "created_at": "[datetime]",
+          "delivered_at": "[datetime]",
+          "error": null,
+          "event": "unyank",
+          "id": "[id]",
+          "last_attempt_at": "[datetime]",
+          "payload": {
+            "actor": "foo",
+            "crate": "foo",
+            "event": "unyank",
+            "time": "[datetime]",
+            "version": "1.1.0"
+          },
+          "status_code": 200
+        },
+        {
+          "attempts": 1,
+          "created_at": "[datetime]",
+          "delivered_at": "[datetime]",
+          "error": null,
+          "event": "publish",
+          "id": "[id]",
+          "last_attempt_at": "[datetime]",
+          "payload": {
+            "actor": "foo",
+            "crate": "foo",
+            "event": "publish",
+            "time": "[datetime]",
+            "version": "1.1.0"
+          },
+          "status_code": 200
+        }
+      ]
+    }
+    "###);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn events_of_followed_crates_are_delivered() {
+    let (webhook_client, requests) = webhook_client(200);
+    let (app, _, _, token) = TestApp::full()
+        .with_webhook_client(webhook_client)
+        .with_token();
+
+    token
+        .publish_crate(PublishBuilder::new("foo", "1.0.0"))
+        .await
+        .good();
+    token
+        .publish_crate(PublishBuilder::new("bar", "1.0.0"))
+        .await
+        .good();
+
+    let follower = app.db_new_user("follower");
+    let response = follower
+        .put::<()>("/api/v1/crates/foo/follow", b"" as &[u8])
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let webhook = json!({ "url": "https://example.com/hook", "events": ["yank", "owner_change"] });
+    create_webhook(&follower, webhook).await;
+
+    token.yank("foo", "1.0.0").await.good();
+    token.yank("bar", "1.0.0").await.good();
+
+    // Owner changes are only triggered once an invitation is accepted
+    let new_owner = app.db_new_user("new_owner");
+    token.add_named_owner("foo", "new_owner").await.good();
+    app.run_pending_background_jobs().await;
+    assert_eq!(requests.lock().unwrap().len(), 1);
+
+    let crate_id = app.db(|conn| {
+        use crates_io::schema::crates;
+        use diesel::prelude::*;
+
+        crates::table
+            .filter(crates::name.eq("foo"))
+            .select(crates::id)
+            .first::<i32>(conn)
+            .unwrap()
+    });
+    accept_invitation(&new_owner, crate_id).await;
+    app.run_pending_background_jobs().await;
+
+    token.remove_named_owner("foo", "new_owner").await.good();
+    app.run_pending_background_jobs().await;
+
+    let payloads = requests
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|request| serde_json::from_slice::<serde_json::Value>(&request.body).unwrap())
+        .collect::<Vec<_>>();
+    assert_json_snapshot!(payloads, { "[].time" => "[datetime]" }, @r###"
+    [
+      {
+        "actor": "foo",
+        "crate": "foo",
+        "event": "yank",
+        "time": "[datetime]",
+        "version": "1.0.0"
+      },
+      {
+        "action": "added",
+        "crate": "foo",
+        "event": "owner_change",
+        "owner": "new_owner"
+      },
+      {
+        "action": "removed",
+        "crate": "foo",
+        "event": "owner_change",
+        "owner": "new_owner"
+      }
+    ]
+    "###);
+}
+
+async fn accept_invitation(user: &MockCookieUser, crate_id: i32) {
+    let body = json!({
+        "crate_owner_invite": {
+            "invited_by_username": "foo",
+            "crate_name": "foo",
+            "crate_id": crate_id,
+            "created_at": "",
+            "accepted": true,
+        }
+    });
+
+    let url = format!("/api/v1/me/crate_owner_invitations/{crate_id}");
+    let response = user.put::<()>(&url, body.to_string()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rejected_deliveries_are_logged() {
+    let (webhook_client, requests) = webhook_client(410);
+    let (_, _, user, token) = TestApp::full()
+        .with_webhook_client(webhook_client)
+        .with_token();
+
+    token
+        .publish_crate(PublishBuilder::new("foo", "1.0.0"))
+        .await
+        .good();
+
+    let webhook = json!({ "url": "https://example.com/hook", "crate": "foo", "events": ["yank"] });
+    let (id, _) = create_webhook(&user, webhook).await;
+
+    token.yank("foo", "1.0.0").await.good();
+
+    // Client errors are not retried
+    assert_eq!(requests.lock().unwrap().len(), 1);
+
+    let response = user
+        .get::<()>(&format!("/api/v1/me/webhooks/{id}/deliveries"))
+        .await;
+    assert_json_snapshot!(response.json()["deliveries"][0], {
+        ".id" => "[id]",
+        ".payload.time" => "[datetime]",
+        ".created_at" => "[datetime]",
+        ".last_attempt_at" => "[datetime]",
+    }, @r###"
+    {
+      "attempts": 1,
+      "created_at": "[datetime]",
+      "delivered_at": null,
+      "error": "Unexpected status code 410",
+      "event": "yank",
+      "id": "[id]",
+      "last_attempt_at": "[datetime]",
+      "payload": {
+        "actor": "foo",
+        "crate": "foo",
+        "event": "yank",
+        "time": "[datetime]",
+        "version": "1.0.0"
+      },
+      "status_code": 410
+    }
+    "###);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn old_deliveries_are_pruned() {
+    let (webhook_client, _requests) = webhook_client(200);
+    let (app, _, user, token) = TestApp::full()
+        .with_webhook_client(webhook_client)
+        .with_token();
+
+    token
+        .publish_crate(PublishBuilder::new("foo", "1.0.0"))
+        .await
+        .good();
+
+    let webhook = json!({ "url": "https://example.com/hook", "crate": "foo", "events": ["yank"] });
+    let (id, _) = create_webhook(&user, webhook).await;
+
+    let max_deliveries = WebhookDelivery::MAX_PER_WEBHOOK;
+    app.db(|conn| {
+        let delivery = (
+            webhook_deliveries::webhook_id.eq(id as i32),
+            webhook_deliveries::event.eq(WebhookEvent::Yank),
+            webhook_deliveries::payload.eq(json!({})),
+        );
+        let deliveries = vec![delivery; max_deliveries as usize];
+        diesel::insert_into(webhook_deliveries::table)
+            .values(deliveries)
+            .execute(conn)
+            .unwrap();
+    });
+
+    token.yank("foo", "1.0.0").await.good();
+
+    let deliveries: Vec<(i64, serde_json::Value)> = app.db(|conn| {
+        webhook_deliveries::table
+            .select((webhook_deliveries::id, webhook_deliveries::payload))
+            .order(webhook_deliveries::id)
+            .load(conn)
+            .unwrap()
+    });
+
+    // The oldest delivery was deleted, and the new one was kept
+    assert_eq!(deliveries.len() as i64, max_deliveries);
+    assert_eq!(deliveries.last().unwrap().1["event"], "yank");
+}
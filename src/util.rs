@@ -5,6 +5,7 @@ pub use self::io_util::{read_fill, read_le_u32};
 pub use self::request_helpers::*;
 
 mod bytes_request;
+pub mod dates;
 pub mod diesel;
 pub mod errors;
 mod io_util;
@@ -0,0 +1,20 @@
+//! Helper functions for working with calendar dates.
+
+use chrono::{Datelike, NaiveDate};
+
+/// Returns the first day of the month of the given date.
+pub fn first_day_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).expect("every month has a first day")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_day_of_month() {
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let expected = NaiveDate::from_ymd_opt(2024, 2, 1).unwrap();
+        assert_eq!(first_day_of_month(date), expected);
+    }
+}
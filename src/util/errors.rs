@@ -163,6 +163,10 @@ impl From<EmailError> for BoxedAppError {
         match error {
             EmailError::AddressError(error) => Box::new(error),
             EmailError::MessageBuilderError(error) => Box::new(error),
+            EmailError::TemplateError(error) => {
+                error!(?error, "Failed to render email");
+                server_error("Failed to render the email")
+            }
             EmailError::TransportError(error) => {
                 error!(?error, "Failed to send email");
                 server_error("Failed to send the email")
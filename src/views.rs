@@ -4,8 +4,8 @@ use secrecy::ExposeSecret;
 use crate::external_urls::remove_blocked_urls;
 use crate::models::{
     ApiToken, Category, Crate, CrateOwnerInvitation, CreatedApiToken, Dependency, DependencyKind,
-    Keyword, Owner, ReverseDependency, Team, TopVersions, User, Version, VersionDownload,
-    VersionOwnerAction,
+    Keyword, NotificationEvent, Owner, ReverseDependency, Team, TopVersions, User, Version,
+    VersionDownload, VersionDownloadByClient, VersionOwnerAction, Webhook, WebhookEvent,
 };
 use crate::util::rfc3339;
 use crates_io_github as github;
@@ -167,6 +167,25 @@ impl From<VersionDownload> for EncodableVersionDownload {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncodableVersionClientDownload {
+    pub version: i32,
+    pub client: String,
+    pub downloads: i32,
+    pub date: String,
+}
+
+impl From<VersionDownloadByClient> for EncodableVersionClientDownload {
+    fn from(download: VersionDownloadByClient) -> Self {
+        Self {
+            version: download.version_id,
+            client: download.client,
+            downloads: download.downloads,
+            date: download.date.to_string(),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct EncodableKeyword {
     pub id: String,
@@ -441,6 +460,41 @@ impl From<CreatedApiToken> for EncodableApiTokenWithToken {
     }
 }
 
+/// The serialization format for the `Webhook` model.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct EncodableWebhook {
+    pub id: i32,
+    pub url: String,
+    /// The name of the crate that the webhook is subscribed to, or `None`
+    /// for all crates that the user follows.
+    #[serde(rename = "crate")]
+    pub krate: Option<String>,
+    pub events: Vec<WebhookEvent>,
+    #[serde(with = "rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+impl EncodableWebhook {
+    pub fn from(webhook: Webhook, crate_name: Option<String>) -> Self {
+        Self {
+            id: webhook.id,
+            url: webhook.url,
+            krate: crate_name,
+            events: webhook.events,
+            created_at: webhook.created_at,
+        }
+    }
+}
+
+/// The serialization format for a newly registered `Webhook`, which is the
+/// only time that its secret is returned.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct EncodableWebhookWithSecret {
+    #[serde(flatten)]
+    pub webhook: EncodableWebhook,
+    pub secret: String,
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 pub struct OwnedCrate {
     pub id: i32,
@@ -448,6 +502,18 @@ pub struct OwnedCrate {
     pub email_notifications: bool,
 }
 
+#[derive(Deserialize, Serialize, Debug)]
+pub struct EncodableNotificationSetting {
+    pub event: NotificationEvent,
+    pub enabled: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct EncodableNotificationSettings {
+    pub events: Vec<EncodableNotificationSetting>,
+    pub crates: Vec<OwnedCrate>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct EncodableMe {
     pub user: EncodablePrivateUser,
@@ -0,0 +1,190 @@
+//! The code in this module delivers event payloads to webhook endpoints that
+//! users registered.
+//!
+//! The [WebhookClient] trait is used to abstract away the HTTP client for
+//! testing purposes. The [WebhookClientImpl] struct is the actual
+//! implementation of the trait.
+//!
+//! Since webhook endpoints are chosen by users, [WebhookClientImpl] only
+//! connects to public IP addresses, see [crate::dns].
+
+use crate::dns::{self, NonPublicAddress, PublicResolver};
+use crate::models::WebhookEvent;
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use mockall::automock;
+use reqwest::redirect::Policy;
+use reqwest::{header, Client};
+use sha2::Sha256;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+/// Name of the header containing the event that triggered the delivery.
+pub const EVENT_HEADER: &str = "X-Crates-Io-Event";
+
+/// Name of the header containing the ID of the delivery.
+pub const DELIVERY_HEADER: &str = "X-Crates-Io-Delivery";
+
+/// Name of the header containing the HMAC-SHA256 signature of the payload.
+pub const SIGNATURE_HEADER: &str = "X-Crates-Io-Signature-256";
+
+/// A single attempt to deliver an event payload to a webhook endpoint.
+#[derive(Debug, Clone)]
+pub struct WebhookRequest {
+    pub url: String,
+    pub event: WebhookEvent,
+    pub delivery_id: i64,
+    /// Value of the [SIGNATURE_HEADER], see [sign()].
+    pub signature: String,
+    pub body: Vec<u8>,
+}
+
+/// The reason why no response was received from a webhook endpoint.
+///
+/// These are stored with the deliveries and shown to the owner of the
+/// webhook, so they intentionally don't include any details of the
+/// underlying error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum DeliveryError {
+    #[error("webhook URL does not resolve to a public IP address")]
+    NonPublicAddress,
+    #[error("failed to connect to the webhook endpoint")]
+    Connect,
+    #[error("timed out waiting for a response")]
+    Timeout,
+    #[error("failed to send the request")]
+    Request,
+}
+
+impl From<reqwest::Error> for DeliveryError {
+    fn from(error: reqwest::Error) -> Self {
+        let mut source = error.source();
+        while let Some(error) = source {
+            if error.is::<NonPublicAddress>() {
+                return Self::NonPublicAddress;
+            }
+            source = error.source();
+        }
+
+        if error.is_timeout() {
+            Self::Timeout
+        } else if error.is_connect() {
+            Self::Connect
+        } else {
+            Self::Request
+        }
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait WebhookClient {
+    /// Sends the payload to the webhook endpoint, and returns the HTTP status
+    /// code of the response.
+    ///
+    /// An error is returned if no response was received at all, e.g. because
+    /// of connection failures or timeouts.
+    async fn deliver(&self, request: WebhookRequest) -> Result<u16, DeliveryError>;
+}
+
+pub struct WebhookClientImpl {
+    client: Client,
+}
+
+impl WebhookClientImpl {
+    fn new(client: Client) -> Self {
+        WebhookClientImpl { client }
+    }
+}
+
+impl Default for WebhookClientImpl {
+    fn default() -> Self {
+        let client = Client::builder()
+            .user_agent("crates.io webhooks (https://crates.io)")
+            .timeout(Duration::from_secs(10))
+            .dns_resolver(Arc::new(PublicResolver::default()))
+            .redirect(Policy::none())
+            .build()
+            .unwrap();
+
+        WebhookClientImpl::new(client)
+    }
+}
+
+#[async_trait]
+impl WebhookClient for WebhookClientImpl {
+    async fn deliver(&self, request: WebhookRequest) -> Result<u16, DeliveryError> {
+        // Host names are checked by the `PublicResolver`, but IP addresses
+        // are not resolved at all.
+        if !Url::parse(&request.url).is_ok_and(|url| dns::is_public_host(&url)) {
+            return Err(DeliveryError::NonPublicAddress);
+        }
+
+        let event: &'static str = request.event.into();
+
+        let response = self
+            .client
+            .post(&request.url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, event)
+            .header(DELIVERY_HEADER, request.delivery_id.to_string())
+            .header(SIGNATURE_HEADER, request.signature)
+            .body(request.body)
+            .send()
+            .await?;
+
+        Ok(response.status().as_u16())
+    }
+}
+
+/// Signs a webhook payload with the secret of the webhook.
+///
+/// The signature has the format `sha256=<hex encoded HMAC-SHA256>`, so that
+/// webhook endpoints can verify that the payload was sent by crates.io.
+///
+/// ```
+/// use crates_io::webhooks::sign;
+///
+/// assert_eq!(
+///     sign("secret", b"{}"),
+///     "sha256=77325902caca812dc259733aacd046b73817372c777b8d95b402647474516e13"
+/// );
+/// ```
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take keys of any size");
+    mac.update(body);
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(url: &str) -> WebhookRequest {
+        WebhookRequest {
+            url: url.to_string(),
+            event: WebhookEvent::Publish,
+            delivery_id: 1,
+            signature: sign("secret", b"{}"),
+            body: b"{}".to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deliver_to_non_public_address() {
+        let client = WebhookClientImpl::default();
+
+        for url in [
+            "https://127.0.0.1/hook",
+            "https://[::1]/hook",
+            "https://10.1.2.3/",
+        ] {
+            let result = client.deliver(request(url)).await;
+            assert_eq!(result, Err(DeliveryError::NonPublicAddress), "{url}");
+        }
+    }
+}
@@ -1,9 +1,12 @@
 use crate::cloudfront::CloudFront;
 use crate::fastly::Fastly;
+use crate::link_checker::LinkChecker;
+use crate::search::SearchBackend;
 use crate::storage::Storage;
 use crate::team_repo::TeamRepo;
 use crate::typosquat;
 use crate::util::diesel::Conn;
+use crate::webhooks::WebhookClient;
 use crate::Emails;
 use anyhow::Context;
 use crates_io_index::{Repository, RepositoryConfig};
@@ -34,6 +37,12 @@ pub struct Environment {
     pub deadpool: Pool<AsyncPgConnection>,
     pub emails: Emails,
     pub team_repo: Box<dyn TeamRepo + Send + Sync>,
+    #[builder(default)]
+    pub search_backend: Option<Arc<dyn SearchBackend>>,
+    #[builder(default)]
+    pub link_checker: Option<Box<dyn LinkChecker + Send + Sync>>,
+    #[builder(default)]
+    pub webhook_client: Option<Box<dyn WebhookClient + Send + Sync>>,
 
     /// A lazily initialised cache of the most popular crates ready to use in typosquatting checks.
     #[builder(default, setter(skip))]
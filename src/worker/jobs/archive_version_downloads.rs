@@ -1,10 +1,12 @@
-use crate::schema::version_downloads;
+use crate::schema::{version_downloads, version_downloads_by_client};
 use crate::tasks::spawn_blocking;
+use crate::util::dates::first_day_of_month;
 use crate::util::diesel::Conn;
+use crate::worker::jobs::rollup_version_downloads::{read_month, save_inner};
 use crate::worker::Environment;
 use anyhow::{anyhow, Context};
 use chrono::{NaiveDate, Utc};
-use crates_io_worker::BackgroundJob;
+use crates_io_worker::{cancellation_token, BackgroundJob, JobCancelled};
 use diesel::prelude::*;
 use diesel::{ExpressionMethods, RunQueryDsl};
 use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
@@ -14,7 +16,7 @@ use futures_util::StreamExt;
 use object_store::ObjectStore;
 use secrecy::{ExposeSecret, SecretString};
 use std::collections::btree_map::Entry;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::Path;
 use std::sync::Arc;
 use std::time::Instant;
@@ -28,7 +30,9 @@ const FILE_NAME: &str = "version_downloads.csv";
 /// This job first exports the data from the database to a CSV file using `psql`
 /// and a `COPY` command. The CSV file is then split into multiple files based
 /// on the date column and those are uploaded to the object store. Finally, the
-/// successfully uploaded dates are deleted from the database.
+/// months of the successfully uploaded dates are rolled up into the
+/// `version_downloads_monthly` table and the uploaded dates are deleted from
+/// the database, both in the same transaction.
 #[derive(Serialize, Deserialize)]
 pub struct ArchiveVersionDownloads {
     before: NaiveDate,
@@ -62,10 +66,25 @@ impl BackgroundJob for ArchiveVersionDownloads {
         let tempdir = tempdir().context("Failed to create temporary directory")?;
         let csv_path = tempdir.path().join(FILE_NAME);
 
+        let cancellation_token = cancellation_token();
+
         export(&env.config.db.primary.url, &csv_path, &self.before).await?;
+        if cancellation_token.is_cancelled() {
+            return Err(
+                anyhow!(JobCancelled).context("Job was cancelled after exporting the CSV file")
+            );
+        }
+
         let dates = spawn_blocking(move || split(csv_path)).await?;
+        if cancellation_token.is_cancelled() {
+            return Err(
+                anyhow!(JobCancelled).context("Job was cancelled after splitting the CSV file")
+            );
+        }
+
         let uploaded_dates = upload(downloads_archive_store, tempdir.path(), dates).await?;
-        delete(&env.deadpool, uploaded_dates).await?;
+        let rollups = read_rollups(downloads_archive_store, &uploaded_dates).await?;
+        save_and_delete(&env.deadpool, rollups, uploaded_dates).await?;
 
         info!("Finished archiving old version downloads");
         Ok(())
@@ -216,12 +235,47 @@ async fn upload_file(store: &impl ObjectStore, path: impl AsRef<Path>) -> anyhow
     Ok(())
 }
 
-/// Delete version downloads for the given dates from the database.
-async fn delete(db_pool: &Pool<AsyncPgConnection>, dates: Vec<NaiveDate>) -> anyhow::Result<()> {
+/// Returns the distinct months of the given dates.
+fn uploaded_months(dates: &[NaiveDate]) -> BTreeSet<NaiveDate> {
+    dates.iter().copied().map(first_day_of_month).collect()
+}
+
+/// Reads the archived downloads of all months of the given dates from the
+/// object store and sums them up per version.
+async fn read_rollups(
+    store: &impl ObjectStore,
+    dates: &[NaiveDate],
+) -> anyhow::Result<BTreeMap<NaiveDate, BTreeMap<i32, i64>>> {
+    let mut rollups = BTreeMap::new();
+    for month in uploaded_months(dates) {
+        let downloads = read_month(store, month).await?;
+        rollups.insert(month, downloads);
+    }
+
+    Ok(rollups)
+}
+
+/// Saves the monthly rollups to the `version_downloads_monthly` table and
+/// deletes version downloads for the given dates from the database.
+///
+/// Both happen in the same transaction, so that the archived downloads are
+/// never missing from both tables at the same time.
+async fn save_and_delete(
+    db_pool: &Pool<AsyncPgConnection>,
+    rollups: BTreeMap<NaiveDate, BTreeMap<i32, i64>>,
+    dates: Vec<NaiveDate>,
+) -> anyhow::Result<()> {
     let conn = db_pool.get().await?;
     spawn_blocking(move || {
         let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
-        delete_inner(conn, dates)
+        conn.transaction(|conn| {
+            for (month, downloads) in rollups {
+                info!(%month, "Rolling up archived version downloads…");
+                save_inner(conn, month, downloads)?;
+            }
+
+            delete_inner(conn, dates)
+        })
     })
     .await
 }
@@ -234,14 +288,19 @@ fn delete_inner(conn: &mut impl Conn, dates: Vec<NaiveDate>) -> anyhow::Result<(
     info!("Deleting old version downloads for {} dates…", dates.len());
     for chunk in dates.chunks(CHUNK_SIZE) {
         let subset = version_downloads::table.filter(version_downloads::date.eq_any(chunk));
-        match diesel::delete(subset).execute(conn) {
-            Ok(num_deleted_rows) => {
-                info!("Deleted {num_deleted_rows} rows from `version_downloads`");
-            }
-            Err(err) => {
-                error!("Failed to delete rows from `version_downloads`: {err}");
-            }
-        }
+        let num_deleted_rows = diesel::delete(subset)
+            .execute(conn)
+            .context("Failed to delete rows from `version_downloads`")?;
+        info!("Deleted {num_deleted_rows} rows from `version_downloads`");
+
+        // The per-client breakdown is not archived, so it is deleted
+        // together with the totals.
+        let subset = version_downloads_by_client::table
+            .filter(version_downloads_by_client::date.eq_any(chunk));
+        let num_deleted_rows = diesel::delete(subset)
+            .execute(conn)
+            .context("Failed to delete rows from `version_downloads_by_client`")?;
+        info!("Deleted {num_deleted_rows} rows from `version_downloads_by_client`");
     }
 
     Ok(())
@@ -250,7 +309,7 @@ fn delete_inner(conn: &mut impl Conn, dates: Vec<NaiveDate>) -> anyhow::Result<(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::schema::{crates, version_downloads, versions};
+    use crate::schema::{crates, version_downloads, version_downloads_monthly, versions};
     use crates_io_test_db::TestDatabase;
     use diesel_async::pooled_connection::AsyncDieselConnectionManager;
     use insta::assert_snapshot;
@@ -358,21 +417,61 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_delete() {
+    async fn test_save_and_delete() {
         let test_db = TestDatabase::new();
         let mut conn = test_db.connect();
         prepare_database(&mut conn);
 
         let manager = AsyncDieselConnectionManager::<AsyncPgConnection>::new(test_db.url());
         let db_pool = Pool::builder(manager).build().unwrap();
+
+        let month = NaiveDate::from_ymd_opt(2021, 1, 1).unwrap();
+        let rollups = BTreeMap::from([(month, BTreeMap::from([(1, 100), (2, 400)]))]);
         let dates = vec![NaiveDate::from_ymd_opt(2021, 1, 1).unwrap()];
-        delete(&db_pool, dates).await.unwrap();
+        save_and_delete(&db_pool, rollups, dates).await.unwrap();
 
         let row_count: i64 = version_downloads::table
             .count()
             .get_result(&mut conn)
             .unwrap();
         assert_eq!(row_count, 4);
+
+        let rows: Vec<(i32, i64)> = version_downloads_monthly::table
+            .select((
+                version_downloads_monthly::version_id,
+                version_downloads_monthly::downloads,
+            ))
+            .filter(version_downloads_monthly::month.eq(month))
+            .order(version_downloads_monthly::version_id)
+            .load(&mut conn)
+            .unwrap();
+        assert_eq!(rows, vec![(1, 100), (2, 400)]);
+    }
+
+    #[tokio::test]
+    async fn test_read_rollups() {
+        let store = object_store::memory::InMemory::new();
+        let path = object_store::path::Path::from("2021-01-01.csv");
+        store
+            .put(&path, "version_id,downloads\n1,100".into())
+            .await
+            .unwrap();
+        let path = object_store::path::Path::from("2021-01-15.csv");
+        store
+            .put(&path, "version_id,downloads\n1,200".into())
+            .await
+            .unwrap();
+
+        // The whole month is rolled up, even if only some of its dates were
+        // uploaded by this run of the job.
+        let dates = vec![NaiveDate::from_ymd_opt(2021, 1, 15).unwrap()];
+        let rollups = read_rollups(&store, &dates).await.unwrap();
+
+        let month = NaiveDate::from_ymd_opt(2021, 1, 1).unwrap();
+        assert_eq!(
+            rollups,
+            BTreeMap::from([(month, BTreeMap::from([(1, 300)]))])
+        );
     }
 
     fn prepare_database(conn: &mut impl Conn) {
@@ -0,0 +1,114 @@
+//! Render changelog files to HTML.
+
+use crate::schema::{changelogs, crates, versions};
+use crate::tasks::spawn_blocking;
+use crate::worker::Environment;
+use crates_io_markdown::{changelog_sections, text_to_html_with_options, RenderOptions};
+use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
+use std::sync::Arc;
+use tokio::runtime::Handle;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RenderAndUploadChangelog {
+    version_id: i32,
+    text: String,
+    changelog_path: String,
+    base_url: Option<String>,
+    pkg_path_in_vcs: Option<String>,
+}
+
+impl RenderAndUploadChangelog {
+    pub fn new(
+        version_id: i32,
+        text: String,
+        changelog_path: String,
+        base_url: Option<String>,
+        pkg_path_in_vcs: Option<String>,
+    ) -> Self {
+        Self {
+            version_id,
+            text,
+            changelog_path,
+            base_url,
+            pkg_path_in_vcs,
+        }
+    }
+}
+
+impl BackgroundJob for RenderAndUploadChangelog {
+    const JOB_NAME: &'static str = "render_and_upload_changelog";
+    const PRIORITY: i16 = 40;
+
+    type Context = Arc<Environment>;
+
+    #[instrument(skip_all, fields(krate.name))]
+    async fn run(&self, env: Self::Context) -> anyhow::Result<()> {
+        use diesel::dsl::now;
+
+        info!(version_id = ?self.version_id, "Rendering changelog");
+
+        let options = RenderOptions {
+            syntax_highlighting: env.config.readme_syntax_highlighting,
+            image_proxy: env.config.readme_image_proxy.clone(),
+        };
+
+        let job = self.clone();
+        let (rendered, sections) = spawn_blocking(move || {
+            let rendered = text_to_html_with_options(
+                &job.text,
+                &job.changelog_path,
+                job.base_url.as_deref(),
+                job.pkg_path_in_vcs.as_ref(),
+                &options,
+            );
+            let sections = changelog_sections(&rendered);
+            Ok::<_, anyhow::Error>((rendered, sections))
+        })
+        .await?;
+
+        if rendered.is_empty() {
+            return Ok(());
+        }
+
+        let job = self.clone();
+        let conn = env.deadpool.get().await?;
+        spawn_blocking(move || {
+            let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+            conn.transaction(|conn| {
+                let sections = serde_json::to_value(&sections)?;
+                diesel::insert_into(changelogs::table)
+                    .values((
+                        changelogs::version_id.eq(job.version_id),
+                        changelogs::path.eq(&job.changelog_path),
+                        changelogs::sections.eq(&sections),
+                    ))
+                    .on_conflict(changelogs::version_id)
+                    .do_update()
+                    .set((
+                        changelogs::path.eq(&job.changelog_path),
+                        changelogs::sections.eq(&sections),
+                        changelogs::rendered_at.eq(now),
+                    ))
+                    .execute(conn)?;
+
+                let (crate_name, vers): (String, String) = versions::table
+                    .find(job.version_id)
+                    .inner_join(crates::table)
+                    .select((crates::name, versions::num))
+                    .first(conn)?;
+
+                tracing::Span::current().record("krate.name", tracing::field::display(&crate_name));
+
+                let bytes = rendered.into();
+                let future = env.storage.upload_changelog(&crate_name, &vers, bytes);
+                Handle::current().block_on(future)?;
+
+                Ok(())
+            })
+        })
+        .await
+    }
+}
@@ -4,7 +4,10 @@ use crate::util::diesel::Conn;
 use crate::worker::Environment;
 use anyhow::Context;
 use chrono::NaiveDate;
-use crates_io_cdn_logs::{count_downloads, Decompressor, DownloadsMap};
+use crates_io_cdn_logs::{
+    count_downloads, ClientType, Decompressor, DiscardedCounts, DownloadFilter, DownloadsMap,
+    FilterConfig,
+};
 use crates_io_worker::BackgroundJob;
 use diesel::dsl::exists;
 use diesel::prelude::*;
@@ -16,7 +19,7 @@ use object_store::aws::AmazonS3Builder;
 use object_store::local::LocalFileSystem;
 use object_store::memory::InMemory;
 use object_store::path::Path;
-use object_store::ObjectStore;
+use object_store::{ObjectMeta, ObjectStore};
 use semver::Version;
 use std::fmt::Debug;
 use std::sync::Arc;
@@ -55,8 +58,9 @@ impl BackgroundJob for ProcessCdnLog {
         let store = build_store(&ctx.config.cdn_log_storage, &self.region, &self.bucket)
             .context("Failed to build object store")?;
 
+        let filter_config = &ctx.config.cdn_log_filter;
         let db_pool = ctx.deadpool.clone();
-        run(store, &self.path, db_pool).await
+        run(store, &self.path, filter_config, db_pool).await
     }
 }
 
@@ -65,7 +69,7 @@ impl BackgroundJob for ProcessCdnLog {
 ///
 /// If the passed in [CdnLogStorageConfig] is using local file or in-memory
 /// storage the `region` and `bucket` arguments are ignored.
-fn build_store(
+pub(super) fn build_store(
     config: &CdnLogStorageConfig,
     region: impl Into<String>,
     bucket: impl Into<String>,
@@ -94,7 +98,8 @@ fn build_store(
 }
 
 /// Loads the given log file from the object store and counts the number of
-/// downloads for each crate and version. The results are printed to the log.
+/// downloads for each crate and version, discarding requests according to the
+/// [FilterConfig]. The results are printed to the log.
 ///
 /// This function is separate from the [`BackgroundJob`] trait method so that
 /// it can be tested without having to construct a full [`Environment`]
@@ -103,6 +108,7 @@ fn build_store(
 async fn run(
     store: Arc<dyn ObjectStore>,
     path: &str,
+    filter_config: &FilterConfig,
     db_pool: Pool<AsyncPgConnection>,
 ) -> anyhow::Result<()> {
     if already_processed(path, db_pool.clone()).await? {
@@ -113,15 +119,25 @@ async fn run(
     let parsed_path =
         Path::parse(path).with_context(|| format!("Failed to parse path: {path:?}"))?;
 
-    let downloads = load_and_count(&parsed_path, store).await?;
+    let meta = store.head(&parsed_path).await;
+    let meta = meta.with_context(|| format!("Failed to request metadata for {path:?}"))?;
+
+    let mut filter = DownloadFilter::new(filter_config);
+    let downloads = load_and_count(&meta, store, &mut filter).await?;
+
+    let discarded = filter.discarded();
+    log_discarded(&discarded);
+
+    // Log files without any downloads are still marked as processed, so that
+    // they are not enqueued again and their discarded counts are recorded.
     if downloads.is_empty() {
         info!("No downloads found in log file");
-        return Ok(());
+    } else {
+        log_stats(&downloads);
     }
 
-    log_stats(&downloads);
-
     let path = path.to_string();
+    let e_tag = meta.e_tag;
     let conn = db_pool.get().await?;
     spawn_blocking(move || {
         let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
@@ -137,7 +153,11 @@ async fn run(
             // When the job is retried the `already_processed()` call above
             // will return `true` and the job will skip processing the log
             // file again.
-            save_as_processed(path, conn)?;
+            save_as_processed(path, e_tag, &discarded, conn)?;
+
+            if downloads.is_empty() {
+                return Ok(());
+            }
 
             save_downloads(downloads, conn)
         })?;
@@ -149,15 +169,16 @@ async fn run(
 
 /// Loads the given log file from the object store and counts the number of
 /// downloads for each crate and version.
-async fn load_and_count(path: &Path, store: Arc<dyn ObjectStore>) -> anyhow::Result<DownloadsMap> {
-    let meta = store.head(path).await;
-    let meta = meta.with_context(|| format!("Failed to request metadata for {path:?}"))?;
-
-    let reader = object_store::buffered::BufReader::new(store, &meta);
-    let decompressor = Decompressor::from_extension(reader, path.extension())?;
+async fn load_and_count(
+    meta: &ObjectMeta,
+    store: Arc<dyn ObjectStore>,
+    filter: &mut DownloadFilter,
+) -> anyhow::Result<DownloadsMap> {
+    let reader = object_store::buffered::BufReader::new(store, meta);
+    let decompressor = Decompressor::from_extension(reader, meta.location.extension())?;
     let reader = BufReader::new(decompressor);
 
-    count_downloads(reader).await
+    count_downloads(reader, filter).await
 }
 
 /// Prints the total number of downloads, the number of crates, and the number
@@ -173,16 +194,28 @@ fn log_stats(downloads: &DownloadsMap) {
     info!("Number of needed inserts: {total_inserts}");
 }
 
+/// Prints the number of discarded requests, by the reason they were discarded
+/// for, to the log.
+fn log_discarded(discarded: &DiscardedCounts) {
+    let total = discarded.total();
+    info!(
+        discarded.duplicate = discarded.duplicate,
+        discarded.blocked_user_agent = discarded.blocked_user_agent,
+        "Number of discarded downloads: {total}"
+    );
+}
+
 table! {
     /// Diesel table definition for the temporary `temp_downloads` table that is
     /// created by the [`create_temp_downloads_table`] function.
     ///
     /// The primary key does not actually exist, but specifying one is
     /// required by Diesel.
-    temp_downloads (name, version, date) {
+    temp_downloads (name, version, date, client) {
         name -> Text,
         version -> Text,
         date -> Date,
+        client -> Text,
         downloads -> BigInt,
     }
 }
@@ -194,22 +227,30 @@ struct NewDownload {
     name: String,
     version: String,
     date: NaiveDate,
+    client: &'static str,
     downloads: i64,
 }
 
-impl From<(String, Version, NaiveDate, u64)> for NewDownload {
-    fn from((name, version, date, downloads): (String, Version, NaiveDate, u64)) -> Self {
+impl NewDownload {
+    fn new(
+        name: String,
+        version: &Version,
+        date: NaiveDate,
+        client: ClientType,
+        downloads: u64,
+    ) -> Self {
         Self {
             name,
             version: version.to_string(),
             date,
+            client: client.as_str(),
             downloads: downloads as i64,
         }
     }
 }
 
 /// Saves the downloads from the given [`DownloadsMap`] to the database into
-/// the `version_downloads` table.
+/// the `version_downloads` and `version_downloads_by_client` tables.
 ///
 /// This function **should be run inside a transaction** to ensure that the
 /// temporary `temp_downloads` table is dropped after the inserts are
@@ -225,9 +266,9 @@ pub fn save_downloads(downloads: DownloadsMap, conn: &mut impl Conn) -> anyhow::
     debug!("Saving counted downloads to temp_downloads table");
     fill_temp_downloads_table(downloads, conn).context("Failed to fill temp_downloads table")?;
 
-    debug!("Saving temp_downloads to version_downloads table");
+    debug!("Saving temp_downloads to version_downloads tables");
     let failed_inserts = save_to_version_downloads(conn)
-        .context("Failed to save temp_downloads to version_downloads table")?;
+        .context("Failed to save temp_downloads to version_downloads tables")?;
 
     if !failed_inserts.is_empty() {
         warn!(
@@ -253,6 +294,7 @@ fn create_temp_downloads_table(conn: &mut impl Conn) -> QueryResult<usize> {
                 name VARCHAR NOT NULL,
                 version VARCHAR NOT NULL,
                 date DATE NOT NULL,
+                client VARCHAR NOT NULL,
                 downloads INTEGER NOT NULL
             ) ON COMMIT DROP;
         "#,
@@ -273,10 +315,15 @@ fn fill_temp_downloads_table(downloads: DownloadsMap, conn: &mut impl Conn) -> Q
     // batches.
     const MAX_BATCH_SIZE: usize = 5_000;
 
+    // Each crate version and date is split into one row per client type.
     let map = downloads
         .into_vec()
         .into_iter()
-        .map(NewDownload::from)
+        .flat_map(|(name, version, date, counts)| {
+            counts.iter().map(move |(client, downloads)| {
+                NewDownload::new(name.clone(), &version, date, client, downloads)
+            })
+        })
         .collect::<Vec<_>>();
 
     for chunk in map.chunks(MAX_BATCH_SIZE) {
@@ -289,8 +336,11 @@ fn fill_temp_downloads_table(downloads: DownloadsMap, conn: &mut impl Conn) -> Q
 }
 
 /// Saves the downloads from the temporary `temp_downloads` table to the
-/// `version_downloads` table and returns the name/version combinations that
-/// were not found in the database.
+/// `version_downloads` and `version_downloads_by_client` tables and returns
+/// the name/version combinations that were not found in the database.
+///
+/// The `version_downloads` table receives the sum across all client types,
+/// while `version_downloads_by_client` receives the per-client breakdown.
 #[instrument(
     "db.query",
     skip_all,
@@ -306,14 +356,23 @@ fn save_to_version_downloads(conn: &mut impl Conn) -> QueryResult<Vec<NameAndVer
                 LEFT JOIN versions ON versions.num = temp_downloads.version AND versions.crate_id = crates.id
             ), inserted AS (
                 INSERT INTO version_downloads (version_id, date, downloads)
-                SELECT joined_data.id, joined_data.date, joined_data.downloads
+                SELECT joined_data.id, joined_data.date, SUM(joined_data.downloads)
                 FROM joined_data
                 WHERE joined_data.id IS NOT NULL
+                GROUP BY joined_data.id, joined_data.date
                 ORDER BY joined_data.id, joined_data.date
                 ON CONFLICT (version_id, date)
                 DO UPDATE SET downloads = version_downloads.downloads + EXCLUDED.downloads
+            ), inserted_by_client AS (
+                INSERT INTO version_downloads_by_client (version_id, date, client, downloads)
+                SELECT joined_data.id, joined_data.date, joined_data.client, joined_data.downloads
+                FROM joined_data
+                WHERE joined_data.id IS NOT NULL
+                ORDER BY joined_data.id, joined_data.date, joined_data.client
+                ON CONFLICT (version_id, date, client)
+                DO UPDATE SET downloads = version_downloads_by_client.downloads + EXCLUDED.downloads
             )
-            SELECT joined_data.name, joined_data.version
+            SELECT DISTINCT joined_data.name, joined_data.version
             FROM joined_data
             WHERE joined_data.id IS NULL;
         "#,
@@ -381,12 +440,24 @@ fn already_processed_inner(path: impl Into<String>, conn: &mut impl Conn) -> Que
 }
 
 /// Inserts the given path into the `processed_log_files` table to mark it as
-/// processed.
-fn save_as_processed(path: impl Into<String>, conn: &mut impl Conn) -> QueryResult<()> {
+/// processed, together with the ETag of the log file and the number of
+/// discarded requests.
+fn save_as_processed(
+    path: impl Into<String>,
+    e_tag: Option<String>,
+    discarded: &DiscardedCounts,
+    conn: &mut impl Conn,
+) -> QueryResult<()> {
     use crate::schema::processed_log_files;
 
     diesel::insert_into(processed_log_files::table)
-        .values(processed_log_files::path.eq(path.into()))
+        .values((
+            processed_log_files::path.eq(path.into()),
+            processed_log_files::e_tag.eq(e_tag),
+            processed_log_files::discarded_duplicates.eq(discarded.duplicate as i32),
+            processed_log_files::discarded_blocked_user_agents
+                .eq(discarded.blocked_user_agent as i32),
+        ))
         .execute(conn)?;
 
     Ok(())
@@ -395,7 +466,7 @@ fn save_as_processed(path: impl Into<String>, conn: &mut impl Conn) -> QueryResu
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::schema::{crates, version_downloads, versions};
+    use crate::schema::{crates, version_downloads, version_downloads_by_client, versions};
     use crate::util::diesel::Conn;
     use crates_io_test_db::TestDatabase;
     use diesel_async::pooled_connection::AsyncDieselConnectionManager;
@@ -416,7 +487,7 @@ mod tests {
 
         assert_ok!({
             let store = store.clone();
-            run(store, CLOUDFRONT_PATH, db_pool.clone()).await
+            run(store, CLOUDFRONT_PATH, &Default::default(), db_pool.clone()).await
         });
         assert_debug_snapshot!(all_version_downloads(db_pool.clone()).await, @r###"
         [
@@ -429,8 +500,8 @@ mod tests {
 
         // Check that processing the same log file again does not insert
         // duplicate data.
-        assert_ok!(run(store, CLOUDFRONT_PATH, db_pool.clone()).await);
-        assert_debug_snapshot!(all_version_downloads(db_pool).await, @r###"
+        assert_ok!(run(store, CLOUDFRONT_PATH, &Default::default(), db_pool.clone()).await);
+        assert_debug_snapshot!(all_version_downloads(db_pool.clone()).await, @r###"
         [
             "bindgen | 0.65.1 | 1 | 0 | 2024-01-16 | false",
             "quick-error | 1.2.3 | 2 | 0 | 2024-01-16 | false",
@@ -438,6 +509,113 @@ mod tests {
             "tracing-core | 0.1.32 | 1 | 0 | 2024-01-16 | false",
         ]
         "###);
+
+        assert_debug_snapshot!(all_version_downloads_by_client(db_pool).await, @r###"
+        [
+            "bindgen | 0.65.1 | 2024-01-16 | cargo | 1",
+            "quick-error | 1.2.3 | 2024-01-16 | cargo | 2",
+            "quick-error | 1.2.3 | 2024-01-17 | cargo | 1",
+            "tracing-core | 0.1.32 | 2024-01-16 | cargo | 1",
+        ]
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_process_cdn_log_with_filter() {
+        crate::util::tracing::init_for_test();
+
+        let test_database = TestDatabase::new();
+        let db_pool = build_connection_pool(test_database.url());
+        create_dummy_crates_and_versions(db_pool.clone()).await;
+
+        let path = "cloudfront/static.crates.io/E35K556QRQDZXW.2024-01-16-23.filtering.gz";
+        let store = InMemory::new();
+        let bytes = include_bytes!(
+            "../../../../crates/crates_io_cdn_logs/test_data/cloudfront/filtering.log.gz"
+        );
+        store.put(&path.into(), bytes[..].into()).await.unwrap();
+
+        let filter_config = FilterConfig {
+            dedup_window: Some(std::time::Duration::from_secs(60)),
+            blocked_user_agents: vec!["scrapy".into(), "bot/".into()],
+        };
+
+        assert_ok!(run(Arc::new(store), path, &filter_config, db_pool.clone()).await);
+        assert_debug_snapshot!(all_version_downloads(db_pool.clone()).await, @r###"
+        [
+            "bindgen | 0.65.1 | 3 | 0 | 2024-01-16 | false",
+        ]
+        "###);
+
+        assert_eq!(discarded_counts(db_pool, path).await, (1, 2));
+    }
+
+    #[tokio::test]
+    async fn test_process_cdn_log_without_downloads() {
+        crate::util::tracing::init_for_test();
+
+        let test_database = TestDatabase::new();
+        let db_pool = build_connection_pool(test_database.url());
+        create_dummy_crates_and_versions(db_pool.clone()).await;
+
+        let path = "cloudfront/static.crates.io/E35K556QRQDZXW.2024-01-16-23.filtering.gz";
+        let store = InMemory::new();
+        let bytes = include_bytes!(
+            "../../../../crates/crates_io_cdn_logs/test_data/cloudfront/filtering.log.gz"
+        );
+        store.put(&path.into(), bytes[..].into()).await.unwrap();
+
+        let filter_config = FilterConfig {
+            blocked_user_agents: vec!["cargo".into(), "scrapy".into(), "bot/".into()],
+            ..Default::default()
+        };
+
+        assert_ok!(run(Arc::new(store), path, &filter_config, db_pool.clone()).await);
+        assert_debug_snapshot!(all_version_downloads(db_pool.clone()).await, @"[]");
+
+        // The log file is marked as processed even though all downloads
+        // were discarded.
+        assert_eq!(discarded_counts(db_pool, path).await, (0, 8));
+    }
+
+    #[tokio::test]
+    async fn test_save_downloads_by_client() {
+        crate::util::tracing::init_for_test();
+
+        let test_database = TestDatabase::new();
+        let db_pool = build_connection_pool(test_database.url());
+        create_dummy_crates_and_versions(db_pool.clone()).await;
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 16).unwrap();
+        let version = Version::new(0, 65, 1);
+
+        let mut downloads = DownloadsMap::new();
+        for client in [ClientType::Cargo, ClientType::Cargo, ClientType::Ci] {
+            downloads.add("bindgen".into(), version.clone(), date, client);
+        }
+        downloads.add("bindgen".into(), version.clone(), date, ClientType::Browser);
+        downloads.add("unknown-crate".into(), version, date, ClientType::Cargo);
+
+        let conn = db_pool.get().await.unwrap();
+        spawn_blocking(move || {
+            let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+            conn.transaction(|conn| save_downloads(downloads, conn))
+        })
+        .await
+        .unwrap();
+
+        assert_debug_snapshot!(all_version_downloads(db_pool.clone()).await, @r###"
+        [
+            "bindgen | 0.65.1 | 4 | 0 | 2024-01-16 | false",
+        ]
+        "###);
+        assert_debug_snapshot!(all_version_downloads_by_client(db_pool).await, @r###"
+        [
+            "bindgen | 0.65.1 | 2024-01-16 | browser | 1",
+            "bindgen | 0.65.1 | 2024-01-16 | cargo | 2",
+            "bindgen | 0.65.1 | 2024-01-16 | ci | 1",
+        ]
+        "###);
     }
 
     #[test]
@@ -516,6 +694,29 @@ mod tests {
             .unwrap();
     }
 
+    /// Queries the number of discarded duplicates and downloads from blocked
+    /// user agents of the given processed log file.
+    async fn discarded_counts(db_pool: Pool<AsyncPgConnection>, path: &str) -> (i32, i32) {
+        use crate::schema::processed_log_files;
+
+        let path = path.to_string();
+        let conn = db_pool.get().await.unwrap();
+        spawn_blocking(move || {
+            let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+            let discarded = processed_log_files::table
+                .select((
+                    processed_log_files::discarded_duplicates,
+                    processed_log_files::discarded_blocked_user_agents,
+                ))
+                .filter(processed_log_files::path.eq(path))
+                .get_result(conn)?;
+
+            Ok::<_, anyhow::Error>(discarded)
+        })
+        .await
+        .unwrap()
+    }
+
     /// Queries all version downloads from the database and returns them as a
     /// [`Vec`] of strings for use with [`assert_debug_snapshot!()`].
     async fn all_version_downloads(db_pool: Pool<AsyncPgConnection>) -> Vec<String> {
@@ -555,4 +756,43 @@ mod tests {
             .load(conn)
             .unwrap()
     }
+
+    /// Queries all per-client version downloads from the database and returns
+    /// them as a [`Vec`] of strings for use with [`assert_debug_snapshot!()`].
+    async fn all_version_downloads_by_client(db_pool: Pool<AsyncPgConnection>) -> Vec<String> {
+        let conn = db_pool.get().await.unwrap();
+        let downloads = spawn_blocking(move || {
+            let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+            let downloads: Vec<(String, String, NaiveDate, String, i32)> =
+                version_downloads_by_client::table
+                    .inner_join(versions::table)
+                    .inner_join(crates::table.on(versions::crate_id.eq(crates::id)))
+                    .select((
+                        crates::name,
+                        versions::num,
+                        version_downloads_by_client::date,
+                        version_downloads_by_client::client,
+                        version_downloads_by_client::downloads,
+                    ))
+                    .order((
+                        crates::name,
+                        versions::num,
+                        version_downloads_by_client::date,
+                        version_downloads_by_client::client,
+                    ))
+                    .load(conn)?;
+
+            Ok::<_, anyhow::Error>(downloads)
+        })
+        .await
+        .unwrap();
+
+        downloads
+            .into_iter()
+            .map(|(name, version, date, client, downloads)| {
+                format!("{name} | {version} | {date} | {client} | {downloads}")
+            })
+            .collect()
+    }
 }
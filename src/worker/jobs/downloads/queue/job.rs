@@ -2,6 +2,7 @@ use crate::config::CdnLogQueueConfig;
 use crate::sqs::{MockSqsQueue, SqsQueue, SqsQueueImpl};
 use crate::tasks::spawn_blocking;
 use crate::util::diesel::Conn;
+use crate::worker::jobs::downloads::process_log::build_store;
 use crate::worker::jobs::ProcessCdnLog;
 use crate::worker::Environment;
 use anyhow::Context;
@@ -12,6 +13,7 @@ use crates_io_worker::BackgroundJob;
 use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
 use diesel_async::pooled_connection::deadpool::Pool;
 use diesel_async::AsyncPgConnection;
+use secrecy::{ExposeSecret, SecretString};
 use std::sync::Arc;
 
 /// A background job that processes messages from the CDN log queue.
@@ -20,9 +22,16 @@ use std::sync::Arc;
 /// message to an SQS queue. This job processes those messages, extracting the
 /// log file path from each message and enqueuing a `ProcessCdnLog` job for each
 /// path.
+///
+/// For self-hosted deployments without SQS, the [CdnLogQueueConfig::Polling]
+/// backend lists the CDN log storage instead and enqueues a `ProcessCdnLog`
+/// job for each new log file.
 #[derive(Debug, Serialize, Deserialize, clap::Parser)]
 pub struct ProcessCdnLogQueue {
     /// The maximum number of messages to receive from the queue and process.
+    ///
+    /// When polling the CDN log storage, this is the maximum number of new
+    /// log files to enqueue.
     #[clap(long, default_value = "1")]
     max_messages: usize,
 }
@@ -33,33 +42,51 @@ impl BackgroundJob for ProcessCdnLogQueue {
     type Context = Arc<Environment>;
 
     async fn run(&self, ctx: Self::Context) -> anyhow::Result<()> {
-        info!("Processing messages from the CDN log queue…");
+        let queue: Box<dyn SqsQueue + Send + Sync> = match &ctx.config.cdn_log_queue {
+            CdnLogQueueConfig::Polling {
+                prefix,
+                pattern,
+                region,
+                bucket,
+            } => {
+                info!("Polling the CDN log storage for new log files…");
+
+                let store = build_store(&ctx.config.cdn_log_storage, region, bucket)
+                    .context("Failed to build object store")?;
+
+                let prefix = prefix.as_deref();
+                let max_files = self.max_messages;
+                let pool = &ctx.deadpool;
+                return super::poll::run(store, region, bucket, prefix, pattern, max_files, pool)
+                    .await;
+            }
+            CdnLogQueueConfig::Mock => Box::new(MockSqsQueue::new()),
+            CdnLogQueueConfig::SQS {
+                access_key,
+                secret_key,
+                region,
+                queue_url,
+            } => Box::new(build_sqs_queue(access_key, secret_key, region, queue_url)),
+        };
 
-        let queue = build_queue(&ctx.config.cdn_log_queue);
+        info!("Processing messages from the CDN log queue…");
         run(&queue, self.max_messages, &ctx.deadpool).await
     }
 }
 
-/// Builds an [SqsQueue] implementation based on the [CdnLogQueueConfig].
-fn build_queue(config: &CdnLogQueueConfig) -> Box<dyn SqsQueue + Send + Sync> {
-    match config {
-        CdnLogQueueConfig::Mock => Box::new(MockSqsQueue::new()),
-        CdnLogQueueConfig::SQS {
-            access_key,
-            secret_key,
-            region,
-            queue_url,
-        } => {
-            use secrecy::ExposeSecret;
+/// Builds an [SqsQueueImpl] for the [CdnLogQueueConfig::SQS] backend.
+fn build_sqs_queue(
+    access_key: &str,
+    secret_key: &SecretString,
+    region: &str,
+    queue_url: &str,
+) -> SqsQueueImpl {
+    let secret_key = secret_key.expose_secret();
+    let credentials = Credentials::from_keys(access_key, secret_key, None);
 
-            let secret_key = secret_key.expose_secret();
-            let credentials = Credentials::from_keys(access_key, secret_key, None);
+    let region = Region::new(region.to_owned());
 
-            let region = Region::new(region.to_owned());
-
-            Box::new(SqsQueueImpl::new(queue_url, region, credentials))
-        }
-    }
+    SqsQueueImpl::new(queue_url, region, credentials)
 }
 
 /// Processes messages from the CDN log queue.
@@ -203,11 +230,11 @@ fn job_from_record(record: super::message::Record) -> Option<ProcessCdnLog> {
 /// The CDN log files for the index domains are also stored in the same S3
 /// bucket, but we know that these don't contain any crate downloads, so we
 /// can ignore them.
-fn is_ignored_path(path: &str) -> bool {
+pub(super) fn is_ignored_path(path: &str) -> bool {
     path.contains("/index.staging.crates.io/") || path.contains("/index.crates.io/")
 }
 
-fn enqueue_jobs(jobs: Vec<ProcessCdnLog>, conn: &mut impl Conn) -> anyhow::Result<()> {
+pub(super) fn enqueue_jobs(jobs: Vec<ProcessCdnLog>, conn: &mut impl Conn) -> anyhow::Result<()> {
     for job in jobs {
         let path = &job.path;
 
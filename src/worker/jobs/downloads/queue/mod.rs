@@ -1,4 +1,5 @@
 mod job;
 mod message;
+mod poll;
 
 pub use job::ProcessCdnLogQueue;
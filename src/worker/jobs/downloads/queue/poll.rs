@@ -0,0 +1,429 @@
+//! Polling backend for the CDN log queue.
+//!
+//! Self-hosted deployments usually don't have S3 event notifications and SQS
+//! available, so instead we periodically list the CDN log storage and enqueue
+//! [ProcessCdnLog] jobs for all log files that have not been processed yet.
+
+use super::job::{enqueue_jobs, is_ignored_path};
+use crate::schema::processed_log_files;
+use crate::tasks::spawn_blocking;
+use crate::util::diesel::Conn;
+use crate::worker::jobs::ProcessCdnLog;
+use anyhow::Context;
+use chrono::{TimeDelta, Utc};
+use crates_io_worker::schema::background_jobs;
+use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
+use diesel_async::pooled_connection::deadpool::Pool;
+use diesel_async::AsyncPgConnection;
+use futures_util::TryStreamExt;
+use object_store::path::Path;
+use object_store::ObjectStore;
+use regex::Regex;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Log files older than this are ignored.
+///
+/// The `processed_log_files` table is cleaned up after one week by the
+/// [CleanProcessedLogFiles](crate::worker::jobs::CleanProcessedLogFiles) job,
+/// so older log files would otherwise be counted again. The additional day
+/// of margin accounts for delays in running the cleanup job.
+const MAX_AGE: TimeDelta = TimeDelta::days(6);
+
+/// Log files that were modified more recently than this are ignored, since
+/// they might still be uploaded to the CDN log storage.
+///
+/// Log files that are still written to, like the current `access.log` of
+/// nginx, have to be excluded by the path pattern instead, since they can be
+/// idle for longer than this.
+const MIN_AGE: TimeDelta = TimeDelta::minutes(10);
+
+/// A log file in the CDN log storage.
+#[derive(Debug)]
+struct LogFile {
+    path: String,
+    e_tag: Option<String>,
+}
+
+/// Lists the log files in the given object store and enqueues
+/// [ProcessCdnLog] jobs for at most `max_files` of them, oldest first.
+///
+/// Only log files with a path matching the `pattern` are considered, which
+/// should only match log files that have been closed for good, like the
+/// compressed log files of logrotate.
+///
+/// Log files that were already processed, or that already have a pending
+/// job, are skipped. Log files are also recognized as processed by their
+/// ETag, so that renamed log files (e.g. by logrotate) are not counted twice.
+pub async fn run(
+    store: Arc<dyn ObjectStore>,
+    region: &str,
+    bucket: &str,
+    prefix: Option<&str>,
+    pattern: &Regex,
+    max_files: usize,
+    connection_pool: &Pool<AsyncPgConnection>,
+) -> anyhow::Result<()> {
+    let prefix = prefix.map(Path::from);
+
+    debug!("Listing log files in the CDN log storage…");
+    let now = Utc::now();
+    let (min_modified, max_modified) = (now - MAX_AGE, now - MIN_AGE);
+    let mut objects = store
+        .list(prefix.as_ref())
+        .try_filter(|meta| {
+            let modified = meta.last_modified;
+            std::future::ready(modified >= min_modified && modified <= max_modified)
+        })
+        .try_collect::<Vec<_>>()
+        .await
+        .context("Failed to list log files")?;
+
+    objects.sort_by_key(|meta| meta.last_modified);
+
+    let log_files = objects
+        .into_iter()
+        .map(|meta| LogFile {
+            path: meta.location.to_string(),
+            e_tag: meta.e_tag,
+        })
+        .filter(|log_file| {
+            let ignored = is_ignored_path(&log_file.path);
+            if ignored {
+                debug!("Skipping ignored path: {}", log_file.path);
+            }
+            !ignored
+        })
+        .filter(|log_file| {
+            let matches = pattern.is_match(&log_file.path);
+            if !matches {
+                debug!("Skipping path not matching the pattern: {}", log_file.path);
+            }
+            matches
+        })
+        .collect::<Vec<_>>();
+
+    debug!("Found {} recent log files", log_files.len());
+    if log_files.is_empty() {
+        info!("No log files found in the CDN log storage");
+        return Ok(());
+    }
+
+    let region = region.to_string();
+    let bucket = bucket.to_string();
+
+    let conn = connection_pool.get().await;
+    let conn = conn.context("Failed to acquire database connection")?;
+    spawn_blocking(move || {
+        let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+        let known = KnownLogFiles::load(&log_files, conn)?;
+
+        let jobs = log_files
+            .into_iter()
+            .filter(|log_file| !known.contains(log_file))
+            .take(max_files)
+            .map(|log_file| ProcessCdnLog::new(region.clone(), bucket.clone(), log_file.path))
+            .collect::<Vec<_>>();
+
+        if jobs.is_empty() {
+            info!("No new log files found in the CDN log storage");
+            return Ok(());
+        }
+
+        enqueue_jobs(jobs, conn)
+    })
+    .await
+}
+
+/// The paths and ETags of log files that were already processed, and the
+/// paths of log files that already have a pending [ProcessCdnLog] job.
+#[derive(Debug, Default)]
+struct KnownLogFiles {
+    paths: HashSet<String>,
+    e_tags: HashSet<String>,
+}
+
+impl KnownLogFiles {
+    /// Loads the known log files among the given log files from the
+    /// database.
+    fn load(log_files: &[LogFile], conn: &mut impl Conn) -> QueryResult<Self> {
+        let paths = log_files.iter().map(|log_file| &log_file.path);
+        let e_tags = log_files
+            .iter()
+            .filter_map(|log_file| log_file.e_tag.as_ref());
+
+        let processed = processed_log_files::table
+            .select((processed_log_files::path, processed_log_files::e_tag))
+            .filter(processed_log_files::path.eq_any(paths))
+            .or_filter(processed_log_files::e_tag.eq_any(e_tags))
+            .load_iter::<(String, Option<String>), _>(conn)?;
+
+        let mut known = Self::default();
+        for row in processed {
+            let (path, e_tag) = row?;
+            known.paths.insert(path);
+            known.e_tags.extend(e_tag);
+        }
+
+        let pending_jobs = background_jobs::table
+            .select(background_jobs::data)
+            .filter(background_jobs::job_type.eq(ProcessCdnLog::JOB_NAME))
+            .load_iter::<serde_json::Value, _>(conn)?;
+
+        for data in pending_jobs {
+            match serde_json::from_value::<ProcessCdnLog>(data?) {
+                Ok(job) => {
+                    known.paths.insert(job.path);
+                }
+                Err(error) => warn!("Failed to deserialize pending job: {error}"),
+            }
+        }
+
+        Ok(known)
+    }
+
+    fn contains(&self, log_file: &LogFile) -> bool {
+        let known_e_tag = log_file.e_tag.as_ref().is_some_and(|e_tag| {
+            let known = self.e_tags.contains(e_tag);
+            if known {
+                debug!("Skipping renamed log file: {}", log_file.path);
+            }
+            known
+        });
+
+        known_e_tag || self.paths.contains(&log_file.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crates_io_test_db::TestDatabase;
+    use diesel_async::pooled_connection::AsyncDieselConnectionManager;
+    use insta::assert_snapshot;
+    use object_store::local::LocalFileSystem;
+    use std::time::{Duration, SystemTime};
+
+    #[tokio::test]
+    async fn test_poll() {
+        crate::util::tracing::init_for_test();
+
+        let dir = tempfile::tempdir().unwrap();
+        let paths = [
+            "nginx/access.log.1.gz",
+            "nginx/access.log.2.gz",
+            "cloudfront/index.crates.io/EUGCXGQIH3GQ3.2024-02-01-10.2e068fc2.gz",
+            "other/foo.log.gz",
+            "nginx/access.log.3.gz",
+        ];
+        for (i, path) in paths.into_iter().enumerate() {
+            let modified = SystemTime::now() - Duration::from_secs(3600 - i as u64);
+            create_log_file(dir.path(), path, modified);
+        }
+
+        // The live log file is still being written to
+        create_log_file(dir.path(), "nginx/access.log", SystemTime::now());
+
+        // The live log file is idle, but might still be written to later
+        let modified = SystemTime::now() - Duration::from_secs(3600);
+        create_log_file(dir.path(), "other/access.log", modified);
+
+        let store = Arc::new(LocalFileSystem::new_with_prefix(dir.path()).unwrap());
+
+        let test_database = TestDatabase::new();
+        let connection_pool = build_connection_pool(test_database.url());
+        let mut conn = test_database.connect();
+
+        diesel::insert_into(processed_log_files::table)
+            .values(processed_log_files::path.eq("nginx/access.log.1.gz"))
+            .execute(&mut conn)
+            .unwrap();
+
+        assert_ok!(
+            run(
+                store.clone(),
+                "local",
+                "logs",
+                Some("nginx"),
+                &pattern(),
+                1,
+                &connection_pool
+            )
+            .await
+        );
+        assert_snapshot!(open_jobs(&mut conn), @"local | logs | nginx/access.log.2.gz");
+
+        // Pending jobs are not enqueued a second time
+        assert_ok!(
+            run(
+                store.clone(),
+                "local",
+                "logs",
+                Some("nginx"),
+                &pattern(),
+                10,
+                &connection_pool
+            )
+            .await
+        );
+        assert_snapshot!(open_jobs(&mut conn), @r###"
+        local | logs | nginx/access.log.2.gz
+        local | logs | nginx/access.log.3.gz
+        "###);
+
+        // Without a prefix the whole store is listed, except for the ignored paths
+        let pattern = pattern();
+        assert_ok!(run(store, "local", "logs", None, &pattern, 10, &connection_pool).await);
+        assert_snapshot!(open_jobs(&mut conn), @r###"
+        local | logs | nginx/access.log.2.gz
+        local | logs | nginx/access.log.3.gz
+        local | logs | other/foo.log.gz
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_poll_renamed_log_files() {
+        crate::util::tracing::init_for_test();
+
+        let dir = tempfile::tempdir().unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(3600);
+        create_log_file(dir.path(), "nginx/access.log.1.gz", modified);
+
+        let store = Arc::new(LocalFileSystem::new_with_prefix(dir.path()).unwrap());
+        let meta = store.head(&"nginx/access.log.1.gz".into()).await.unwrap();
+
+        let test_database = TestDatabase::new();
+        let connection_pool = build_connection_pool(test_database.url());
+        let mut conn = test_database.connect();
+
+        diesel::insert_into(processed_log_files::table)
+            .values((
+                processed_log_files::path.eq("nginx/access.log.1.gz"),
+                processed_log_files::e_tag.eq(meta.e_tag),
+            ))
+            .execute(&mut conn)
+            .unwrap();
+
+        // logrotate renames the processed log file, without changing it
+        std::fs::rename(
+            dir.path().join("nginx/access.log.1.gz"),
+            dir.path().join("nginx/access.log.2.gz"),
+        )
+        .unwrap();
+
+        let pattern = pattern();
+        assert_ok!(run(store, "local", "logs", None, &pattern, 10, &connection_pool).await);
+        assert_snapshot!(open_jobs(&mut conn), @"");
+    }
+
+    #[tokio::test]
+    async fn test_poll_rotated_log_files() {
+        crate::util::tracing::init_for_test();
+
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(LocalFileSystem::new_with_prefix(dir.path()).unwrap());
+
+        let test_database = TestDatabase::new();
+        let connection_pool = build_connection_pool(test_database.url());
+        let mut conn = test_database.connect();
+
+        let pattern = pattern();
+        let poll = || {
+            run(
+                store.clone(),
+                "local",
+                "logs",
+                None,
+                &pattern,
+                10,
+                &connection_pool,
+            )
+        };
+
+        // The live log file has been idle for a while
+        let modified = SystemTime::now() - Duration::from_secs(3600);
+        std::fs::create_dir_all(dir.path().join("nginx")).unwrap();
+        std::fs::write(dir.path().join("nginx/access.log"), "foo\n").unwrap();
+        set_modified(dir.path(), "nginx/access.log", modified);
+
+        assert_ok!(poll().await);
+        assert_snapshot!(open_jobs(&mut conn), @"");
+
+        // logrotate renames the log file, but delays compressing it
+        let (from, to) = ("nginx/access.log", "nginx/access.log.1");
+        std::fs::rename(dir.path().join(from), dir.path().join(to)).unwrap();
+
+        assert_ok!(poll().await);
+        assert_snapshot!(open_jobs(&mut conn), @"");
+
+        // logrotate compresses the log file during the next rotation
+        std::fs::remove_file(dir.path().join("nginx/access.log.1")).unwrap();
+        std::fs::write(dir.path().join("nginx/access.log.2.gz"), "compressed").unwrap();
+        set_modified(dir.path(), "nginx/access.log.2.gz", modified);
+
+        assert_ok!(poll().await);
+        assert_snapshot!(open_jobs(&mut conn), @"local | logs | nginx/access.log.2.gz");
+
+        // The log file is processed
+        let meta = store.head(&"nginx/access.log.2.gz".into()).await.unwrap();
+        diesel::insert_into(processed_log_files::table)
+            .values((
+                processed_log_files::path.eq("nginx/access.log.2.gz"),
+                processed_log_files::e_tag.eq(meta.e_tag),
+            ))
+            .execute(&mut conn)
+            .unwrap();
+        diesel::delete(background_jobs::table)
+            .execute(&mut conn)
+            .unwrap();
+
+        // logrotate renames the compressed log file during the next rotation
+        let (from, to) = ("nginx/access.log.2.gz", "nginx/access.log.3.gz");
+        std::fs::rename(dir.path().join(from), dir.path().join(to)).unwrap();
+
+        assert_ok!(poll().await);
+        assert_snapshot!(open_jobs(&mut conn), @"");
+    }
+
+    fn pattern() -> Regex {
+        Regex::new(r"\.gz$").unwrap()
+    }
+
+    fn set_modified(dir: &std::path::Path, path: &str, modified: SystemTime) {
+        let file = std::fs::File::options()
+            .write(true)
+            .open(dir.join(path))
+            .unwrap();
+        file.set_modified(modified).unwrap();
+    }
+
+    fn create_log_file(dir: &std::path::Path, path: &str, modified: SystemTime) {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        let file = std::fs::File::create(path).unwrap();
+        file.set_modified(modified).unwrap();
+    }
+
+    fn build_connection_pool(url: &str) -> Pool<AsyncPgConnection> {
+        let manager = AsyncDieselConnectionManager::<AsyncPgConnection>::new(url);
+        Pool::builder(manager).build().unwrap()
+    }
+
+    fn open_jobs(conn: &mut impl Conn) -> String {
+        let jobs = background_jobs::table
+            .select(background_jobs::data)
+            .filter(background_jobs::job_type.eq(ProcessCdnLog::JOB_NAME))
+            .load::<serde_json::Value>(conn)
+            .unwrap();
+
+        jobs.into_iter()
+            .map(|data| serde_json::from_value::<ProcessCdnLog>(data).unwrap())
+            .map(|job| format!("{} | {} | {}", job.region, job.bucket, job.path))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
@@ -1,6 +1,7 @@
 use crate::schema::version_downloads;
 use crate::tasks::spawn_blocking;
 use crate::util::diesel::Conn;
+use crate::worker::jobs::SyncSearchIndexDownloads;
 use crate::worker::Environment;
 use crates_io_worker::BackgroundJob;
 use diesel::prelude::*;
@@ -21,7 +22,14 @@ impl BackgroundJob for UpdateDownloads {
         let conn = env.deadpool.get().await?;
         spawn_blocking(move || {
             let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
-            Ok(update(conn)?)
+            update(conn)?;
+
+            // The download counts in the search index would get stale otherwise
+            if env.search_backend.is_some() {
+                SyncSearchIndexDownloads.enqueue(conn)?;
+            }
+
+            Ok(())
         })
         .await
     }
@@ -2,7 +2,7 @@ use self::configuration::VisibilityConfig;
 use crate::tasks::spawn_blocking;
 use crate::worker::Environment;
 use anyhow::{anyhow, Context};
-use crates_io_worker::BackgroundJob;
+use crates_io_worker::{cancellation_token, BackgroundJob, JobCancelled};
 use secrecy::ExposeSecret;
 use std::fs::{self, File};
 use std::path::{Path, PathBuf};
@@ -41,6 +41,11 @@ impl BackgroundJob for DumpDb {
         })
         .await?;
 
+        if cancellation_token().is_cancelled() {
+            let error = anyhow!(JobCancelled);
+            return Err(error.context("Job was cancelled before uploading the archives"));
+        }
+
         info!("Uploading tarball…");
         env.storage
             .upload_db_dump(TAR_PATH, archives.tar.path())
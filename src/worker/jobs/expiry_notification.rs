@@ -1,4 +1,4 @@
-use crate::models::ApiToken;
+use crate::models::{ApiToken, NotificationEvent};
 use crate::schema::api_tokens;
 use crate::tasks::spawn_blocking;
 use crate::util::diesel::Conn;
@@ -79,9 +79,17 @@ fn handle_expiring_token(
     debug!("Looking up user {} for token {}…", token.user_id, token.id);
     let user = User::find(conn, token.user_id)?;
 
+    debug!("Looking up notification settings for user {}…", user.id);
+    let enabled = NotificationEvent::TokenExpiry.is_enabled_for(user.id, conn)?;
+
     debug!("Looking up email address for user {}…", user.id);
     let recipient = user.email(conn)?;
-    if let Some(recipient) = recipient {
+    if !enabled {
+        info!(
+            "User {} has disabled token expiry notifications. Skipping expiry notification.",
+            user.id
+        );
+    } else if let Some(recipient) = recipient {
         debug!("Sending expiry notification to {}…", recipient);
         let email = ExpiryNotificationEmail {
             name: &user.gh_login,
@@ -133,31 +141,24 @@ pub fn find_expiring_tokens(
 }
 
 #[derive(Debug, Clone)]
-struct ExpiryNotificationEmail<'a> {
-    name: &'a str,
-    token_id: i32,
-    token_name: &'a str,
-    expiry_date: chrono::DateTime<chrono::Utc>,
+pub struct ExpiryNotificationEmail<'a> {
+    pub name: &'a str,
+    pub token_id: i32,
+    pub token_name: &'a str,
+    pub expiry_date: chrono::DateTime<chrono::Utc>,
 }
 
-impl<'a> Email for ExpiryNotificationEmail<'a> {
+impl Email for ExpiryNotificationEmail<'_> {
     const SUBJECT: &'static str = "Your token is about to expire";
-
-    fn body(&self) -> String {
-        format!(
-            r#"Hi {},
-
-We noticed your token "{}" will expire on {}.
-
-If this token is still needed, visit https://crates.io/settings/tokens/new?from={} to generate a new one.
-
-Thanks,
-The crates.io team"#,
-            self.name,
-            self.token_name,
-            self.expiry_date.to_rfc3339_opts(SecondsFormat::Secs, true),
-            self.token_id
-        )
+    const TEMPLATE: &'static str = "token_expiry";
+
+    fn context(&self) -> minijinja::Value {
+        minijinja::context! {
+            user_name => self.name,
+            token_id => self.token_id,
+            token_name => self.token_name,
+            expiry_date => self.expiry_date.to_rfc3339_opts(SecondsFormat::Secs, true),
+        }
     }
 }
 
@@ -255,4 +256,45 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_expiry_notification_disabled() -> anyhow::Result<()> {
+        let emails = Emails::new_in_memory();
+        let (_test_db, mut conn) = test_db_connection();
+
+        let user = NewUser::new(0, "a", None, None, "token").create_or_update(
+            Some("testuser@test.com"),
+            &Emails::new_in_memory(),
+            &mut conn,
+        )?;
+        NotificationEvent::update_settings(
+            user.id,
+            &[(NotificationEvent::TokenExpiry, false)],
+            &mut conn,
+        )?;
+
+        let token = PlainToken::generate();
+        let token: ApiToken = diesel::insert_into(api_tokens::table)
+            .values((
+                api_tokens::user_id.eq(user.id),
+                api_tokens::name.eq("test_token"),
+                api_tokens::token.eq(token.hashed()),
+                api_tokens::expired_at.eq(now.nullable() + (EXPIRY_THRESHOLD.num_days() - 1).day()),
+            ))
+            .returning(ApiToken::as_returning())
+            .get_result(&mut conn)?;
+
+        check(&emails, &mut conn)?;
+
+        // Check that no email was sent, but the token is still marked as
+        // notified, so that it is not checked again.
+        assert_eq!(emails.mails_in_memory().unwrap().len(), 0);
+        let notified_at: Option<chrono::NaiveDateTime> = api_tokens::table
+            .find(token.id)
+            .select(api_tokens::expiry_notification_at)
+            .first(&mut conn)?;
+        assert!(notified_at.is_some());
+
+        Ok(())
+    }
 }
@@ -7,32 +7,54 @@ use diesel::sql_types::{Int2, Jsonb, Text};
 use std::fmt::Display;
 
 mod archive_version_downloads;
+mod changelogs;
 mod daily_db_maintenance;
 mod downloads;
 pub mod dump_db;
 mod expiry_notification;
 mod git;
+mod ownership_notifications;
+mod readme_links;
 mod readmes;
+mod reverse_dependency_notifications;
+mod rollup_version_downloads;
 pub mod rss;
 mod sync_admins;
+mod sync_search_index;
 mod typosquat;
 mod update_default_version;
+mod update_quality_scores;
+mod version_notifications;
+mod webhooks;
 
 pub use self::archive_version_downloads::ArchiveVersionDownloads;
+pub use self::changelogs::RenderAndUploadChangelog;
 pub use self::daily_db_maintenance::DailyDbMaintenance;
 pub use self::downloads::{
     CleanProcessedLogFiles, ProcessCdnLog, ProcessCdnLogQueue, UpdateDownloads,
 };
 pub use self::dump_db::DumpDb;
-pub use self::expiry_notification::SendTokenExpiryNotifications;
+pub use self::expiry_notification::{ExpiryNotificationEmail, SendTokenExpiryNotifications};
 pub use self::git::{NormalizeIndex, SquashIndex, SyncToGitIndex, SyncToSparseIndex};
-pub use self::readmes::RenderAndUploadReadme;
-pub use self::sync_admins::SyncAdmins;
-pub use self::typosquat::CheckTyposquat;
+pub use self::ownership_notifications::{OwnershipChangeEmail, SendOwnershipChangeNotifications};
+pub use self::readme_links::{BrokenLink, BrokenReadmeLinksEmail, CheckReadmeLinks};
+pub use self::readmes::{RenderAndUploadReadme, RerenderReadme};
+pub use self::reverse_dependency_notifications::{
+    NewReverseDependencyEmail, SendReverseDependencyNotifications,
+};
+pub use self::rollup_version_downloads::RollupVersionDownloads;
+pub use self::sync_admins::{AdminAccountEmail, SyncAdmins};
+pub use self::sync_search_index::{SyncSearchIndex, SyncSearchIndexDownloads};
+pub use self::typosquat::{CheckTyposquat, PossibleTyposquatEmail};
 pub use self::update_default_version::UpdateDefaultVersion;
+pub use self::update_quality_scores::UpdateQualityScores;
+pub use self::version_notifications::{
+    SendVersionNotifications, VersionActionDetails, VersionPublishedEmail, VersionYankedEmail,
+};
+pub use self::webhooks::{DeliverWebhook, TriggerWebhooks};
 
-/// Enqueue both index sync jobs (git and sparse) for a crate, unless they
-/// already exist in the background job queue.
+/// Enqueue both index sync jobs (git and sparse) and the search index sync
+/// job for a crate, unless they already exist in the background job queue.
 ///
 /// Note that there are currently no explicit tests for this functionality,
 /// since our test suite only allows us to use a single database connection
@@ -82,10 +104,16 @@ pub fn enqueue_sync_to_index<T: Display>(
         SyncToSparseIndex::PRIORITY,
     );
 
+    let to_search = deduplicated_select_query(
+        SyncSearchIndex::JOB_NAME,
+        serde_json::to_value(SyncSearchIndex::new(krate.to_string()))?,
+        SyncSearchIndex::PRIORITY,
+    );
+
     // Insert index update background jobs, but only if they do not
     // already exist.
     let added_jobs_count = diesel::insert_into(background_jobs::table)
-        .values(to_git.union_all(to_sparse))
+        .values(to_git.union_all(to_sparse).union_all(to_search))
         .into_columns((
             background_jobs::job_type,
             background_jobs::data,
@@ -94,8 +122,8 @@ pub fn enqueue_sync_to_index<T: Display>(
         .execute(conn)?;
 
     // Print a log event if we skipped inserting a job due to deduplication.
-    if added_jobs_count != 2 {
-        let skipped_jobs_count = 2 - added_jobs_count;
+    if added_jobs_count != 3 {
+        let skipped_jobs_count = 3 - added_jobs_count;
         info!(%skipped_jobs_count, "Skipped adding duplicate jobs to the background worker queue");
     }
 
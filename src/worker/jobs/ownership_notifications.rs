@@ -0,0 +1,101 @@
+//! Notify the owners of a crate when an owner is added to or removed from
+//! the crate.
+
+use crate::email::Email;
+use crate::models::{CrateOwner, NotificationEvent};
+use crate::schema::crates;
+use crate::tasks::spawn_blocking;
+use crate::worker::Environment;
+use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
+use std::sync::Arc;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SendOwnershipChangeNotifications {
+    crate_id: i32,
+    /// The login of the user or team that was added or removed.
+    owner: String,
+    added: bool,
+}
+
+impl SendOwnershipChangeNotifications {
+    pub fn new(crate_id: i32, owner: &str, added: bool) -> Self {
+        let owner = owner.to_string();
+        Self {
+            crate_id,
+            owner,
+            added,
+        }
+    }
+}
+
+impl BackgroundJob for SendOwnershipChangeNotifications {
+    const JOB_NAME: &'static str = "send_ownership_change_notifications";
+
+    type Context = Arc<Environment>;
+
+    #[instrument(skip_all, fields(crate_id = self.crate_id))]
+    async fn run(&self, env: Self::Context) -> anyhow::Result<()> {
+        let crate_id = self.crate_id;
+        let owner = self.owner.clone();
+        let added = self.added;
+
+        let conn = env.deadpool.get().await?;
+        spawn_blocking(move || {
+            let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+            let crate_name: String = crates::table
+                .find(crate_id)
+                .select(crates::name)
+                .first(conn)?;
+
+            let email = OwnershipChangeEmail {
+                crate_name: &crate_name,
+                owner: &owner,
+                added,
+            };
+
+            let event = NotificationEvent::OwnershipChange;
+            let recipients = CrateOwner::notification_emails(crate_id, event, conn)?;
+            info!(
+                num_recipients = recipients.len(),
+                "Sending {crate_name} ownership change notifications"
+            );
+
+            for recipient in recipients {
+                if let Err(error) = env.emails.send(&recipient, email) {
+                    error!(
+                        ?error,
+                        ?recipient,
+                        "Failed to send ownership change notification"
+                    );
+                }
+            }
+
+            Ok(())
+        })
+        .await
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OwnershipChangeEmail<'a> {
+    pub crate_name: &'a str,
+    /// The login of the user or team that was added or removed.
+    pub owner: &'a str,
+    pub added: bool,
+}
+
+impl Email for OwnershipChangeEmail<'_> {
+    const SUBJECT: &'static str = "The owners of your crate have changed";
+    const TEMPLATE: &'static str = "ownership_change";
+
+    fn context(&self) -> minijinja::Value {
+        minijinja::context! {
+            crate_name => self.crate_name,
+            owner => self.owner,
+            added => self.added,
+        }
+    }
+}
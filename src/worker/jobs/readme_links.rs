@@ -0,0 +1,169 @@
+//! Check the links in rendered READMEs and notify the crate owners about
+//! broken ones.
+
+use crate::email::Email;
+use crate::models::{CrateOwner, NotificationEvent};
+use crate::schema::{crates, readme_link_reports, versions};
+use crate::tasks::spawn_blocking;
+use crate::worker::Environment;
+use crates_io_worker::BackgroundJob;
+use diesel::dsl::now;
+use diesel::prelude::*;
+use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
+use futures_util::{future, stream, StreamExt};
+use std::sync::Arc;
+
+/// The maximum number of links that are checked per README.
+const MAX_LINKS: usize = 100;
+
+/// The number of links that are checked concurrently.
+const CONCURRENCY: usize = 8;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CheckReadmeLinks {
+    version_id: i32,
+    links: Vec<String>,
+}
+
+impl CheckReadmeLinks {
+    pub fn new(version_id: i32, links: Vec<String>) -> Self {
+        Self { version_id, links }
+    }
+}
+
+impl BackgroundJob for CheckReadmeLinks {
+    const JOB_NAME: &'static str = "check_readme_links";
+
+    type Context = Arc<Environment>;
+
+    #[instrument(skip_all, fields(version_id = self.version_id))]
+    async fn run(&self, env: Self::Context) -> anyhow::Result<()> {
+        let Some(link_checker) = env.link_checker.as_deref() else {
+            warn!("Skipping README link checks, since no link checker is configured");
+            return Ok(());
+        };
+
+        info!(num_links = self.links.len(), "Checking README links");
+
+        let checks = self
+            .links
+            .iter()
+            .take(MAX_LINKS)
+            .cloned()
+            .map(|url| async move {
+                let reason = link_checker.check(&url).await?;
+                Some(BrokenLink { url, reason })
+            });
+
+        let broken_links: Vec<BrokenLink> = stream::iter(checks)
+            .buffered(CONCURRENCY)
+            .filter_map(future::ready)
+            .collect()
+            .await;
+
+        if broken_links.is_empty() {
+            info!("Found no broken README links");
+        } else {
+            info!(?broken_links, "Found broken README links");
+        }
+
+        let version_id = self.version_id;
+        let conn = env.deadpool.get().await?;
+        spawn_blocking(move || {
+            let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+            let (crate_id, crate_name, version): (i32, String, String) = versions::table
+                .find(version_id)
+                .inner_join(crates::table)
+                .select((crates::id, crates::name, versions::num))
+                .first(conn)?;
+
+            // The README is checked every time it is rendered, so the owners
+            // are only notified if the broken links differ from the last report.
+            let mut urls = broken_links
+                .iter()
+                .map(|link| link.url.clone())
+                .collect::<Vec<_>>();
+            urls.sort();
+            urls.dedup();
+
+            let previous_urls: Option<Vec<String>> = readme_link_reports::table
+                .find(crate_id)
+                .select(readme_link_reports::broken_links)
+                .first(conn)
+                .optional()?;
+
+            diesel::insert_into(readme_link_reports::table)
+                .values((
+                    readme_link_reports::crate_id.eq(crate_id),
+                    readme_link_reports::broken_links.eq(&urls),
+                ))
+                .on_conflict(readme_link_reports::crate_id)
+                .do_update()
+                .set((
+                    readme_link_reports::broken_links.eq(&urls),
+                    readme_link_reports::checked_at.eq(now),
+                ))
+                .execute(conn)?;
+
+            if urls.is_empty() {
+                return Ok(());
+            }
+
+            if previous_urls.as_ref() == Some(&urls) {
+                info!("Skipping notification, since the broken links have already been reported");
+                return Ok(());
+            }
+
+            let email = BrokenReadmeLinksEmail {
+                crate_name: &crate_name,
+                version: &version,
+                broken_links: &broken_links,
+            };
+
+            for recipient in CrateOwner::notification_emails(
+                crate_id,
+                NotificationEvent::BrokenReadmeLinks,
+                conn,
+            )? {
+                if let Err(error) = env.emails.send(&recipient, email.clone()) {
+                    error!(
+                        ?error,
+                        ?recipient,
+                        "Failed to send broken README links notification"
+                    );
+                }
+            }
+
+            Ok(())
+        })
+        .await
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BrokenLink {
+    pub url: String,
+    /// A short description of why the link is considered to be broken.
+    pub reason: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct BrokenReadmeLinksEmail<'a> {
+    pub crate_name: &'a str,
+    pub version: &'a str,
+    pub broken_links: &'a [BrokenLink],
+}
+
+impl Email for BrokenReadmeLinksEmail<'_> {
+    const SUBJECT: &'static str = "Broken links in the README of your crate";
+    const TEMPLATE: &'static str = "broken_readme_links";
+
+    fn context(&self) -> minijinja::Value {
+        minijinja::context! {
+            crate_name => self.crate_name,
+            version => self.version,
+            broken_links => self.broken_links,
+        }
+    }
+}
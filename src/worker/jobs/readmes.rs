@@ -2,8 +2,11 @@
 
 use crate::models::Version;
 use crate::tasks::spawn_blocking;
+use crate::worker::jobs::CheckReadmeLinks;
 use crate::worker::Environment;
-use crates_io_markdown::text_to_html;
+use anyhow::anyhow;
+use crates_io_markdown::{extract_links, extract_toc, text_to_html_with_options, RenderOptions};
+use crates_io_tarball::find_readme;
 use crates_io_worker::BackgroundJob;
 use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
 use std::sync::Arc;
@@ -49,13 +52,19 @@ impl BackgroundJob for RenderAndUploadReadme {
 
         info!(version_id = ?self.version_id, "Rendering README");
 
+        let options = RenderOptions {
+            syntax_highlighting: env.config.readme_syntax_highlighting,
+            image_proxy: env.config.readme_image_proxy.clone(),
+        };
+
         let job = self.clone();
         let rendered = spawn_blocking(move || {
-            Ok::<_, anyhow::Error>(text_to_html(
+            Ok::<_, anyhow::Error>(text_to_html_with_options(
                 &job.text,
                 &job.readme_path,
                 job.base_url.as_deref(),
                 job.pkg_path_in_vcs.as_ref(),
+                &options,
             ))
         })
         .await?;
@@ -70,6 +79,7 @@ impl BackgroundJob for RenderAndUploadReadme {
 
             conn.transaction(|conn| {
                 Version::record_readme_rendering(job.version_id, conn)?;
+                Version::record_readme_toc(job.version_id, &extract_toc(&rendered), conn)?;
                 let (crate_name, vers): (String, String) = versions::table
                     .find(job.version_id)
                     .inner_join(crates::table)
@@ -78,6 +88,13 @@ impl BackgroundJob for RenderAndUploadReadme {
 
                 tracing::Span::current().record("krate.name", tracing::field::display(&crate_name));
 
+                if env.link_checker.is_some() {
+                    let links = extract_links(&rendered);
+                    if !links.is_empty() {
+                        CheckReadmeLinks::new(job.version_id, links).enqueue(conn)?;
+                    }
+                }
+
                 let bytes = rendered.into();
                 let future = env.storage.upload_readme(&crate_name, &vers, bytes);
                 Handle::current().block_on(future)?;
@@ -88,3 +105,77 @@ impl BackgroundJob for RenderAndUploadReadme {
         .await
     }
 }
+
+/// Renders the README of an already published version again, e.g. to pick up
+/// fixes to the renderer.
+///
+/// The README is read from the uploaded crate file, since the README that was
+/// part of the publish request is not stored anywhere.
+#[derive(Serialize, Deserialize)]
+pub struct RerenderReadme {
+    version_id: i32,
+}
+
+impl RerenderReadme {
+    pub fn new(version_id: i32) -> Self {
+        Self { version_id }
+    }
+}
+
+impl BackgroundJob for RerenderReadme {
+    const JOB_NAME: &'static str = "rerender_readme";
+    const PRIORITY: i16 = 50;
+
+    type Context = Arc<Environment>;
+
+    #[instrument(skip_all, fields(krate.name))]
+    async fn run(&self, env: Self::Context) -> anyhow::Result<()> {
+        use crate::schema::*;
+        use diesel::prelude::*;
+
+        let version_id = self.version_id;
+        info!(?version_id, "Re-rendering README");
+
+        let conn = env.deadpool.get().await?;
+        let (crate_name, vers): (String, String) = spawn_blocking(move || {
+            let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+            let version = versions::table
+                .find(version_id)
+                .inner_join(crates::table)
+                .select((crates::name, versions::num))
+                .first(conn)
+                .optional()?;
+
+            Ok::<_, anyhow::Error>(version)
+        })
+        .await?
+        .ok_or_else(|| anyhow!("Version {version_id} not found"))?;
+
+        tracing::Span::current().record("krate.name", tracing::field::display(&crate_name));
+
+        let tarball = env.storage.download_crate_file(&crate_name, &vers).await?;
+
+        let pkg_name = format!("{crate_name}-{vers}");
+        let max_unpack = env.config.max_unpack_size;
+        let readme = spawn_blocking(move || {
+            Ok::<_, anyhow::Error>(find_readme(&pkg_name, &tarball, max_unpack)?)
+        })
+        .await?;
+
+        let Some(readme) = readme else {
+            info!("Skipping re-rendering, since the crate file does not contain a README");
+            return Ok(());
+        };
+
+        let job = RenderAndUploadReadme::new(
+            version_id,
+            readme.contents,
+            readme.path,
+            readme.repository,
+            readme.path_in_vcs,
+        );
+
+        job.run(env).await
+    }
+}
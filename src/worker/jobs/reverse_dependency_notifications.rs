@@ -0,0 +1,121 @@
+//! Notify the owners of crates when a new crate starts depending on them.
+
+use crate::email::Email;
+use crate::models::{CrateOwner, DependencyKind, NotificationEvent, OwnerKind};
+use crate::schema::{crate_owners, crates, dependencies, versions};
+use crate::tasks::spawn_blocking;
+use crate::worker::Environment;
+use crates_io_worker::BackgroundJob;
+use diesel::dsl::{exists, not};
+use diesel::prelude::*;
+use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
+use std::sync::Arc;
+
+/// Sends notifications to the owners of all dependencies of the first
+/// version of a new crate.
+///
+/// Dev-dependencies are skipped, and so are dependencies that share an owner
+/// with the new crate, since their owners already know about it.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SendReverseDependencyNotifications {
+    version_id: i32,
+}
+
+impl SendReverseDependencyNotifications {
+    pub fn new(version_id: i32) -> Self {
+        Self { version_id }
+    }
+}
+
+impl BackgroundJob for SendReverseDependencyNotifications {
+    const JOB_NAME: &'static str = "send_reverse_dependency_notifications";
+
+    type Context = Arc<Environment>;
+
+    #[instrument(skip_all, fields(version_id = self.version_id))]
+    async fn run(&self, env: Self::Context) -> anyhow::Result<()> {
+        let version_id = self.version_id;
+        let conn = env.deadpool.get().await?;
+        spawn_blocking(move || {
+            let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+            let (crate_id, dependent, version): (i32, String, String) = versions::table
+                .find(version_id)
+                .inner_join(crates::table)
+                .select((crates::id, crates::name, versions::num))
+                .first(conn)?;
+
+            let dependent_owners: Vec<i32> = CrateOwner::by_owner_kind(OwnerKind::User)
+                .filter(crate_owners::crate_id.eq(crate_id))
+                .select(crate_owners::owner_id)
+                .load(conn)?;
+
+            // Dependencies that are (partially) owned by the same users as
+            // the new crate are skipped.
+            let shared_owners = crate_owners::table
+                .filter(crate_owners::crate_id.eq(crates::id))
+                .filter(crate_owners::deleted.eq(false))
+                .filter(crate_owners::owner_kind.eq(OwnerKind::User))
+                .filter(crate_owners::owner_id.eq_any(dependent_owners));
+
+            let dependencies: Vec<(i32, String)> = dependencies::table
+                .filter(dependencies::version_id.eq(version_id))
+                .filter(dependencies::kind.ne(DependencyKind::Dev))
+                .inner_join(crates::table)
+                .filter(not(exists(shared_owners)))
+                .select((crates::id, crates::name))
+                .distinct()
+                .load(conn)?;
+
+            let event = NotificationEvent::NewReverseDependency;
+            for (dependency_id, crate_name) in dependencies {
+                let recipients = CrateOwner::notification_emails(dependency_id, event, conn)?;
+                info!(
+                    num_recipients = recipients.len(),
+                    "Sending {crate_name} reverse dependency notifications"
+                );
+
+                let email = NewReverseDependencyEmail {
+                    crate_name: &crate_name,
+                    dependent: &dependent,
+                    version: &version,
+                };
+
+                for recipient in recipients {
+                    if let Err(error) = env.emails.send(&recipient, email) {
+                        error!(
+                            ?error,
+                            ?recipient,
+                            "Failed to send reverse dependency notification"
+                        );
+                    }
+                }
+            }
+
+            Ok(())
+        })
+        .await
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct NewReverseDependencyEmail<'a> {
+    /// The name of the crate that is depended on.
+    pub crate_name: &'a str,
+    /// The name of the new crate that depends on `crate_name`.
+    pub dependent: &'a str,
+    pub version: &'a str,
+}
+
+impl Email for NewReverseDependencyEmail<'_> {
+    const SUBJECT: &'static str = "A new crate depends on your crate";
+    const TEMPLATE: &'static str = "new_reverse_dependency";
+
+    fn context(&self) -> minijinja::Value {
+        minijinja::context! {
+            crate_name => self.crate_name,
+            dependent => self.dependent,
+            version => self.version,
+        }
+    }
+}
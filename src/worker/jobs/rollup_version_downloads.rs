@@ -0,0 +1,259 @@
+use crate::schema::version_downloads_monthly;
+use crate::tasks::spawn_blocking;
+use crate::util::dates::first_day_of_month;
+use crate::util::diesel::Conn;
+use crate::worker::Environment;
+use anyhow::Context;
+use chrono::{Datelike, NaiveDate};
+use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+use diesel::sql_types::{Array, BigInt, Date, Integer};
+use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
+use diesel_async::pooled_connection::deadpool::Pool;
+use diesel_async::AsyncPgConnection;
+use object_store::ObjectStore;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Roll up the archived daily version downloads of a month into the
+/// `version_downloads_monthly` table.
+///
+/// The per-date CSV files that were uploaded by the
+/// [`ArchiveVersionDownloads`](super::ArchiveVersionDownloads) job are read
+/// from the downloads archive store and summed up per version. Existing
+/// rollup rows for the month are replaced, so running this job multiple
+/// times for the same month is safe.
+#[derive(Serialize, Deserialize)]
+pub struct RollupVersionDownloads {
+    month: NaiveDate,
+}
+
+impl RollupVersionDownloads {
+    /// Creates a job for the month of the given date.
+    pub fn month(date: NaiveDate) -> Self {
+        Self {
+            month: first_day_of_month(date),
+        }
+    }
+}
+
+impl BackgroundJob for RollupVersionDownloads {
+    const JOB_NAME: &'static str = "rollup_version_downloads";
+
+    type Context = Arc<Environment>;
+
+    async fn run(&self, env: Self::Context) -> anyhow::Result<()> {
+        let month = first_day_of_month(self.month);
+        info!(%month, "Rolling up archived version downloads…");
+
+        let Some(downloads_archive_store) = env.downloads_archive_store.as_ref() else {
+            warn!("No downloads archive store configured");
+            return Ok(());
+        };
+
+        let downloads = read_month(downloads_archive_store, month).await?;
+        if downloads.is_empty() {
+            info!(%month, "No archived version downloads found");
+            return Ok(());
+        }
+
+        save(&env.deadpool, month, downloads).await?;
+
+        info!(%month, "Finished rolling up archived version downloads");
+        Ok(())
+    }
+}
+
+/// Reads all per-date CSV files of the given month from the object store
+/// and sums up the downloads per version.
+///
+/// Dates without a CSV file in the object store are skipped.
+pub(super) async fn read_month(
+    store: &impl ObjectStore,
+    month: NaiveDate,
+) -> anyhow::Result<BTreeMap<i32, i64>> {
+    #[derive(Deserialize)]
+    struct Row {
+        version_id: i32,
+        downloads: i64,
+    }
+
+    let mut downloads = BTreeMap::new();
+
+    let dates = month
+        .iter_days()
+        .take_while(|date| date.month() == month.month());
+
+    for date in dates {
+        let path = object_store::path::Path::from(format!("{date}.csv"));
+        let bytes = match store.get(&path).await {
+            Ok(result) => result.bytes().await?,
+            Err(object_store::Error::NotFound { .. }) => continue,
+            Err(error) => return Err(error.into()),
+        };
+
+        debug!(%path, "Reading archived version downloads…");
+        let mut reader = csv::Reader::from_reader(&bytes[..]);
+        for row in reader.deserialize() {
+            let row: Row = row.with_context(|| format!("Failed to parse {path}"))?;
+            *downloads.entry(row.version_id).or_default() += row.downloads;
+        }
+    }
+
+    Ok(downloads)
+}
+
+/// Saves the monthly version downloads to the `version_downloads_monthly`
+/// table, replacing all existing rows for the same month.
+async fn save(
+    db_pool: &Pool<AsyncPgConnection>,
+    month: NaiveDate,
+    downloads: BTreeMap<i32, i64>,
+) -> anyhow::Result<()> {
+    let conn = db_pool.get().await?;
+    spawn_blocking(move || {
+        let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+        save_inner(conn, month, downloads)
+    })
+    .await
+}
+
+pub(super) fn save_inner(
+    conn: &mut impl Conn,
+    month: NaiveDate,
+    downloads: BTreeMap<i32, i64>,
+) -> anyhow::Result<()> {
+    let (version_ids, downloads): (Vec<_>, Vec<_>) = downloads.into_iter().unzip();
+
+    let existing_rows =
+        version_downloads_monthly::table.filter(version_downloads_monthly::month.eq(month));
+    diesel::delete(existing_rows).execute(conn)?;
+
+    // Versions that have been deleted since the downloads were archived are
+    // skipped by the join with the `versions` table.
+    let num_rows = diesel::sql_query(
+        r#"
+            INSERT INTO version_downloads_monthly (version_id, month, downloads)
+            SELECT versions.id, $1, archived.downloads
+            FROM UNNEST($2, $3) AS archived (version_id, downloads)
+            INNER JOIN versions ON versions.id = archived.version_id
+        "#,
+    )
+    .bind::<Date, _>(month)
+    .bind::<Array<Integer>, _>(version_ids)
+    .bind::<Array<BigInt>, _>(downloads)
+    .execute(conn)?;
+
+    info!(%month, "Saved {num_rows} rows to `version_downloads_monthly`");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{crates, versions};
+    use crates_io_test_db::TestDatabase;
+    use insta::assert_debug_snapshot;
+    use object_store::memory::InMemory;
+
+    #[tokio::test]
+    async fn test_read_month() {
+        let store = InMemory::new();
+        put(
+            &store,
+            "2021-01-01.csv",
+            "version_id,downloads\n1,100\n2,400",
+        )
+        .await;
+        put(
+            &store,
+            "2021-01-31.csv",
+            "version_id,downloads\n1,200\n3,50",
+        )
+        .await;
+        put(&store, "2021-02-01.csv", "version_id,downloads\n1,1000").await;
+
+        let month = NaiveDate::from_ymd_opt(2021, 1, 1).unwrap();
+        let downloads = read_month(&store, month).await.unwrap();
+        assert_debug_snapshot!(downloads, @r###"
+        {
+            1: 300,
+            2: 400,
+            3: 50,
+        }
+        "###);
+
+        let month = NaiveDate::from_ymd_opt(2021, 3, 1).unwrap();
+        let downloads = read_month(&store, month).await.unwrap();
+        assert!(downloads.is_empty());
+    }
+
+    #[test]
+    fn test_save() {
+        let test_db = TestDatabase::new();
+        let mut conn = test_db.connect();
+
+        let crate_id: i32 = diesel::insert_into(crates::table)
+            .values(crates::name.eq("foo"))
+            .returning(crates::id)
+            .get_result(&mut conn)
+            .unwrap();
+
+        let version_ids: Vec<i32> = diesel::insert_into(versions::table)
+            .values(vec![
+                (
+                    versions::crate_id.eq(crate_id),
+                    versions::num.eq("1.0.0"),
+                    versions::checksum.eq("checksum"),
+                ),
+                (
+                    versions::crate_id.eq(crate_id),
+                    versions::num.eq("1.1.0"),
+                    versions::checksum.eq("checksum"),
+                ),
+            ])
+            .returning(versions::id)
+            .get_results(&mut conn)
+            .unwrap();
+
+        let (version_id, other_version_id) = (version_ids[0], version_ids[1]);
+        let deleted_version_id = other_version_id + 1;
+
+        let month = NaiveDate::from_ymd_opt(2021, 1, 1).unwrap();
+        let downloads = BTreeMap::from([
+            (version_id, 300),
+            (other_version_id, 100),
+            (deleted_version_id, 400),
+        ]);
+        save_inner(&mut conn, month, downloads).unwrap();
+
+        // Saving the same month again replaces the previous rollup, instead
+        // of adding to it.
+        let downloads = BTreeMap::from([(version_id, 500)]);
+        save_inner(&mut conn, month, downloads).unwrap();
+
+        let rows: Vec<(i32, NaiveDate, i64)> = version_downloads_monthly::table
+            .select((
+                version_downloads_monthly::version_id,
+                version_downloads_monthly::month,
+                version_downloads_monthly::downloads,
+            ))
+            .load(&mut conn)
+            .unwrap();
+
+        assert_eq!(rows, vec![(version_id, month, 500)]);
+    }
+
+    #[test]
+    fn test_month() {
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let expected = NaiveDate::from_ymd_opt(2024, 2, 1).unwrap();
+        assert_eq!(RollupVersionDownloads::month(date).month, expected);
+    }
+
+    async fn put(store: &impl ObjectStore, path: &str, content: &'static str) {
+        let path = object_store::path::Path::from(path);
+        store.put(&path, content.into()).await.unwrap();
+    }
+}
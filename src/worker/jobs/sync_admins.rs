@@ -7,7 +7,6 @@ use diesel::prelude::*;
 use diesel::RunQueryDsl;
 use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
 use std::collections::HashSet;
-use std::fmt::{Display, Formatter};
 use std::sync::Arc;
 
 /// See <https://github.com/rust-lang/team/pull/1197>.
@@ -165,13 +164,13 @@ impl BackgroundJob for SyncAdmins {
 }
 
 #[derive(Debug, Clone)]
-struct AdminAccountEmail {
-    added_admins: Vec<String>,
-    removed_admins: Vec<String>,
+pub struct AdminAccountEmail {
+    pub added_admins: Vec<String>,
+    pub removed_admins: Vec<String>,
 }
 
 impl AdminAccountEmail {
-    fn new(added_admins: Vec<String>, removed_admins: Vec<String>) -> Self {
+    pub fn new(added_admins: Vec<String>, removed_admins: Vec<String>) -> Self {
         Self {
             added_admins,
             removed_admins,
@@ -181,29 +180,12 @@ impl AdminAccountEmail {
 
 impl Email for AdminAccountEmail {
     const SUBJECT: &'static str = "crates.io: Admin account changes";
+    const TEMPLATE: &'static str = "admin_accounts";
 
-    fn body(&self) -> String {
-        self.to_string()
-    }
-}
-
-impl Display for AdminAccountEmail {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        if !self.added_admins.is_empty() {
-            writeln!(f, "Granted admin access:\n")?;
-            for new_admin in &self.added_admins {
-                writeln!(f, "- {}", new_admin)?;
-            }
-            writeln!(f)?;
+    fn context(&self) -> minijinja::Value {
+        minijinja::context! {
+            added_admins => self.added_admins,
+            removed_admins => self.removed_admins,
         }
-
-        if !self.removed_admins.is_empty() {
-            writeln!(f, "Revoked admin access:")?;
-            for obsolete_admin in &self.removed_admins {
-                writeln!(f, "- {}", obsolete_admin)?;
-            }
-        }
-
-        Ok(())
     }
 }
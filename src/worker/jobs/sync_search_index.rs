@@ -0,0 +1,122 @@
+use crate::schema::{crate_downloads, crates};
+use crate::search::CrateDocument;
+use crate::tasks::spawn_blocking;
+use crate::worker::Environment;
+use anyhow::Context;
+use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
+use std::sync::Arc;
+
+/// Updates or removes the document of a crate in the external search
+/// backend.
+///
+/// This job is enqueued together with the index sync jobs whenever a crate
+/// is published, yanked, unyanked or deleted. If no external search backend
+/// is configured, the job does nothing.
+#[derive(Serialize, Deserialize)]
+pub struct SyncSearchIndex {
+    krate: String,
+}
+
+impl SyncSearchIndex {
+    pub fn new(krate: impl Into<String>) -> Self {
+        let krate = krate.into();
+        Self { krate }
+    }
+}
+
+impl BackgroundJob for SyncSearchIndex {
+    const JOB_NAME: &'static str = "sync_search_index";
+    const PRIORITY: i16 = 50;
+
+    type Context = Arc<Environment>;
+
+    #[instrument(skip_all, fields(krate.name = ? self.krate))]
+    async fn run(&self, env: Self::Context) -> anyhow::Result<()> {
+        let Some(search_backend) = env.search_backend.clone() else {
+            debug!("No search backend configured");
+            return Ok(());
+        };
+
+        let crate_name = self.krate.clone();
+        let conn = env.deadpool.get().await?;
+        let document = spawn_blocking(move || {
+            let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+            CrateDocument::load(&crate_name, conn).context("Failed to load search document")
+        })
+        .await?;
+
+        match document {
+            Some(document) => {
+                info!("Updating search index document");
+                search_backend.upsert(&document).await?;
+            }
+            None => {
+                info!("Removing search index document");
+                search_backend.delete(&self.krate).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Updates the documents of the most downloaded crates in the external
+/// search backend.
+///
+/// The download counts of the documents are only updated when a crate is
+/// published, yanked or unyanked otherwise, but they are used to rank the
+/// search results. This job is enqueued by
+/// [`UpdateDownloads`](super::UpdateDownloads) to keep the counts of the
+/// crates whose ranking matters the most reasonably fresh.
+#[derive(Serialize, Deserialize)]
+pub struct SyncSearchIndexDownloads;
+
+impl SyncSearchIndexDownloads {
+    /// The number of crates whose documents are updated.
+    const NUM_CRATES: i64 = 1000;
+}
+
+impl BackgroundJob for SyncSearchIndexDownloads {
+    const JOB_NAME: &'static str = "sync_search_index_downloads";
+
+    type Context = Arc<Environment>;
+
+    async fn run(&self, env: Self::Context) -> anyhow::Result<()> {
+        let Some(search_backend) = env.search_backend.clone() else {
+            debug!("No search backend configured");
+            return Ok(());
+        };
+
+        let conn = env.deadpool.get().await?;
+        let documents = spawn_blocking(move || {
+            let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+            let names: Vec<String> = crates::table
+                .inner_join(crate_downloads::table)
+                .select(crates::name)
+                .order(crate_downloads::downloads.desc())
+                .limit(Self::NUM_CRATES)
+                .load(conn)?;
+
+            names
+                .iter()
+                .filter_map(|name| CrateDocument::load(name, conn).transpose())
+                .collect::<QueryResult<Vec<_>>>()
+                .context("Failed to load search documents")
+        })
+        .await?;
+
+        info!(
+            num_documents = documents.len(),
+            "Updating download counts in the search index"
+        );
+
+        for document in &documents {
+            search_backend.upsert(document).await?;
+        }
+
+        Ok(())
+    }
+}
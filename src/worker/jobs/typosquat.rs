@@ -5,6 +5,7 @@ use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
 use typomania::Package;
 
 use crate::email::Email;
+use crate::models::NotificationEvent;
 use crate::tasks::spawn_blocking;
 use crate::util::diesel::Conn;
 use crate::{
@@ -58,12 +59,16 @@ fn check(emails: &Emails, cache: &Cache, conn: &mut impl Conn, name: &str) -> an
             info!(?squats, "Found potential typosquatting");
 
             let email = PossibleTyposquatEmail {
-                domain: &emails.domain,
                 crate_name: name,
                 squats: &squats,
             };
 
             for recipient in cache.iter_emails() {
+                if !NotificationEvent::Typosquat.is_enabled_for_email(recipient, conn)? {
+                    info!(?recipient, "Skipping disabled typosquat notification");
+                    continue;
+                }
+
                 if let Err(error) = emails.send(recipient, email.clone()) {
                     error!(
                         ?error,
@@ -79,43 +84,37 @@ fn check(emails: &Emails, cache: &Cache, conn: &mut impl Conn, name: &str) -> an
 }
 
 #[derive(Debug, Clone)]
-struct PossibleTyposquatEmail<'a> {
-    domain: &'a str,
-    crate_name: &'a str,
-    squats: &'a [typomania::checks::Squat],
+pub struct PossibleTyposquatEmail<'a> {
+    pub crate_name: &'a str,
+    pub squats: &'a [typomania::checks::Squat],
 }
 
 impl Email for PossibleTyposquatEmail<'_> {
     const SUBJECT: &'static str = "Possible typosquatting in new crate";
+    const TEMPLATE: &'static str = "possible_typosquat";
 
-    fn body(&self) -> String {
+    fn context(&self) -> minijinja::Value {
         let squats = self
             .squats
             .iter()
             .map(|squat| {
-                let domain = self.domain;
-                let crate_name = squat.package();
-                format!("- {squat} (https://{domain}/crates/{crate_name})\n")
+                minijinja::context! {
+                    description => squat.to_string(),
+                    crate_name => squat.package(),
+                }
             })
-            .collect::<Vec<_>>()
-            .join("");
-
-        format!(
-            "New crate {crate_name} may be typosquatting one or more other crates.
-
-Visit https://{domain}/crates/{crate_name} to see the offending crate.
+            .collect::<Vec<_>>();
 
-Specific squat checks that triggered:
-
-{squats}",
-            domain = self.domain,
-            crate_name = self.crate_name,
-        )
+        minijinja::context! {
+            crate_name => self.crate_name,
+            squats,
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::models::NewUser;
     use crate::{test_util::test_db_connection, typosquat::test_util::Faker};
     use lettre::Address;
 
@@ -164,4 +163,51 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn disabled_notifications() -> anyhow::Result<()> {
+        let emails = Emails::new_in_memory();
+        let (_test_db, mut conn) = test_db_connection();
+        let mut faker = Faker::new();
+
+        let user = faker.user(&mut conn, "a")?;
+        faker.crate_and_version(&mut conn, "my-crate", "It's awesome", &user, 100)?;
+
+        // The first recipient has opted out of typosquatting notifications.
+        let admin = NewUser::new(100, "admin", None, None, "token").create_or_update(
+            Some("admin@example.com"),
+            &Emails::new_in_memory(),
+            &mut conn,
+        )?;
+        NotificationEvent::update_settings(
+            admin.id,
+            &[(NotificationEvent::Typosquat, false)],
+            &mut conn,
+        )?;
+
+        let recipients = vec![
+            "admin@example.com".to_string(),
+            "security@example.com".to_string(),
+        ];
+        let cache = Cache::new(recipients, &mut conn)?;
+
+        let other_user = faker.user(&mut conn, "b")?;
+        let (demon, _version) = faker.crate_and_version(
+            &mut conn,
+            "mycrate",
+            "I'm even more innocent, obviously",
+            &other_user,
+            0,
+        )?;
+
+        check(&emails, &cache, &mut conn, &demon.name)?;
+        let sent_mail = emails.mails_in_memory().unwrap();
+        assert_eq!(sent_mail.len(), 1);
+        assert_eq!(
+            &sent_mail[0].0.to(),
+            &["security@example.com".parse::<Address>()?]
+        );
+
+        Ok(())
+    }
 }
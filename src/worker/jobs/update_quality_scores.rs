@@ -0,0 +1,155 @@
+use crate::tasks::spawn_blocking;
+use crate::util::diesel::Conn;
+use crate::worker::Environment;
+use crates_io_worker::BackgroundJob;
+use diesel::sql_types::Double;
+use diesel::{QueryResult, RunQueryDsl};
+use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
+use std::sync::Arc;
+
+/// The weight of the recent downloads, relative to the most downloaded crate.
+const DOWNLOADS_WEIGHT: f64 = 0.4;
+/// The weight of the number of distinct reverse dependencies, relative to
+/// the crate with the most reverse dependencies.
+const DEPENDENTS_WEIGHT: f64 = 0.3;
+/// The weight of the recency of the last non-yanked release.
+const RECENCY_WEIGHT: f64 = 0.2;
+/// The weight of the ratio of versions that are not yanked.
+const NOT_YANKED_WEIGHT: f64 = 0.1;
+
+/// Recompute the quality scores of all crates, which are used for the
+/// `quality` sort of the crate search.
+///
+/// The scores are stored in the `crate_quality_scores` table and range from
+/// `0` to `1`. Since they use the `recent_crate_downloads` view, this job
+/// should run after the [`UpdateDownloads`](super::UpdateDownloads) job.
+#[derive(Serialize, Deserialize)]
+pub struct UpdateQualityScores;
+
+impl BackgroundJob for UpdateQualityScores {
+    const JOB_NAME: &'static str = "update_quality_scores";
+
+    type Context = Arc<Environment>;
+
+    async fn run(&self, env: Self::Context) -> anyhow::Result<()> {
+        let conn = env.deadpool.get().await?;
+        spawn_blocking(move || {
+            let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+            info!("Updating crate quality scores…");
+            let num_rows = update(conn)?;
+            info!("Updated {num_rows} crate quality scores");
+
+            Ok(())
+        })
+        .await
+    }
+}
+
+fn update(conn: &mut impl Conn) -> QueryResult<usize> {
+    diesel::sql_query(include_str!("update_quality_scores.sql"))
+        .bind::<Double, _>(DOWNLOADS_WEIGHT)
+        .bind::<Double, _>(DEPENDENTS_WEIGHT)
+        .bind::<Double, _>(RECENCY_WEIGHT)
+        .bind::<Double, _>(NOT_YANKED_WEIGHT)
+        .execute(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::update_default_version;
+    use crate::schema::{crate_quality_scores, crates, dependencies, versions};
+    use chrono::{Duration, Utc};
+    use crates_io_test_db::TestDatabase;
+    use diesel::prelude::*;
+
+    #[test]
+    fn test_update() {
+        let test_db = TestDatabase::new();
+        let mut conn = test_db.connect();
+        let conn = &mut conn;
+
+        let now = Utc::now().naive_utc();
+
+        let popular = create_crate(conn, "popular", &[(now, false)]);
+        let abandoned = create_crate(conn, "abandoned", &[(now - Duration::days(1000), false)]);
+        let yanked = create_crate(conn, "yanked", &[(now, false), (now, true), (now, true)]);
+        let dependent = create_crate(conn, "dependent", &[(now, false)]);
+
+        // Multiple dependencies of the same crate only count once
+        for kind in [0, 1] {
+            let version_id = default_version_id(conn, dependent);
+            diesel::insert_into(dependencies::table)
+                .values((
+                    dependencies::version_id.eq(version_id),
+                    dependencies::crate_id.eq(popular),
+                    dependencies::req.eq("^1"),
+                    dependencies::optional.eq(false),
+                    dependencies::default_features.eq(true),
+                    dependencies::features.eq(Vec::<String>::new()),
+                    dependencies::kind.eq(kind),
+                ))
+                .execute(conn)
+                .unwrap();
+        }
+
+        assert_eq!(update(conn).unwrap(), 4);
+        // Running the job again updates the existing scores
+        assert_eq!(update(conn).unwrap(), 4);
+
+        let scores: Vec<(i32, f32)> = crate_quality_scores::table
+            .select((crate_quality_scores::crate_id, crate_quality_scores::score))
+            .order(crate_quality_scores::score.desc())
+            .load(conn)
+            .unwrap();
+
+        let ids = scores.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+        assert_eq!(ids, [popular, dependent, yanked, abandoned]);
+
+        let score = |id| scores.iter().find(|s| s.0 == id).unwrap().1;
+        assert!((score(popular) - 0.6).abs() < 0.01);
+        assert!((score(dependent) - 0.3).abs() < 0.01);
+        assert!((score(yanked) - 0.2333).abs() < 0.01);
+        assert!(score(abandoned) < 0.2);
+    }
+
+    fn create_crate(
+        conn: &mut PgConnection,
+        name: &str,
+        versions: &[(chrono::NaiveDateTime, bool)],
+    ) -> i32 {
+        let crate_id = diesel::insert_into(crates::table)
+            .values(crates::name.eq(name))
+            .returning(crates::id)
+            .get_result(conn)
+            .unwrap();
+
+        for (i, &(created_at, yanked)) in versions.iter().enumerate() {
+            diesel::insert_into(versions::table)
+                .values((
+                    versions::crate_id.eq(crate_id),
+                    versions::num.eq(format!("1.0.{i}")),
+                    versions::checksum.eq("checksum"),
+                    versions::created_at.eq(created_at),
+                    versions::yanked.eq(yanked),
+                ))
+                .execute(conn)
+                .unwrap();
+        }
+
+        update_default_version(crate_id, conn).unwrap();
+
+        crate_id
+    }
+
+    fn default_version_id(conn: &mut PgConnection, crate_id: i32) -> i32 {
+        use crate::schema::default_versions;
+
+        default_versions::table
+            .find(crate_id)
+            .select(default_versions::version_id)
+            .get_result(conn)
+            .unwrap()
+    }
+}
@@ -0,0 +1,53 @@
+WITH reverse_dependencies AS (
+    -- Number of distinct crates whose default version depends on the crate,
+    -- ignoring fully yanked crates (see `krate_reverse_dependencies.sql`)
+    SELECT
+        dependencies.crate_id,
+        COUNT(DISTINCT default_versions.crate_id) AS dependents
+    FROM default_versions
+    INNER JOIN versions
+        ON versions.id = default_versions.version_id
+    INNER JOIN dependencies
+        ON dependencies.version_id = default_versions.version_id
+    WHERE NOT versions.yanked
+        AND dependencies.crate_id != default_versions.crate_id
+    GROUP BY dependencies.crate_id
+), version_stats AS (
+    SELECT
+        crate_id,
+        MAX(created_at) FILTER (WHERE NOT yanked) AS last_release,
+        COUNT(*) FILTER (WHERE yanked)::float8 / COUNT(*) AS yank_ratio
+    FROM versions
+    GROUP BY crate_id
+), inputs AS (
+    SELECT
+        crates.id AS crate_id,
+        -- Logarithmic scales, so that a few very popular crates don't push
+        -- the scores of all other crates towards zero
+        ln(1 + COALESCE(recent_crate_downloads.downloads, 0)) AS downloads,
+        ln(1 + COALESCE(reverse_dependencies.dependents, 0)) AS dependents,
+        -- Decays to ~37% one year after the last (non-yanked) release
+        exp(-EXTRACT(EPOCH FROM LOCALTIMESTAMP - version_stats.last_release)::float8 / (365 * 24 * 60 * 60)) AS recency,
+        COALESCE(version_stats.yank_ratio, 1) AS yank_ratio
+    FROM crates
+    LEFT JOIN recent_crate_downloads
+        ON recent_crate_downloads.crate_id = crates.id
+    LEFT JOIN reverse_dependencies
+        ON reverse_dependencies.crate_id = crates.id
+    LEFT JOIN version_stats
+        ON version_stats.crate_id = crates.id
+)
+INSERT INTO crate_quality_scores (crate_id, score, computed_at)
+SELECT
+    crate_id,
+    (
+        $1 * COALESCE(downloads / NULLIF(MAX(downloads) OVER (), 0), 0)
+        + $2 * COALESCE(dependents / NULLIF(MAX(dependents) OVER (), 0), 0)
+        + $3 * COALESCE(LEAST(recency, 1), 0)
+        + $4 * (1 - yank_ratio)
+    )::real,
+    LOCALTIMESTAMP
+FROM inputs
+ON CONFLICT (crate_id) DO UPDATE
+    SET score = EXCLUDED.score,
+        computed_at = EXCLUDED.computed_at
@@ -0,0 +1,146 @@
+//! Notify the owners of a crate when one of its versions is published or
+//! yanked.
+
+use crate::email::Email;
+use crate::models::{CrateOwner, NotificationEvent, VersionAction};
+use crate::schema::{api_tokens, crates, users, version_owner_actions, versions};
+use crate::tasks::spawn_blocking;
+use crate::worker::Environment;
+use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
+use std::sync::Arc;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SendVersionNotifications {
+    /// The `version_owner_actions` row describing what happened to which
+    /// version, and who did it.
+    action_id: i32,
+}
+
+impl SendVersionNotifications {
+    pub fn new(action_id: i32) -> Self {
+        Self { action_id }
+    }
+}
+
+impl BackgroundJob for SendVersionNotifications {
+    const JOB_NAME: &'static str = "send_version_notifications";
+
+    type Context = Arc<Environment>;
+
+    #[instrument(skip_all, fields(action_id = self.action_id))]
+    async fn run(&self, env: Self::Context) -> anyhow::Result<()> {
+        let action_id = self.action_id;
+        let conn = env.deadpool.get().await?;
+        spawn_blocking(move || {
+            let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+            let (action, crate_id, crate_name, version, actor, token_name): (
+                VersionAction,
+                i32,
+                String,
+                String,
+                String,
+                Option<String>,
+            ) = version_owner_actions::table
+                .find(action_id)
+                .inner_join(versions::table.inner_join(crates::table))
+                .inner_join(users::table)
+                .left_join(api_tokens::table)
+                .select((
+                    version_owner_actions::action,
+                    crates::id,
+                    crates::name,
+                    versions::num,
+                    users::gh_login,
+                    api_tokens::name.nullable(),
+                ))
+                .first(conn)?;
+
+            let details = VersionActionDetails {
+                crate_name: &crate_name,
+                version: &version,
+                actor: &actor,
+                token_name: token_name.as_deref(),
+            };
+
+            let event = match action {
+                VersionAction::Publish => NotificationEvent::Publish,
+                VersionAction::Yank => NotificationEvent::Yank,
+                VersionAction::Unyank => {
+                    warn!("Skipping notifications for unsupported version action");
+                    return Ok(());
+                }
+            };
+
+            let recipients = CrateOwner::notification_emails(crate_id, event, conn)?;
+            info!(
+                num_recipients = recipients.len(),
+                "Sending {crate_name}@{version} notifications"
+            );
+
+            for recipient in recipients {
+                let result = match action {
+                    VersionAction::Publish => {
+                        env.emails.send(&recipient, VersionPublishedEmail(details))
+                    }
+                    _ => env.emails.send(&recipient, VersionYankedEmail(details)),
+                };
+
+                if let Err(error) = result {
+                    error!(?error, ?recipient, "Failed to send version notification");
+                }
+            }
+
+            Ok(())
+        })
+        .await
+    }
+}
+
+/// The details of a version action that owners are notified about.
+#[derive(Debug, Clone, Copy)]
+pub struct VersionActionDetails<'a> {
+    pub crate_name: &'a str,
+    pub version: &'a str,
+    pub actor: &'a str,
+    /// The name of the API token that was used, if any.
+    pub token_name: Option<&'a str>,
+}
+
+impl VersionActionDetails<'_> {
+    fn context(&self, action: &str) -> minijinja::Value {
+        minijinja::context! {
+            action,
+            crate_name => self.crate_name,
+            version => self.version,
+            actor => self.actor,
+            token_name => self.token_name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct VersionPublishedEmail<'a>(pub VersionActionDetails<'a>);
+
+impl Email for VersionPublishedEmail<'_> {
+    const SUBJECT: &'static str = "A new version of your crate has been published";
+    const TEMPLATE: &'static str = "version_action";
+
+    fn context(&self) -> minijinja::Value {
+        self.0.context("published")
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct VersionYankedEmail<'a>(pub VersionActionDetails<'a>);
+
+impl Email for VersionYankedEmail<'_> {
+    const SUBJECT: &'static str = "A version of your crate has been yanked";
+    const TEMPLATE: &'static str = "version_action";
+
+    fn context(&self) -> minijinja::Value {
+        self.0.context("yanked")
+    }
+}
@@ -0,0 +1,289 @@
+//! Deliver crate events to the webhook endpoints that users registered.
+//!
+//! [TriggerWebhooks] is enqueued when an event happens. It looks up the
+//! subscribed webhooks, records a delivery for each of them in the
+//! `webhook_deliveries` table, and enqueues a [DeliverWebhook] job per
+//! delivery.
+
+use crate::models::{VersionAction, Webhook, WebhookDelivery, WebhookEvent};
+use crate::schema::{crates, users, version_owner_actions, versions, webhook_deliveries, webhooks};
+use crate::tasks::spawn_blocking;
+use crate::util::diesel::Conn;
+use crate::webhooks::{sign, WebhookRequest};
+use crate::worker::Environment;
+use anyhow::anyhow;
+use chrono::NaiveDateTime;
+use crates_io_worker::BackgroundJob;
+use diesel::dsl::now;
+use diesel::prelude::*;
+use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
+use std::sync::Arc;
+
+/// The number of delivery attempts after which a delivery is given up.
+///
+/// Failed deliveries are retried by the background worker with an
+/// exponential backoff.
+const MAX_ATTEMPTS: i32 = 5;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerWebhooks {
+    /// A version was published, yanked or unyanked. The `action_id` points to
+    /// the corresponding `version_owner_actions` row.
+    VersionAction { action_id: i32 },
+    /// A user or team was added to or removed from the owners of a crate.
+    OwnerChange {
+        crate_id: i32,
+        owner: String,
+        added: bool,
+    },
+}
+
+impl TriggerWebhooks {
+    pub fn version_action(action_id: i32) -> Self {
+        Self::VersionAction { action_id }
+    }
+
+    pub fn owner_change(crate_id: i32, owner: &str, added: bool) -> Self {
+        let owner = owner.to_string();
+        Self::OwnerChange {
+            crate_id,
+            owner,
+            added,
+        }
+    }
+
+    /// Returns the crate that the event belongs to, the event itself and the
+    /// payload that is sent to the webhook endpoints.
+    fn event(&self, conn: &mut impl Conn) -> QueryResult<(i32, WebhookEvent, serde_json::Value)> {
+        match *self {
+            Self::VersionAction { action_id } => {
+                let (action, time, crate_id, crate_name, version, actor): (
+                    VersionAction,
+                    NaiveDateTime,
+                    i32,
+                    String,
+                    String,
+                    String,
+                ) = version_owner_actions::table
+                    .find(action_id)
+                    .inner_join(versions::table.inner_join(crates::table))
+                    .inner_join(users::table)
+                    .select((
+                        version_owner_actions::action,
+                        version_owner_actions::time,
+                        crates::id,
+                        crates::name,
+                        versions::num,
+                        users::gh_login,
+                    ))
+                    .first(conn)?;
+
+                let event = match action {
+                    VersionAction::Publish => WebhookEvent::Publish,
+                    VersionAction::Yank => WebhookEvent::Yank,
+                    VersionAction::Unyank => WebhookEvent::Unyank,
+                };
+
+                let payload = json!({
+                    "event": event,
+                    "crate": crate_name,
+                    "version": version,
+                    "actor": actor,
+                    "time": time.and_utc().to_rfc3339(),
+                });
+
+                Ok((crate_id, event, payload))
+            }
+            Self::OwnerChange {
+                crate_id,
+                ref owner,
+                added,
+            } => {
+                let crate_name: String = crates::table
+                    .find(crate_id)
+                    .select(crates::name)
+                    .first(conn)?;
+
+                let event = WebhookEvent::OwnerChange;
+                let payload = json!({
+                    "event": event,
+                    "crate": crate_name,
+                    "owner": owner,
+                    "action": if added { "added" } else { "removed" },
+                });
+
+                Ok((crate_id, event, payload))
+            }
+        }
+    }
+}
+
+impl BackgroundJob for TriggerWebhooks {
+    const JOB_NAME: &'static str = "trigger_webhooks";
+
+    type Context = Arc<Environment>;
+
+    #[instrument(skip_all, fields(job = ?self))]
+    async fn run(&self, env: Self::Context) -> anyhow::Result<()> {
+        let job = self.clone();
+        let conn = env.deadpool.get().await?;
+        spawn_blocking(move || {
+            let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+            let (crate_id, event, payload) = job.event(conn)?;
+
+            let webhooks = Webhook::subscribed_to(crate_id, event, conn)?;
+            if webhooks.is_empty() {
+                return Ok(());
+            }
+
+            info!(num_webhooks = webhooks.len(), "Triggering webhooks");
+
+            conn.transaction(|conn| -> anyhow::Result<()> {
+                for webhook in webhooks {
+                    let delivery_id = diesel::insert_into(webhook_deliveries::table)
+                        .values((
+                            webhook_deliveries::webhook_id.eq(webhook.id),
+                            webhook_deliveries::event.eq(event),
+                            webhook_deliveries::payload.eq(&payload),
+                        ))
+                        .returning(webhook_deliveries::id)
+                        .get_result(conn)?;
+
+                    DeliverWebhook::new(delivery_id).enqueue(conn)?;
+
+                    WebhookDelivery::prune(webhook.id, conn)?;
+                }
+
+                Ok(())
+            })
+        })
+        .await
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeliverWebhook {
+    delivery_id: i64,
+}
+
+impl DeliverWebhook {
+    pub fn new(delivery_id: i64) -> Self {
+        Self { delivery_id }
+    }
+}
+
+impl BackgroundJob for DeliverWebhook {
+    const JOB_NAME: &'static str = "deliver_webhook";
+    const QUEUE: &'static str = "webhooks";
+
+    type Context = Arc<Environment>;
+
+    #[instrument(skip_all, fields(delivery_id = self.delivery_id))]
+    async fn run(&self, env: Self::Context) -> anyhow::Result<()> {
+        let Some(webhook_client) = env.webhook_client.as_deref() else {
+            warn!("Skipping webhook delivery, since no webhook client is configured");
+            return Ok(());
+        };
+
+        let delivery_id = self.delivery_id;
+        let conn = env.deadpool.get().await?;
+        let delivery = spawn_blocking(move || {
+            let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+            let delivery = webhook_deliveries::table
+                .find(delivery_id)
+                .inner_join(webhooks::table)
+                .select((
+                    WebhookDelivery::as_select(),
+                    webhooks::url,
+                    webhooks::secret,
+                ))
+                .first::<(WebhookDelivery, String, String)>(conn)
+                .optional()?;
+
+            Ok::<_, anyhow::Error>(delivery)
+        })
+        .await?;
+
+        // The delivery is deleted together with its webhook, or if it is one
+        // of the oldest deliveries of a busy webhook.
+        let Some((delivery, url, secret)) = delivery else {
+            info!("Skipping deleted webhook delivery");
+            return Ok(());
+        };
+
+        if delivery.delivered_at.is_some() {
+            info!("Skipping webhook delivery, since it was delivered already");
+            return Ok(());
+        }
+
+        let body = serde_json::to_vec(&delivery.payload)?;
+        let request = WebhookRequest {
+            url,
+            event: delivery.event,
+            delivery_id,
+            signature: sign(&secret, &body),
+            body,
+        };
+
+        let result = webhook_client.deliver(request).await;
+
+        let (status_code, error) = match &result {
+            Ok(status) if (200..300).contains(status) => (Some(*status as i32), None),
+            Ok(status) => (
+                Some(*status as i32),
+                Some(format!("Unexpected status code {status}")),
+            ),
+            Err(error) => (None, Some(error.to_string())),
+        };
+
+        let attempts = delivery.attempts + 1;
+        let delivered = error.is_none();
+
+        let conn = env.deadpool.get().await?;
+        spawn_blocking(move || {
+            let conn: &mut AsyncConnectionWrapper<_> = &mut conn.into();
+
+            diesel::update(webhook_deliveries::table.find(delivery_id))
+                .set((
+                    webhook_deliveries::attempts.eq(attempts),
+                    webhook_deliveries::status_code.eq(status_code),
+                    webhook_deliveries::error.eq(&error),
+                    webhook_deliveries::last_attempt_at.eq(now.nullable()),
+                ))
+                .execute(conn)?;
+
+            if delivered {
+                diesel::update(webhook_deliveries::table.find(delivery_id))
+                    .set(webhook_deliveries::delivered_at.eq(now.nullable()))
+                    .execute(conn)?;
+            }
+
+            Ok::<_, anyhow::Error>(())
+        })
+        .await?;
+
+        match result {
+            Ok(status) if delivered => {
+                info!(status, "Delivered webhook payload");
+                Ok(())
+            }
+            // Client errors are not going to go away by retrying the delivery,
+            // except for timeouts and rate limits.
+            Ok(status) if (400..500).contains(&status) && status != 408 && status != 429 => {
+                warn!(status, "Webhook endpoint rejected the payload");
+                Ok(())
+            }
+            _ if attempts >= MAX_ATTEMPTS => {
+                warn!(attempts, "Giving up on webhook delivery");
+                Ok(())
+            }
+            Ok(status) => Err(anyhow!(
+                "Webhook endpoint responded with status code {status}"
+            )),
+            Err(error) => Err(anyhow!("Failed to deliver webhook payload: {error}")),
+        }
+    }
+}
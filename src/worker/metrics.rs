@@ -0,0 +1,111 @@
+//! Persists the job execution metrics reported by the background worker, so
+//! that they can be exported as service-level metrics by the web servers.
+//!
+//! See [crate::metrics::ServiceMetrics] for the export side.
+
+use crates_io_worker::{JobMetrics, JobRun};
+use diesel::sql_types::{Array, BigInt, Double, Text};
+use diesel_async::pooled_connection::deadpool::Pool;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use std::time::Duration;
+use tokio::runtime::Handle;
+
+/// Upper bounds (in seconds) of the histogram buckets used for the queue wait
+/// times and run durations of background jobs.
+///
+/// Background jobs take a lot longer than HTTP requests, so these buckets are
+/// a lot coarser than the ones in `src/metrics/macros.rs`. Changing these
+/// buckets requires resetting the `background_job_metrics` table.
+pub const JOB_HISTOGRAM_BUCKETS: &[f64] = &[
+    0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0, 900.0, 3600.0,
+];
+
+/// A [JobMetrics] hook that aggregates the job runs into the
+/// `background_job_metrics` database table.
+pub struct DatabaseJobMetrics {
+    deadpool: Pool<AsyncPgConnection>,
+}
+
+impl DatabaseJobMetrics {
+    pub fn new(deadpool: Pool<AsyncPgConnection>) -> Self {
+        Self { deadpool }
+    }
+}
+
+impl JobMetrics for DatabaseJobMetrics {
+    fn record(&self, run: &JobRun<'_>) {
+        let deadpool = self.deadpool.clone();
+        let job_type = run.job_type.to_string();
+
+        let (successes, failures) = match run.success {
+            true => (1, 0),
+            false => (0, 1),
+        };
+        let retries = i64::from(run.retries > 0);
+
+        let queue_wait_sum = run.queue_wait.map(|d| d.as_secs_f64()).unwrap_or_default();
+        let queue_wait_buckets = run.queue_wait.map(bucket_counts).unwrap_or_default();
+        let duration_sum = run.duration.as_secs_f64();
+        let duration_buckets = bucket_counts(run.duration);
+
+        // The job is still locked while this hook is called, so we don't want
+        // to wait for the metrics to be saved.
+        Handle::current().spawn(async move {
+            let result = async {
+                let mut conn = deadpool.get().await?;
+                diesel::sql_query(include_str!("metrics.sql"))
+                    .bind::<Text, _>(job_type)
+                    .bind::<BigInt, _>(successes)
+                    .bind::<BigInt, _>(failures)
+                    .bind::<BigInt, _>(retries)
+                    .bind::<Double, _>(queue_wait_sum)
+                    .bind::<Array<BigInt>, _>(queue_wait_buckets)
+                    .bind::<Double, _>(duration_sum)
+                    .bind::<Array<BigInt>, _>(duration_buckets)
+                    .execute(&mut conn)
+                    .await?;
+
+                Ok::<_, anyhow::Error>(())
+            };
+
+            if let Err(error) = result.await {
+                warn!("Failed to save background job metrics: {error}");
+            }
+        });
+    }
+}
+
+/// Returns the non-cumulative bucket counts for a single observation, with an
+/// additional bucket at the end for observations above the largest bound.
+fn bucket_counts(value: Duration) -> Vec<i64> {
+    let value = value.as_secs_f64();
+
+    let index = JOB_HISTOGRAM_BUCKETS
+        .iter()
+        .position(|upper_bound| value <= *upper_bound)
+        .unwrap_or(JOB_HISTOGRAM_BUCKETS.len());
+
+    let mut counts = vec![0; JOB_HISTOGRAM_BUCKETS.len() + 1];
+    counts[index] = 1;
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_counts() {
+        let counts = bucket_counts(Duration::from_millis(1));
+        assert_eq!(counts, vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        let counts = bucket_counts(Duration::from_secs(1));
+        assert_eq!(counts, vec![0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        let counts = bucket_counts(Duration::from_secs(20));
+        assert_eq!(counts, vec![0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0]);
+
+        let counts = bucket_counts(Duration::from_secs(7200));
+        assert_eq!(counts, vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    }
+}
@@ -0,0 +1,29 @@
+-- Adds the metrics of a single job run to the aggregated metrics of its job
+-- type. The histogram bucket arrays are added element-wise.
+INSERT INTO background_job_metrics AS m (
+    job_type,
+    successes,
+    failures,
+    retries,
+    queue_wait_sum,
+    queue_wait_buckets,
+    duration_sum,
+    duration_buckets
+)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+ON CONFLICT (job_type) DO UPDATE SET
+    successes = m.successes + excluded.successes,
+    failures = m.failures + excluded.failures,
+    retries = m.retries + excluded.retries,
+    queue_wait_sum = m.queue_wait_sum + excluded.queue_wait_sum,
+    queue_wait_buckets = ARRAY(
+        SELECT coalesce(a, 0) + coalesce(b, 0)
+        FROM unnest(m.queue_wait_buckets, excluded.queue_wait_buckets) WITH ORDINALITY AS t(a, b, i)
+        ORDER BY i
+    ),
+    duration_sum = m.duration_sum + excluded.duration_sum,
+    duration_buckets = ARRAY(
+        SELECT coalesce(a, 0) + coalesce(b, 0)
+        FROM unnest(m.duration_buckets, excluded.duration_buckets) WITH ORDINALITY AS t(a, b, i)
+        ORDER BY i
+    );
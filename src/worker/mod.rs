@@ -10,8 +10,10 @@ use std::sync::Arc;
 
 mod environment;
 pub mod jobs;
+mod metrics;
 
 pub use self::environment::Environment;
+pub use self::metrics::{DatabaseJobMetrics, JOB_HISTOGRAM_BUCKETS};
 
 pub trait RunnerExt {
     fn register_crates_io_job_types(self) -> Self;
@@ -20,21 +22,33 @@ pub trait RunnerExt {
 impl RunnerExt for Runner<Arc<Environment>> {
     fn register_crates_io_job_types(self) -> Self {
         self.register_job_type::<jobs::ArchiveVersionDownloads>()
+            .register_job_type::<jobs::CheckReadmeLinks>()
             .register_job_type::<jobs::CheckTyposquat>()
             .register_job_type::<jobs::CleanProcessedLogFiles>()
             .register_job_type::<jobs::DailyDbMaintenance>()
+            .register_job_type::<jobs::DeliverWebhook>()
             .register_job_type::<jobs::DumpDb>()
             .register_job_type::<jobs::NormalizeIndex>()
             .register_job_type::<jobs::ProcessCdnLog>()
             .register_job_type::<jobs::ProcessCdnLogQueue>()
+            .register_job_type::<jobs::RenderAndUploadChangelog>()
             .register_job_type::<jobs::RenderAndUploadReadme>()
+            .register_job_type::<jobs::RerenderReadme>()
+            .register_job_type::<jobs::RollupVersionDownloads>()
             .register_job_type::<jobs::SquashIndex>()
             .register_job_type::<jobs::SyncAdmins>()
+            .register_job_type::<jobs::SyncSearchIndex>()
+            .register_job_type::<jobs::SyncSearchIndexDownloads>()
             .register_job_type::<jobs::SyncToGitIndex>()
             .register_job_type::<jobs::SyncToSparseIndex>()
+            .register_job_type::<jobs::TriggerWebhooks>()
             .register_job_type::<jobs::UpdateDownloads>()
             .register_job_type::<jobs::UpdateDefaultVersion>()
+            .register_job_type::<jobs::UpdateQualityScores>()
             .register_job_type::<jobs::SendTokenExpiryNotifications>()
+            .register_job_type::<jobs::SendOwnershipChangeNotifications>()
+            .register_job_type::<jobs::SendReverseDependencyNotifications>()
+            .register_job_type::<jobs::SendVersionNotifications>()
             .register_job_type::<jobs::rss::SyncCrateFeed>()
             .register_job_type::<jobs::rss::SyncCratesFeed>()
             .register_job_type::<jobs::rss::SyncUpdatesFeed>()